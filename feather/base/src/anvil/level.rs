@@ -1,9 +1,12 @@
 //! Implements level.dat file loading.
 
+use blocks::BlockId;
 use generated::{Biome, Item};
 use serde::{Deserialize, Serialize};
 use std::io::{Cursor, Read, Write};
+use std::str::FromStr;
 use std::{collections::HashMap, fs::File};
+use thiserror::Error;
 
 /// Root level tag
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -147,6 +150,99 @@ pub struct SuperflatLayer {
     pub height: u8,
 }
 
+impl SuperflatLayer {
+    /// Returns the block of this layer, or `None` if it is unknown.
+    ///
+    /// The `minecraft:` namespace may be omitted.
+    pub fn block_id(&self) -> Option<BlockId> {
+        BlockId::from_identifier(&namespaced(&self.block))
+    }
+}
+
+/// Error returned when parsing a superflat layer string.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SuperflatParseError {
+    #[error("unknown block '{0}'")]
+    UnknownBlock(String),
+    #[error("unknown biome '{0}'")]
+    UnknownBiome(String),
+    #[error("invalid layer height in '{0}'")]
+    InvalidHeight(String),
+    #[error("layers are taller than the world")]
+    TooTall,
+}
+
+impl FromStr for SuperflatGeneratorOptions {
+    type Err = SuperflatParseError;
+
+    /// Parses a vanilla-style layer string, e.g.
+    /// `minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:plains`.
+    ///
+    /// Layers are listed from the bottom up, each optionally
+    /// prefixed with a height. The biome defaults to plains.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(2, ';');
+        let layers_str = parts.next().unwrap_or_default();
+
+        let mut layers = Vec::new();
+        let mut total_height = 0u32;
+        for layer in layers_str
+            .split(',')
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+        {
+            let (height, block) = match layer.find('*') {
+                Some(index) => {
+                    let height = layer[..index]
+                        .trim()
+                        .parse()
+                        .map_err(|_| SuperflatParseError::InvalidHeight(layer.to_owned()))?;
+                    (height, layer[index + 1..].trim())
+                }
+                None => (1, layer),
+            };
+
+            let layer = SuperflatLayer {
+                block: namespaced(block),
+                height,
+            };
+            if layer.block_id().is_none() {
+                return Err(SuperflatParseError::UnknownBlock(block.to_owned()));
+            }
+
+            total_height += height as u32;
+            if total_height > 256 {
+                return Err(SuperflatParseError::TooTall);
+            }
+            layers.push(layer);
+        }
+
+        let biome = match parts.next().map(str::trim).filter(|b| !b.is_empty()) {
+            Some(biome) => {
+                let name = biome.trim_start_matches("minecraft:");
+                Biome::from_name(name)
+                    .ok_or_else(|| SuperflatParseError::UnknownBiome(biome.to_owned()))?
+            }
+            None => Biome::Plains,
+        };
+
+        Ok(Self {
+            layers,
+            biome: biome.name().to_owned(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Adds the `minecraft:` namespace to an identifier without one.
+fn namespaced(identifier: &str) -> String {
+    if identifier.contains(':') {
+        identifier.to_owned()
+    } else {
+        format!("minecraft:{}", identifier)
+    }
+}
+
 /// The type of world generator for a level.
 #[derive(Debug, PartialEq)]
 pub enum LevelGeneratorType {
@@ -203,4 +299,57 @@ mod tests {
         assert_eq!(level.generator_name, "default");
         assert!(level.generator_options.is_none());
     }
+
+    #[test]
+    fn parse_superflat_layers() {
+        let options: SuperflatGeneratorOptions =
+            "minecraft:bedrock,2*minecraft:dirt,grass_block;minecraft:desert"
+                .parse()
+                .unwrap();
+        let layers: Vec<_> = options
+            .layers
+            .iter()
+            .map(|layer| (layer.block_id().unwrap(), layer.height))
+            .collect();
+        assert_eq!(
+            layers,
+            vec![
+                (BlockId::bedrock(), 1),
+                (BlockId::dirt(), 2),
+                (BlockId::grass_block(), 1)
+            ]
+        );
+        assert_eq!(options.biome, "desert");
+
+        let options: SuperflatGeneratorOptions = "3*stone".parse().unwrap();
+        assert_eq!(options.biome, "plains");
+    }
+
+    #[test]
+    fn parse_invalid_superflat_layers() {
+        assert_eq!(
+            "minecraft:bedrock,minecraft:nonsense"
+                .parse::<SuperflatGeneratorOptions>()
+                .unwrap_err(),
+            SuperflatParseError::UnknownBlock("minecraft:nonsense".to_owned())
+        );
+        assert_eq!(
+            "x*minecraft:dirt"
+                .parse::<SuperflatGeneratorOptions>()
+                .unwrap_err(),
+            SuperflatParseError::InvalidHeight("x*minecraft:dirt".to_owned())
+        );
+        assert_eq!(
+            "200*stone,100*dirt"
+                .parse::<SuperflatGeneratorOptions>()
+                .unwrap_err(),
+            SuperflatParseError::TooTall
+        );
+        assert_eq!(
+            "stone;moon"
+                .parse::<SuperflatGeneratorOptions>()
+                .unwrap_err(),
+            SuperflatParseError::UnknownBiome("moon".to_owned())
+        );
+    }
 }
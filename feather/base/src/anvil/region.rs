@@ -150,6 +150,12 @@ pub struct RegionHandle {
 }
 
 impl RegionHandle {
+    /// Returns whether the chunk at the given position (global,
+    /// not region-relative) is saved in this region file.
+    pub fn has_chunk(&self, pos: ChunkPosition) -> bool {
+        self.header.location_for_chunk(pos).exists()
+    }
+
     /// Loads the chunk at the given position (global, not region-relative).
     ///
     /// The specified chunk is expected to be contained within this region.
@@ -238,7 +244,7 @@ impl RegionHandle {
                 Biome::from_id(id as u32).ok_or(Error::InvalidBiomeId(id))?;
         }
 
-        // chunk.recalculate_heightmap();
+        chunk.recalculate_heightmaps();
 
         Ok((chunk, level.entities.clone(), level.block_entities.clone()))
     }
@@ -169,9 +169,18 @@ impl Chunk {
             section.fill(block);
         }
 
+        self.recalculate_heightmaps();
         true
     }
 
+    /// Shrinks the palette of each section to the blocks it
+    /// contains. See `BlockStore::optimize`.
+    pub fn optimize(&mut self) {
+        for section in self.sections.iter_mut().flatten() {
+            section.optimize();
+        }
+    }
+
     /// Recalculates heightmaps for this chunk.
     pub fn recalculate_heightmaps(&mut self) {
         self.heightmaps
@@ -323,6 +332,12 @@ impl ChunkSection {
         self.blocks.set_block_at(x, y, z, block)
     }
 
+    /// Shrinks the palette of this section to the
+    /// blocks it contains. See `BlockStore::optimize`.
+    pub fn optimize(&mut self) {
+        self.blocks.optimize();
+    }
+
     /// Fills this chunk section with the given block.
     ///
     /// Does not currently update heightmaps.
@@ -551,6 +566,44 @@ mod tests {
         assert_eq!(chunk.block_at(0, 1, 0).unwrap(), BlockId::stone());
     }
 
+    #[test]
+    fn optimize_shrinks_palette() {
+        let mut section = ChunkSection::default();
+        // Enough distinct blocks to overflow into the global palette.
+        for i in 0..300 {
+            section.set_block_at(
+                i % 16,
+                i / 256,
+                (i / 16) % 16,
+                BlockId::from_vanilla_id(i as u16 + 100),
+            );
+        }
+        assert!(section.blocks().palette().is_none());
+
+        for i in 2..300 {
+            section.set_block_at(i % 16, i / 256, (i / 16) % 16, BlockId::stone());
+        }
+        section.optimize();
+
+        let palette = section.blocks().palette().unwrap();
+        assert_eq!(palette.len(), 4); // air, stone and the two blocks left over
+        assert_eq!(
+            section.blocks().data().bits_per_value(),
+            MIN_BITS_PER_BLOCK as usize
+        );
+        assert_eq!(
+            section.block_at(0, 0, 0),
+            Some(BlockId::from_vanilla_id(100))
+        );
+        assert_eq!(
+            section.block_at(1, 0, 0),
+            Some(BlockId::from_vanilla_id(101))
+        );
+        assert_eq!(section.block_at(2, 0, 0), Some(BlockId::stone()));
+        assert_eq!(section.block_at(15, 15, 15), Some(BlockId::air()));
+        assert_eq!(section.non_air_blocks(), 300);
+    }
+
     #[test]
     fn test_biomes() {
         let mut chunk = Chunk::default();
@@ -587,7 +640,55 @@ mod tests {
         let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
 
         chunk.set_block_at(0, 10, 0, BlockId::stone());
-        assert_eq!(chunk.heightmaps.motion_blocking.height(0, 0), Some(10));
+        assert_eq!(chunk.heightmaps.motion_blocking.height(0, 0), Some(11));
+
+        chunk.set_block_at(0, 20, 0, BlockId::oak_sign());
+        assert_eq!(chunk.heightmaps.motion_blocking.height(0, 0), Some(11));
+        assert_eq!(chunk.heightmaps.world_surface.height(0, 0), Some(21));
+
+        chunk.set_block_at(0, 20, 0, BlockId::air());
+        assert_eq!(chunk.heightmaps.world_surface.height(0, 0), Some(11));
+        chunk.set_block_at(0, 10, 0, BlockId::air());
+        assert_eq!(chunk.heightmaps.motion_blocking.height(0, 0), Some(0));
+        assert_eq!(chunk.heightmaps.world_surface.height(0, 0), Some(0));
+    }
+
+    #[test]
+    fn heightmaps_match_recalculation() {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        chunk.fill_section(1, BlockId::stone());
+        assert_eq!(chunk.heightmaps.motion_blocking.height(3, 4), Some(16));
+
+        for y in 10..40 {
+            chunk.set_block_at(3, y, 4, BlockId::water());
+        }
+        chunk.set_block_at(3, 50, 4, BlockId::oak_leaves());
+        chunk.set_block_at(3, 45, 4, BlockId::air());
+        chunk.set_block_at(3, 39, 4, BlockId::air());
+
+        let incremental = chunk.heightmaps().clone();
+        chunk.recalculate_heightmaps();
+        for x in 0..CHUNK_WIDTH {
+            for z in 0..CHUNK_WIDTH {
+                assert_eq!(
+                    incremental.motion_blocking.height(x, z),
+                    chunk.heightmaps.motion_blocking.height(x, z)
+                );
+                assert_eq!(
+                    incremental.motion_blocking_no_leaves.height(x, z),
+                    chunk.heightmaps.motion_blocking_no_leaves.height(x, z)
+                );
+                assert_eq!(
+                    incremental.world_surface.height(x, z),
+                    chunk.heightmaps.world_surface.height(x, z)
+                );
+            }
+        }
+        assert_eq!(chunk.heightmaps.motion_blocking.height(3, 4), Some(51));
+        assert_eq!(
+            chunk.heightmaps.motion_blocking_no_leaves.height(3, 4),
+            Some(39)
+        );
     }
 
     #[test]
@@ -75,12 +75,7 @@ impl BlockStore {
 
     pub fn block_at(&self, x: usize, y: usize, z: usize) -> Option<BlockId> {
         let index = ChunkSection::block_index(x, y, z)?;
-        let block_index = self.blocks.get(index).expect("block_index out of bounds?");
-
-        Some(match &self.palette {
-            Some(palette) => palette.get(block_index as usize),
-            None => BlockId::from_vanilla_id(block_index as u16),
-        })
+        Some(self.block_at_index(index))
     }
 
     pub fn set_block_at(&mut self, x: usize, y: usize, z: usize, block: BlockId) -> Option<()> {
@@ -111,6 +106,40 @@ impl BlockStore {
         }
     }
 
+    /// Rebuilds the palette to contain only the blocks in this
+    /// section, and repacks the data with the fewest bits per
+    /// block allowed by the palette. Sections which overflowed
+    /// into the global palette switch back to a section palette
+    /// if few enough distinct blocks remain.
+    ///
+    /// Palettes only grow as blocks are set, so this is worth
+    /// calling once a section is no longer being modified.
+    pub fn optimize(&mut self) {
+        let mut palette = Palette::new();
+        let indices: Vec<u64> = (0..SECTION_VOLUME)
+            .map(|i| palette.index_or_insert(self.block_at_index(i)) as u64)
+            .collect();
+
+        let needed_bits = (usize::BITS - (palette.len() - 1).leading_zeros()) as u8;
+        if needed_bits > MAX_BITS_PER_BLOCK {
+            if self.palette.is_some() {
+                self.use_global_palette();
+            }
+            return;
+        }
+
+        self.blocks = PackedArray::from_iter(indices, needed_bits.max(MIN_BITS_PER_BLOCK) as usize);
+        self.palette = Some(palette);
+    }
+
+    fn block_at_index(&self, index: usize) -> BlockId {
+        let value = self.blocks.get(index).expect("block_index out of bounds?");
+        match &self.palette {
+            Some(palette) => palette.get(value as usize),
+            None => BlockId::from_vanilla_id(value as u16),
+        }
+    }
+
     fn get_block_palette_index(&mut self, block: BlockId) -> usize {
         match &mut self.palette {
             Some(p) => {
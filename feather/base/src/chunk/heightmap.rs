@@ -156,6 +156,9 @@ where
     }
 
     /// Updates this height map after a block has been updated.
+    ///
+    /// Heights are one above the highest solid block
+    /// in the column, or zero if there is none.
     pub fn update(
         &mut self,
         x: usize,
@@ -165,20 +168,19 @@ where
         new_block: BlockId,
         get_block: impl Fn(usize, usize, usize) -> BlockId,
     ) {
-        if F::is_solid(old_block) && self.height(x, z) == Some(y) {
-            // This was old the highest block
-            for i in (0..y).rev() {
-                let block = get_block(x, i, z);
-
-                if F::is_solid(block) {
-                    self.set_height(x, z, i + 1);
-                    break;
-                }
+        let height = self.height(x, z).unwrap_or(0);
+        if F::is_solid(new_block) {
+            if y + 1 > height {
+                // This is the new highest block
+                self.set_height(x, z, y + 1);
             }
-        }
-        if F::is_solid(new_block) && self.height(x, z).unwrap() < y {
-            // This is the new highest block
-            self.set_height(x, z, y);
+        } else if F::is_solid(old_block) && height == y + 1 {
+            // This was the highest block, so find the next one below it
+            let below = (0..y)
+                .rev()
+                .find(|&i| F::is_solid(get_block(x, i, z)))
+                .map_or(0, |i| i + 1);
+            self.set_height(x, z, below);
         }
     }
 
@@ -186,12 +188,11 @@ where
     pub fn recalculate(&mut self, get_block: impl Fn(usize, usize, usize) -> BlockId) {
         for x in 0..CHUNK_WIDTH {
             for z in 0..CHUNK_WIDTH {
-                for y in (0..CHUNK_HEIGHT).rev() {
-                    if F::is_solid(get_block(x, y, z)) {
-                        self.set_height(x, z, y + 1);
-                        break;
-                    }
-                }
+                let height = (0..CHUNK_HEIGHT)
+                    .rev()
+                    .find(|&y| F::is_solid(get_block(x, y, z)))
+                    .map_or(0, |y| y + 1);
+                self.set_height(x, z, height);
             }
         }
     }
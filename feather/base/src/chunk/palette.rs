@@ -79,6 +79,7 @@ impl Palette {
     pub fn clear(&mut self) {
         self.blocks.clear();
         self.blocks.push(BlockId::air());
+        self.free_indices.clear();
     }
 
     fn index_of(&self, block: BlockId) -> Option<usize> {
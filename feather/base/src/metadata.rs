@@ -24,6 +24,14 @@ pub const META_INDEX_ITEM_SLOT: u8 = 6;
 
 pub const META_INDEX_FALLING_BLOCK_SPAWN_POSITION: u8 = 7;
 
+pub const META_INDEX_POSE: u8 = 6;
+pub const META_INDEX_LIVING_BED_POSITION: u8 = 13;
+
+pub const META_INDEX_STRIDER_SHIVERING: u8 = 17;
+
+pub const META_INDEX_END_CRYSTAL_BEAM_TARGET: u8 = 7;
+pub const META_INDEX_END_CRYSTAL_SHOW_BOTTOM: u8 = 8;
+
 bitflags! {
     pub struct EntityBitMask: u8 {
         const ON_FIRE = 0x01;
@@ -36,6 +44,18 @@ bitflags! {
     }
 }
 
+/// An entity's pose, sent in metadata as `MetaEntry::Pose`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pose {
+    Standing = 0,
+    FallFlying = 1,
+    Sleeping = 2,
+    Swimming = 3,
+    SpinAttack = 4,
+    Sneaking = 5,
+    Dying = 6,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum MetaEntry {
     Byte(i8),
@@ -143,13 +163,25 @@ impl ToMetaEntry for OptUuid {
     }
 }
 
+impl ToMetaEntry for Option<BlockPosition> {
+    fn to_meta_entry(&self) -> MetaEntry {
+        MetaEntry::OptPosition(*self)
+    }
+}
+
+impl ToMetaEntry for Pose {
+    fn to_meta_entry(&self) -> MetaEntry {
+        MetaEntry::Pose(*self as i32)
+    }
+}
+
 impl ToMetaEntry for OptVarInt {
     fn to_meta_entry(&self) -> MetaEntry {
         MetaEntry::OptVarInt(*self)
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct EntityMetadata {
     pub values: BTreeMap<u8, MetaEntry>,
 }
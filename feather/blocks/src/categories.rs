@@ -56,6 +56,11 @@ impl BlockId {
         )
     }
 
+    #[inline]
+    pub fn is_lava(self) -> bool {
+        self.simplified_kind() == SimplifiedBlockKind::Lava
+    }
+
     #[inline]
     pub fn is_replaceable(self) -> bool {
         matches!(
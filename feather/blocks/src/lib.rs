@@ -120,6 +120,16 @@ impl BlockId {
     pub fn from_vanilla_id(id: u16) -> Self {
         FROM_VANILLA_ID_TABLE[id as usize]
     }
+
+    /// Returns the block corresponding to the given vanilla ID,
+    /// or `None` if the ID is invalid.
+    pub fn try_from_vanilla_id(id: u16) -> Option<Self> {
+        if id <= HIGHEST_ID {
+            Some(FROM_VANILLA_ID_TABLE[id as usize])
+        } else {
+            None
+        }
+    }
 }
 
 impl From<BlockId> for u32 {
@@ -0,0 +1,224 @@
+//! Entity attributes, such as movement speed, and
+//! the modifiers applied to them by equipment and effects.
+
+use ahash::AHashMap;
+use uuid::Uuid;
+
+/// An entity attribute.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Attribute {
+    MaxHealth,
+    KnockbackResistance,
+    MovementSpeed,
+    AttackDamage,
+    Armor,
+}
+
+impl Attribute {
+    /// Returns the namespaced key of this attribute.
+    pub fn name(self) -> &'static str {
+        match self {
+            Attribute::MaxHealth => "generic.max_health",
+            Attribute::KnockbackResistance => "generic.knockback_resistance",
+            Attribute::MovementSpeed => "generic.movement_speed",
+            Attribute::AttackDamage => "generic.attack_damage",
+            Attribute::Armor => "generic.armor",
+        }
+    }
+
+    /// Returns the base value of this attribute for
+    /// entities which don't override it.
+    pub fn default_value(self) -> f64 {
+        match self {
+            Attribute::MaxHealth => 20.0,
+            Attribute::KnockbackResistance => 0.0,
+            Attribute::MovementSpeed => 0.7,
+            Attribute::AttackDamage => 2.0,
+            Attribute::Armor => 0.0,
+        }
+    }
+}
+
+/// How a modifier's amount is applied.
+///
+/// Operations are applied in the order listed here,
+/// regardless of the order in which modifiers were added.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Adds the amount to the base value.
+    Add,
+    /// Adds the amount multiplied by the base value,
+    /// after `Add` modifiers have been applied.
+    MultiplyBase,
+    /// Multiplies the value by one plus the amount.
+    MultiplyTotal,
+}
+
+impl Operation {
+    /// Returns the protocol ID of this operation.
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+}
+
+/// A modifier of an attribute, identified by a UUID
+/// so that its source can remove it again.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AttributeModifier {
+    pub id: Uuid,
+    pub amount: f64,
+    pub operation: Operation,
+}
+
+/// The base value of an attribute and its modifiers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttributeInstance {
+    pub base: f64,
+    pub modifiers: Vec<AttributeModifier>,
+}
+
+impl AttributeInstance {
+    pub fn new(base: f64) -> Self {
+        Self {
+            base,
+            modifiers: Vec::new(),
+        }
+    }
+
+    /// Computes the value of the attribute by applying
+    /// `Add`, then `MultiplyBase`, then `MultiplyTotal` modifiers.
+    pub fn compute_value(&self) -> f64 {
+        let amounts = |operation| {
+            self.modifiers
+                .iter()
+                .filter(move |modifier| modifier.operation == operation)
+                .map(|modifier| modifier.amount)
+        };
+
+        let base = self.base + amounts(Operation::Add).sum::<f64>();
+        let mut value = base;
+        for amount in amounts(Operation::MultiplyBase) {
+            value += base * amount;
+        }
+        for amount in amounts(Operation::MultiplyTotal) {
+            value *= 1.0 + amount;
+        }
+        value
+    }
+}
+
+/// Component storing an entity's attributes. Attributes
+/// without an instance have their default value.
+#[derive(Clone, Debug, Default)]
+pub struct AttributeComponent {
+    attributes: AHashMap<Attribute, AttributeInstance>,
+}
+
+impl AttributeComponent {
+    /// Overrides the base value of `attribute`.
+    pub fn with_base(mut self, attribute: Attribute, base: f64) -> Self {
+        self.instance_mut(attribute).base = base;
+        self
+    }
+
+    /// Returns the instance of `attribute`, if it
+    /// has a custom base value or any modifiers.
+    pub fn get(&self, attribute: Attribute) -> Option<&AttributeInstance> {
+        self.attributes.get(&attribute)
+    }
+
+    /// Computes the value of `attribute`.
+    pub fn value(&self, attribute: Attribute) -> f64 {
+        self.get(attribute)
+            .map_or(attribute.default_value(), AttributeInstance::compute_value)
+    }
+
+    /// Adds a modifier to `attribute`, replacing
+    /// any modifier with the same ID.
+    pub fn add_modifier(&mut self, attribute: Attribute, modifier: AttributeModifier) {
+        let instance = self.instance_mut(attribute);
+        instance.modifiers.retain(|m| m.id != modifier.id);
+        instance.modifiers.push(modifier);
+    }
+
+    /// Removes the modifier with ID `id` from `attribute`.
+    /// Returns whether it was present.
+    pub fn remove_modifier(&mut self, attribute: Attribute, id: Uuid) -> bool {
+        match self.attributes.get_mut(&attribute) {
+            Some(instance) => {
+                let count = instance.modifiers.len();
+                instance.modifiers.retain(|m| m.id != id);
+                instance.modifiers.len() != count
+            }
+            None => false,
+        }
+    }
+
+    fn instance_mut(&mut self, attribute: Attribute) -> &mut AttributeInstance {
+        self.attributes
+            .entry(attribute)
+            .or_insert_with(|| AttributeInstance::new(attribute.default_value()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        effects::{self, ActiveEffect, StatusEffect},
+        Game,
+    };
+
+    #[test]
+    fn modifiers_apply_in_operation_order() {
+        let mut attributes = AttributeComponent::default().with_base(Attribute::MovementSpeed, 0.1);
+        let modifier = |id, amount, operation| AttributeModifier {
+            id: Uuid::from_u128(id),
+            amount,
+            operation,
+        };
+        // Added out of order on purpose.
+        attributes.add_modifier(
+            Attribute::MovementSpeed,
+            modifier(1, 0.2, Operation::MultiplyTotal),
+        );
+        attributes.add_modifier(
+            Attribute::MovementSpeed,
+            modifier(2, 0.5, Operation::MultiplyBase),
+        );
+        attributes.add_modifier(Attribute::MovementSpeed, modifier(3, 0.05, Operation::Add));
+
+        // ((0.1 + 0.05) + 0.15 * 0.5) * 1.2
+        let value = attributes.value(Attribute::MovementSpeed);
+        assert!((value - 0.27).abs() < 1e-9, "{}", value);
+
+        assert!(attributes.remove_modifier(Attribute::MovementSpeed, Uuid::from_u128(1)));
+        let value = attributes.value(Attribute::MovementSpeed);
+        assert!((value - 0.225).abs() < 1e-9, "{}", value);
+        assert_eq!(attributes.value(Attribute::Armor), 0.0);
+    }
+
+    #[test]
+    fn speed_effect_modifies_movement_speed() {
+        let mut game = Game::new();
+        let entity = game.ecs.spawn((AttributeComponent::default(),));
+        let speed = |game: &Game| {
+            game.ecs
+                .get::<AttributeComponent>(entity)
+                .unwrap()
+                .value(Attribute::MovementSpeed)
+        };
+        let base = speed(&game);
+
+        let effect = ActiveEffect {
+            effect: StatusEffect::Speed,
+            amplifier: 1,
+            duration: 100,
+        };
+        effects::add_effect(&mut game, entity, effect).unwrap();
+        assert!((speed(&game) - base * 1.4).abs() < 1e-9);
+
+        effects::remove_effect(&mut game, entity, StatusEffect::Speed).unwrap();
+        assert_eq!(speed(&game), base);
+    }
+}
@@ -12,7 +12,9 @@ use ecs::{Entity, SysResult, SystemExecutor};
 use utils::vec_remove_item;
 
 use crate::{
-    events::{EntityRemoveEvent, ViewUpdateEvent},
+    dimensions::DimensionId,
+    events::{EntityRemoveEvent, TeleportEvent, ViewUpdateEvent},
+    view::View,
     Game,
 };
 
@@ -22,10 +24,14 @@ pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
         .group::<ChunkLoadState>()
         .add_system(remove_dead_entities)
         .add_system(update_tickets_for_players)
+        .add_system(move_tickets_on_dimension_change)
         .add_system(unload_chunks)
         .add_system(load_chunks);
 }
 
+/// A chunk in a specific dimension.
+type DimensionChunk = (DimensionId, ChunkPosition);
+
 /// Amount of time to wait after a chunk has
 /// no tickets until it is unloaded.
 const UNLOAD_DELAY: Duration = Duration::from_secs(10);
@@ -39,7 +45,7 @@ struct ChunkLoadState {
 }
 
 impl ChunkLoadState {
-    pub fn remove_ticket(&mut self, chunk: ChunkPosition, ticket: Ticket) {
+    pub fn remove_ticket(&mut self, chunk: DimensionChunk, ticket: Ticket) {
         self.chunk_tickets.remove_ticket(chunk, ticket);
 
         // If this was the last ticket, then queue the chunk to be
@@ -54,13 +60,13 @@ impl ChunkLoadState {
 
 #[derive(Copy, Clone, Debug)]
 struct QueuedChunkUnload {
-    pos: ChunkPosition,
+    pos: DimensionChunk,
     /// Time after which the chunk should be unloaded.
     unload_at_time: Instant,
 }
 
 impl QueuedChunkUnload {
-    pub fn new(pos: ChunkPosition) -> Self {
+    pub fn new(pos: DimensionChunk) -> Self {
         Self {
             pos,
             unload_at_time: Instant::now() + UNLOAD_DELAY,
@@ -72,38 +78,38 @@ impl QueuedChunkUnload {
 /// A chunk is queued for unloading when it has no more tickets.
 #[derive(Default)]
 struct ChunkTickets {
-    tickets: AHashMap<ChunkPosition, Vec<Ticket>>,
-    by_entity: AHashMap<Ticket, Vec<ChunkPosition>>,
+    tickets: AHashMap<DimensionChunk, Vec<Ticket>>,
+    by_entity: AHashMap<Ticket, Vec<DimensionChunk>>,
 }
 
 impl ChunkTickets {
-    pub fn insert_ticket(&mut self, chunk: ChunkPosition, ticket: Ticket) {
+    pub fn insert_ticket(&mut self, chunk: DimensionChunk, ticket: Ticket) {
         self.tickets.entry(chunk).or_default().push(ticket);
         self.by_entity.entry(ticket).or_default().push(chunk);
     }
 
-    pub fn remove_ticket(&mut self, chunk: ChunkPosition, ticket: Ticket) {
+    pub fn remove_ticket(&mut self, chunk: DimensionChunk, ticket: Ticket) {
         if let Some(vec) = self.tickets.get_mut(&chunk) {
             vec_remove_item(vec, &ticket);
         }
         vec_remove_item(self.by_entity.get_mut(&ticket).unwrap(), &chunk);
     }
 
-    pub fn num_tickets(&self, chunk: ChunkPosition) -> usize {
+    pub fn num_tickets(&self, chunk: DimensionChunk) -> usize {
         match self.tickets.get(&chunk) {
             Some(vec) => vec.len(),
             None => 0,
         }
     }
 
-    pub fn take_entity_tickets(&mut self, ticket: Ticket) -> Vec<ChunkPosition> {
+    pub fn take_entity_tickets(&mut self, ticket: Ticket) -> Vec<DimensionChunk> {
         self.by_entity
             .get_mut(&ticket)
             .map(mem::take)
             .unwrap_or_default()
     }
 
-    pub fn remove_chunk(&mut self, pos: ChunkPosition) {
+    pub fn remove_chunk(&mut self, pos: DimensionChunk) {
         self.tickets.remove(&pos);
     }
 }
@@ -116,28 +122,83 @@ impl ChunkTickets {
 struct Ticket(Entity);
 
 /// System to populate chunk tickets based on players' views.
+///
+/// Players without a `DimensionId` component are in the overworld.
 fn update_tickets_for_players(game: &mut Game, state: &mut ChunkLoadState) -> SysResult {
-    for (player, event) in game.ecs.query::<&ViewUpdateEvent>().iter() {
+    let mut to_load = Vec::new();
+    for (player, (event, dimension)) in game
+        .ecs
+        .query::<(&ViewUpdateEvent, Option<&DimensionId>)>()
+        .iter()
+    {
         let player_ticket = Ticket(player);
+        let dimension = dimension.copied().unwrap_or(DimensionId::OVERWORLD);
 
         // Remove old tickets
         for &old_chunk in &event.old_chunks {
-            state.remove_ticket(old_chunk, player_ticket);
+            state.remove_ticket((dimension, old_chunk), player_ticket);
         }
 
         // Create new tickets
         for &new_chunk in &event.new_chunks {
-            state.chunk_tickets.insert_ticket(new_chunk, player_ticket);
+            state
+                .chunk_tickets
+                .insert_ticket((dimension, new_chunk), player_ticket);
+            to_load.push((dimension, new_chunk));
+        }
+    }
 
-            // Load if needed
-            if !game.world.is_chunk_loaded(new_chunk) && !game.world.is_chunk_loading(new_chunk) {
-                game.world.queue_chunk_load(new_chunk);
-            }
+    // Load if needed
+    queue_chunk_loads(game, to_load);
+    Ok(())
+}
+
+/// System to move the tickets of players who changed
+/// dimensions into their new dimension.
+fn move_tickets_on_dimension_change(game: &mut Game, state: &mut ChunkLoadState) -> SysResult {
+    let mut to_load = Vec::new();
+    for (player, (event, &view)) in game.ecs.query::<(&TeleportEvent, &View)>().iter() {
+        if !event.changed_dimension() {
+            continue;
+        }
+
+        let player_ticket = Ticket(player);
+        for chunk in state.chunk_tickets.take_entity_tickets(player_ticket) {
+            state.remove_ticket(chunk, player_ticket);
+        }
+        for chunk in view.iter() {
+            state
+                .chunk_tickets
+                .insert_ticket((event.dimension, chunk), player_ticket);
+            to_load.push((event.dimension, chunk));
         }
     }
+
+    queue_chunk_loads(game, to_load);
     Ok(())
 }
 
+/// Queues the given chunks to be loaded if they
+/// aren't loaded or loading already.
+fn queue_chunk_loads(game: &mut Game, chunks: Vec<DimensionChunk>) {
+    for (dimension, chunk) in chunks {
+        let world = match game.dimensions.world_mut(dimension) {
+            Some(world) => world,
+            None => {
+                log::warn!(
+                    "Cannot load chunk {:?} in unregistered {}",
+                    chunk,
+                    dimension
+                );
+                continue;
+            }
+        };
+        if !world.is_chunk_loaded(chunk) && !world.is_chunk_loading(chunk) {
+            world.queue_chunk_load(chunk);
+        }
+    }
+}
+
 /// System to unload chunks from the `ChunkUnloadQueue`.
 fn unload_chunks(game: &mut Game, state: &mut ChunkLoadState) -> SysResult {
     while let Some(&unload) = state.chunk_unload_queue.get(0) {
@@ -155,7 +216,11 @@ fn unload_chunks(game: &mut Game, state: &mut ChunkLoadState) -> SysResult {
             continue;
         }
 
-        game.world.unload_chunk(unload.pos);
+        // The dimension may have been unregistered since.
+        let (dimension, pos) = unload.pos;
+        if let Some(world) = game.dimensions.world_mut(dimension) {
+            world.unload_chunk(pos);
+        }
     }
     Ok(())
 }
@@ -170,8 +235,9 @@ fn remove_dead_entities(game: &mut Game, state: &mut ChunkLoadState) -> SysResul
     Ok(())
 }
 
-/// System to call `World::load_chunks` each tick
+/// System to call `World::load_chunks` on the world
+/// of each dimension every tick
 fn load_chunks(game: &mut Game, _state: &mut ChunkLoadState) -> SysResult {
-    game.world.load_chunks(&mut game.ecs);
+    game.dimensions.load_chunks(&mut game.ecs);
     Ok(())
 }
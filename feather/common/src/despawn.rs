@@ -0,0 +1,120 @@
+//! Despawning of mobs far away from players.
+
+use base::Position;
+use ecs::SysResult;
+use quill_common::{components::CustomName, entities::Player};
+use rand::Rng;
+
+use crate::{
+    entity_systems::{EntitySystemStage, EntitySystems},
+    Game,
+};
+
+/// Distance from the nearest player beyond which
+/// mobs are despawned immediately.
+pub const HARD_DESPAWN_DISTANCE: f64 = 128.0;
+/// Distance from the nearest player beyond which
+/// mobs may despawn at random.
+pub const SOFT_DESPAWN_DISTANCE: f64 = 32.0;
+/// Chance per tick that a mob beyond the soft
+/// distance is despawned.
+const RANDOM_DESPAWN_CHANCE: f64 = 1.0 / 800.0;
+
+pub fn register(systems: &mut EntitySystems) {
+    systems.register_entity_system(EntitySystemStage::Effects, despawn_distant_mobs);
+}
+
+/// Component for entities which despawn when
+/// far away from all players.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DespawnRulesComponent {
+    /// Whether the entity never despawns,
+    /// e.g. because it is named or persistent.
+    pub immune: bool,
+}
+
+impl DespawnRulesComponent {
+    /// Rules for an entity which never despawns.
+    pub fn immune() -> Self {
+        Self { immune: true }
+    }
+}
+
+/// Despawns mobs beyond the hard distance from the nearest
+/// player, and randomly despawns those beyond the soft distance.
+///
+/// Mobs with a custom name never despawn. Nothing is
+/// despawned while no players are online.
+fn despawn_distant_mobs(game: &mut Game) -> SysResult {
+    let players: Vec<Position> = game
+        .ecs
+        .query::<(&Position, &Player)>()
+        .iter()
+        .map(|(_, (&position, _))| position)
+        .collect();
+    if players.is_empty() {
+        return Ok(());
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut despawned = Vec::new();
+    for (entity, (&position, rules, custom_name)) in game
+        .ecs
+        .query::<(&Position, &DespawnRulesComponent, Option<&CustomName>)>()
+        .iter()
+    {
+        if rules.immune || custom_name.is_some() {
+            continue;
+        }
+
+        let distance_squared = players
+            .iter()
+            .map(|player| player.distance_squared_to(position))
+            .fold(f64::INFINITY, f64::min);
+        if distance_squared > HARD_DESPAWN_DISTANCE * HARD_DESPAWN_DISTANCE
+            || (distance_squared > SOFT_DESPAWN_DISTANCE * SOFT_DESPAWN_DISTANCE
+                && rng.gen_bool(RANDOM_DESPAWN_CHANCE))
+        {
+            despawned.push(entity);
+        }
+    }
+
+    for entity in despawned {
+        game.remove_entity(entity)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+    use crate::events::EntityRemoveEvent;
+
+    #[test]
+    fn distant_mobs_despawn() {
+        let mut game = Game::new();
+        game.ecs.spawn((position!(0.0, 64.0, 0.0), Player));
+
+        let mob = |game: &mut Game, x: f64, rules: DespawnRulesComponent| {
+            game.ecs.spawn((position!(x, 64.0, 0.0), rules))
+        };
+        let near = mob(&mut game, 10.0, DespawnRulesComponent::default());
+        let far = mob(&mut game, 200.0, DespawnRulesComponent::default());
+        let far_immune = mob(&mut game, 200.0, DespawnRulesComponent::immune());
+        let far_named = game.ecs.spawn((
+            position!(200.0, 64.0, 0.0),
+            DespawnRulesComponent::default(),
+            CustomName::new("Steve"),
+        ));
+
+        despawn_distant_mobs(&mut game).unwrap();
+
+        let removed = |entity| game.ecs.get::<EntityRemoveEvent>(entity).is_ok();
+        assert!(!removed(near));
+        assert!(removed(far));
+        assert!(!removed(far_immune));
+        assert!(!removed(far_named));
+    }
+}
@@ -0,0 +1,219 @@
+//! The registry of dimensions, mapping each dimension ID to
+//! its `World` and the generator of its missing chunks.
+//!
+//! The overworld, the Nether, and the End are registered by
+//! default. Servers and plugins may register more dimensions,
+//! or replace the default ones, through [`Game::dimensions`](crate::Game::dimensions).
+
+use std::{collections::BTreeMap, fmt, sync::Arc};
+
+use ecs::Ecs;
+use libcraft_core::Dimension;
+use worldgen::WorldGenerator;
+
+use crate::World;
+
+/// Identifies a dimension in the [`Dimensions`] registry.
+///
+/// The vanilla dimensions keep their vanilla IDs,
+/// and custom dimensions may use any other ID.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DimensionId(pub i32);
+
+impl DimensionId {
+    pub const OVERWORLD: DimensionId = DimensionId(0);
+    pub const THE_NETHER: DimensionId = DimensionId(-1);
+    pub const THE_END: DimensionId = DimensionId(1);
+}
+
+impl From<Dimension> for DimensionId {
+    fn from(dimension: Dimension) -> Self {
+        DimensionId(dimension.id())
+    }
+}
+
+impl fmt::Display for DimensionId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "dimension {}", self.0)
+    }
+}
+
+/// Describes a registered dimension.
+#[derive(Clone)]
+pub struct DimensionInfo {
+    /// Name of the dimension's world, as sent to clients.
+    pub world_name: String,
+    /// The vanilla dimension whose sky, lighting,
+    /// and height clients render this dimension with.
+    pub kind: Dimension,
+    /// The generator of chunks missing from the world save,
+    /// if the dimension has one.
+    pub generator: Option<Arc<dyn WorldGenerator>>,
+}
+
+impl DimensionInfo {
+    pub fn new(world_name: impl Into<String>, kind: Dimension) -> Self {
+        Self {
+            world_name: world_name.into(),
+            kind,
+            generator: None,
+        }
+    }
+
+    /// Sets the generator of the dimension.
+    pub fn with_generator(mut self, generator: Arc<dyn WorldGenerator>) -> Self {
+        self.generator = Some(generator);
+        self
+    }
+}
+
+impl fmt::Debug for DimensionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DimensionInfo")
+            .field("world_name", &self.world_name)
+            .field("kind", &self.kind)
+            .field("generator", &self.generator.is_some())
+            .finish()
+    }
+}
+
+/// Maps dimension IDs to their worlds.
+pub struct Dimensions {
+    dimensions: BTreeMap<DimensionId, (DimensionInfo, World)>,
+}
+
+impl Default for Dimensions {
+    /// Creates a registry containing the vanilla
+    /// dimensions, each with an empty world.
+    fn default() -> Self {
+        let mut dimensions = Self::empty();
+        for &(kind, world_name) in &[
+            (Dimension::Overworld, "world"),
+            (Dimension::TheNether, "world_nether"),
+            (Dimension::TheEnd, "world_the_end"),
+        ] {
+            dimensions.register(
+                kind.into(),
+                DimensionInfo::new(world_name, kind),
+                World::new(),
+            );
+        }
+        dimensions
+    }
+}
+
+impl Dimensions {
+    /// Creates a registry containing no dimensions.
+    pub fn empty() -> Self {
+        Self {
+            dimensions: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a dimension, replacing any
+    /// dimension previously registered with `id`.
+    pub fn register(&mut self, id: DimensionId, info: DimensionInfo, world: World) {
+        log::debug!("Registering {} ({})", id, info.world_name);
+        self.dimensions.insert(id, (info, world.with_dimension(id)));
+    }
+
+    /// Removes a dimension, returning its world.
+    pub fn unregister(&mut self, id: DimensionId) -> Option<World> {
+        self.dimensions.remove(&id).map(|(_, world)| world)
+    }
+
+    /// Determines whether a dimension is registered with `id`.
+    pub fn contains(&self, id: DimensionId) -> bool {
+        self.dimensions.contains_key(&id)
+    }
+
+    /// Returns the IDs of the registered dimensions, in ascending order.
+    pub fn ids(&self) -> impl Iterator<Item = DimensionId> + '_ {
+        self.dimensions.keys().copied()
+    }
+
+    /// Returns the description of a dimension.
+    pub fn info(&self, id: DimensionId) -> Option<&DimensionInfo> {
+        self.dimensions.get(&id).map(|(info, _)| info)
+    }
+
+    /// Returns the world of a dimension.
+    pub fn world(&self, id: DimensionId) -> Option<&World> {
+        self.dimensions.get(&id).map(|(_, world)| world)
+    }
+
+    /// Mutably gets the world of a dimension.
+    pub fn world_mut(&mut self, id: DimensionId) -> Option<&mut World> {
+        self.dimensions.get_mut(&id).map(|(_, world)| world)
+    }
+
+    /// Returns the generator of a dimension, if it has one.
+    pub fn generator(&self, id: DimensionId) -> Option<&Arc<dyn WorldGenerator>> {
+        self.info(id).and_then(|info| info.generator.as_ref())
+    }
+
+    /// Returns the world names of all dimensions, sent to clients
+    /// when they join.
+    pub fn world_names(&self) -> Vec<String> {
+        self.dimensions
+            .values()
+            .map(|(info, _)| info.world_name.clone())
+            .collect()
+    }
+
+    /// Calls `World::load_chunks` on the world of each dimension.
+    pub fn load_chunks(&mut self, ecs: &mut Ecs) {
+        for (_, world) in self.dimensions.values_mut() {
+            world.load_chunks(ecs);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vanilla_dimensions_are_registered() {
+        let dimensions = Dimensions::default();
+        assert_eq!(
+            dimensions.ids().collect::<Vec<_>>(),
+            vec![
+                DimensionId::THE_NETHER,
+                DimensionId::OVERWORLD,
+                DimensionId::THE_END
+            ]
+        );
+        assert_eq!(DimensionId::from(Dimension::TheNether), DimensionId(-1));
+        let nether = dimensions.info(DimensionId::THE_NETHER).unwrap();
+        assert_eq!(nether.world_name, "world_nether");
+        assert_eq!(nether.kind, Dimension::TheNether);
+        assert_eq!(
+            dimensions.world(DimensionId::THE_END).unwrap().dimension(),
+            DimensionId::THE_END
+        );
+    }
+
+    #[test]
+    fn custom_dimensions_can_be_registered() {
+        let mut dimensions = Dimensions::default();
+        let mining = DimensionId(7);
+        assert!(!dimensions.contains(mining));
+
+        dimensions.register(
+            mining,
+            DimensionInfo::new("world_mining", Dimension::Overworld),
+            World::new(),
+        );
+        assert!(dimensions.contains(mining));
+        assert_eq!(dimensions.world(mining).unwrap().dimension(), mining);
+        assert!(dimensions.generator(mining).is_none());
+        assert_eq!(
+            dimensions.world_names(),
+            vec!["world_nether", "world", "world_the_end", "world_mining"]
+        );
+
+        assert!(dimensions.unregister(mining).is_some());
+        assert!(dimensions.world(mining).is_none());
+    }
+}
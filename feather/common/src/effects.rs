@@ -0,0 +1,256 @@
+//! Status effects such as speed, poison, and regeneration.
+
+use ecs::{Entity, RefMut, SysResult};
+use uuid::Uuid;
+
+use crate::{
+    attributes::{Attribute, AttributeComponent, AttributeModifier, Operation},
+    entity_systems::{EntitySystemStage, EntitySystems},
+    events::{EffectChange, EffectChangeEvent},
+    health::{self, Health},
+    Game,
+};
+
+pub fn register(systems: &mut EntitySystems) {
+    systems.register_entity_system(EntitySystemStage::Effects, tick_effects);
+}
+
+/// A status effect, identified on the network by [`id`](Self::id).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StatusEffect {
+    Speed,
+    Slowness,
+    Regeneration,
+    Resistance,
+    FireResistance,
+    WaterBreathing,
+    Invisibility,
+    NightVision,
+    Poison,
+    Wither,
+}
+
+impl StatusEffect {
+    /// Returns the protocol ID of this effect.
+    pub fn id(self) -> u8 {
+        match self {
+            StatusEffect::Speed => 1,
+            StatusEffect::Slowness => 2,
+            StatusEffect::Regeneration => 10,
+            StatusEffect::Resistance => 11,
+            StatusEffect::FireResistance => 12,
+            StatusEffect::WaterBreathing => 13,
+            StatusEffect::Invisibility => 14,
+            StatusEffect::NightVision => 16,
+            StatusEffect::Poison => 19,
+            StatusEffect::Wither => 20,
+        }
+    }
+
+    /// Returns the number of ticks between applications of this
+    /// effect's per-tick consequence, or `None` if it has none.
+    fn interval(self, amplifier: i8) -> Option<u32> {
+        let base: u32 = match self {
+            StatusEffect::Regeneration => 50,
+            StatusEffect::Poison => 25,
+            StatusEffect::Wither => 40,
+            _ => return None,
+        };
+        Some((base >> amplifier.max(0)).max(1))
+    }
+
+    /// Returns the attribute modifier applied while
+    /// this effect is active, if any.
+    fn attribute_modifier(self, amplifier: i8) -> Option<(Attribute, AttributeModifier)> {
+        let level = amplifier as f64 + 1.0;
+        let (id, amount) = match self {
+            StatusEffect::Speed => (0x91aeaa56376b4498935b2f7f68070635, 0.2 * level),
+            StatusEffect::Slowness => (0x7107de5e7ce84030940e514c1f160890, -0.15 * level),
+            _ => return None,
+        };
+        let modifier = AttributeModifier {
+            id: Uuid::from_u128(id),
+            amount,
+            operation: Operation::MultiplyTotal,
+        };
+        Some((Attribute::MovementSpeed, modifier))
+    }
+}
+
+/// An effect applied to an entity.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ActiveEffect {
+    pub effect: StatusEffect,
+    /// The level of the effect minus one.
+    pub amplifier: i8,
+    /// Remaining duration in ticks.
+    pub duration: u32,
+}
+
+/// Component storing the effects applied to an entity.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EffectsComponent {
+    pub effects: Vec<ActiveEffect>,
+}
+
+impl EffectsComponent {
+    /// Returns the active instance of `effect`, if any.
+    pub fn get(&self, effect: StatusEffect) -> Option<&ActiveEffect> {
+        self.effects.iter().find(|active| active.effect == effect)
+    }
+}
+
+/// Applies `effect` to `entity`, replacing any active
+/// instance of the same effect.
+///
+/// Triggers an `EffectChangeEvent`.
+pub fn add_effect(game: &mut Game, entity: Entity, effect: ActiveEffect) -> SysResult {
+    if game.ecs.get::<EffectsComponent>(entity).is_err() {
+        game.ecs.insert(entity, EffectsComponent::default())?;
+    }
+    {
+        let mut effects = game.ecs.get_mut::<EffectsComponent>(entity)?;
+        effects
+            .effects
+            .retain(|active| active.effect != effect.effect);
+        effects.effects.push(effect);
+    }
+    if let (Some((attribute, modifier)), Ok(mut attributes)) = (
+        effect.effect.attribute_modifier(effect.amplifier),
+        game.ecs.get_mut::<AttributeComponent>(entity),
+    ) {
+        attributes.add_modifier(attribute, modifier);
+    }
+
+    change_event(game, entity)?
+        .changes
+        .push(EffectChange::Added(effect));
+    Ok(())
+}
+
+/// Removes `effect` from `entity`, if it is active.
+///
+/// Triggers an `EffectChangeEvent` if the effect was removed.
+pub fn remove_effect(game: &mut Game, entity: Entity, effect: StatusEffect) -> SysResult {
+    let removed = match game.ecs.get_mut::<EffectsComponent>(entity) {
+        Ok(mut effects) => {
+            let count = effects.effects.len();
+            effects.effects.retain(|active| active.effect != effect);
+            effects.effects.len() != count
+        }
+        Err(_) => false,
+    };
+
+    if removed {
+        if let (Some((attribute, modifier)), Ok(mut attributes)) = (
+            effect.attribute_modifier(0),
+            game.ecs.get_mut::<AttributeComponent>(entity),
+        ) {
+            attributes.remove_modifier(attribute, modifier.id);
+        }
+        change_event(game, entity)?
+            .changes
+            .push(EffectChange::Removed(effect));
+    }
+    Ok(())
+}
+
+/// Returns the `EffectChangeEvent` for `entity` in this tick,
+/// inserting an empty one if there is none yet.
+fn change_event(game: &mut Game, entity: Entity) -> anyhow::Result<RefMut<EffectChangeEvent>> {
+    if game.ecs.get::<EffectChangeEvent>(entity).is_err() {
+        game.ecs
+            .insert_entity_event(entity, EffectChangeEvent::default())?;
+    }
+    Ok(game.ecs.get_mut::<EffectChangeEvent>(entity)?)
+}
+
+/// Applies per-tick consequences of effects and
+/// counts down their durations, removing expired ones.
+fn tick_effects(game: &mut Game) -> SysResult {
+    let mut consequences = Vec::new();
+    let mut expired = Vec::new();
+    for (entity, effects) in game.ecs.query::<&mut EffectsComponent>().iter() {
+        for active in &mut effects.effects {
+            if let Some(interval) = active.effect.interval(active.amplifier) {
+                if active.duration % interval == 0 {
+                    consequences.push((entity, active.effect));
+                }
+            }
+            active.duration = active.duration.saturating_sub(1);
+            if active.duration == 0 {
+                expired.push((entity, active.effect));
+            }
+        }
+    }
+
+    for (entity, effect) in consequences {
+        apply_consequence(game, entity, effect)?;
+    }
+    for (entity, effect) in expired {
+        remove_effect(game, entity, effect)?;
+    }
+    Ok(())
+}
+
+fn apply_consequence(game: &mut Game, entity: Entity, effect: StatusEffect) -> SysResult {
+    let current = match game.ecs.get::<Health>(entity) {
+        Ok(health) => health.current,
+        Err(_) => return Ok(()),
+    };
+
+    match effect {
+        StatusEffect::Regeneration => game.ecs.get_mut::<Health>(entity)?.heal(1.0),
+        // Poison never kills.
+        // Effect damage bypasses the invulnerability window.
+        StatusEffect::Poison if current > 1.0 => {
+            health::damage_ignoring_resistance(game, entity, 1.0)?
+        }
+        StatusEffect::Wither => health::damage_ignoring_resistance(game, entity, 1.0)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poison_deals_periodic_damage_then_expires() {
+        let mut game = Game::new();
+        let entity = game.ecs.spawn((Health::new(20.0),));
+        let poison = ActiveEffect {
+            effect: StatusEffect::Poison,
+            amplifier: 0,
+            duration: 60,
+        };
+        add_effect(&mut game, entity, poison).unwrap();
+        assert_eq!(
+            game.ecs.get::<EffectChangeEvent>(entity).unwrap().changes,
+            vec![EffectChange::Added(poison)]
+        );
+
+        // Damage is dealt at remaining durations 50 and 25.
+        for _ in 0..11 {
+            tick_effects(&mut game).unwrap();
+        }
+        assert_eq!(game.ecs.get::<Health>(entity).unwrap().current, 19.0);
+        // Poison damage ignores the invulnerability
+        // window, which is never counted down here.
+        for _ in 11..60 {
+            tick_effects(&mut game).unwrap();
+        }
+        assert_eq!(game.ecs.get::<Health>(entity).unwrap().current, 18.0);
+
+        let effects = game.ecs.get::<EffectsComponent>(entity).unwrap();
+        assert!(effects.get(StatusEffect::Poison).is_none());
+        assert_eq!(
+            game.ecs.get::<EffectChangeEvent>(entity).unwrap().changes,
+            vec![
+                EffectChange::Added(poison),
+                EffectChange::Removed(StatusEffect::Poison)
+            ]
+        );
+    }
+}
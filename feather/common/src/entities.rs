@@ -4,15 +4,81 @@
 //! It should export a `build_default(&mut EntityBuilder)` function to
 //! add default components for that entity.
 
+use base::{metadata::Pose, EntityKind, Position, Vec3d};
 use ecs::EntityBuilder;
 use quill_common::{components::OnGround, entity_init::EntityInit};
 use uuid::Uuid;
+use vek::Aabb;
+
+use crate::attributes::AttributeComponent;
 
 /// Adds default components shared between all entities.
 fn build_default(builder: &mut EntityBuilder) {
     builder.add(Uuid::new_v4()).add(OnGround(true));
 }
 
+/// Implemented by entities whose bounding box scales
+/// with a size field, like slimes and phantoms.
+pub trait ScalableEntity {
+    /// The kind of this entity. Its default bounding box
+    /// is the one scaled by `scale_for_size`.
+    const KIND: EntityKind;
+
+    /// Returns the factor applied to the default
+    /// bounding box for an entity with the given size.
+    fn scale_for_size(size: u8) -> f32;
+
+    /// Returns the width, height, and depth of the bounding box
+    /// for an entity with the given size.
+    fn bbox_for_size(size: u8) -> (f32, f32, f32) {
+        scaled_bbox(Self::KIND, Self::scale_for_size(size))
+    }
+}
+
+/// Scales the default bounding box of `kind` by `scale`,
+/// returning its width, height, and depth.
+pub fn scaled_bbox(kind: EntityKind, scale: f32) -> (f32, f32, f32) {
+    let size = kind.bounding_box().size();
+    (
+        size.w as f32 * scale,
+        size.h as f32 * scale,
+        size.d as f32 * scale,
+    )
+}
+
+/// Returns whether the bounding boxes of two standing entities,
+/// given their kinds and positions, intersect.
+pub fn bboxes_intersect(a: (EntityKind, Position), b: (EntityKind, Position)) -> bool {
+    let a = world_bbox(a.0, a.1, Pose::Standing);
+    let b = world_bbox(b.0, b.1, Pose::Standing);
+    (0..3).all(|axis| a.min[axis] < b.max[axis] && b.min[axis] < a.max[axis])
+}
+
+/// Returns the width, height, and depth of an entity's
+/// bounding box in the given pose.
+pub fn pose_size(kind: EntityKind, pose: Pose) -> (f64, f64, f64) {
+    let size = kind.bounding_box().size();
+    match (kind, pose) {
+        (_, Pose::Sleeping) => (0.2, 0.2, 0.2),
+        (EntityKind::Player, Pose::Sneaking) => (size.w, 1.5, size.d),
+        (EntityKind::Player, Pose::Swimming)
+        | (EntityKind::Player, Pose::FallFlying)
+        | (EntityKind::Player, Pose::SpinAttack) => (size.w, 0.6, size.d),
+        _ => (size.w, size.h, size.d),
+    }
+}
+
+/// Returns an entity's bounding box in world space for
+/// the given pose, centered horizontally on its position.
+pub fn world_bbox(kind: EntityKind, position: Position, pose: Pose) -> Aabb<f64> {
+    let (w, h, d) = pose_size(kind, pose);
+    let min = Vec3d::new(position.x - w / 2.0, position.y, position.z - d / 2.0);
+    Aabb {
+        min,
+        max: min + Vec3d::new(w, h, d),
+    }
+}
+
 pub mod area_effect_cloud;
 pub mod armor_stand;
 pub mod arrow;
@@ -122,7 +188,57 @@ pub mod zombie_horse;
 pub mod zombie_villager;
 pub mod zombified_piglin;
 
+/// Returns whether entities of type `init` are living entities,
+/// which have attributes, as opposed to objects like items,
+/// projectiles and vehicles.
+pub fn is_living(init: &EntityInit) -> bool {
+    !matches!(
+        init,
+        EntityInit::AreaEffectCloud
+            | EntityInit::Arrow
+            | EntityInit::Boat
+            | EntityInit::DragonFireball
+            | EntityInit::EndCrystal
+            | EntityInit::EvokerFangs
+            | EntityInit::ExperienceOrb
+            | EntityInit::EyeOfEnder
+            | EntityInit::FallingBlock
+            | EntityInit::FireworkRocket
+            | EntityInit::Item
+            | EntityInit::ItemFrame
+            | EntityInit::Fireball
+            | EntityInit::LeashKnot
+            | EntityInit::LightningBolt
+            | EntityInit::LlamaSpit
+            | EntityInit::Minecart
+            | EntityInit::ChestMinecart
+            | EntityInit::CommandBlockMinecart
+            | EntityInit::FurnaceMinecart
+            | EntityInit::HopperMinecart
+            | EntityInit::SpawnerMinecart
+            | EntityInit::TntMinecart
+            | EntityInit::Painting
+            | EntityInit::Tnt
+            | EntityInit::ShulkerBullet
+            | EntityInit::SmallFireball
+            | EntityInit::Snowball
+            | EntityInit::SpectralArrow
+            | EntityInit::Egg
+            | EntityInit::EnderPearl
+            | EntityInit::ExperienceBottle
+            | EntityInit::Potion
+            | EntityInit::Trident
+            | EntityInit::WitherSkull
+            | EntityInit::FishingBobber
+    )
+}
+
 pub fn add_entity_components(builder: &mut EntityBuilder, init: &EntityInit) {
+    // Added first, so entities can override it with their own base values.
+    if is_living(init) {
+        builder.add(AttributeComponent::default());
+    }
+
     match init {
         EntityInit::AreaEffectCloud => area_effect_cloud::build_default(builder),
         EntityInit::ArmorStand => armor_stand::build_default(builder),
@@ -234,3 +350,54 @@ pub fn add_entity_components(builder: &mut EntityBuilder, init: &EntityInit) {
         EntityInit::FishingBobber => fishing_bobber::build_default(builder),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+    use quill_common::entities::{Phantom, Slime};
+
+    use super::*;
+    use crate::{attributes::Attribute, nether::STRIDER_MOVEMENT_SPEED, Game};
+
+    #[test]
+    fn scalable_bboxes_are_proportional() {
+        let (small_w, small_h, small_d) = Slime::bbox_for_size(1);
+        let (large_w, large_h, large_d) = Slime::bbox_for_size(4);
+        assert!((large_w / small_w - 4.0).abs() < 1e-5);
+        assert!((large_h / small_h - 4.0).abs() < 1e-5);
+        assert!((large_d / small_d - 4.0).abs() < 1e-5);
+
+        let (w, h, _) = Phantom::bbox_for_size(0);
+        let default = EntityKind::Phantom.bounding_box().size();
+        assert!((w - default.w as f32).abs() < 1e-5);
+        assert!((h - default.h as f32).abs() < 1e-5);
+        assert!(Phantom::bbox_for_size(10).0 > w);
+    }
+
+    #[test]
+    fn living_entities_spawn_with_attributes() {
+        let mut game = Game::new();
+        game.add_entity_spawn_callback(add_entity_components);
+        let mut spawn = |init| {
+            let builder = game.create_entity_builder(position!(0.0, 64.0, 0.0), init);
+            game.spawn_entity(builder)
+        };
+        let zombie = spawn(EntityInit::Zombie);
+        let player = spawn(EntityInit::Player);
+        let strider = spawn(EntityInit::Strider);
+        let arrow = spawn(EntityInit::Arrow);
+
+        let attributes = |entity| game.ecs.get::<AttributeComponent>(entity);
+        assert!(attributes(zombie).is_ok());
+        assert_eq!(
+            attributes(player).unwrap().value(Attribute::AttackDamage),
+            1.0
+        );
+        // Base values set by the entity are kept.
+        assert_eq!(
+            attributes(strider).unwrap().value(Attribute::MovementSpeed),
+            STRIDER_MOVEMENT_SPEED
+        );
+        assert!(attributes(arrow).is_err());
+    }
+}
@@ -2,7 +2,13 @@ use base::EntityKind;
 use ecs::EntityBuilder;
 use quill_common::entities::Arrow;
 
+use crate::physics::{Physics, Velocity};
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(Arrow).add(EntityKind::Arrow);
+    builder
+        .add(Arrow)
+        .add(EntityKind::Arrow)
+        .add(Velocity::default())
+        .add(Physics::for_projectile());
 }
@@ -2,7 +2,12 @@ use base::EntityKind;
 use ecs::EntityBuilder;
 use quill_common::entities::Creeper;
 
+use crate::despawn::DespawnRulesComponent;
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(Creeper).add(EntityKind::Creeper);
+    builder
+        .add(Creeper)
+        .add(EntityKind::Creeper)
+        .add(DespawnRulesComponent::default());
 }
@@ -2,7 +2,13 @@ use base::EntityKind;
 use ecs::EntityBuilder;
 use quill_common::entities::Dolphin;
 
+use crate::physics::{Physics, Velocity};
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(Dolphin).add(EntityKind::Dolphin);
+    builder
+        .add(Dolphin)
+        .add(EntityKind::Dolphin)
+        .add(Velocity::default())
+        .add(Physics::for_swimming());
 }
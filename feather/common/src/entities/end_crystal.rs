@@ -1,8 +1,83 @@
-use base::EntityKind;
+use base::{
+    metadata::{
+        META_INDEX_END_CRYSTAL_BEAM_TARGET, META_INDEX_END_CRYSTAL_SHOW_BOTTOM,
+        META_INDEX_NO_GRAVITY,
+    },
+    BlockPosition, EntityKind, EntityMetadata,
+};
 use ecs::EntityBuilder;
 use quill_common::entities::EndCrystal;
 
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(EndCrystal).add(EntityKind::EndCrystal);
+    let crystal = EndCrystalComponent::default();
+    let mut metadata = EntityMetadata::entity_base().with(META_INDEX_NO_GRAVITY, true);
+    crystal.write_metadata(&mut metadata);
+    builder
+        .add(EndCrystal)
+        .add(EntityKind::EndCrystal)
+        .add(crystal)
+        .add(metadata);
+}
+
+/// Component storing the state of an end crystal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EndCrystalComponent {
+    /// The block the crystal's beam points at, if any.
+    pub beam_target: Option<BlockPosition>,
+    /// Whether the bedrock base is shown.
+    pub show_bottom: bool,
+}
+
+impl Default for EndCrystalComponent {
+    fn default() -> Self {
+        Self {
+            beam_target: None,
+            show_bottom: true,
+        }
+    }
+}
+
+impl EndCrystalComponent {
+    /// Writes this crystal's state into `metadata`.
+    pub fn write_metadata(&self, metadata: &mut EntityMetadata) {
+        metadata.set(META_INDEX_END_CRYSTAL_BEAM_TARGET, self.beam_target);
+        metadata.set(META_INDEX_END_CRYSTAL_SHOW_BOTTOM, self.show_bottom);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::metadata::MetaEntry;
+
+    use super::*;
+
+    #[test]
+    fn beam_target_and_bottom_in_metadata() {
+        let mut metadata = EntityMetadata::entity_base();
+        EndCrystalComponent::default().write_metadata(&mut metadata);
+        assert_eq!(
+            metadata.get(META_INDEX_END_CRYSTAL_BEAM_TARGET),
+            Some(MetaEntry::OptPosition(None))
+        );
+        assert_eq!(
+            metadata.get(META_INDEX_END_CRYSTAL_SHOW_BOTTOM),
+            Some(MetaEntry::Boolean(true))
+        );
+
+        let target = BlockPosition::new(0, 128, 0);
+        EndCrystalComponent {
+            beam_target: Some(target),
+            show_bottom: false,
+        }
+        .write_metadata(&mut metadata);
+        assert_eq!(
+            metadata.get(META_INDEX_END_CRYSTAL_BEAM_TARGET),
+            Some(MetaEntry::OptPosition(Some(target)))
+        );
+        assert_eq!(
+            metadata.get(META_INDEX_END_CRYSTAL_SHOW_BOTTOM),
+            Some(MetaEntry::Boolean(false))
+        );
+    }
 }
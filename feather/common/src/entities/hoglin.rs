@@ -2,7 +2,13 @@ use base::EntityKind;
 use ecs::EntityBuilder;
 use quill_common::entities::Hoglin;
 
+use crate::physics::{Physics, Velocity};
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(Hoglin).add(EntityKind::Hoglin);
+    builder
+        .add(Hoglin)
+        .add(EntityKind::Hoglin)
+        .add(Velocity::default())
+        .add(Physics::for_living());
 }
@@ -2,7 +2,13 @@ use base::EntityKind;
 use ecs::EntityBuilder;
 use quill_common::entities::Item;
 
+use crate::physics::{Physics, Velocity};
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(Item).add(EntityKind::Item);
+    builder
+        .add(Item)
+        .add(EntityKind::Item)
+        .add(Velocity::default())
+        .add(Physics::for_item());
 }
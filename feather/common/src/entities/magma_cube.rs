@@ -2,7 +2,18 @@ use base::EntityKind;
 use ecs::EntityBuilder;
 use quill_common::entities::MagmaCube;
 
+use super::ScalableEntity;
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
     builder.add(MagmaCube).add(EntityKind::MagmaCube);
 }
+
+impl ScalableEntity for MagmaCube {
+    const KIND: EntityKind = EntityKind::MagmaCube;
+
+    /// A magma cube's bounding box grows linearly with its size (1, 2, or 4).
+    fn scale_for_size(size: u8) -> f32 {
+        0.255 * size as f32
+    }
+}
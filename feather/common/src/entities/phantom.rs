@@ -2,7 +2,27 @@ use base::EntityKind;
 use ecs::EntityBuilder;
 use quill_common::entities::Phantom;
 
+use super::ScalableEntity;
+use crate::{
+    despawn::DespawnRulesComponent,
+    physics::{Physics, Velocity},
+};
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(Phantom).add(EntityKind::Phantom);
+    builder
+        .add(Phantom)
+        .add(EntityKind::Phantom)
+        .add(Velocity::default())
+        .add(Physics::for_flying())
+        .add(DespawnRulesComponent::default());
+}
+
+impl ScalableEntity for Phantom {
+    const KIND: EntityKind = EntityKind::Phantom;
+
+    /// Each size level grows a phantom by 15% of its default size.
+    fn scale_for_size(size: u8) -> f32 {
+        1.0 + 0.15 * size as f32
+    }
 }
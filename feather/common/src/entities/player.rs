@@ -3,9 +3,16 @@ use base::EntityKind;
 use ecs::{EntityBuilder, SysResult};
 use quill_common::entities::Player;
 
+use crate::attributes::{Attribute, AttributeComponent};
+
+/// Attack damage of players without a weapon.
+const PLAYER_ATTACK_DAMAGE: f64 = 1.0;
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(Player).add(EntityKind::Player);
+    builder.add(Player).add(EntityKind::Player).add(
+        AttributeComponent::default().with_base(Attribute::AttackDamage, PLAYER_ATTACK_DAMAGE),
+    );
 }
 
 /// The hotbar slot a player's cursor is currently on
@@ -2,7 +2,12 @@ use base::EntityKind;
 use ecs::EntityBuilder;
 use quill_common::entities::Skeleton;
 
+use crate::despawn::DespawnRulesComponent;
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(Skeleton).add(EntityKind::Skeleton);
+    builder
+        .add(Skeleton)
+        .add(EntityKind::Skeleton)
+        .add(DespawnRulesComponent::default());
 }
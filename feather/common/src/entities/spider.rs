@@ -2,7 +2,12 @@ use base::EntityKind;
 use ecs::EntityBuilder;
 use quill_common::entities::Spider;
 
+use crate::despawn::DespawnRulesComponent;
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(Spider).add(EntityKind::Spider);
+    builder
+        .add(Spider)
+        .add(EntityKind::Spider)
+        .add(DespawnRulesComponent::default());
 }
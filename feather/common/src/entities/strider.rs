@@ -1,8 +1,23 @@
-use base::EntityKind;
+use base::{metadata::META_INDEX_STRIDER_SHIVERING, EntityKind, EntityMetadata};
 use ecs::EntityBuilder;
 use quill_common::entities::Strider;
 
+use crate::{
+    attributes::{Attribute, AttributeComponent},
+    nether::STRIDER_MOVEMENT_SPEED,
+    physics::{Physics, Velocity},
+};
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(Strider).add(EntityKind::Strider);
+    builder
+        .add(Strider)
+        .add(EntityKind::Strider)
+        .add(Velocity::default())
+        .add(Physics::for_lava_walking())
+        .add(
+            AttributeComponent::default()
+                .with_base(Attribute::MovementSpeed, STRIDER_MOVEMENT_SPEED),
+        )
+        .add(EntityMetadata::entity_base().with(META_INDEX_STRIDER_SHIVERING, false));
 }
@@ -2,7 +2,12 @@ use base::EntityKind;
 use ecs::EntityBuilder;
 use quill_common::entities::Zombie;
 
+use crate::despawn::DespawnRulesComponent;
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(Zombie).add(EntityKind::Zombie);
+    builder
+        .add(Zombie)
+        .add(EntityKind::Zombie)
+        .add(DespawnRulesComponent::default());
 }
@@ -0,0 +1,104 @@
+//! Ordering of per-tick entity systems.
+//!
+//! Entity systems declare an [`EntitySystemStage`] when registered.
+//! Within a tick, stages run in declaration order, and systems
+//! within a stage run in the order they were registered.
+
+use std::any::type_name;
+
+use ecs::{SysResult, SystemExecutor};
+
+use crate::Game;
+
+/// The stage of a tick in which an entity system runs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EntitySystemStage {
+    /// Applies velocity, gravity, and drag.
+    Physics,
+    /// Decides entity behavior based on the
+    /// positions resulting from physics.
+    Ai,
+    /// Applies effects and constraints such as
+    /// leads and beds on top of movement.
+    Effects,
+    /// Collects state changes to be sent to clients.
+    Sync,
+}
+
+type EntitySystemFn = Box<dyn FnMut(&mut Game) -> SysResult>;
+
+struct EntitySystem {
+    stage: EntitySystemStage,
+    function: EntitySystemFn,
+    name: &'static str,
+}
+
+/// Collects entity systems along with their stage,
+/// then adds them to a `SystemExecutor` in stage order.
+#[derive(Default)]
+pub struct EntitySystems {
+    systems: Vec<EntitySystem>,
+}
+
+impl EntitySystems {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an entity system to run during `stage`.
+    pub fn register_entity_system<F>(&mut self, stage: EntitySystemStage, system: F) -> &mut Self
+    where
+        F: FnMut(&mut Game) -> SysResult + 'static,
+    {
+        self.systems.push(EntitySystem {
+            stage,
+            function: Box::new(system),
+            name: type_name::<F>(),
+        });
+        self
+    }
+
+    /// Adds all registered systems to `systems`, ordered by stage.
+    pub fn register(mut self, systems: &mut SystemExecutor<Game>) {
+        // Stable sort, so registration order is kept within a stage.
+        self.systems.sort_by_key(|system| system.stage);
+        for system in self.systems {
+            systems.add_system_with_name(system.function, system.name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    #[test]
+    fn systems_run_in_stage_order() {
+        let order = Rc::new(RefCell::new(Vec::new()));
+        let mut entity_systems = EntitySystems::new();
+        for &(stage, label) in &[
+            (EntitySystemStage::Sync, "sync"),
+            (EntitySystemStage::Ai, "ai 1"),
+            (EntitySystemStage::Physics, "physics"),
+            (EntitySystemStage::Effects, "effects"),
+            (EntitySystemStage::Ai, "ai 2"),
+        ] {
+            let order = Rc::clone(&order);
+            entity_systems.register_entity_system(stage, move |_| {
+                order.borrow_mut().push(label);
+                Ok(())
+            });
+        }
+
+        let mut systems = SystemExecutor::new();
+        entity_systems.register(&mut systems);
+        systems.run(&mut Game::new());
+
+        assert_eq!(
+            *order.borrow(),
+            ["physics", "ai 1", "ai 2", "effects", "sync"]
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Attribute modifiers granted by equipped items:
+//! armor points from worn armor and attack damage
+//! from the weapon held in the main hand.
+
+use base::{Area, Inventory, Item, ItemStack};
+use ecs::SysResult;
+use uuid::Uuid;
+
+use crate::{
+    attributes::{Attribute, AttributeComponent, AttributeModifier, Operation},
+    entities::player::HotbarSlot,
+    entity_systems::{EntitySystemStage, EntitySystems},
+    Game,
+};
+
+/// Armor slots with the IDs of the modifiers granted by
+/// the items worn in them. These are the IDs vanilla's
+/// `ArmorItem` uses for each slot.
+const ARMOR_SLOTS: [(Area, Uuid); 4] = [
+    (
+        Area::Helmet,
+        Uuid::from_u128(0x2ad3f246_fee1_4e67_b886_69fd380bb150),
+    ),
+    (
+        Area::Chestplate,
+        Uuid::from_u128(0x9f3d476d_c118_4544_8365_64846904b48e),
+    ),
+    (
+        Area::Leggings,
+        Uuid::from_u128(0xd8499b04_0e66_4726_ab29_64469d734e0d),
+    ),
+    (
+        Area::Boots,
+        Uuid::from_u128(0x845db27c_c624_495f_8c9f_6020a9a58b6b),
+    ),
+];
+
+/// ID of the modifier granted by the item held in the main
+/// hand, vanilla's `Item.BASE_ATTACK_DAMAGE_UUID`.
+const MAIN_HAND_ID: Uuid = Uuid::from_u128(0xcb3f55d3_645c_4f38_a497_9c13a33db5cf);
+
+pub fn register(systems: &mut EntitySystems) {
+    systems.register_entity_system(EntitySystemStage::Effects, update_equipment_modifiers);
+}
+
+/// Returns the armor points granted by wearing `item`.
+pub fn armor_points(item: Item) -> Option<f64> {
+    let points = match item {
+        Item::LeatherHelmet | Item::LeatherBoots | Item::ChainmailBoots | Item::GoldenBoots => 1.0,
+        Item::ChainmailHelmet
+        | Item::IronHelmet
+        | Item::GoldenHelmet
+        | Item::TurtleHelmet
+        | Item::LeatherLeggings
+        | Item::IronBoots => 2.0,
+        Item::DiamondHelmet
+        | Item::NetheriteHelmet
+        | Item::LeatherChestplate
+        | Item::GoldenLeggings
+        | Item::DiamondBoots
+        | Item::NetheriteBoots => 3.0,
+        Item::ChainmailLeggings => 4.0,
+        Item::ChainmailChestplate | Item::GoldenChestplate | Item::IronLeggings => 5.0,
+        Item::IronChestplate | Item::DiamondLeggings | Item::NetheriteLeggings => 6.0,
+        Item::DiamondChestplate | Item::NetheriteChestplate => 8.0,
+        _ => return None,
+    };
+    Some(points)
+}
+
+/// Returns the attack damage added by holding `item`
+/// in the main hand.
+pub fn attack_damage_bonus(item: Item) -> Option<f64> {
+    let bonus = match item {
+        Item::WoodenSword | Item::GoldenSword => 3.0,
+        Item::StoneSword => 4.0,
+        Item::IronSword => 5.0,
+        Item::DiamondSword | Item::WoodenAxe | Item::GoldenAxe => 6.0,
+        Item::NetheriteSword => 7.0,
+        Item::StoneAxe | Item::IronAxe | Item::DiamondAxe => 8.0,
+        Item::NetheriteAxe => 9.0,
+        _ => return None,
+    };
+    Some(bonus)
+}
+
+/// Returns the item in a slot of `inventory`, if any.
+fn item_in(inventory: &Inventory, area: Area, slot: usize) -> Option<Item> {
+    let stack = inventory.item(area, slot)?;
+    (*stack).as_ref().map(ItemStack::item)
+}
+
+/// Adds the modifier granted by an equipped item, or
+/// removes it if the item grants none.
+fn equip(attributes: &mut AttributeComponent, attribute: Attribute, id: Uuid, amount: Option<f64>) {
+    match amount {
+        Some(amount) => attributes.add_modifier(
+            attribute,
+            AttributeModifier {
+                id,
+                amount,
+                operation: Operation::Add,
+            },
+        ),
+        None => {
+            attributes.remove_modifier(attribute, id);
+        }
+    }
+}
+
+/// Keeps the modifiers granted by equipment in sync
+/// with the items in the entity's inventory.
+fn update_equipment_modifiers(game: &mut Game) -> SysResult {
+    for (_, (inventory, hotbar_slot, attributes)) in game
+        .ecs
+        .query::<(&Inventory, &HotbarSlot, &mut AttributeComponent)>()
+        .iter()
+    {
+        for &(area, id) in &ARMOR_SLOTS {
+            let item = item_in(inventory, area, 0);
+            equip(
+                attributes,
+                Attribute::Armor,
+                id,
+                item.and_then(armor_points),
+            );
+        }
+
+        let held = item_in(inventory, Area::Hotbar, hotbar_slot.get());
+        equip(
+            attributes,
+            Attribute::AttackDamage,
+            MAIN_HAND_ID,
+            held.and_then(attack_damage_bonus),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equipment_modifiers_follow_inventory() {
+        let mut game = Game::new();
+        let inventory = Inventory::player();
+        let entity = game.ecs.spawn((
+            inventory.new_handle(),
+            HotbarSlot::new(1),
+            AttributeComponent::default().with_base(Attribute::AttackDamage, 1.0),
+        ));
+        let value = |game: &Game, attribute| {
+            game.ecs
+                .get::<AttributeComponent>(entity)
+                .unwrap()
+                .value(attribute)
+        };
+
+        *inventory.item(Area::Helmet, 0).unwrap() = Some(ItemStack::new(Item::DiamondHelmet, 1));
+        *inventory.item(Area::Chestplate, 0).unwrap() =
+            Some(ItemStack::new(Item::IronChestplate, 1));
+        *inventory.item(Area::Hotbar, 1).unwrap() = Some(ItemStack::new(Item::IronSword, 1));
+        update_equipment_modifiers(&mut game).unwrap();
+        assert_eq!(value(&game, Attribute::Armor), 9.0);
+        assert_eq!(value(&game, Attribute::AttackDamage), 6.0);
+
+        // Unequipping removes the modifiers again.
+        *inventory.item(Area::Helmet, 0).unwrap() = None;
+        game.ecs
+            .get_mut::<HotbarSlot>(entity)
+            .unwrap()
+            .set(0)
+            .unwrap();
+        update_equipment_modifiers(&mut game).unwrap();
+        assert_eq!(value(&game, Attribute::Armor), 6.0);
+        assert_eq!(value(&game, Attribute::AttackDamage), 1.0);
+    }
+}
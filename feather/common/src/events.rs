@@ -3,7 +3,11 @@ use std::sync::Arc;
 use base::{Chunk, ChunkPosition};
 use parking_lot::RwLock;
 
-use crate::view::View;
+use crate::{
+    dimensions::DimensionId,
+    effects::{ActiveEffect, StatusEffect},
+    view::View,
+};
 
 mod block_change;
 mod plugin_message;
@@ -58,6 +62,8 @@ pub struct ChunkCrossEvent {
 pub struct ChunkLoadEvent {
     pub position: ChunkPosition,
     pub chunk: Arc<RwLock<Chunk>>,
+    /// The dimension of the world the chunk was loaded into.
+    pub dimension: DimensionId,
 }
 
 /// Triggered when an error occurs while loading a chunk.
@@ -76,3 +82,56 @@ pub struct EntityRemoveEvent;
 /// Triggered when an entity is added into the world.
 #[derive(Debug)]
 pub struct EntityCreateEvent;
+
+/// Triggered when an entity is leashed to a new holder or unleashed.
+///
+/// The entity's current `LeashComponent` (if any) reflects the new state.
+#[derive(Debug)]
+pub struct LeashChangeEvent;
+
+/// Triggered when an entity falls asleep in or wakes up from a bed.
+///
+/// The entity's current `SleepingComponent` (if any) reflects the new state.
+#[derive(Debug)]
+pub struct SleepChangeEvent;
+
+/// Triggered when a player is teleported by a `TeleportTriggerComponent`.
+///
+/// The player's `Position` and `DimensionId` have already been updated;
+/// `dimension` is the destination dimension.
+#[derive(Debug)]
+pub struct TeleportEvent {
+    pub dimension: DimensionId,
+    /// The dimension the player was in before teleporting.
+    pub previous_dimension: DimensionId,
+}
+
+impl TeleportEvent {
+    /// Returns whether the player changed dimensions.
+    pub fn changed_dimension(&self) -> bool {
+        self.dimension != self.previous_dimension
+    }
+}
+
+/// Triggered when an entity takes damage.
+#[derive(Debug)]
+pub struct EntityDamageEvent {
+    /// The damage taken, after invulnerability was applied.
+    pub amount: f32,
+}
+
+/// Triggered when status effects are applied to
+/// or removed from an entity.
+#[derive(Debug, Default)]
+pub struct EffectChangeEvent {
+    /// Effects applied, removed or expired this tick,
+    /// in the order they happened.
+    pub changes: Vec<EffectChange>,
+}
+
+/// A change to an entity's status effects.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EffectChange {
+    Added(ActiveEffect),
+    Removed(StatusEffect),
+}
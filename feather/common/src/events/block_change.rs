@@ -6,6 +6,8 @@ use base::{
 };
 use itertools::Either;
 
+use crate::DimensionId;
+
 /// Event triggered when one or more blocks are changed.
 ///
 /// This event can efficiently store bulk block updates
@@ -13,25 +15,33 @@ use itertools::Either;
 /// is cheap as it is, at worst, cloning an `Arc`.
 #[derive(Debug, Clone)]
 pub struct BlockChangeEvent {
+    dimension: DimensionId,
     changes: BlockChanges,
 }
 
 impl BlockChangeEvent {
     /// Creates an event affecting a single block.
-    pub fn single(pos: BlockPosition) -> Self {
+    pub fn single(dimension: DimensionId, pos: BlockPosition) -> Self {
         Self {
+            dimension,
             changes: BlockChanges::Single { pos },
         }
     }
 
     /// Creates an event corresponding to a block update
     /// that fills an entire chunk section with the same block.
-    pub fn fill_chunk_section(chunk: ChunkPosition, section: u32) -> Self {
+    pub fn fill_chunk_section(dimension: DimensionId, chunk: ChunkPosition, section: u32) -> Self {
         Self {
+            dimension,
             changes: BlockChanges::FillChunkSection { chunk, section },
         }
     }
 
+    /// Returns the dimension in which the blocks were changed.
+    pub fn dimension(&self) -> DimensionId {
+        self.dimension
+    }
+
     /// Determines the number of blocks that were
     /// changed in this block change event.
     pub fn count(&self) -> usize {
@@ -99,7 +109,8 @@ mod tests {
     #[test]
     fn create_single() {
         let pos = BlockPosition::new(5, 64, 9);
-        let event = BlockChangeEvent::single(pos);
+        let event = BlockChangeEvent::single(DimensionId::THE_NETHER, pos);
+        assert_eq!(event.dimension(), DimensionId::THE_NETHER);
         assert_eq!(event.count(), 1);
         assert_eq!(event.iter_changed_blocks().collect::<Vec<_>>(), vec![pos]);
         assert_eq!(
@@ -112,7 +123,7 @@ mod tests {
     fn create_chunk_section_fill() {
         let chunk = ChunkPosition::new(10, 15);
         let section_y = 5;
-        let event = BlockChangeEvent::fill_chunk_section(chunk, section_y);
+        let event = BlockChangeEvent::fill_chunk_section(DimensionId::OVERWORLD, chunk, section_y);
         assert_eq!(event.count(), SECTION_VOLUME);
         assert_eq!(event.iter_changed_blocks().count(), SECTION_VOLUME);
         assert_eq!(
@@ -11,7 +11,7 @@ use crate::{
     chat::{ChatKind, ChatMessage},
     chunk_entities::ChunkEntities,
     events::{BlockChangeEvent, EntityCreateEvent, EntityRemoveEvent, PlayerJoinEvent},
-    ChatBox, World,
+    ChatBox, DimensionId, Dimensions, World,
 };
 
 type EntitySpawnCallback = Box<dyn FnMut(&mut EntityBuilder, &EntityInit)>;
@@ -19,7 +19,7 @@ type EntitySpawnCallback = Box<dyn FnMut(&mut EntityBuilder, &EntityInit)>;
 /// Stores the entire state of a Minecraft game.
 ///
 /// This contains:
-/// * A [`World`](base::World) containing chunks and blocks.
+/// * A [`Dimensions`] registry containing the worlds of all dimensions.
 /// * An [`Ecs`](ecs::Ecs) containing entities.
 /// * A [`Resources`](ecs::Resources) containing additional, user-defined data.
 /// * A [`SystemExecutor`] to run systems.
@@ -28,13 +28,15 @@ type EntitySpawnCallback = Box<dyn FnMut(&mut EntityBuilder, &EntityInit)>;
 /// as "drop item" or "kill entity." These high-level methods
 /// should be preferred over raw interaction with the ECS.
 pub struct Game {
-    /// Contains chunks and blocks.
+    /// Contains the worlds of all dimensions.
     ///
     /// NB: use methods on `Game` to update
     /// blocks, not direct methods on `World`.
     /// The `Game` methods will automatically
     /// trigger the necessary `BlockChangeEvent`s.
-    pub world: World,
+    pub dimensions: Dimensions,
+    /// The block above which players spawn in the overworld.
+    pub spawn: BlockPosition,
     /// Contains entities, including players.
     pub ecs: Ecs,
     /// Contains systems.
@@ -66,7 +68,8 @@ impl Game {
     /// Creates a new, empty `Game`.
     pub fn new() -> Self {
         Self {
-            world: World::new(),
+            dimensions: Dimensions::default(),
+            spawn: BlockPosition::default(),
             ecs: Ecs::new(),
             system_executor: Rc::new(RefCell::new(SystemExecutor::new())),
             resources: Arc::new(Resources::new()),
@@ -77,6 +80,47 @@ impl Game {
         }
     }
 
+    /// Returns the world for the given dimension.
+    ///
+    /// # Panics
+    /// Panics if no dimension is registered with `dimension`.
+    pub fn world_for(&self, dimension: DimensionId) -> &World {
+        self.dimensions
+            .world(dimension)
+            .unwrap_or_else(|| panic!("{} is not registered", dimension))
+    }
+
+    /// Mutably gets the world for the given dimension.
+    ///
+    /// # Panics
+    /// Panics if no dimension is registered with `dimension`.
+    pub fn world_for_mut(&mut self, dimension: DimensionId) -> &mut World {
+        self.dimensions
+            .world_mut(dimension)
+            .unwrap_or_else(|| panic!("{} is not registered", dimension))
+    }
+
+    /// Returns the world of the overworld, in which
+    /// players spawn.
+    pub fn overworld(&self) -> &World {
+        self.world_for(DimensionId::OVERWORLD)
+    }
+
+    /// Mutably gets the world of the overworld.
+    pub fn overworld_mut(&mut self) -> &mut World {
+        self.world_for_mut(DimensionId::OVERWORLD)
+    }
+
+    /// Returns the dimension `entity` is in.
+    ///
+    /// Entities without a `DimensionId` are in the overworld.
+    pub fn dimension_of(&self, entity: Entity) -> DimensionId {
+        self.ecs
+            .get::<DimensionId>(entity)
+            .map(|dimension| *dimension)
+            .unwrap_or(DimensionId::OVERWORLD)
+    }
+
     /// Inserts a new resource.
     ///
     /// An existing resource with type `T` is overriden.
@@ -112,9 +156,12 @@ impl Game {
 
     /// Creates an entity builder with the default components
     /// for an entity of type `init`.
+    ///
+    /// The entity is placed in the overworld; add another
+    /// `DimensionId` to the builder to spawn it elsewhere.
     pub fn create_entity_builder(&mut self, position: Position, init: EntityInit) -> EntityBuilder {
         let mut builder = mem::take(&mut self.entity_builder);
-        builder.add(position);
+        builder.add(position).add(DimensionId::OVERWORLD);
         self.invoke_entity_spawn_callbacks(&mut builder, init);
         builder
     }
@@ -180,43 +227,59 @@ impl Game {
         Ok(())
     }
 
-    /// Gets the block at the given position.
-    pub fn block(&self, pos: BlockPosition) -> Option<BlockId> {
-        self.world.block_at(pos)
+    /// Gets the block at the given position in `dimension`.
+    ///
+    /// Returns `None` if the dimension is not registered
+    /// or the chunk is not loaded.
+    pub fn block(&self, dimension: DimensionId, pos: BlockPosition) -> Option<BlockId> {
+        self.dimensions.world(dimension)?.block_at(pos)
     }
 
-    /// Sets the block at the given position.
+    /// Sets the block at the given position in `dimension`.
     ///
     /// Triggers necessary `BlockChangeEvent`s.
-    pub fn set_block(&mut self, pos: BlockPosition, block: BlockId) -> bool {
-        let was_successful = self.world.set_block_at(pos, block);
+    pub fn set_block(
+        &mut self,
+        dimension: DimensionId,
+        pos: BlockPosition,
+        block: BlockId,
+    ) -> bool {
+        let was_successful = match self.dimensions.world(dimension) {
+            Some(world) => world.set_block_at(pos, block),
+            None => return false,
+        };
         if was_successful {
-            self.ecs.insert_event(BlockChangeEvent::single(pos));
+            self.ecs
+                .insert_event(BlockChangeEvent::single(dimension, pos));
         }
         was_successful
     }
 
-    /// Fills the given chunk section (16x16x16 blocks).
+    /// Fills the given chunk section (16x16x16 blocks) in `dimension`.
     ///
     /// All blocks in the chunk section are overwritten with `block`.
     pub fn fill_chunk_section(
         &mut self,
+        dimension: DimensionId,
         chunk_pos: ChunkPosition,
         section_y: usize,
         block: BlockId,
     ) -> bool {
-        let mut chunk = match self.world.chunk_map().chunk_at_mut(chunk_pos) {
-            Some(chunk) => chunk,
+        let world = match self.dimensions.world(dimension) {
+            Some(world) => world,
+            None => return false,
+        };
+        let was_successful = match world.chunk_map().chunk_at_mut(chunk_pos) {
+            Some(mut chunk) => chunk.fill_section(section_y + 1, block),
             None => return false,
         };
-
-        let was_successful = chunk.fill_section(section_y + 1, block);
 
         if !was_successful {
             return false;
         }
 
         self.ecs.insert_event(BlockChangeEvent::fill_chunk_section(
+            dimension,
             chunk_pos,
             section_y as u32,
         ));
@@ -224,10 +287,10 @@ impl Game {
         true
     }
 
-    /// Breaks the block at the given position, propagating any
-    /// necessary block updates.
-    pub fn break_block(&mut self, pos: BlockPosition) -> bool {
-        self.set_block(pos, BlockId::air())
+    /// Breaks the block at the given position in `dimension`,
+    /// propagating any necessary block updates.
+    pub fn break_block(&mut self, dimension: DimensionId, pos: BlockPosition) -> bool {
+        self.set_block(dimension, pos, BlockId::air())
     }
 }
 
@@ -0,0 +1,276 @@
+//! Entity health and damage.
+
+use base::{metadata::Pose, BlockId, BlockPosition, EntityKind, Position, Vec3d};
+use blocks::BlockKind;
+use ecs::{Entity, SysResult};
+use vek::Aabb;
+
+use crate::{
+    entities::world_bbox,
+    entity_systems::{EntitySystemStage, EntitySystems},
+    events::EntityDamageEvent,
+    pose::PoseComponent,
+    DimensionId, Game, World,
+};
+
+/// Number of ticks after taking damage during which
+/// an entity ignores hits no stronger than the last one.
+pub const HURT_RESISTANT_TICKS: u32 = 10;
+
+pub fn register(systems: &mut EntitySystems) {
+    systems.register_entity_system(EntitySystemStage::Effects, damage_from_blocks);
+    systems.register_entity_system(EntitySystemStage::Effects, tick_hurt_resistance);
+}
+
+/// Component storing an entity's health.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+    /// Remaining ticks of invulnerability after the last hit.
+    pub hurt_resistant_ticks: u32,
+    /// The amount of the hit which started the current
+    /// invulnerability window.
+    pub last_damage: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            hurt_resistant_ticks: 0,
+            last_damage: 0.0,
+        }
+    }
+
+    /// Applies `amount` damage, respecting the invulnerability
+    /// window, and returns the damage actually taken.
+    ///
+    /// Within the window, only the portion of a hit exceeding
+    /// the last hit is taken, and the window is not restarted.
+    pub fn damage(&mut self, amount: f32) -> f32 {
+        let taken = if self.hurt_resistant_ticks > 0 {
+            if amount <= self.last_damage {
+                return 0.0;
+            }
+            amount - self.last_damage
+        } else {
+            self.hurt_resistant_ticks = HURT_RESISTANT_TICKS;
+            amount
+        };
+
+        self.last_damage = amount;
+        self.current = (self.current - taken).max(0.0);
+        taken
+    }
+
+    /// Applies `amount` damage regardless of the invulnerability
+    /// window, without starting one, and returns the damage taken.
+    pub fn damage_ignoring_resistance(&mut self, amount: f32) -> f32 {
+        let taken = amount.min(self.current);
+        self.current -= taken;
+        taken
+    }
+
+    /// Restores `amount` health, up to the maximum.
+    pub fn heal(&mut self, amount: f32) {
+        self.current = (self.current + amount).min(self.max);
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+}
+
+/// Damages `entity` by `amount`.
+///
+/// Triggers an `EntityDamageEvent` if any damage was taken.
+pub fn damage(game: &mut Game, entity: Entity, amount: f32) -> SysResult {
+    let taken = game.ecs.get_mut::<Health>(entity)?.damage(amount);
+    trigger_damage_event(game, entity, taken)
+}
+
+/// Damages `entity` by `amount`, ignoring its invulnerability window.
+///
+/// Triggers an `EntityDamageEvent` if any damage was taken.
+pub fn damage_ignoring_resistance(game: &mut Game, entity: Entity, amount: f32) -> SysResult {
+    let taken = game
+        .ecs
+        .get_mut::<Health>(entity)?
+        .damage_ignoring_resistance(amount);
+    trigger_damage_event(game, entity, taken)
+}
+
+fn trigger_damage_event(game: &mut Game, entity: Entity, taken: f32) -> SysResult {
+    if taken > 0.0 {
+        game.ecs
+            .insert_entity_event(entity, EntityDamageEvent { amount: taken })?;
+    }
+    Ok(())
+}
+
+/// Returns whether entities of the given kind are immune
+/// to fire, and so to the heat of magma blocks.
+pub fn is_fire_immune(kind: EntityKind) -> bool {
+    matches!(
+        kind,
+        EntityKind::Blaze
+            | EntityKind::EnderDragon
+            | EntityKind::Ghast
+            | EntityKind::MagmaCube
+            | EntityKind::Strider
+            | EntityKind::Wither
+            | EntityKind::WitherSkeleton
+            | EntityKind::Zoglin
+            | EntityKind::ZombifiedPiglin
+    )
+}
+
+/// Returns the damage dealt to an entity with bounding box
+/// `bbox` by the blocks it touches, if any.
+///
+/// Magma blocks only damage entities standing on them
+/// which aren't sneaking or fire immune. Cactuses and sweet
+/// berry bushes damage entities touching them from any side.
+pub fn block_contact_damage(
+    world: &World,
+    bbox: Aabb<f64>,
+    sneaking: bool,
+    fire_immune: bool,
+) -> Option<f32> {
+    // Slightly expanded so that blocks the entity is
+    // standing on or leaning against count as touched.
+    let touched = Aabb {
+        min: bbox.min - Vec3d::new(0.001, 0.001, 0.001),
+        max: bbox.max + Vec3d::new(0.001, 0.0, 0.001),
+    };
+    let feet_y = (bbox.min.y - 0.001).floor() as i32;
+
+    for x in touched.min.x.floor() as i32..=touched.max.x.floor() as i32 {
+        for y in touched.min.y.floor() as i32..=touched.max.y.floor() as i32 {
+            for z in touched.min.z.floor() as i32..=touched.max.z.floor() as i32 {
+                let block = world.block_at(BlockPosition::new(x, y, z));
+                let damage = match block.map(BlockId::kind) {
+                    Some(BlockKind::MagmaBlock) if y == feet_y && !sneaking && !fire_immune => 1.0,
+                    Some(BlockKind::Cactus) | Some(BlockKind::SweetBerryBush) => 1.0,
+                    _ => continue,
+                };
+                return Some(damage);
+            }
+        }
+    }
+    None
+}
+
+/// Damages entities touching harmful blocks.
+fn damage_from_blocks(game: &mut Game) -> SysResult {
+    let mut damaged = Vec::new();
+    for (entity, (&position, &kind, _, pose, dimension)) in game
+        .ecs
+        .query::<(
+            &Position,
+            &EntityKind,
+            &Health,
+            Option<&PoseComponent>,
+            Option<&DimensionId>,
+        )>()
+        .iter()
+    {
+        let dimension = dimension.copied().unwrap_or(DimensionId::OVERWORLD);
+        let world = match game.dimensions.world(dimension) {
+            Some(world) => world,
+            None => continue,
+        };
+        let pose = pose.map_or(Pose::Standing, |pose| pose.pose);
+        let bbox = world_bbox(kind, position, pose);
+        let sneaking = pose == Pose::Sneaking;
+        if let Some(amount) = block_contact_damage(world, bbox, sneaking, is_fire_immune(kind)) {
+            damaged.push((entity, amount));
+        }
+    }
+
+    for (entity, amount) in damaged {
+        damage(game, entity, amount)?;
+    }
+    Ok(())
+}
+
+/// Counts down invulnerability windows.
+fn tick_hurt_resistance(game: &mut Game) -> SysResult {
+    for (_, health) in game.ecs.query::<&mut Health>().iter() {
+        health.hurt_resistant_ticks = health.hurt_resistant_ticks.saturating_sub(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quick_hits_are_ignored() {
+        let mut game = Game::new();
+        let entity = game.ecs.spawn((Health::new(20.0),));
+
+        damage(&mut game, entity, 4.0).unwrap();
+        tick_hurt_resistance(&mut game).unwrap();
+        damage(&mut game, entity, 3.0).unwrap();
+        assert_eq!(game.ecs.get::<Health>(entity).unwrap().current, 16.0);
+
+        // A stronger hit only deals the difference.
+        damage(&mut game, entity, 6.0).unwrap();
+        assert_eq!(game.ecs.get::<Health>(entity).unwrap().current, 14.0);
+    }
+
+    #[test]
+    fn hits_after_window_apply() {
+        let mut game = Game::new();
+        let entity = game.ecs.spawn((Health::new(20.0),));
+
+        damage(&mut game, entity, 4.0).unwrap();
+        for _ in 0..HURT_RESISTANT_TICKS {
+            tick_hurt_resistance(&mut game).unwrap();
+        }
+        damage(&mut game, entity, 3.0).unwrap();
+
+        let health = *game.ecs.get::<Health>(entity).unwrap();
+        assert_eq!(health.current, 13.0);
+        assert_eq!(health.hurt_resistant_ticks, HURT_RESISTANT_TICKS);
+    }
+
+    #[test]
+    fn magma_block_deals_periodic_damage() {
+        use base::{position, Chunk, ChunkPosition};
+
+        let mut game = Game::new();
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        chunk.set_block_at(4, 63, 4, BlockId::magma_block());
+        game.overworld_mut().chunk_map_mut().insert_chunk(chunk);
+
+        let on_magma = game.ecs.spawn((
+            position!(4.5, 64.0, 4.5),
+            EntityKind::Zombie,
+            Health::new(20.0),
+        ));
+        let on_air = game.ecs.spawn((
+            position!(10.5, 64.0, 10.5),
+            EntityKind::Zombie,
+            Health::new(20.0),
+        ));
+        let strider = game.ecs.spawn((
+            position!(4.5, 64.0, 4.5),
+            EntityKind::Strider,
+            Health::new(20.0),
+        ));
+
+        // Damage is dealt once per invulnerability window.
+        for _ in 0..=2 * HURT_RESISTANT_TICKS {
+            damage_from_blocks(&mut game).unwrap();
+            tick_hurt_resistance(&mut game).unwrap();
+        }
+        assert_eq!(game.ecs.get::<Health>(on_magma).unwrap().current, 17.0);
+        assert_eq!(game.ecs.get::<Health>(on_air).unwrap().current, 20.0);
+        assert_eq!(game.ecs.get::<Health>(strider).unwrap().current, 20.0);
+    }
+}
@@ -0,0 +1,254 @@
+//! Leashes (leads) tying a mob to a fence or another entity.
+
+use base::{BlockPosition, Position};
+use ecs::{Entity, SysResult};
+use quill_common::entity_init::EntityInit;
+
+use crate::{
+    entity_systems::{EntitySystemStage, EntitySystems},
+    events::{EntityRemoveEvent, LeashChangeEvent},
+    Game,
+};
+
+/// Distance beyond which a leashed entity
+/// is pulled toward its holder.
+pub const PULL_DISTANCE: f64 = 6.0;
+
+/// Distance beyond which a lead snaps.
+pub const BREAK_DISTANCE: f64 = 10.0;
+
+/// Fraction of the excess distance a leashed
+/// entity is pulled each tick.
+const PULL_STRENGTH: f64 = 0.4;
+
+pub fn register(systems: &mut EntitySystems) {
+    systems.register_entity_system(EntitySystemStage::Effects, pull_leashed_entities);
+    systems.register_entity_system(EntitySystemStage::Effects, remove_knots_of_removed_entities);
+}
+
+/// What a leashed entity is tied to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LeashHolder {
+    /// Another entity, usually a player.
+    Entity(Entity),
+    /// A fence post.
+    Fence(BlockPosition),
+}
+
+/// Component for an entity tied to a [`LeashHolder`] by a lead.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LeashComponent {
+    pub holder: LeashHolder,
+    /// The leash knot entity spawned on the fence
+    /// for `LeashHolder::Fence` holders. Clients attach the
+    /// lead to this entity.
+    pub knot: Option<Entity>,
+}
+
+impl LeashComponent {
+    /// Returns the entity clients should render
+    /// the lead as attached to.
+    pub fn holding_entity(&self) -> Option<Entity> {
+        match self.holder {
+            LeashHolder::Entity(entity) => Some(entity),
+            LeashHolder::Fence(_) => self.knot,
+        }
+    }
+}
+
+/// Leashes `entity` to `holder`, replacing any existing lead.
+///
+/// Triggers a `LeashChangeEvent`.
+pub fn attach(game: &mut Game, entity: Entity, holder: LeashHolder) -> SysResult {
+    remove_leash(game, entity)?;
+
+    let knot = match holder {
+        LeashHolder::Fence(fence) => {
+            let dimension = game.dimension_of(entity);
+            let mut builder =
+                game.create_entity_builder(knot_position(fence), EntityInit::LeashKnot);
+            builder.add(dimension);
+            Some(game.spawn_entity(builder))
+        }
+        LeashHolder::Entity(_) => None,
+    };
+
+    game.ecs.insert(entity, LeashComponent { holder, knot })?;
+    game.ecs.insert_entity_event(entity, LeashChangeEvent)?;
+    Ok(())
+}
+
+/// Removes the lead from `entity`, if it has one.
+///
+/// Triggers a `LeashChangeEvent` if a lead was removed.
+pub fn detach(game: &mut Game, entity: Entity) -> SysResult {
+    if remove_leash(game, entity)? {
+        game.ecs.insert_entity_event(entity, LeashChangeEvent)?;
+    }
+    Ok(())
+}
+
+fn remove_leash(game: &mut Game, entity: Entity) -> anyhow::Result<bool> {
+    let leash = match game.ecs.remove::<LeashComponent>(entity) {
+        Ok(leash) => leash,
+        Err(_) => return Ok(false),
+    };
+    if let Some(knot) = leash.knot {
+        game.remove_entity(knot)?;
+    }
+    Ok(true)
+}
+
+/// Returns the position the lead is tied to.
+fn holder_position(game: &Game, holder: LeashHolder) -> Option<Position> {
+    match holder {
+        LeashHolder::Entity(entity) => game.ecs.get::<Position>(entity).ok().map(|pos| *pos),
+        LeashHolder::Fence(fence) => Some(knot_position(fence)),
+    }
+}
+
+fn knot_position(fence: BlockPosition) -> Position {
+    let mut pos = fence.position();
+    pos.x += 0.5;
+    pos.y += 0.5;
+    pos.z += 0.5;
+    pos
+}
+
+/// Removes the leash knots of removed leashed entities.
+fn remove_knots_of_removed_entities(game: &mut Game) -> SysResult {
+    let knots: Vec<Entity> = game
+        .ecs
+        .query::<(&EntityRemoveEvent, &LeashComponent)>()
+        .iter()
+        .filter_map(|(_, (_, leash))| leash.knot)
+        .collect();
+
+    for knot in knots {
+        // The knot may already be gone.
+        if game.ecs.entity(knot).is_ok() {
+            game.remove_entity(knot)?;
+        }
+    }
+    Ok(())
+}
+
+/// Pulls leashed entities toward their holder when
+/// the lead is stretched, and snaps leads stretched
+/// past `BREAK_DISTANCE`.
+fn pull_leashed_entities(game: &mut Game) -> SysResult {
+    let leashed: Vec<_> = game
+        .ecs
+        .query::<(&Position, &LeashComponent)>()
+        .iter()
+        .map(|(entity, (&position, leash))| (entity, position, leash.holder))
+        .collect();
+
+    for (entity, position, holder) in leashed {
+        let holder_pos = match holder_position(game, holder) {
+            Some(pos) => pos,
+            None => {
+                // Holder no longer exists
+                detach(game, entity)?;
+                continue;
+            }
+        };
+
+        let distance = position.distance_to(holder_pos);
+        if distance > BREAK_DISTANCE {
+            detach(game, entity)?;
+        } else if distance > PULL_DISTANCE {
+            let direction = (holder_pos.vec() - position.vec()) / distance;
+            let pull = direction * (distance - PULL_DISTANCE) * PULL_STRENGTH;
+            *game.ecs.get_mut::<Position>(entity)? = position + pull;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+
+    #[test]
+    fn attach_and_detach() {
+        let mut game = Game::new();
+        let holder = game.ecs.spawn((position!(0.0, 64.0, 0.0),));
+        let entity = game.ecs.spawn((position!(2.0, 64.0, 0.0),));
+
+        attach(&mut game, entity, LeashHolder::Entity(holder)).unwrap();
+        let leash = *game.ecs.get::<LeashComponent>(entity).unwrap();
+        assert_eq!(leash.holder, LeashHolder::Entity(holder));
+        assert_eq!(leash.holding_entity(), Some(holder));
+        assert!(game.ecs.get::<LeashChangeEvent>(entity).is_ok());
+
+        detach(&mut game, entity).unwrap();
+        assert!(game.ecs.get::<LeashComponent>(entity).is_err());
+    }
+
+    #[test]
+    fn fence_holder_spawns_knot() {
+        let mut game = Game::new();
+        let entity = game.ecs.spawn((position!(2.0, 64.0, 0.0),));
+        let fence = BlockPosition::new(0, 64, 0);
+
+        attach(&mut game, entity, LeashHolder::Fence(fence)).unwrap();
+        let leash = *game.ecs.get::<LeashComponent>(entity).unwrap();
+        let knot = leash.knot.unwrap();
+        assert_eq!(leash.holding_entity(), Some(knot));
+        assert_eq!(
+            *game.ecs.get::<Position>(knot).unwrap(),
+            knot_position(fence)
+        );
+    }
+
+    #[test]
+    fn knot_is_removed_with_entity() {
+        let mut game = Game::new();
+        let entity = game.ecs.spawn((position!(2.0, 64.0, 0.0),));
+        attach(
+            &mut game,
+            entity,
+            LeashHolder::Fence(BlockPosition::new(0, 64, 0)),
+        )
+        .unwrap();
+        let knot = game
+            .ecs
+            .get::<LeashComponent>(entity)
+            .unwrap()
+            .knot
+            .unwrap();
+
+        game.remove_entity(entity).unwrap();
+        remove_knots_of_removed_entities(&mut game).unwrap();
+        assert!(game.ecs.get::<EntityRemoveEvent>(knot).is_ok());
+    }
+
+    #[test]
+    fn stretched_lead_pulls_entity() {
+        let mut game = Game::new();
+        let holder = game.ecs.spawn((position!(0.0, 64.0, 0.0),));
+        let entity = game.ecs.spawn((position!(8.0, 64.0, 0.0),));
+        attach(&mut game, entity, LeashHolder::Entity(holder)).unwrap();
+
+        pull_leashed_entities(&mut game).unwrap();
+
+        let pos = *game.ecs.get::<Position>(entity).unwrap();
+        assert!(pos.x < 8.0 && pos.x > PULL_DISTANCE);
+    }
+
+    #[test]
+    fn overstretched_lead_snaps() {
+        let mut game = Game::new();
+        let holder = game.ecs.spawn((position!(0.0, 64.0, 0.0),));
+        let entity = game.ecs.spawn((position!(20.0, 64.0, 0.0),));
+        attach(&mut game, entity, LeashHolder::Entity(holder)).unwrap();
+
+        pull_leashed_entities(&mut game).unwrap();
+
+        assert!(game.ecs.get::<LeashComponent>(entity).is_err());
+    }
+}
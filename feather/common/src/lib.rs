@@ -24,22 +24,72 @@ pub mod world_source;
 pub mod world;
 pub use world::World;
 
+pub mod dimensions;
+pub use dimensions::{DimensionId, DimensionInfo, Dimensions};
+
 mod chunk_loading;
 
 mod chunk_entities;
 
+pub mod attributes;
+
 pub mod chat;
 pub use chat::ChatBox;
 
+pub mod despawn;
+
+pub mod effects;
+
 pub mod entities;
 
+pub mod entity_systems;
+use entity_systems::EntitySystems;
+
+pub mod equipment;
+
+pub mod health;
+
 pub mod interactable;
 
+pub mod leash;
+
+pub mod nether;
+
+pub mod physics;
+
+pub mod pose;
+
+pub mod pregen;
+
+pub mod sleeping;
+
+pub mod spawning;
+
+pub mod teleport;
+
+pub mod trader;
+
+pub mod villagers;
+
 /// Registers gameplay systems with the given `Game` and `SystemExecutor`.
 pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
     view::register(game, systems);
     chunk_loading::register(game, systems);
     chunk_entities::register(systems);
+
+    let mut entity_systems = EntitySystems::new();
+    physics::register(&mut entity_systems);
+    leash::register(&mut entity_systems);
+    nether::register(&mut entity_systems);
+    sleeping::register(&mut entity_systems);
+    health::register(&mut entity_systems);
+    effects::register(&mut entity_systems);
+    equipment::register(&mut entity_systems);
+    despawn::register(&mut entity_systems);
+    teleport::register(&mut entity_systems);
+    trader::register(&mut entity_systems);
+    entity_systems.register(systems);
+
     interactable::register(game);
 
     game.add_entity_spawn_callback(entities::add_entity_components);
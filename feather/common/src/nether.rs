@@ -0,0 +1,159 @@
+//! Behavior of nether mobs, such as striders
+//! shivering when out of lava and hoglins
+//! fleeing warped fungi.
+
+use base::{
+    metadata::META_INDEX_STRIDER_SHIVERING, BlockId, BlockPosition, EntityMetadata, Position, Vec3d,
+};
+use blocks::BlockKind;
+use ecs::SysResult;
+use quill_common::entities::{Hoglin, Strider};
+use uuid::Uuid;
+
+use crate::{
+    attributes::{Attribute, AttributeComponent, AttributeModifier, Operation},
+    entity_systems::{EntitySystemStage, EntitySystems},
+    physics::{is_lava, Velocity},
+    DimensionId, Game, World,
+};
+
+/// Movement speed of striders, in vanilla units.
+pub const STRIDER_MOVEMENT_SPEED: f64 = 0.175;
+
+/// Movement speed modifier applied to striders
+/// while they are out of lava.
+const SUFFOCATING_MODIFIER: AttributeModifier = AttributeModifier {
+    id: Uuid::from_u128(0x9e362924_01de_4ddd_a2b2_d0f7a405a174),
+    amount: -0.34,
+    operation: Operation::MultiplyBase,
+};
+
+/// Horizontal and vertical distances within which
+/// hoglins notice repellent blocks.
+const REPELLENT_RANGE: (i32, i32) = (8, 4);
+
+/// Speed at which hoglins flee repellent blocks,
+/// in blocks per tick.
+pub const HOGLIN_FLEE_SPEED: f64 = 0.15;
+
+pub fn register(systems: &mut EntitySystems) {
+    systems
+        .register_entity_system(EntitySystemStage::Ai, flee_hoglin_repellents)
+        .register_entity_system(EntitySystemStage::Effects, update_strider_shivering);
+}
+
+/// Returns whether an entity at `position` is in lava
+/// or standing on it.
+pub fn touches_lava(world: &World, position: Position) -> bool {
+    let feet = position.block();
+    is_lava(world, feet) || is_lava(world, BlockPosition::new(feet.x, feet.y - 1, feet.z))
+}
+
+/// Makes striders shiver while they are out of lava.
+fn update_strider_shivering(game: &mut Game) -> SysResult {
+    for (_, (&position, metadata, _, dimension)) in game
+        .ecs
+        .query::<(
+            &Position,
+            &mut EntityMetadata,
+            &Strider,
+            Option<&DimensionId>,
+        )>()
+        .iter()
+    {
+        let dimension = dimension.copied().unwrap_or(DimensionId::OVERWORLD);
+        let world = match game.dimensions.world(dimension) {
+            Some(world) => world,
+            None => continue,
+        };
+        metadata.set(META_INDEX_STRIDER_SHIVERING, !touches_lava(world, position));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{metadata::MetaEntry, position, Chunk, ChunkPosition};
+
+    use super::*;
+
+    #[test]
+    fn strider_shivers_out_of_lava() {
+        let mut game = Game::new();
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        chunk.set_block_at(2, 63, 2, BlockId::lava());
+        game.world_for_mut(DimensionId::THE_NETHER)
+            .chunk_map_mut()
+            .insert_chunk(chunk);
+
+        let metadata = EntityMetadata::entity_base().with(META_INDEX_STRIDER_SHIVERING, false);
+        let on_lava = game.ecs.spawn((
+            position!(2.5, 64.0, 2.5),
+            metadata.clone(),
+            Strider,
+            DimensionId::THE_NETHER,
+        ));
+        let on_land = game.ecs.spawn((
+            position!(8.5, 64.0, 8.5),
+            metadata.clone(),
+            Strider,
+            DimensionId::THE_NETHER,
+            AttributeComponent::default()
+                .with_base(Attribute::MovementSpeed, STRIDER_MOVEMENT_SPEED),
+        ));
+        // The overworld has no lava at this position.
+        let in_overworld = game
+            .ecs
+            .spawn((position!(2.5, 64.0, 2.5), metadata, Strider));
+
+        update_strider_shivering(&mut game).unwrap();
+
+        let shivering = |strider| {
+            game.ecs
+                .get::<EntityMetadata>(strider)
+                .unwrap()
+                .get(META_INDEX_STRIDER_SHIVERING)
+        };
+        assert_eq!(shivering(on_lava), Some(MetaEntry::Boolean(false)));
+        assert_eq!(shivering(on_land), Some(MetaEntry::Boolean(true)));
+        assert_eq!(shivering(in_overworld), Some(MetaEntry::Boolean(true)));
+
+        // Out of lava, striders are slowed down.
+        let speed = game
+            .ecs
+            .get::<AttributeComponent>(on_land)
+            .unwrap()
+            .value(Attribute::MovementSpeed);
+        assert!((speed - STRIDER_MOVEMENT_SPEED * 0.66).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hoglin_flees_warped_fungus() {
+        let mut game = Game::new();
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        chunk.set_block_at(4, 64, 8, BlockId::warped_fungus());
+        game.world_for_mut(DimensionId::THE_NETHER)
+            .chunk_map_mut()
+            .insert_chunk(chunk);
+
+        let near = game.ecs.spawn((
+            position!(8.5, 64.0, 8.5),
+            Velocity::default(),
+            Hoglin,
+            DimensionId::THE_NETHER,
+        ));
+        let far = game.ecs.spawn((
+            position!(14.5, 64.0, 8.5),
+            Velocity(Vec3d::new(0.1, 0.0, 0.0)),
+            Hoglin,
+            DimensionId::THE_NETHER,
+        ));
+
+        flee_hoglin_repellents(&mut game).unwrap();
+
+        let velocity = |hoglin| game.ecs.get::<Velocity>(hoglin).unwrap().0;
+        assert!((velocity(near).x - HOGLIN_FLEE_SPEED).abs() < 1e-9);
+        assert_eq!(velocity(near).z, 0.0);
+        assert_eq!(velocity(far), Vec3d::zero());
+    }
+}
@@ -0,0 +1,854 @@
+//! Basic entity physics: velocity, drag, and gravity,
+//! plus movement behaviors layered on top of them.
+//!
+//! Entities with continuous collision, such as projectiles,
+//! stop at the first solid block they hit and stay there.
+//! Entities with ground collision have their bounding box
+//! moved one axis at a time, stopping at solid blocks.
+
+use base::{metadata::Pose, BlockId, BlockPosition, EntityKind, Position, Vec3d};
+use blocks::BlockKind;
+use ecs::{Entity, SysResult};
+use vek::Aabb;
+
+use crate::{
+    entities::world_bbox,
+    entity_systems::{EntitySystemStage, EntitySystems},
+    pose::PoseComponent,
+    DimensionId, Game, World,
+};
+
+/// Velocity added each tick to entities in flowing water,
+/// in blocks per tick squared.
+pub const CURRENT_PUSH: f64 = 0.014;
+
+/// Upward speed of lava-walking entities submerged in lava,
+/// in blocks per tick.
+pub const LAVA_RISE_SPEED: f64 = 0.06;
+
+pub fn register(systems: &mut EntitySystems) {
+    systems.register_entity_system(EntitySystemStage::Physics, update_physics);
+}
+
+/// An entity's velocity, in blocks per tick.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Velocity(pub Vec3d);
+
+/// Component for entities with physics applied to them.
+///
+/// Physics will only be performed on entities with
+/// both this component and a [`Velocity`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Physics {
+    /// The drag coefficient for this entity. Each tick,
+    /// the entity's velocity will be multiplied by this amount
+    /// (so higher values cause less drag).
+    pub drag: f64,
+    /// Gravitational acceleration for this entity. Each tick,
+    /// this value will be added to the entity's Y speed.
+    ///
+    /// This value should generally be negative.
+    pub gravity: f64,
+    /// Swooping attack behavior, if enabled.
+    pub swoop: Option<Swoop>,
+    /// Direction of the water current the entity is in, if any.
+    /// The entity is pushed by `CURRENT_PUSH` in this direction each tick.
+    ///
+    /// Updated each tick from the water the entity is in.
+    pub current: Option<Vec3d>,
+    /// Whether to sweep the entity's bounding box along its
+    /// velocity each tick and stop it at the first solid block,
+    /// so that fast entities can't tunnel through thin walls.
+    pub continuous_collision: bool,
+    /// Whether the entity collides with solid blocks
+    /// instead of moving through them.
+    pub ground_collision: bool,
+    /// Whether the entity floats up through lava
+    /// and can stand on its surface, like a strider.
+    pub lava_walking: bool,
+    /// Whether gravity is ignored while the entity is in water.
+    pub buoyant: bool,
+    /// The block the entity is stuck in, if it hit one
+    /// with continuous collision. Stuck entities don't move
+    /// until the block is no longer solid.
+    pub stuck_in: Option<BlockPosition>,
+}
+
+impl Physics {
+    /// Returns physics settings for living entities.
+    pub fn for_living() -> Self {
+        Self {
+            drag: 0.98,
+            gravity: -0.08,
+            swoop: None,
+            current: None,
+            continuous_collision: false,
+            ground_collision: true,
+            lava_walking: false,
+            buoyant: false,
+            stuck_in: None,
+        }
+    }
+
+    /// Returns physics settings for flying entities,
+    /// which are unaffected by gravity.
+    pub fn for_flying() -> Self {
+        Self {
+            drag: 0.91,
+            gravity: 0.0,
+            swoop: None,
+            current: None,
+            continuous_collision: false,
+            ground_collision: false,
+            lava_walking: false,
+            buoyant: false,
+            stuck_in: None,
+        }
+    }
+
+    /// Returns physics settings for swimming entities,
+    /// which are buoyant and slowed by water.
+    /// Out of water, they fall like other living entities.
+    pub fn for_swimming() -> Self {
+        Self {
+            drag: 0.8,
+            buoyant: true,
+            ..Self::for_living()
+        }
+    }
+
+    /// Returns physics settings for living entities
+    /// which walk on lava, like striders. Out of lava,
+    /// they walk on the ground like other living entities.
+    pub fn for_lava_walking() -> Self {
+        Self {
+            lava_walking: true,
+            ..Self::for_living()
+        }
+    }
+
+    /// Returns physics settings for dropped items,
+    /// which fall slowly and land on the ground.
+    pub fn for_item() -> Self {
+        Self {
+            gravity: -0.04,
+            ..Self::for_living()
+        }
+    }
+
+    /// Returns physics settings for projectiles like arrows,
+    /// which use continuous collision.
+    pub fn for_projectile() -> Self {
+        Self {
+            drag: 0.99,
+            gravity: -0.05,
+            swoop: None,
+            current: None,
+            continuous_collision: true,
+            ground_collision: false,
+            lava_walking: false,
+            buoyant: false,
+            stuck_in: None,
+        }
+    }
+
+    /// Makes this entity repeatedly dive at `target`
+    /// and climb away again, like a phantom.
+    pub fn swoop(mut self, target: Entity) -> Self {
+        self.swoop = Some(Swoop::new(target));
+        self
+    }
+}
+
+/// The current phase of a [`Swoop`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SwoopPhase {
+    /// Descending toward the target.
+    Dive,
+    /// Ascending away from the target.
+    Climb,
+}
+
+/// Swooping attack behavior: the entity dives toward
+/// its target, then climbs until it is `climb_height` blocks
+/// above the target, then dives again.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Swoop {
+    pub target: Entity,
+    pub phase: SwoopPhase,
+    /// Height above the target at which the climb ends
+    /// and the next dive begins.
+    pub climb_height: f64,
+    /// Distance from the target at which the dive ends
+    /// and the climb begins.
+    pub dive_end_distance: f64,
+    /// Acceleration applied each tick, in blocks per tick squared.
+    pub acceleration: f64,
+}
+
+impl Swoop {
+    pub fn new(target: Entity) -> Self {
+        Self {
+            target,
+            phase: SwoopPhase::Dive,
+            climb_height: 16.0,
+            dive_end_distance: 1.5,
+            acceleration: 0.1,
+        }
+    }
+
+    /// Sets the dive and climb thresholds.
+    pub fn with_thresholds(mut self, climb_height: f64, dive_end_distance: f64) -> Self {
+        self.climb_height = climb_height;
+        self.dive_end_distance = dive_end_distance;
+        self
+    }
+
+    /// Updates the phase and steers `velocity` for one tick.
+    fn steer(&mut self, position: Position, target: Position, velocity: &mut Vec3d) {
+        match self.phase {
+            SwoopPhase::Dive => {
+                if position.distance_to(target) <= self.dive_end_distance || position.y <= target.y
+                {
+                    self.phase = SwoopPhase::Climb;
+                }
+            }
+            SwoopPhase::Climb => {
+                if position.y >= target.y + self.climb_height {
+                    self.phase = SwoopPhase::Dive;
+                }
+            }
+        }
+
+        match self.phase {
+            SwoopPhase::Dive => {
+                let offset = target.vec() - position.vec();
+                let distance = offset.magnitude();
+                if distance > 0.0 {
+                    *velocity += offset / distance * self.acceleration;
+                }
+            }
+            SwoopPhase::Climb => velocity.y += self.acceleration,
+        }
+    }
+}
+
+/// Returns the fraction of a timestep at which `start`, moving by
+/// `velocity` over the timestep, first contacts `obstacle`,
+/// or `None` if it doesn't within the timestep.
+///
+/// Boxes which already overlap contact at `0.0`. Boxes which
+/// only graze each other's faces don't contact.
+pub fn sweep(start: Aabb<f64>, velocity: Vec3d, obstacle: Aabb<f64>) -> Option<f32> {
+    let mut entry = f64::NEG_INFINITY;
+    let mut exit = f64::INFINITY;
+
+    for axis in 0..3 {
+        let v = velocity[axis];
+        if v == 0.0 {
+            if start.max[axis] <= obstacle.min[axis] || start.min[axis] >= obstacle.max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let (near, far) = if v > 0.0 {
+            (
+                obstacle.min[axis] - start.max[axis],
+                obstacle.max[axis] - start.min[axis],
+            )
+        } else {
+            (
+                obstacle.max[axis] - start.min[axis],
+                obstacle.min[axis] - start.max[axis],
+            )
+        };
+        entry = entry.max(near / v);
+        exit = exit.min(far / v);
+    }
+
+    if entry >= exit || entry > 1.0 || exit <= 0.0 {
+        None
+    } else {
+        Some(entry.max(0.0) as f32)
+    }
+}
+
+/// Returns the solid blocks which `bbox` could touch
+/// while moving by `motion`, along with their bounding boxes.
+fn solid_blocks_along(
+    world: &World,
+    bbox: Aabb<f64>,
+    motion: Vec3d,
+) -> impl Iterator<Item = (BlockPosition, Aabb<f64>)> + '_ {
+    let swept = bbox.union(Aabb {
+        min: bbox.min + motion,
+        max: bbox.max + motion,
+    });
+    let (min, max) = (
+        swept.min.map(|c| c.floor() as i32),
+        swept.max.map(|c| c.floor() as i32),
+    );
+
+    (min.x..=max.x)
+        .flat_map(move |x| {
+            (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| (x, y, z)))
+        })
+        .map(|(x, y, z)| BlockPosition::new(x, y, z))
+        .filter(move |&pos| world.block_at(pos).map_or(false, |block| block.is_solid()))
+        .map(|pos| {
+            let min = Vec3d::new(pos.x as f64, pos.y as f64, pos.z as f64);
+            (
+                pos,
+                Aabb {
+                    min,
+                    max: min + Vec3d::one(),
+                },
+            )
+        })
+}
+
+/// Returns the earliest fraction of the timestep at which `bbox`,
+/// moving by `motion`, contacts a solid block, along with that block.
+fn first_block_contact(
+    world: &World,
+    bbox: Aabb<f64>,
+    motion: Vec3d,
+) -> Option<(f32, BlockPosition)> {
+    solid_blocks_along(world, bbox, motion)
+        .filter_map(|(pos, block)| Some((sweep(bbox, motion, block)?, pos)))
+        .fold(
+            None,
+            |first: Option<(f32, BlockPosition)>, contact| match first {
+                Some(first) if first.0 <= contact.0 => Some(first),
+                _ => Some(contact),
+            },
+        )
+}
+
+/// Moves `bbox` by `motion` one axis at a time, Y first,
+/// stopping it at solid blocks. Returns the motion
+/// actually performed, and zeroes the components of
+/// `velocity` along the axes where a block was hit.
+///
+/// Blocks the box already overlaps are ignored,
+/// so that entities inside blocks can move out of them.
+fn collide(world: &World, mut bbox: Aabb<f64>, motion: Vec3d, velocity: &mut Vec3d) -> Vec3d {
+    let mut moved = Vec3d::zero();
+    for &axis in &[1, 0, 2] {
+        let mut distance = motion[axis];
+        if distance == 0.0 {
+            continue;
+        }
+
+        let mut step = Vec3d::zero();
+        step[axis] = distance;
+        for (_, block) in solid_blocks_along(world, bbox, step) {
+            let beside = (0..3).filter(|&other| other != axis).any(|other| {
+                bbox.max[other] <= block.min[other] || bbox.min[other] >= block.max[other]
+            });
+            if beside {
+                continue;
+            }
+            if distance > 0.0 && block.min[axis] >= bbox.max[axis] {
+                distance = distance.min(block.min[axis] - bbox.max[axis]);
+            } else if distance < 0.0 && block.max[axis] <= bbox.min[axis] {
+                distance = distance.max(block.max[axis] - bbox.min[axis]);
+            }
+        }
+
+        if distance != motion[axis] {
+            velocity[axis] = 0.0;
+        }
+        moved[axis] = distance;
+        bbox.min[axis] += distance;
+        bbox.max[axis] += distance;
+    }
+    moved
+}
+
+/// Returns whether the block at `pos` is lava.
+pub fn is_lava(world: &World, pos: BlockPosition) -> bool {
+    world.block_at(pos).map_or(false, BlockId::is_lava)
+}
+
+/// Returns the height of the water surface in the block
+/// at `pos`, as a fraction of the block, or `None` if
+/// the block isn't water.
+fn water_height(world: &World, pos: BlockPosition) -> Option<f64> {
+    let block = world.block_at(pos)?;
+    if block.kind() != BlockKind::Water {
+        return None;
+    }
+    // Levels 8 and above are falling water, which fills the block.
+    let level = block.water_level().unwrap_or(0);
+    Some(if level >= 8 {
+        1.0
+    } else {
+        (8 - level) as f64 / 9.0
+    })
+}
+
+/// Returns the direction in which the water at `pos` flows,
+/// or `None` if the block isn't flowing water.
+///
+/// Water flows toward neighbors with a lower surface,
+/// including empty neighbors, and falling water flows down.
+pub fn water_flow(world: &World, pos: BlockPosition) -> Option<Vec3d> {
+    let height = water_height(world, pos)?;
+    let mut flow = Vec3d::zero();
+    for &(dx, dz) in &[(1, 0), (-1, 0), (0, 1), (0, -1)] {
+        let neighbor = BlockPosition::new(pos.x + dx, pos.y, pos.z + dz);
+        let neighbor_height = match water_height(world, neighbor) {
+            Some(neighbor_height) => neighbor_height,
+            None if world
+                .block_at(neighbor)
+                .map_or(false, |block| !block.is_solid()) =>
+            {
+                0.0
+            }
+            None => continue,
+        };
+        flow += Vec3d::new(dx as f64, 0.0, dz as f64) * (height - neighbor_height);
+    }
+
+    let falling = world
+        .block_at(pos)
+        .and_then(BlockId::water_level)
+        .map_or(false, |level| level >= 8);
+    if falling {
+        flow.y -= 1.0;
+    }
+
+    if flow.magnitude() > 0.0 {
+        Some(flow)
+    } else {
+        None
+    }
+}
+
+/// Makes a lava-walking entity rise through lava,
+/// adjusting its `velocity` for this tick.
+fn float_in_lava(world: &World, position: Position, velocity: &mut Vec3d) {
+    if is_lava(world, position.block()) {
+        velocity.y = LAVA_RISE_SPEED;
+    }
+}
+
+/// Makes a falling lava-walking entity land exactly on top of
+/// the first lava block below its feet it would fall into
+/// this tick, adjusting its `velocity`.
+///
+/// Solid blocks are handled by [`collide`].
+fn land_on_lava(world: &World, position: Position, velocity: &mut Vec3d) {
+    if velocity.y >= 0.0 {
+        return;
+    }
+
+    let feet = position.block();
+    let target_y = position.y + velocity.y;
+    let mut y = feet.y - 1;
+    while (y + 1) as f64 > target_y {
+        match world.block_at(BlockPosition::new(feet.x, y, feet.z)) {
+            Some(block) if block.is_lava() => {
+                velocity.y = (y + 1) as f64 - position.y;
+                return;
+            }
+            Some(block) if block.is_solid() => return,
+            _ => y -= 1,
+        }
+    }
+}
+
+/// Applies velocity, gravity, and drag to entities.
+fn update_physics(game: &mut Game) -> SysResult {
+    let targets: Vec<(Entity, Position)> = game
+        .ecs
+        .query::<&Physics>()
+        .iter()
+        .filter_map(|(_, physics)| physics.swoop)
+        .filter_map(|swoop| {
+            let pos = *game.ecs.get::<Position>(swoop.target).ok()?;
+            Some((swoop.target, pos))
+        })
+        .collect();
+
+    for (_, (position, velocity, physics, kind, pose, dimension)) in game
+        .ecs
+        .query::<(
+            &mut Position,
+            &mut Velocity,
+            &mut Physics,
+            Option<&EntityKind>,
+            Option<&PoseComponent>,
+            Option<&DimensionId>,
+        )>()
+        .iter()
+    {
+        let world = game
+            .dimensions
+            .world(dimension.copied().unwrap_or(DimensionId::OVERWORLD));
+
+        if let Some(block) = physics.stuck_in {
+            let still_solid = world
+                .and_then(|world| world.block_at(block))
+                .map_or(false, |block| block.is_solid());
+            if still_solid {
+                continue;
+            }
+            physics.stuck_in = None;
+        }
+
+        if let Some(swoop) = &mut physics.swoop {
+            let target = targets
+                .iter()
+                .find(|(entity, _)| *entity == swoop.target)
+                .map(|(_, pos)| *pos);
+            match target {
+                Some(target) => swoop.steer(*position, target, &mut velocity.0),
+                None => physics.swoop = None, // Target no longer exists
+            }
+        }
+
+        physics.current = world.and_then(|world| water_flow(world, position.block()));
+        if let Some(current) = physics.current {
+            if current.magnitude() > 0.0 {
+                velocity.0 += current.normalized() * CURRENT_PUSH;
+            }
+        }
+
+        let in_water = world
+            .and_then(|world| water_height(world, position.block()))
+            .is_some();
+        if !(physics.buoyant && in_water) {
+            velocity.0.y += physics.gravity;
+        }
+
+        if let (Some(world), true) = (world, physics.lava_walking) {
+            float_in_lava(world, *position, &mut velocity.0);
+            land_on_lava(world, *position, &mut velocity.0);
+        }
+
+        let mut motion = velocity.0;
+        if let Some(world) = world {
+            // Entities without a kind collide as a point.
+            let bbox = match kind {
+                Some(&kind) => world_bbox(
+                    kind,
+                    *position,
+                    pose.map_or(Pose::Standing, |pose| pose.pose),
+                ),
+                None => Aabb {
+                    min: position.vec(),
+                    max: position.vec(),
+                },
+            };
+            if physics.continuous_collision {
+                if let Some((fraction, block)) = first_block_contact(world, bbox, motion) {
+                    motion *= fraction as f64;
+                    velocity.0 = Vec3d::zero();
+                    physics.stuck_in = Some(block);
+                }
+            } else if physics.ground_collision {
+                motion = collide(world, bbox, motion, &mut velocity.0);
+            }
+        }
+
+        *position = *position + motion;
+        velocity.0 *= physics.drag;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{position, Chunk, ChunkPosition};
+
+    use super::*;
+
+    fn spawn_phantom(game: &mut Game, target: Entity, pos: Position) -> Entity {
+        let physics = Physics::for_flying().swoop(target);
+        game.ecs.spawn((pos, Velocity::default(), physics))
+    }
+
+    #[test]
+    fn dive_accelerates_toward_target() {
+        let mut game = Game::new();
+        let target = game.ecs.spawn((position!(0.0, 64.0, 0.0),));
+        let phantom = spawn_phantom(&mut game, target, position!(10.0, 80.0, 0.0));
+
+        update_physics(&mut game).unwrap();
+
+        let velocity = game.ecs.get::<Velocity>(phantom).unwrap().0;
+        assert!(velocity.y < 0.0);
+        assert!(velocity.x < 0.0);
+        assert_eq!(
+            game.ecs
+                .get::<Physics>(phantom)
+                .unwrap()
+                .swoop
+                .unwrap()
+                .phase,
+            SwoopPhase::Dive
+        );
+    }
+
+    #[test]
+    fn climb_after_reaching_target() {
+        let mut game = Game::new();
+        let target = game.ecs.spawn((position!(0.0, 64.0, 0.0),));
+        let phantom = spawn_phantom(&mut game, target, position!(1.0, 64.5, 0.0));
+
+        update_physics(&mut game).unwrap();
+
+        let velocity = game.ecs.get::<Velocity>(phantom).unwrap().0;
+        assert!(velocity.y > 0.0);
+        let swoop = game.ecs.get::<Physics>(phantom).unwrap().swoop.unwrap();
+        assert_eq!(swoop.phase, SwoopPhase::Climb);
+    }
+
+    #[test]
+    fn dive_again_after_climbing() {
+        let mut game = Game::new();
+        let target = game.ecs.spawn((position!(0.0, 64.0, 0.0),));
+        let mut physics = Physics::for_flying().swoop(target);
+        let mut swoop = Swoop::new(target).with_thresholds(4.0, 1.5);
+        swoop.phase = SwoopPhase::Climb;
+        physics.swoop = Some(swoop);
+        let phantom = game
+            .ecs
+            .spawn((position!(3.0, 69.0, 0.0), Velocity::default(), physics));
+
+        update_physics(&mut game).unwrap();
+
+        let swoop = game.ecs.get::<Physics>(phantom).unwrap().swoop.unwrap();
+        assert_eq!(swoop.phase, SwoopPhase::Dive);
+        assert!(game.ecs.get::<Velocity>(phantom).unwrap().0.y < 0.0);
+    }
+
+    #[test]
+    fn current_pushes_entity() {
+        // A channel along Z, from a source block into flowing water.
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        let mut flowing = BlockId::water();
+        flowing.set_water_level(2);
+        chunk.set_block_at(2, 50, 2, BlockId::water());
+        chunk.set_block_at(2, 50, 1, flowing);
+        for &(x, z) in &[(1, 2), (3, 2), (2, 3)] {
+            chunk.set_block_at(x, 50, z, BlockId::stone());
+        }
+        let mut game = Game::new();
+        game.overworld_mut().chunk_map_mut().insert_chunk(chunk);
+
+        let physics = Physics::for_flying();
+        let squid = game
+            .ecs
+            .spawn((position!(2.5, 50.2, 2.5), Velocity::default(), physics));
+
+        update_physics(&mut game).unwrap();
+
+        let velocity = game.ecs.get::<Velocity>(squid).unwrap().0;
+        assert!((velocity.z + CURRENT_PUSH * physics.drag).abs() < 1e-9);
+        assert_eq!(velocity.x, 0.0);
+        assert!(game.ecs.get::<Position>(squid).unwrap().z < 2.5);
+        let current = game.ecs.get::<Physics>(squid).unwrap().current.unwrap();
+        assert_eq!(current.x, 0.0);
+        assert!(current.z < 0.0);
+    }
+
+    #[test]
+    fn items_fall_to_the_ground() {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        chunk.set_block_at(2, 63, 2, BlockId::stone());
+        let mut game = Game::new();
+        game.overworld_mut().chunk_map_mut().insert_chunk(chunk);
+
+        let item = game.ecs.spawn((
+            position!(2.5, 66.0, 2.5),
+            Velocity::default(),
+            Physics::for_item(),
+        ));
+        for _ in 0..100 {
+            update_physics(&mut game).unwrap();
+        }
+
+        assert!((game.ecs.get::<Position>(item).unwrap().y - 64.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sweep_through_thin_wall() {
+        let start = Aabb {
+            min: Vec3d::new(0.0, 0.0, 0.0),
+            max: Vec3d::new(0.5, 0.5, 0.5),
+        };
+        let wall = Aabb {
+            min: Vec3d::new(5.0, -1.0, -1.0),
+            max: Vec3d::new(5.1, 2.0, 2.0),
+        };
+
+        let fraction = sweep(start, Vec3d::new(10.0, 0.0, 0.0), wall).unwrap();
+        assert!((fraction - 0.45).abs() < 1e-6);
+
+        // Too slow to reach the wall, or moving away from it.
+        assert_eq!(sweep(start, Vec3d::new(4.0, 0.0, 0.0), wall), None);
+        assert_eq!(sweep(start, Vec3d::new(-10.0, 0.0, 0.0), wall), None);
+        // Passing beside the wall.
+        assert_eq!(sweep(start, Vec3d::new(10.0, 0.0, 10.0), wall), None);
+    }
+
+    #[test]
+    fn projectile_stops_at_block() {
+        let mut game = Game::new();
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        chunk.set_block_at(8, 64, 2, base::BlockId::stone());
+        game.overworld_mut().chunk_map_mut().insert_chunk(chunk);
+
+        let arrow = game.ecs.spawn((
+            position!(2.0, 64.2, 2.5),
+            Velocity(Vec3d::new(20.0, 0.0, 0.0)),
+            Physics::for_projectile(),
+            EntityKind::Arrow,
+        ));
+
+        update_physics(&mut game).unwrap();
+
+        let pos = *game.ecs.get::<Position>(arrow).unwrap();
+        let half_width = EntityKind::Arrow.bounding_box().size().w / 2.0;
+        assert!((pos.x + half_width - 8.0).abs() < 1e-6);
+        assert_eq!(game.ecs.get::<Velocity>(arrow).unwrap().0, Vec3d::zero());
+
+        // The arrow stays in the block until it is broken.
+        for _ in 0..10 {
+            update_physics(&mut game).unwrap();
+        }
+        assert_eq!(*game.ecs.get::<Position>(arrow).unwrap(), pos);
+
+        game.overworld_mut()
+            .set_block_at(BlockPosition::new(8, 64, 2), BlockId::air());
+        update_physics(&mut game).unwrap();
+        assert!(game.ecs.get::<Position>(arrow).unwrap().y < pos.y);
+        assert_eq!(game.ecs.get::<Physics>(arrow).unwrap().stuck_in, None);
+    }
+
+    #[test]
+    fn living_entities_collide_with_their_whole_bbox() {
+        let mut game = Game::new();
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        // A floor block beside the column of the first zombie's feet.
+        chunk.set_block_at(3, 63, 2, BlockId::stone());
+        // A floor with a wall at head height for the second zombie.
+        for x in 0..8 {
+            chunk.set_block_at(x, 63, 8, BlockId::stone());
+        }
+        chunk.set_block_at(5, 65, 8, BlockId::stone());
+        game.overworld_mut().chunk_map_mut().insert_chunk(chunk);
+
+        let falling = game.ecs.spawn((
+            position!(2.9, 66.0, 2.5),
+            Velocity::default(),
+            Physics::for_living(),
+            EntityKind::Zombie,
+        ));
+        let walking = game.ecs.spawn((
+            position!(0.5, 64.0, 8.5),
+            Velocity(Vec3d::new(0.5, 0.0, 0.0)),
+            Physics::for_living(),
+            EntityKind::Zombie,
+        ));
+        for _ in 0..100 {
+            update_physics(&mut game).unwrap();
+        }
+
+        let y = game.ecs.get::<Position>(falling).unwrap().y;
+        assert!((y - 64.0).abs() < 1e-9, "zombie at y = {}", y);
+        let pos = *game.ecs.get::<Position>(walking).unwrap();
+        let half_width = EntityKind::Zombie.bounding_box().size().w / 2.0;
+        assert!(
+            (pos.x + half_width - 5.0).abs() < 1e-9,
+            "zombie at x = {}",
+            pos.x
+        );
+        assert!((pos.y - 64.0).abs() < 1e-9, "zombie at y = {}", pos.y);
+        assert_eq!(game.ecs.get::<Velocity>(walking).unwrap().0.x, 0.0);
+    }
+
+    #[test]
+    fn swimming_entities_only_float_in_water() {
+        let mut game = Game::new();
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        chunk.set_block_at(2, 50, 2, BlockId::water());
+        chunk.set_block_at(6, 40, 2, BlockId::stone());
+        game.overworld_mut().chunk_map_mut().insert_chunk(chunk);
+
+        let swimming = game.ecs.spawn((
+            position!(2.5, 50.2, 2.5),
+            Velocity::default(),
+            Physics::for_swimming(),
+        ));
+        let stranded = game.ecs.spawn((
+            position!(6.5, 45.0, 2.5),
+            Velocity::default(),
+            Physics::for_swimming(),
+        ));
+        for _ in 0..100 {
+            update_physics(&mut game).unwrap();
+        }
+
+        assert_eq!(game.ecs.get::<Position>(swimming).unwrap().y, 50.2);
+        let y = game.ecs.get::<Position>(stranded).unwrap().y;
+        assert!((y - 41.0).abs() < 1e-9, "squid at y = {}", y);
+    }
+
+    #[test]
+    fn strider_floats_on_lava() {
+        let mut game = Game::new();
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        for y in 60..64 {
+            chunk.set_block_at(2, y, 2, BlockId::lava());
+        }
+        game.overworld_mut().chunk_map_mut().insert_chunk(chunk);
+
+        let submerged = game.ecs.spawn((
+            position!(2.5, 61.0, 2.5),
+            Velocity::default(),
+            Physics::for_lava_walking(),
+        ));
+        let standing = game.ecs.spawn((
+            position!(2.5, 64.0, 2.5),
+            Velocity::default(),
+            Physics::for_lava_walking(),
+        ));
+
+        for _ in 0..100 {
+            update_physics(&mut game).unwrap();
+        }
+
+        for &strider in &[submerged, standing] {
+            let y = game.ecs.get::<Position>(strider).unwrap().y;
+            assert!((y - 64.0).abs() < 1e-9, "strider at y = {}", y);
+        }
+    }
+
+    #[test]
+    fn strider_stops_falling_on_stone() {
+        let mut game = Game::new();
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        chunk.set_block_at(2, 63, 2, BlockId::stone());
+        game.overworld_mut().chunk_map_mut().insert_chunk(chunk);
+
+        let strider = game.ecs.spawn((
+            position!(2.5, 70.0, 2.5),
+            Velocity::default(),
+            Physics::for_lava_walking(),
+        ));
+
+        for _ in 0..100 {
+            update_physics(&mut game).unwrap();
+        }
+
+        let y = game.ecs.get::<Position>(strider).unwrap().y;
+        assert!((y - 64.0).abs() < 1e-9, "strider at y = {}", y);
+        assert_eq!(game.ecs.get::<Velocity>(strider).unwrap().0.y, 0.0);
+    }
+}
@@ -0,0 +1,65 @@
+//! Entity poses, such as swimming or sneaking,
+//! which change an entity's bounding box.
+
+use base::{
+    metadata::{Pose, META_INDEX_POSE},
+    EntityMetadata,
+};
+use ecs::{Entity, SysResult};
+
+use crate::Game;
+
+/// Component storing an entity's pose. Entities
+/// without this component are standing.
+///
+/// Use [`set_pose`] to update it along with the metadata.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PoseComponent {
+    pub pose: Pose,
+}
+
+/// Sets the pose of `entity`, updating its
+/// `PoseComponent` and pose metadata.
+pub fn set_pose(game: &mut Game, entity: Entity, pose: Pose) -> SysResult {
+    game.ecs.insert(entity, PoseComponent { pose })?;
+    if game.ecs.get::<EntityMetadata>(entity).is_err() {
+        game.ecs.insert(entity, EntityMetadata::entity_base())?;
+    }
+    game.ecs
+        .get_mut::<EntityMetadata>(entity)?
+        .set(META_INDEX_POSE, pose);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{metadata::MetaEntry, position, EntityKind, Position};
+
+    use super::*;
+    use crate::entities::world_bbox;
+
+    #[test]
+    fn swimming_player_has_shorter_bbox() {
+        let mut game = Game::new();
+        let pos = position!(0.0, 64.0, 0.0);
+        let player = game.ecs.spawn((pos, EntityKind::Player));
+
+        set_pose(&mut game, player, Pose::Swimming).unwrap();
+        let pose = game.ecs.get::<PoseComponent>(player).unwrap().pose;
+        assert_eq!(pose, Pose::Swimming);
+        assert_eq!(
+            game.ecs
+                .get::<EntityMetadata>(player)
+                .unwrap()
+                .get(META_INDEX_POSE),
+            Some(MetaEntry::Pose(Pose::Swimming as i32))
+        );
+
+        let position = *game.ecs.get::<Position>(player).unwrap();
+        let standing = world_bbox(EntityKind::Player, position, Pose::Standing);
+        let swimming = world_bbox(EntityKind::Player, position, pose);
+        assert!(swimming.size().h < standing.size().h);
+        assert_eq!(swimming.size().w, standing.size().w);
+        assert_eq!(swimming.min, standing.min);
+    }
+}
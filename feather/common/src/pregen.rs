@@ -0,0 +1,162 @@
+//! Pre-generation of the chunks around spawn, so that
+//! a world can be warmed up before players explore it.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use base::ChunkPosition;
+
+use crate::world_source::{generating::SharedScheduler, region::RegionFiles};
+
+/// Number of progress reports logged over a pre-generation run.
+const PROGRESS_REPORTS: usize = 10;
+
+/// Generates the chunks within a radius of the spawn chunk on a
+/// background thread, saving them to the world's region files.
+/// Chunks already in the region files are skipped.
+///
+/// Shares its scheduler and region files with the world's
+/// live world sources, so chunks are neither generated twice
+/// nor loaded while being saved.
+///
+/// Stored as a resource in the `Game`.
+pub struct Pregenerator {
+    scheduler: SharedScheduler,
+    region_files: RegionFiles,
+    spawn: ChunkPosition,
+    running: Arc<AtomicBool>,
+}
+
+impl Pregenerator {
+    pub fn new(
+        scheduler: SharedScheduler,
+        region_files: RegionFiles,
+        spawn: ChunkPosition,
+    ) -> Self {
+        Self {
+            scheduler,
+            region_files,
+            spawn,
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Returns whether chunks are currently being pre-generated.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Starts pre-generating the chunks within `radius` chunks of
+    /// spawn. Returns `false` if a previous run is still going.
+    pub fn start(&self, radius: u32) -> bool {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+
+        let run = Run {
+            scheduler: self.scheduler.clone(),
+            region_files: self.region_files.clone(),
+        };
+        let center = self.spawn;
+        let running = Arc::clone(&self.running);
+        std::thread::Builder::new()
+            .name("pregen_worker".to_owned())
+            .spawn(move || {
+                log::info!(
+                    "Pre-generating chunks within {} chunks of {:?}",
+                    radius,
+                    center
+                );
+                match run.run(center, radius as i32) {
+                    Ok(()) => log::info!("Finished pre-generating chunks"),
+                    Err(e) => log::error!("Failed to pre-generate chunks: {:?}", e),
+                }
+                running.store(false, Ordering::SeqCst);
+            })
+            .expect("failed to create pregen worker thread");
+        true
+    }
+}
+
+struct Run {
+    scheduler: SharedScheduler,
+    region_files: RegionFiles,
+}
+
+impl Run {
+    fn run(self, center: ChunkPosition, radius: i32) -> anyhow::Result<()> {
+        let side = (2 * radius + 1) as usize;
+        let total = side * side;
+        let report_interval = (total / PROGRESS_REPORTS).max(1);
+
+        // Generate row by row, so only the chunks bordering
+        // the current row are kept by the scheduler.
+        let positions = (-radius..=radius).flat_map(|z| {
+            (-radius..=radius).map(move |x| ChunkPosition::new(center.x + x, center.z + z))
+        });
+        for (i, pos) in positions.enumerate() {
+            if !self.region_files.has_chunk(pos)? {
+                let chunk = self.scheduler.generate_chunk(pos);
+                self.region_files.save_chunk(&chunk)?;
+            }
+
+            let done = i + 1;
+            if done % report_interval == 0 || done == total {
+                log::info!(
+                    "Pre-generated {}/{} chunks ({}%)",
+                    done,
+                    total,
+                    done * 100 / total
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use base::anvil::region::{self, RegionPosition};
+    use worldgen::EmptyWorldGenerator;
+
+    use super::*;
+
+    #[test]
+    fn pregenerated_chunks_are_saved() {
+        let world_dir = std::env::temp_dir().join(format!("feather-pregen-{}", std::process::id()));
+        let spawn = ChunkPosition::new(31, 0);
+        let scheduler = SharedScheduler::new(Arc::new(EmptyWorldGenerator {}));
+        let pregen = Pregenerator::new(scheduler, RegionFiles::new(&world_dir), spawn);
+
+        assert!(pregen.start(1));
+        assert!(!pregen.start(1));
+
+        let start = Instant::now();
+        while pregen.is_running() {
+            assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        // The chunks span four region files.
+        for x in 30..=32 {
+            for z in -1..=1 {
+                let pos = ChunkPosition::new(x, z);
+                let region =
+                    region::load_region(&world_dir, RegionPosition::from_chunk(pos)).unwrap();
+                assert!(region.has_chunk(pos));
+            }
+        }
+        assert!(!region::load_region(
+            &world_dir,
+            RegionPosition::from_chunk(ChunkPosition::new(33, 0))
+        )
+        .unwrap()
+        .has_chunk(ChunkPosition::new(33, 0)));
+
+        std::fs::remove_dir_all(&world_dir).unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+//! Entities sleeping in beds, e.g. villagers at night.
+
+use base::{
+    metadata::{Pose, META_INDEX_LIVING_BED_POSITION},
+    BlockPosition, EntityMetadata, Position,
+};
+use ecs::{Entity, SysResult};
+
+use crate::{
+    entity_systems::{EntitySystemStage, EntitySystems},
+    events::SleepChangeEvent,
+    pose, Game,
+};
+
+/// Height of a bed's top surface above its block position.
+const BED_HEIGHT: f64 = 0.5625;
+
+pub fn register(systems: &mut EntitySystems) {
+    systems.register_entity_system(EntitySystemStage::Effects, pin_sleeping_entities);
+}
+
+/// Component for an entity sleeping in a bed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SleepingComponent {
+    pub bed_pos: BlockPosition,
+}
+
+/// Puts `entity` to sleep in the bed at `bed`.
+///
+/// Sets the sleeping pose and bed position metadata
+/// and moves the entity onto the bed.
+/// Triggers a `SleepChangeEvent`.
+pub fn sleep(game: &mut Game, entity: Entity, bed: BlockPosition) -> SysResult {
+    game.ecs
+        .insert(entity, SleepingComponent { bed_pos: bed })?;
+    *game.ecs.get_mut::<Position>(entity)? = sleeping_position(bed);
+    update_metadata(game, entity, Pose::Sleeping, Some(bed))?;
+    game.ecs.insert_entity_event(entity, SleepChangeEvent)?;
+    Ok(())
+}
+
+/// Wakes `entity` up, if it is sleeping.
+///
+/// Triggers a `SleepChangeEvent` if the entity was asleep.
+pub fn wake(game: &mut Game, entity: Entity) -> SysResult {
+    if game.ecs.remove::<SleepingComponent>(entity).is_ok() {
+        update_metadata(game, entity, Pose::Standing, None)?;
+        game.ecs.insert_entity_event(entity, SleepChangeEvent)?;
+    }
+    Ok(())
+}
+
+fn update_metadata(
+    game: &mut Game,
+    entity: Entity,
+    pose: Pose,
+    bed: Option<BlockPosition>,
+) -> SysResult {
+    pose::set_pose(game, entity, pose)?;
+    game.ecs
+        .get_mut::<EntityMetadata>(entity)?
+        .set(META_INDEX_LIVING_BED_POSITION, bed);
+    Ok(())
+}
+
+/// Returns the position of an entity lying in the bed at `bed`.
+fn sleeping_position(bed: BlockPosition) -> Position {
+    let mut pos = bed.position();
+    pos.x += 0.5;
+    pos.y += BED_HEIGHT;
+    pos.z += 0.5;
+    pos
+}
+
+/// Keeps sleeping entities in their beds.
+fn pin_sleeping_entities(game: &mut Game) -> SysResult {
+    for (_, (position, sleeping)) in game
+        .ecs
+        .query::<(&mut Position, &SleepingComponent)>()
+        .iter()
+    {
+        *position = sleeping_position(sleeping.bed_pos);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{
+        metadata::{MetaEntry, META_INDEX_POSE},
+        position,
+    };
+    use quill_common::entity_init::EntityInit;
+
+    use super::*;
+
+    #[test]
+    fn villager_sleeps_and_wakes() {
+        let mut game = Game::new();
+        let builder = game.create_entity_builder(position!(3.0, 64.0, 7.0), EntityInit::Villager);
+        let villager = game.spawn_entity(builder);
+        let bed = BlockPosition::new(10, 64, -4);
+
+        sleep(&mut game, villager, bed).unwrap();
+        {
+            let metadata = game.ecs.get::<EntityMetadata>(villager).unwrap();
+            assert_eq!(
+                metadata.get(META_INDEX_POSE),
+                Some(MetaEntry::Pose(Pose::Sleeping as i32))
+            );
+            assert_eq!(
+                metadata.get(META_INDEX_LIVING_BED_POSITION),
+                Some(MetaEntry::OptPosition(Some(bed)))
+            );
+        }
+
+        // Sleeping entities stay pinned to the bed.
+        *game.ecs.get_mut::<Position>(villager).unwrap() = position!(0.0, 64.0, 0.0);
+        pin_sleeping_entities(&mut game).unwrap();
+        assert_eq!(
+            *game.ecs.get::<Position>(villager).unwrap(),
+            sleeping_position(bed)
+        );
+
+        wake(&mut game, villager).unwrap();
+        assert!(game.ecs.get::<SleepingComponent>(villager).is_err());
+        assert_eq!(
+            game.ecs
+                .get::<EntityMetadata>(villager)
+                .unwrap()
+                .get(META_INDEX_POSE),
+            Some(MetaEntry::Pose(Pose::Standing as i32))
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! Natural mob spawning: rolling packs of mobs from the spawn
+//! lists of each biome, gated by light and time of day.
+//!
+//! The spawn lists themselves live in `worldgen::mob_spawns`.
+
+use base::{Biome, EntityKind, Position};
+use ecs::EntityBuilder;
+use quill_common::entity_init::EntityInit;
+use rand::Rng;
+
+use crate::Game;
+
+pub use worldgen::SpawnCategory;
+
+/// Maximum horizontal distance, in blocks, of a pack
+/// member from the center of its pack.
+const PACK_SPREAD: f64 = 5.0;
+
+/// Highest light level at which monsters spawn.
+const MAX_MONSTER_LIGHT: u8 = 7;
+/// Lowest light level at which animals spawn.
+const MIN_CREATURE_LIGHT: u8 = 9;
+/// Amount by which sky light is dimmed at night.
+const NIGHT_SKY_DARKENING: u8 = 11;
+/// Length of a day, in ticks.
+const DAY_LENGTH: u64 = 24_000;
+
+/// The conditions at a spawn attempt's location.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SpawnConditions {
+    pub block_light: u8,
+    pub sky_light: u8,
+    /// Time of day, in ticks.
+    pub time: u64,
+}
+
+impl SpawnConditions {
+    /// Returns the light level at the location, with
+    /// sky light dimmed at night.
+    pub fn light(&self) -> u8 {
+        let time = self.time % DAY_LENGTH;
+        let darkening = if (13_000..23_000).contains(&time) {
+            NIGHT_SKY_DARKENING
+        } else {
+            0
+        };
+        self.block_light
+            .max(self.sky_light.saturating_sub(darkening))
+    }
+
+    /// Returns whether mobs of the given category can spawn here.
+    pub fn allow(&self, category: SpawnCategory) -> bool {
+        match category {
+            SpawnCategory::Hostile => self.light() <= MAX_MONSTER_LIGHT,
+            SpawnCategory::Passive => self.light() >= MIN_CREATURE_LIGHT,
+            SpawnCategory::Water => true,
+        }
+    }
+}
+
+/// A candidate mob in a [`SpawnTable`].
+#[derive(Clone, Debug)]
+pub struct SpawnEntry {
+    pub init: EntityInit,
+    /// Relative chance of this entry being chosen.
+    pub weight: u32,
+    /// Minimum number of mobs in a pack, inclusive.
+    pub min_group: u32,
+    /// Maximum number of mobs in a pack, inclusive.
+    pub max_group: u32,
+}
+
+impl SpawnEntry {
+    pub fn new(init: EntityInit, weight: u32, min_group: u32, max_group: u32) -> Self {
+        Self {
+            init,
+            weight,
+            min_group,
+            max_group,
+        }
+    }
+}
+
+/// Weighted list of mobs which can spawn naturally in a biome.
+#[derive(Clone, Debug, Default)]
+pub struct SpawnTable {
+    pub entries: Vec<SpawnEntry>,
+}
+
+impl SpawnTable {
+    pub fn new(entries: Vec<SpawnEntry>) -> Self {
+        Self { entries }
+    }
+
+    /// Returns the spawn table for mobs of the given category
+    /// in a biome, as listed by `worldgen::mob_spawns`.
+    pub fn for_biome(biome: Biome, category: SpawnCategory) -> Self {
+        let entries = worldgen::mob_spawns(biome)
+            .get(category)
+            .iter()
+            .filter_map(|entry| {
+                Some(SpawnEntry::new(
+                    spawn_init(entry.kind)?,
+                    entry.weight,
+                    entry.min_group,
+                    entry.max_group,
+                ))
+            })
+            .collect();
+        Self::new(entries)
+    }
+
+    /// Returns the sum of the weights of all entries.
+    pub fn total_weight(&self) -> u32 {
+        self.entries.iter().map(|entry| entry.weight).sum()
+    }
+
+    /// Picks an entry at random, with each entry's chance
+    /// proportional to its weight.
+    ///
+    /// Returns `None` if the table is empty or all weights are zero.
+    pub fn choose(&self, rng: &mut impl Rng) -> Option<&SpawnEntry> {
+        let total = self.total_weight();
+        if total == 0 {
+            return None;
+        }
+
+        let mut roll = rng.gen_range(0..total);
+        for entry in &self.entries {
+            if roll < entry.weight {
+                return Some(entry);
+            }
+            roll -= entry.weight;
+        }
+        None
+    }
+}
+
+/// Returns the `EntityInit` spawning a naturally spawning mob,
+/// or `None` if mobs of that kind don't spawn naturally.
+fn spawn_init(kind: EntityKind) -> Option<EntityInit> {
+    let init = match kind {
+        EntityKind::Chicken => EntityInit::Chicken,
+        EntityKind::Cod => EntityInit::Cod,
+        EntityKind::Cow => EntityInit::Cow,
+        EntityKind::Creeper => EntityInit::Creeper,
+        EntityKind::Dolphin => EntityInit::Dolphin,
+        EntityKind::Donkey => EntityInit::Donkey,
+        EntityKind::Drowned => EntityInit::Drowned,
+        EntityKind::Enderman => EntityInit::Enderman,
+        EntityKind::Fox => EntityInit::Fox,
+        EntityKind::Ghast => EntityInit::Ghast,
+        EntityKind::Hoglin => EntityInit::Hoglin,
+        EntityKind::Horse => EntityInit::Horse,
+        EntityKind::Husk => EntityInit::Husk,
+        EntityKind::Llama => EntityInit::Llama,
+        EntityKind::MagmaCube => EntityInit::MagmaCube,
+        EntityKind::Mooshroom => EntityInit::Mooshroom,
+        EntityKind::Ocelot => EntityInit::Ocelot,
+        EntityKind::Panda => EntityInit::Panda,
+        EntityKind::Parrot => EntityInit::Parrot,
+        EntityKind::Pig => EntityInit::Pig,
+        EntityKind::Piglin => EntityInit::Piglin,
+        EntityKind::PolarBear => EntityInit::PolarBear,
+        EntityKind::Pufferfish => EntityInit::Pufferfish,
+        EntityKind::Rabbit => EntityInit::Rabbit,
+        EntityKind::Salmon => EntityInit::Salmon,
+        EntityKind::Sheep => EntityInit::Sheep,
+        EntityKind::Skeleton => EntityInit::Skeleton,
+        EntityKind::Slime => EntityInit::Slime,
+        EntityKind::Spider => EntityInit::Spider,
+        EntityKind::Squid => EntityInit::Squid,
+        EntityKind::Stray => EntityInit::Stray,
+        EntityKind::Strider => EntityInit::Strider,
+        EntityKind::TropicalFish => EntityInit::TropicalFish,
+        EntityKind::Turtle => EntityInit::Turtle,
+        EntityKind::Witch => EntityInit::Witch,
+        EntityKind::Wolf => EntityInit::Wolf,
+        EntityKind::Zombie => EntityInit::Zombie,
+        EntityKind::ZombieVillager => EntityInit::ZombieVillager,
+        EntityKind::ZombifiedPiglin => EntityInit::ZombifiedPiglin,
+        _ => return None,
+    };
+    Some(init)
+}
+
+/// An entity chosen to spawn whose builder has not been created yet.
+///
+/// Call [`LazyBuilder::build`] to obtain an `EntityBuilder`
+/// with the entity's default components.
+#[derive(Clone, Debug)]
+pub struct LazyBuilder {
+    pub position: Position,
+    pub init: EntityInit,
+}
+
+impl LazyBuilder {
+    /// Creates the entity builder through
+    /// [`Game::create_entity_builder`].
+    pub fn build(self, game: &mut Game) -> EntityBuilder {
+        game.create_entity_builder(self.position, self.init)
+    }
+}
+
+/// Attempts to spawn a pack of mobs of the given category
+/// around `center` in the given biome.
+///
+/// A candidate is chosen from the biome's [`SpawnTable`] by weight,
+/// then a group size between its minimum and maximum. Pack members are
+/// scattered horizontally around `center`. Returns an empty `Vec` if
+/// the `conditions` at `center` don't allow the category to spawn or
+/// nothing of the category can spawn in the biome.
+pub fn attempt_spawn(
+    biome: Biome,
+    category: SpawnCategory,
+    conditions: SpawnConditions,
+    center: Position,
+    rng: &mut impl Rng,
+) -> Vec<LazyBuilder> {
+    if !conditions.allow(category) {
+        return Vec::new();
+    }
+    spawn_from_table(&SpawnTable::for_biome(biome, category), center, rng)
+}
+
+/// Like [`attempt_spawn`], but rolls from an explicit table.
+pub fn spawn_from_table(
+    table: &SpawnTable,
+    center: Position,
+    rng: &mut impl Rng,
+) -> Vec<LazyBuilder> {
+    let entry = match table.choose(rng) {
+        Some(entry) => entry,
+        None => return Vec::new(),
+    };
+
+    let group_size = rng.gen_range(entry.min_group..=entry.max_group.max(entry.min_group));
+    (0..group_size)
+        .map(|_| {
+            let mut position = center;
+            position.x += rng.gen_range(-PACK_SPREAD..=PACK_SPREAD);
+            position.z += rng.gen_range(-PACK_SPREAD..=PACK_SPREAD);
+            LazyBuilder {
+                position,
+                init: entry.init.clone(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+    use rand::rngs::mock::StepRng;
+
+    use super::*;
+
+    #[test]
+    fn fixed_rng_chooses_expected_pack() {
+        let table = SpawnTable::new(vec![
+            SpawnEntry::new(EntityInit::Cow, 10, 2, 4),
+            SpawnEntry::new(EntityInit::Zombie, 90, 1, 1),
+        ]);
+        let center = position!(8.0, 64.0, 8.0);
+
+        // A zero RNG always lands on the first entry and its minimum group size.
+        let pack = spawn_from_table(&table, center, &mut StepRng::new(0, 0));
+        assert_eq!(pack.len(), 2);
+        for member in &pack {
+            assert!(matches!(member.init, EntityInit::Cow));
+            assert!((member.position.x - center.x).abs() <= PACK_SPREAD);
+            assert!((member.position.z - center.z).abs() <= PACK_SPREAD);
+            assert_eq!(member.position.y, center.y);
+        }
+
+        let dark = SpawnConditions {
+            block_light: 0,
+            sky_light: 0,
+            time: 0,
+        };
+        let empty = attempt_spawn(
+            Biome::TheVoid,
+            SpawnCategory::Hostile,
+            dark,
+            center,
+            &mut StepRng::new(0, 0),
+        );
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn categories_are_gated_by_light_and_time() {
+        let noon = SpawnConditions {
+            block_light: 0,
+            sky_light: 15,
+            time: 6_000,
+        };
+        let midnight = SpawnConditions {
+            time: 18_000,
+            ..noon
+        };
+        assert!(!noon.allow(SpawnCategory::Hostile));
+        assert!(noon.allow(SpawnCategory::Passive));
+        assert!(midnight.allow(SpawnCategory::Hostile));
+        assert!(!midnight.allow(SpawnCategory::Passive));
+        assert!(midnight.allow(SpawnCategory::Water));
+
+        // Torches keep monsters away at night.
+        let lit = SpawnConditions {
+            block_light: 14,
+            ..midnight
+        };
+        assert!(!lit.allow(SpawnCategory::Hostile));
+
+        let center = position!(8.0, 64.0, 8.0);
+        let pack = attempt_spawn(
+            Biome::Plains,
+            SpawnCategory::Hostile,
+            noon,
+            center,
+            &mut StepRng::new(0, 0),
+        );
+        assert!(pack.is_empty());
+    }
+
+    #[test]
+    fn tables_are_split_by_category() {
+        let creatures = SpawnTable::for_biome(Biome::Plains, SpawnCategory::Passive);
+        assert!(creatures
+            .entries
+            .iter()
+            .any(|entry| matches!(entry.init, EntityInit::Cow)));
+        assert!(!creatures
+            .entries
+            .iter()
+            .any(|entry| matches!(entry.init, EntityInit::Zombie)));
+
+        let desert = SpawnTable::for_biome(Biome::Desert, SpawnCategory::Hostile);
+        let weight = |init: fn(&EntityInit) -> bool| -> u32 {
+            desert
+                .entries
+                .iter()
+                .filter(|entry| init(&entry.init))
+                .map(|entry| entry.weight)
+                .sum()
+        };
+        assert_eq!(weight(|init| matches!(init, EntityInit::Zombie)), 19);
+        assert_eq!(weight(|init| matches!(init, EntityInit::Husk)), 80);
+
+        let swamp = SpawnTable::for_biome(Biome::Swamp, SpawnCategory::Hostile);
+        let slimes = swamp
+            .entries
+            .iter()
+            .filter(|entry| matches!(entry.init, EntityInit::Slime))
+            .count();
+        assert_eq!(slimes, 1);
+
+        let ocean = SpawnTable::for_biome(Biome::Ocean, SpawnCategory::Water);
+        assert!(ocean.entries.iter().all(|entry| matches!(
+            entry.init,
+            EntityInit::Squid | EntityInit::Cod | EntityInit::Dolphin
+        )));
+    }
+
+    #[test]
+    fn every_listed_mob_can_spawn() {
+        let categories = [
+            SpawnCategory::Passive,
+            SpawnCategory::Hostile,
+            SpawnCategory::Water,
+        ];
+        for biome in (0..=255).filter_map(Biome::from_id) {
+            for &category in &categories {
+                let listed = worldgen::mob_spawns(biome).get(category);
+                for entry in listed {
+                    assert!(
+                        spawn_init(entry.kind).is_some(),
+                        "{:?} in {:?}",
+                        entry.kind,
+                        biome
+                    );
+                }
+                let table = SpawnTable::for_biome(biome, category);
+                assert_eq!(table.entries.len(), listed.len());
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Entities which teleport players on contact,
+//! such as end gateways.
+
+use base::{EntityKind, Position};
+use ecs::{Entity, SysResult};
+use quill_common::entities::Player;
+
+use crate::{
+    dimensions::DimensionId,
+    entities::bboxes_intersect,
+    entity_systems::{EntitySystemStage, EntitySystems},
+    events::TeleportEvent,
+    Game,
+};
+
+pub fn register(systems: &mut EntitySystems) {
+    systems
+        .register_entity_system(EntitySystemStage::Effects, detect_teleport_triggers)
+        .register_entity_system(EntitySystemStage::Effects, run_pending_teleports);
+}
+
+/// Component for an entity which teleports players
+/// whose bounding box intersects its own.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TeleportTriggerComponent {
+    /// Block coordinates and dimension to teleport to.
+    pub destination: (i32, i32, i32, DimensionId),
+    /// Ticks between contact and the teleport.
+    pub cooldown: u32,
+}
+
+/// Component for a player who will be teleported
+/// once `ticks_remaining` reaches zero.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingTeleport {
+    pub destination: (i32, i32, i32, DimensionId),
+    pub ticks_remaining: u32,
+}
+
+/// Schedules teleports for players touching a trigger.
+fn detect_teleport_triggers(game: &mut Game) -> SysResult {
+    let triggers: Vec<_> = game
+        .ecs
+        .query::<(&Position, &EntityKind, &TeleportTriggerComponent)>()
+        .iter()
+        .map(|(_, (&position, &kind, trigger))| (position, kind, trigger.clone()))
+        .collect();
+    if triggers.is_empty() {
+        return Ok(());
+    }
+
+    let mut scheduled: Vec<(Entity, PendingTeleport)> = Vec::new();
+    for (player, (&position, &kind, _, pending)) in game
+        .ecs
+        .query::<(&Position, &EntityKind, &Player, Option<&PendingTeleport>)>()
+        .iter()
+    {
+        if pending.is_some() {
+            continue;
+        }
+        let touched = triggers.iter().find(|(trigger_pos, trigger_kind, _)| {
+            bboxes_intersect((kind, position), (*trigger_kind, *trigger_pos))
+        });
+        if let Some((_, _, trigger)) = touched {
+            scheduled.push((
+                player,
+                PendingTeleport {
+                    destination: trigger.destination.clone(),
+                    ticks_remaining: trigger.cooldown,
+                },
+            ));
+        }
+    }
+
+    for (player, pending) in scheduled {
+        game.ecs.insert(player, pending)?;
+    }
+    Ok(())
+}
+
+/// Counts down pending teleports and performs them.
+///
+/// Triggers a `TeleportEvent` for each teleported player.
+fn run_pending_teleports(game: &mut Game) -> SysResult {
+    let mut due = Vec::new();
+    for (player, pending) in game.ecs.query::<&mut PendingTeleport>().iter() {
+        if pending.ticks_remaining == 0 {
+            due.push(player);
+        } else {
+            pending.ticks_remaining -= 1;
+        }
+    }
+
+    for player in due {
+        let pending = game.ecs.remove::<PendingTeleport>(player)?;
+        let (x, y, z, dimension) = pending.destination;
+        {
+            let mut position = game.ecs.get_mut::<Position>(player)?;
+            position.x = x as f64 + 0.5;
+            position.y = y as f64;
+            position.z = z as f64 + 0.5;
+        }
+
+        let previous_dimension = game.dimension_of(player);
+        game.ecs.insert(player, dimension)?;
+        game.ecs.insert_entity_event(
+            player,
+            TeleportEvent {
+                dimension,
+                previous_dimension,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+
+    fn spawn_gateway(game: &mut Game, position: Position) -> Entity {
+        let trigger = TeleportTriggerComponent {
+            destination: (100, 70, -20, DimensionId::THE_END),
+            cooldown: 2,
+        };
+        game.ecs
+            .spawn((position, EntityKind::AreaEffectCloud, trigger))
+    }
+
+    #[test]
+    fn overlapping_player_is_teleported() {
+        let mut game = Game::new();
+        spawn_gateway(&mut game, position!(0.0, 64.0, 0.0));
+        let player = game
+            .ecs
+            .spawn((position!(1.0, 64.0, 1.0), EntityKind::Player, Player));
+
+        detect_teleport_triggers(&mut game).unwrap();
+        let pending = game.ecs.get::<PendingTeleport>(player).unwrap().clone();
+        assert_eq!(pending.destination, (100, 70, -20, DimensionId::THE_END));
+        assert_eq!(pending.ticks_remaining, 2);
+
+        for _ in 0..3 {
+            run_pending_teleports(&mut game).unwrap();
+        }
+        assert!(game.ecs.get::<PendingTeleport>(player).is_err());
+        assert_eq!(
+            *game.ecs.get::<Position>(player).unwrap(),
+            position!(100.5, 70.0, -19.5)
+        );
+        let event = game.ecs.get::<TeleportEvent>(player).unwrap();
+        assert_eq!(event.dimension, DimensionId::THE_END);
+        assert_eq!(event.previous_dimension, DimensionId::OVERWORLD);
+        assert_eq!(
+            *game.ecs.get::<DimensionId>(player).unwrap(),
+            DimensionId::THE_END
+        );
+    }
+
+    #[test]
+    fn distant_player_is_not_teleported() {
+        let mut game = Game::new();
+        spawn_gateway(&mut game, position!(0.0, 64.0, 0.0));
+        let player = game
+            .ecs
+            .spawn((position!(10.0, 64.0, 0.0), EntityKind::Player, Player));
+
+        detect_teleport_triggers(&mut game).unwrap();
+        assert!(game.ecs.get::<PendingTeleport>(player).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+//! Wandering traders, which spawn with two
+//! trader llamas leashed to them.
+
+use base::Position;
+use ecs::{Entity, SysResult};
+use quill_common::entity_init::EntityInit;
+
+use crate::{
+    entity_systems::{EntitySystemStage, EntitySystems},
+    events::EntityRemoveEvent,
+    leash::{self, LeashHolder},
+    Game,
+};
+
+/// Horizontal distance from the trader at which
+/// its llamas spawn.
+const LLAMA_OFFSET: f64 = 2.0;
+
+pub fn register(systems: &mut EntitySystems) {
+    systems.register_entity_system(EntitySystemStage::Effects, remove_trader_llamas);
+}
+
+/// Component for a wandering trader storing the
+/// llamas spawned with it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TraderLlamas(pub [Entity; 2]);
+
+/// Spawns a wandering trader at `position` together
+/// with two trader llamas leashed to it. The llamas
+/// are removed along with the trader.
+///
+/// Returns the trader.
+pub fn spawn_wandering_trader(game: &mut Game, position: Position) -> anyhow::Result<Entity> {
+    let builder = game.create_entity_builder(position, EntityInit::WanderingTrader);
+    let trader = game.spawn_entity(builder);
+
+    let mut llamas = [trader; 2];
+    for (llama, side) in llamas.iter_mut().zip([-1.0, 1.0].iter()) {
+        let mut llama_pos = position;
+        llama_pos.x += LLAMA_OFFSET * side;
+        let mut builder = game.create_entity_builder(llama_pos, EntityInit::TraderLlama);
+        builder.add(game.dimension_of(trader));
+        *llama = game.spawn_entity(builder);
+        leash::attach(game, *llama, LeashHolder::Entity(trader))?;
+    }
+
+    game.ecs.insert(trader, TraderLlamas(llamas))?;
+    Ok(trader)
+}
+
+/// Removes the llamas of removed traders.
+fn remove_trader_llamas(game: &mut Game) -> SysResult {
+    let llamas: Vec<Entity> = game
+        .ecs
+        .query::<(&EntityRemoveEvent, &TraderLlamas)>()
+        .iter()
+        .flat_map(|(_, (_, llamas))| llamas.0.to_vec())
+        .collect();
+
+    for llama in llamas {
+        // The llama may already be gone.
+        if game.ecs.entity(llama).is_ok() {
+            game.remove_entity(llama)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+    use crate::leash::LeashComponent;
+
+    #[test]
+    fn llamas_are_leashed_and_removed_with_trader() {
+        let mut game = Game::new();
+        let trader = spawn_wandering_trader(&mut game, position!(0.0, 64.0, 0.0)).unwrap();
+
+        let llamas = game.ecs.get::<TraderLlamas>(trader).unwrap().0;
+        assert_ne!(llamas[0], llamas[1]);
+        for &llama in &llamas {
+            let leash = *game.ecs.get::<LeashComponent>(llama).unwrap();
+            assert_eq!(leash.holder, LeashHolder::Entity(trader));
+            assert!(game.ecs.get::<EntityRemoveEvent>(llama).is_err());
+        }
+
+        game.remove_entity(trader).unwrap();
+        remove_trader_llamas(&mut game).unwrap();
+
+        for &llama in &llamas {
+            assert!(game.ecs.get::<EntityRemoveEvent>(llama).is_ok());
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Villager professions and trades, and conversion
+//! between villagers and zombie villagers.
+
+use base::{ItemStack, Position};
+use ecs::Entity;
+use quill_common::entity_init::EntityInit;
+
+use crate::Game;
+
+/// A villager's profession.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Profession {
+    None,
+    Armorer,
+    Butcher,
+    Cartographer,
+    Cleric,
+    Farmer,
+    Fisherman,
+    Fletcher,
+    Leatherworker,
+    Librarian,
+    Mason,
+    Nitwit,
+    Shepherd,
+    Toolsmith,
+    Weaponsmith,
+}
+
+impl Profession {
+    /// Returns the protocol ID of this profession.
+    pub fn id(self) -> i32 {
+        self as i32
+    }
+}
+
+/// A trade offered by a villager.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trade {
+    pub input: ItemStack,
+    pub output: ItemStack,
+    /// Number of times this trade has been used.
+    pub uses: u32,
+    /// Number of uses after which the trade is locked.
+    pub max_uses: u32,
+}
+
+/// Component storing a villager's profession and trades.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VillagerData {
+    pub profession: Profession,
+    /// The villager's level, from 1 (novice) to 5 (master).
+    pub level: u8,
+    pub trades: Vec<Trade>,
+}
+
+impl VillagerData {
+    pub fn new(profession: Profession) -> Self {
+        Self {
+            profession,
+            level: 1,
+            trades: Vec::new(),
+        }
+    }
+}
+
+/// Component for a zombie villager storing the data of
+/// the villager it was converted from, restored on curing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZombieVillagerComponent {
+    pub original: VillagerData,
+}
+
+/// Replaces `entity` with a new entity of type `into`
+/// at the same position, returning the new entity.
+///
+/// Villager data is carried over when converting a villager
+/// into a zombie villager and back.
+pub fn convert_entity(game: &mut Game, entity: Entity, into: EntityInit) -> anyhow::Result<Entity> {
+    let position = *game.ecs.get::<Position>(entity)?;
+    let dimension = game.dimension_of(entity);
+    let villager_data = game.ecs.remove::<VillagerData>(entity).ok();
+    let zombie_data = game.ecs.remove::<ZombieVillagerComponent>(entity).ok();
+    game.remove_entity(entity)?;
+
+    let into_zombie = matches!(into, EntityInit::ZombieVillager);
+    let into_villager = matches!(into, EntityInit::Villager);
+    let mut builder = game.create_entity_builder(position, into);
+    builder.add(dimension);
+    match (villager_data, zombie_data) {
+        (Some(original), _) if into_zombie => {
+            builder.add(ZombieVillagerComponent { original });
+        }
+        (_, Some(zombie)) if into_villager => {
+            builder.add(zombie.original);
+        }
+        _ => {}
+    }
+    Ok(game.spawn_entity(builder))
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{position, Item};
+
+    use super::*;
+
+    #[test]
+    fn zombie_villager_retains_profession() {
+        let mut game = Game::new();
+        let mut data = VillagerData::new(Profession::Librarian);
+        data.level = 3;
+        data.trades.push(Trade {
+            input: ItemStack::new(Item::Emerald, 9),
+            output: ItemStack::new(Item::Bookshelf, 1),
+            uses: 2,
+            max_uses: 12,
+        });
+        let villager = game.ecs.spawn((position!(0.0, 64.0, 0.0), data.clone()));
+
+        let zombie = convert_entity(&mut game, villager, EntityInit::ZombieVillager).unwrap();
+        assert_eq!(
+            game.ecs
+                .get::<ZombieVillagerComponent>(zombie)
+                .unwrap()
+                .original,
+            data
+        );
+        assert!(game.ecs.get::<VillagerData>(zombie).is_err());
+
+        let cured = convert_entity(&mut game, zombie, EntityInit::Villager).unwrap();
+        assert_eq!(*game.ecs.get::<VillagerData>(cured).unwrap(), data);
+        assert_eq!(
+            *game.ecs.get::<Position>(cured).unwrap(),
+            position!(0.0, 64.0, 0.0)
+        );
+    }
+}
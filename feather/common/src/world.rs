@@ -6,6 +6,7 @@ use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::sync::Arc;
 
 use crate::{
+    dimensions::DimensionId,
     events::ChunkLoadEvent,
     world_source::{null::NullWorldSource, ChunkLoadResult, WorldSource},
 };
@@ -17,6 +18,7 @@ use crate::{
 /// NB: _not_ what most Rust ECSs call "world."
 /// This does not store entities; it only contains blocks.
 pub struct World {
+    dimension: DimensionId,
     chunk_map: ChunkMap,
     world_source: Box<dyn WorldSource>,
     loading_chunks: AHashSet<ChunkPosition>,
@@ -26,6 +28,7 @@ pub struct World {
 impl Default for World {
     fn default() -> Self {
         Self {
+            dimension: DimensionId::OVERWORLD,
             chunk_map: ChunkMap::new(),
             world_source: Box::new(NullWorldSource::default()),
             loading_chunks: AHashSet::new(),
@@ -47,6 +50,19 @@ impl World {
         }
     }
 
+    /// Sets the dimension this world belongs to.
+    ///
+    /// This is done by `Dimensions::register`.
+    pub fn with_dimension(mut self, dimension: DimensionId) -> Self {
+        self.dimension = dimension;
+        self
+    }
+
+    /// Returns the dimension this world belongs to.
+    pub fn dimension(&self) -> DimensionId {
+        self.dimension
+    }
+
     /// Queues the given chunk to be loaded.
     pub fn queue_chunk_load(&mut self, pos: ChunkPosition) {
         self.loading_chunks.insert(pos);
@@ -80,6 +96,7 @@ impl World {
             ecs.insert_event(ChunkLoadEvent {
                 chunk: Arc::clone(&self.chunk_map.0[&loaded.pos]),
                 position: loaded.pos,
+                dimension: self.dimension,
             });
             log::trace!("Loaded chunk {:?}", loaded.pos);
         }
@@ -1,6 +1,7 @@
 use base::{Chunk, ChunkPosition};
 
 pub mod flat;
+pub mod generating;
 pub mod null;
 pub mod region;
 
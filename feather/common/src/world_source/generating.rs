@@ -0,0 +1,182 @@
+use std::sync::Arc;
+
+use base::{Chunk, ChunkPosition};
+use flume::{Receiver, Sender};
+use parking_lot::Mutex;
+use worldgen::{PopulationScheduler, WorldGenerator};
+
+use super::{ChunkLoadResult, LoadedChunk, WorldSource};
+
+/// Number of threads generating chunks for each `GeneratingWorldSource`.
+const WORKER_COUNT: usize = 4;
+
+/// A world source which generates chunks
+/// using a [`WorldGenerator`].
+///
+/// Chunks are generated on a pool of worker threads,
+/// so generation doesn't block the tick loop. They are
+/// only returned once populated, which requires their
+/// neighbors to be generated as well.
+pub struct GeneratingWorldSource {
+    request_sender: Sender<ChunkPosition>,
+    result_receiver: Receiver<LoadedChunk>,
+}
+
+impl GeneratingWorldSource {
+    pub fn new(generator: Arc<dyn WorldGenerator>) -> Self {
+        Self::with_scheduler(SharedScheduler::new(generator))
+    }
+
+    /// Creates a world source generating chunks with the given
+    /// scheduler, which may be shared with other generators of
+    /// the same world.
+    pub fn with_scheduler(scheduler: SharedScheduler) -> Self {
+        let (request_sender, request_receiver) = flume::unbounded();
+        let (result_sender, result_receiver) = flume::unbounded();
+
+        for id in 0..WORKER_COUNT {
+            let worker = Worker {
+                scheduler: scheduler.clone(),
+                request_receiver: request_receiver.clone(),
+                result_sender: result_sender.clone(),
+            };
+            worker.start(id);
+        }
+
+        Self {
+            request_sender,
+            result_receiver,
+        }
+    }
+}
+
+impl WorldSource for GeneratingWorldSource {
+    fn queue_load(&mut self, pos: ChunkPosition) {
+        self.request_sender
+            .send(pos)
+            .expect("generation workers panicked");
+    }
+
+    fn poll_loaded_chunk(&mut self) -> Option<LoadedChunk> {
+        self.result_receiver.try_recv().ok()
+    }
+}
+
+/// A [`PopulationScheduler`] shared between threads, so
+/// that each chunk of a world is only generated once.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone)]
+pub struct SharedScheduler {
+    generator: Arc<dyn WorldGenerator>,
+    scheduler: Arc<Mutex<PopulationScheduler>>,
+}
+
+impl SharedScheduler {
+    pub fn new(generator: Arc<dyn WorldGenerator>) -> Self {
+        Self {
+            scheduler: Arc::new(Mutex::new(PopulationScheduler::new(Arc::clone(&generator)))),
+            generator,
+        }
+    }
+
+    /// Generates and populates the chunk at `pos`.
+    pub fn generate_chunk(&self, pos: ChunkPosition) -> Chunk {
+        // Generate the chunks needed for population without holding
+        // the lock, so that threads only wait on each other to populate.
+        let missing = self.scheduler.lock().missing_chunks(pos);
+        let generated: Vec<Chunk> = missing
+            .into_iter()
+            .map(|required| self.generator.generate_chunk(required))
+            .collect();
+
+        let mut scheduler = self.scheduler.lock();
+        for chunk in generated {
+            scheduler.insert_generated(chunk);
+        }
+        scheduler.generate_chunk(pos)
+    }
+}
+
+struct Worker {
+    scheduler: SharedScheduler,
+    request_receiver: Receiver<ChunkPosition>,
+    result_sender: Sender<LoadedChunk>,
+}
+
+impl Worker {
+    fn start(self, id: usize) {
+        std::thread::Builder::new()
+            .name(format!("generation_worker_{}", id))
+            .spawn(move || self.run())
+            .expect("failed to create generation worker thread");
+    }
+
+    /// Generates chunks until the world source is dropped.
+    fn run(self) {
+        while let Ok(pos) = self.request_receiver.recv() {
+            let chunk = self.scheduler.generate_chunk(pos);
+            let result = ChunkLoadResult::Loaded { chunk };
+            if self
+                .result_sender
+                .send(LoadedChunk { pos, result })
+                .is_err()
+            {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use worldgen::EmptyWorldGenerator;
+
+    use super::*;
+
+    fn poll(source: &mut GeneratingWorldSource) -> LoadedChunk {
+        let start = Instant::now();
+        loop {
+            if let Some(loaded) = source.poll_loaded_chunk() {
+                return loaded;
+            }
+            assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn generates_queued_chunks() {
+        let mut source = GeneratingWorldSource::new(Arc::new(EmptyWorldGenerator {}));
+        source.queue_load(ChunkPosition::new(4, -3));
+
+        let loaded = poll(&mut source);
+        assert_eq!(loaded.pos, ChunkPosition::new(4, -3));
+        match loaded.result {
+            ChunkLoadResult::Loaded { chunk } => {
+                assert_eq!(chunk.position(), ChunkPosition::new(4, -3))
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert!(source.poll_loaded_chunk().is_none());
+    }
+
+    #[test]
+    fn generates_all_queued_chunks() {
+        let mut source = GeneratingWorldSource::new(Arc::new(EmptyWorldGenerator {}));
+        let mut queued: Vec<ChunkPosition> = (0..4)
+            .flat_map(|x| (0..4).map(move |z| ChunkPosition::new(x, z)))
+            .collect();
+        for &pos in &queued {
+            source.queue_load(pos);
+        }
+
+        let mut loaded: Vec<ChunkPosition> =
+            (0..queued.len()).map(|_| poll(&mut source).pos).collect();
+        queued.sort_by_key(|pos| (pos.x, pos.z));
+        loaded.sort_by_key(|pos| (pos.x, pos.z));
+        assert_eq!(loaded, queued);
+    }
+}
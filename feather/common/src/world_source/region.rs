@@ -1,15 +1,18 @@
 use std::{
     collections::hash_map::Entry,
+    io,
     path::PathBuf,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
 use ahash::AHashMap;
 use base::{
-    anvil::region::{RegionHandle, RegionPosition},
-    ChunkPosition,
+    anvil::region::{self, RegionHandle, RegionPosition},
+    Chunk, ChunkPosition,
 };
 use flume::{Receiver, Sender};
+use parking_lot::Mutex;
 
 use super::{ChunkLoadResult, LoadedChunk, WorldSource};
 
@@ -21,8 +24,14 @@ pub struct RegionWorldSource {
 
 impl RegionWorldSource {
     pub fn new(world_dir: impl Into<PathBuf>) -> Self {
+        Self::with_files(RegionFiles::new(world_dir))
+    }
+
+    /// Creates a world source loading from the given region files,
+    /// which may be shared with other users of the world save.
+    pub fn with_files(files: RegionFiles) -> Self {
         let (request_sender, request_receiver) = flume::unbounded();
-        let (worker, result_receiver) = Worker::new(world_dir.into(), request_receiver);
+        let (worker, result_receiver) = Worker::new(files, request_receiver);
 
         worker.start();
 
@@ -66,17 +75,120 @@ impl OpenRegionFile {
     }
 }
 
+/// The open region files of a world save.
+///
+/// Every access to the world's region files should go through
+/// the same `RegionFiles`, so that a chunk is never read
+/// while it is being written.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone)]
+pub struct RegionFiles {
+    world_dir: PathBuf,
+    files: Arc<Mutex<AHashMap<RegionPosition, OpenRegionFile>>>,
+}
+
+impl RegionFiles {
+    pub fn new(world_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            world_dir: world_dir.into(),
+            files: Arc::new(Mutex::new(AHashMap::new())),
+        }
+    }
+
+    /// Loads the chunk at the given position.
+    pub fn load_chunk(&self, pos: ChunkPosition) -> ChunkLoadResult {
+        let mut files = self.files.lock();
+        let file = match self.open(&mut files, RegionPosition::from_chunk(pos), false) {
+            Ok(Some(file)) => file,
+            Ok(None) | Err(_) => return ChunkLoadResult::Missing,
+        };
+
+        let chunk = match file.handle.load_chunk(pos) {
+            Ok((chunk, _, _)) => chunk,
+            Err(e) => return ChunkLoadResult::Error(e.into()),
+        };
+
+        file.last_used = Instant::now();
+
+        ChunkLoadResult::Loaded { chunk }
+    }
+
+    /// Returns whether the chunk at the given position is saved.
+    pub fn has_chunk(&self, pos: ChunkPosition) -> anyhow::Result<bool> {
+        let mut files = self.files.lock();
+        match self.open(&mut files, RegionPosition::from_chunk(pos), false)? {
+            Some(file) => {
+                file.last_used = Instant::now();
+                Ok(file.handle.has_chunk(pos))
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Saves a chunk, creating its region file if it does not exist.
+    pub fn save_chunk(&self, chunk: &Chunk) -> anyhow::Result<()> {
+        let mut files = self.files.lock();
+        let region = RegionPosition::from_chunk(chunk.position());
+        let file = self
+            .open(&mut files, region, true)?
+            .expect("region file is created if missing");
+        file.handle.save_chunk(chunk, &[], &[])?;
+        file.last_used = Instant::now();
+        Ok(())
+    }
+
+    /// Closes the region files which have not been used recently.
+    /// Returns the number of files closed.
+    pub fn close_unused(&self) -> usize {
+        let mut files = self.files.lock();
+        let initial_len = files.len();
+        files.retain(|_, file| !file.should_close());
+        initial_len - files.len()
+    }
+
+    /// Returns the number of open region files.
+    pub fn open_count(&self) -> usize {
+        self.files.lock().len()
+    }
+
+    /// Opens a region file, returning `None` if it
+    /// does not exist and `create` is `false`.
+    fn open<'a>(
+        &self,
+        files: &'a mut AHashMap<RegionPosition, OpenRegionFile>,
+        region: RegionPosition,
+        create: bool,
+    ) -> Result<Option<&'a mut OpenRegionFile>, region::Error> {
+        match files.entry(region) {
+            Entry::Occupied(e) => Ok(Some(e.into_mut())),
+            Entry::Vacant(e) => {
+                let handle = match region::load_region(&self.world_dir, region) {
+                    Ok(handle) => handle,
+                    Err(region::Error::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
+                        if !create {
+                            return Ok(None);
+                        }
+                        region::create_region(&self.world_dir, region)?
+                    }
+                    Err(err) => return Err(err),
+                };
+                Ok(Some(e.insert(OpenRegionFile::new(handle))))
+            }
+        }
+    }
+}
+
 struct Worker {
     request_receiver: Receiver<ChunkPosition>,
     result_sender: Sender<LoadedChunk>,
-    world_dir: PathBuf,
-    region_files: AHashMap<RegionPosition, OpenRegionFile>,
+    files: RegionFiles,
     last_cache_update: Instant,
 }
 
 impl Worker {
     pub fn new(
-        world_dir: PathBuf,
+        files: RegionFiles,
         request_receiver: Receiver<ChunkPosition>,
     ) -> (Self, Receiver<LoadedChunk>) {
         let (result_sender, result_receiver) = flume::bounded(256);
@@ -84,8 +196,7 @@ impl Worker {
             Self {
                 request_receiver,
                 result_sender,
-                world_dir,
-                region_files: AHashMap::new(),
+                files,
                 last_cache_update: Instant::now(),
             },
             result_receiver,
@@ -115,54 +226,20 @@ impl Worker {
     }
 
     fn load_chunk(&mut self, pos: ChunkPosition) {
-        let result = self.get_chunk_load_result(pos);
+        let result = self.files.load_chunk(pos);
         let _ = self.result_sender.send(LoadedChunk { pos, result });
     }
 
-    fn get_chunk_load_result(&mut self, pos: ChunkPosition) -> ChunkLoadResult {
-        let region = RegionPosition::from_chunk(pos);
-        let file = match self.region_file_handle(region) {
-            Some(file) => file,
-            None => return ChunkLoadResult::Missing,
-        };
-
-        let chunk = match file.handle.load_chunk(pos) {
-            Ok((chunk, _, _)) => chunk,
-            Err(e) => return ChunkLoadResult::Error(e.into()),
-        };
-
-        file.last_used = Instant::now();
-
-        ChunkLoadResult::Loaded { chunk }
-    }
-
-    fn region_file_handle(&mut self, region: RegionPosition) -> Option<&mut OpenRegionFile> {
-        match self.region_files.entry(region) {
-            Entry::Occupied(e) => Some(e.into_mut()),
-            Entry::Vacant(e) => {
-                let handle = base::anvil::region::load_region(&self.world_dir, region);
-                if let Ok(handle) = handle {
-                    Some(e.insert(OpenRegionFile::new(handle)))
-                } else {
-                    None
-                }
-            }
-        }
-    }
-
     fn update_cache(&mut self) {
         if self.last_cache_update.elapsed() >= CACHE_TIME {
-            let initial_len = self.region_files.len();
-
-            self.region_files.retain(|_, file| !file.should_close());
+            let num_closed = self.files.close_unused();
             self.last_cache_update = Instant::now();
 
-            let num_closed = initial_len - self.region_files.len();
             if num_closed != 0 {
                 log::debug!(
                     "Closed {} region files ({} still open)",
                     num_closed,
-                    self.region_files.len()
+                    self.files.open_count()
                 );
             }
         }
@@ -1,6 +1,10 @@
-use crate::{io::VarInt, ProtocolVersion, Readable, Writeable};
+use crate::{
+    io::{try_frame, VarInt},
+    ProtocolVersion, Readable, Writeable,
+};
 use aes::Aes128;
-use bytes::BytesMut;
+use anyhow::{anyhow, bail, Context};
+use bytes::{Bytes, BytesMut};
 use cfb8::{
     stream_cipher::{NewStreamCipher, StreamCipher},
     Cfb8,
@@ -9,11 +13,22 @@ use flate2::{
     bufread::{ZlibDecoder, ZlibEncoder},
     Compression,
 };
-use std::io::{Cursor, Read};
+use std::{
+    convert::TryFrom,
+    io::{Cursor, Read},
+};
 
 type AesCfb8 = Cfb8<Aes128>;
 pub type CompressionThreshold = usize;
 
+/// The maximum uncompressed length of a packet: 2^21 bytes.
+///
+/// Packet lengths are framed with at most three-byte VarInts,
+/// which hold up to 2^21 - 1, and the vanilla decompressor
+/// rejects any larger data length, so a client can't make us
+/// inflate an arbitrarily large buffer.
+const MAX_PACKET_DATA_LENGTH: usize = 1 << 21;
+
 /// An encryption key for use with AES-CFB8.
 pub type CryptKey = [u8; 16];
 
@@ -137,45 +152,178 @@ impl MinecraftCodec {
         }
     }
 
+    /// Splits the body of the next complete frame off the
+    /// received bytes, if it has fully arrived.
+    ///
+    /// The body shares memory with the receive buffer, so
+    /// no bytes are copied.
+    pub fn next_frame(&mut self) -> anyhow::Result<Option<Bytes>> {
+        let mut frames = Cursor::new(&self.received_buf[..]);
+        let body_length = match try_frame(&mut frames, ProtocolVersion::V1_16_2)? {
+            Some(body) => body.len(),
+            None => return Ok(None),
+        };
+        let frame_length = frames.position() as usize;
+
+        let frame = self.received_buf.split_to(frame_length).freeze();
+        Ok(Some(frame.slice(frame_length - body_length..)))
+    }
+
     /// Gets the next packet that was received, if any.
+    ///
+    /// Malformed frames, such as those with a negative length
+    /// or corrupt compressed data, produce an error rather than
+    /// being read past their end. Each frame is consumed whole,
+    /// even if the packet doesn't read all of it.
     pub fn next_packet<T>(&mut self) -> anyhow::Result<Option<T>>
     where
         T: Readable,
     {
-        let mut cursor = Cursor::new(&self.received_buf[..]);
-        let packet = if let Ok(length) = VarInt::read(&mut cursor, ProtocolVersion::V1_16_2) {
-            let length_field_length = cursor.position() as usize;
-
-            if self.received_buf.len() - length_field_length >= length.0 as usize {
-                cursor = Cursor::new(
-                    &self.received_buf
-                        [length_field_length..length_field_length + length.0 as usize],
-                );
+        let frame = match self.next_frame()? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
 
-                if self.compression.is_some() {
-                    let data_length = VarInt::read(&mut cursor, ProtocolVersion::V1_16_2)?;
-                    if data_length.0 != 0 {
-                        let mut decoder =
-                            ZlibDecoder::new(&cursor.get_ref()[cursor.position() as usize..]);
-                        decoder.read_to_end(&mut self.compression_target)?;
-                        cursor = Cursor::new(&self.compression_target);
-                    }
-                }
-
-                let packet = T::read(&mut cursor, ProtocolVersion::V1_16_2)?;
-
-                let bytes_read = cursor.position() as usize + length_field_length;
-                self.received_buf = self.received_buf.split_off(bytes_read);
-
-                self.compression_target.clear();
-                Some(packet)
-            } else {
-                None
+        let packet = decode_frame(&frame, self.compression, &mut self.compression_target);
+        self.compression_target.clear();
+        packet.map(Some)
+    }
+}
+
+/// Reads a packet from the body of a frame, first
+/// decompressing it if compression is enabled.
+///
+/// Like vanilla, packets compressed despite being
+/// smaller than the threshold are rejected.
+fn decode_frame<T>(
+    frame: &[u8],
+    compression: Option<CompressionThreshold>,
+    compression_target: &mut Vec<u8>,
+) -> anyhow::Result<T>
+where
+    T: Readable,
+{
+    let mut cursor = Cursor::new(frame);
+    if let Some(threshold) = compression {
+        let data_length = VarInt::read(&mut cursor, ProtocolVersion::V1_16_2)?.0;
+        if data_length != 0 {
+            let data_length = usize::try_from(data_length)
+                .ok()
+                .filter(|&length| length >= threshold && length <= MAX_PACKET_DATA_LENGTH)
+                .ok_or_else(|| anyhow!("invalid uncompressed length {}", data_length))?;
+            let decoder = ZlibDecoder::new(&frame[cursor.position() as usize..]);
+            decoder
+                .take(data_length as u64)
+                .read_to_end(compression_target)
+                .context("failed to decompress packet")?;
+            if compression_target.len() != data_length {
+                bail!(
+                    "uncompressed length {} does not match the declared length {}",
+                    compression_target.len(),
+                    data_length
+                );
             }
-        } else {
-            None
-        };
+            cursor = Cursor::new(&compression_target[..]);
+        }
+    }
+    T::read(&mut cursor, ProtocolVersion::V1_16_2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_frames_are_buffered() {
+        let mut codec = MinecraftCodec::new();
+        codec.accept(&[0x02, 0xAC]);
+        assert!(codec.next_packet::<VarInt>().unwrap().is_none());
+        codec.accept(&[0x02]);
+        assert_eq!(codec.next_packet::<VarInt>().unwrap(), Some(VarInt(300)));
+        assert!(codec.next_packet::<VarInt>().unwrap().is_none());
+    }
+
+    #[test]
+    fn frames_are_consumed_whole() {
+        let mut codec = MinecraftCodec::new();
+        codec.accept(&[0x03, 0x05, 0x00, 0x00, 0x01, 0x07]);
+        assert_eq!(codec.next_packet::<VarInt>().unwrap(), Some(VarInt(5)));
+        assert_eq!(codec.next_packet::<VarInt>().unwrap(), Some(VarInt(7)));
+    }
+
+    #[test]
+    fn frames_are_split_off() {
+        let mut codec = MinecraftCodec::new();
+        codec.accept(&[0x02, 0x01, 0x02, 0x03, 0x04, 0x05]);
+        assert_eq!(codec.next_frame().unwrap().unwrap(), &[0x01, 0x02][..]);
+        assert!(codec.next_frame().unwrap().is_none());
+        codec.accept(&[0x06]);
+        assert_eq!(
+            codec.next_frame().unwrap().unwrap(),
+            &[0x04, 0x05, 0x06][..]
+        );
+    }
+
+    #[test]
+    fn malformed_frames_are_errors() {
+        // Negative frame length
+        let mut codec = MinecraftCodec::new();
+        codec.accept(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+        assert!(codec.next_packet::<VarInt>().is_err());
+
+        // Body shorter than the packet
+        let mut codec = MinecraftCodec::new();
+        codec.accept(&[0x01, 0x80]);
+        assert!(codec.next_packet::<VarInt>().is_err());
+
+        // Compressed data shorter than its declared length
+        let mut codec = MinecraftCodec::new();
+        codec.enable_compression(0);
+        let mut encoded = Vec::new();
+        codec.encode(&VarInt(300), &mut encoded);
+        let declared_length = encoded.iter().position(|&byte| byte & 0x80 == 0).unwrap() + 1;
+        encoded[declared_length] += 1;
+        codec.accept(&encoded);
+        assert!(codec.next_packet::<VarInt>().is_err());
+    }
+
+    #[test]
+    fn packets_are_compressed_above_threshold() {
+        let mut codec = MinecraftCodec::new();
+        codec.enable_compression(3);
+
+        // Below the threshold: sent with a data length of 0
+        let mut encoded = Vec::new();
+        codec.encode(&VarInt(1), &mut encoded);
+        assert_eq!(encoded, [0x02, 0x00, 0x01]);
+        codec.accept(&encoded);
+        assert_eq!(codec.next_packet::<VarInt>().unwrap(), Some(VarInt(1)));
+
+        let mut encoded = Vec::new();
+        codec.encode(&VarInt(i32::MAX), &mut encoded);
+        assert_eq!(encoded[1], 5);
+        codec.accept(&encoded);
+        assert_eq!(
+            codec.next_packet::<VarInt>().unwrap(),
+            Some(VarInt(i32::MAX))
+        );
+
+        // Compressed packets below the threshold are rejected
+        let mut small = MinecraftCodec::new();
+        small.enable_compression(0);
+        let mut encoded = Vec::new();
+        small.encode(&VarInt(1), &mut encoded);
+        codec.accept(&encoded);
+        assert!(codec.next_packet::<VarInt>().is_err());
+    }
 
-        Ok(packet)
+    #[test]
+    fn compressed_packets_round_trip() {
+        let mut codec = MinecraftCodec::new();
+        codec.enable_compression(0);
+        let mut encoded = Vec::new();
+        codec.encode(&VarInt(300), &mut encoded);
+        codec.accept(&encoded);
+        assert_eq!(codec.next_packet::<VarInt>().unwrap(), Some(VarInt(300)));
     }
 }
@@ -3,8 +3,8 @@
 use crate::{ProtocolVersion, Slot};
 use anyhow::{anyhow, bail, Context};
 use base::{
-    anvil::entity::ItemNbt, metadata::MetaEntry, BlockId, BlockPosition, Direction, EntityMetadata,
-    Gamemode, Item, ItemStack,
+    anvil::entity::ItemNbt, chunk::PackedArray, metadata::MetaEntry, BlockId, BlockPosition,
+    Direction, EntityMetadata, Gamemode, Item, ItemStack,
 };
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use num_traits::{FromPrimitive, ToPrimitive};
@@ -122,13 +122,7 @@ where
         Self: Sized,
     {
         // Assume boolean prefix.
-        let present = bool::read(buffer, version)?;
-
-        if present {
-            Ok(Some(T::read(buffer, version)?))
-        } else {
-            Ok(None)
-        }
+        read_optional(buffer, version, T::read)
     }
 }
 
@@ -137,12 +131,42 @@ where
     T: Writeable,
 {
     fn write(&self, buffer: &mut Vec<u8>, version: ProtocolVersion) {
-        let present = self.is_some();
-        present.write(buffer, version);
+        write_optional(buffer, self.as_ref(), version, T::write);
+    }
+}
 
-        if let Some(value) = self {
-            value.write(buffer, version);
-        }
+/// Writes an optional field: a boolean presence flag,
+/// followed by the value (using `write_fn`) if present.
+///
+/// Use this over the `Option<T>` impl when the value
+/// needs a non-default encoding, e.g. as a `VarInt`.
+pub fn write_optional<T>(
+    buffer: &mut Vec<u8>,
+    value: Option<&T>,
+    version: ProtocolVersion,
+    write_fn: impl FnOnce(&T, &mut Vec<u8>, ProtocolVersion),
+) {
+    value.is_some().write(buffer, version);
+
+    if let Some(value) = value {
+        write_fn(value, buffer, version);
+    }
+}
+
+/// Reads an optional field written by `write_optional`:
+/// a boolean presence flag, followed by the value
+/// (using `read_fn`) if present.
+pub fn read_optional<T>(
+    buffer: &mut Cursor<&[u8]>,
+    version: ProtocolVersion,
+    read_fn: impl FnOnce(&mut Cursor<&[u8]>, ProtocolVersion) -> anyhow::Result<T>,
+) -> anyhow::Result<Option<T>> {
+    let present = bool::read(buffer, version)?;
+
+    if present {
+        Ok(Some(read_fn(buffer, version)?))
+    } else {
+        Ok(None)
     }
 }
 
@@ -251,7 +275,7 @@ impl Readable for VarLong {
 
             if num_read > 10 {
                 bail!(
-                    "VarInt too long (max length: 5, value read so far: {})",
+                    "VarLong too long (max length: 10, value read so far: {})",
                     result
                 );
             }
@@ -294,39 +318,57 @@ impl Writeable for VarLong {
     }
 }
 
+/// Maximum number of characters in a string, unless
+/// a packet specifies a lower limit.
+pub const MAX_STRING_LENGTH: usize = std::i16::MAX as usize;
+
 impl Readable for String {
     fn read(buffer: &mut Cursor<&[u8]>, version: ProtocolVersion) -> anyhow::Result<Self>
     where
         Self: Sized,
     {
-        // Length is encoded as VarInt.
-        // Following `length` bytes are the UTF8-encoded
-        // string.
-
-        let length = VarInt::read(buffer, version)
-            .context("failed to read string length")?
-            .0 as usize;
-
-        // TODO: support custom length limits
-        // Current max length is max value of a signed 16-bit int.
-        let max_length = std::i16::MAX as usize;
-        if length > max_length {
-            bail!(
-                "string length {} exceeds maximum allowed length of {}",
-                length,
-                max_length
-            );
-        }
+        read_string(buffer, MAX_STRING_LENGTH, version)
+    }
+}
 
-        // Read string into buffer.
-        let mut temp = vec![0u8; length];
-        buffer
-            .read_exact(&mut temp)
-            .map_err(|_| Error::UnexpectedEof("String"))?;
-        let s = std::str::from_utf8(&temp).context("string contained invalid UTF8")?;
+/// Reads a string of at most `max_length` characters.
+///
+/// The string is prefixed with its length in bytes as a VarInt,
+/// followed by its UTF-8 bytes. Like vanilla, the byte length may
+/// be up to four times `max_length`, and the decoded string may
+/// have at most `max_length` UTF-16 code units.
+pub fn read_string(
+    buffer: &mut Cursor<&[u8]>,
+    max_length: usize,
+    version: ProtocolVersion,
+) -> anyhow::Result<String> {
+    let length =
+        usize::try_from(VarInt::read(buffer, version).context("failed to read string length")?)
+            .context("negative string length")?;
+    if length > max_length * 4 {
+        bail!(
+            "string length {} exceeds maximum allowed length of {}",
+            length,
+            max_length * 4
+        );
+    }
 
-        Ok(s.to_owned())
+    // Read string into buffer.
+    let mut temp = vec![0u8; length];
+    buffer
+        .read_exact(&mut temp)
+        .map_err(|_| Error::UnexpectedEof("String"))?;
+    let s = String::from_utf8(temp).context("string contained invalid UTF8")?;
+
+    let chars = s.encode_utf16().count();
+    if chars > max_length {
+        bail!(
+            "string of {} characters exceeds maximum allowed length of {}",
+            chars,
+            max_length
+        );
     }
+    Ok(s)
 }
 
 impl Writeable for String {
@@ -466,6 +508,341 @@ impl<'a> From<LengthInferredVecU8<'a>> for Vec<u8> {
     }
 }
 
+/// Writes the body of a plugin message: the channel identifier
+/// followed by the raw payload, which extends to the end of the packet.
+pub fn write_plugin_message(
+    buffer: &mut Vec<u8>,
+    channel: &str,
+    payload: &[u8],
+    version: ProtocolVersion,
+) {
+    channel.to_owned().write(buffer, version);
+    LengthInferredVecU8::from(payload).write(buffer, version);
+}
+
+/// Reads the body of a plugin message written by [`write_plugin_message`].
+/// Returns the channel and the remaining bytes as the payload.
+pub fn read_plugin_message<'a>(
+    buffer: &mut Cursor<&'a [u8]>,
+    version: ProtocolVersion,
+) -> anyhow::Result<(String, &'a [u8])> {
+    let channel = String::read(buffer, version).context("failed to read plugin channel")?;
+
+    let data: &'a [u8] = *buffer.get_ref();
+    let payload = &data[(buffer.position() as usize).min(data.len())..];
+    buffer.set_position(data.len() as u64);
+
+    Ok((channel, payload))
+}
+
+/// Writes a byte array prefixed with its `VarInt` length.
+pub fn write_byte_array(buffer: &mut Vec<u8>, data: &[u8], version: ProtocolVersion) {
+    VarInt::from(data.len()).write(buffer, version);
+    buffer.extend_from_slice(data);
+}
+
+/// Reads a byte array written by [`write_byte_array`].
+///
+/// Declared lengths larger than `max_len` are rejected
+/// before anything is allocated.
+pub fn read_byte_array(
+    buffer: &mut Cursor<&[u8]>,
+    max_len: usize,
+    version: ProtocolVersion,
+) -> anyhow::Result<Vec<u8>> {
+    let length: usize = VarInt::read(buffer, version)?.0.try_into()?;
+
+    if length > max_len {
+        bail!("byte array length too large ({} > {})", length, max_len);
+    }
+
+    let mut data = vec![0; length];
+    buffer
+        .read_exact(&mut data)
+        .context("byte array shorter than its declared length")?;
+    Ok(data)
+}
+
+/// Writes an array of `VarInt`s prefixed with its `VarInt` length,
+/// as used for palette entries.
+pub fn write_var_int_array(buffer: &mut Vec<u8>, values: &[i32], version: ProtocolVersion) {
+    VarInt::from(values.len()).write(buffer, version);
+    for &value in values {
+        VarInt(value).write(buffer, version);
+    }
+}
+
+/// Reads an array written by [`write_var_int_array`].
+///
+/// This will reject arrays of lengths larger than MAX_LENGTH.
+pub fn read_var_int_array(
+    buffer: &mut Cursor<&[u8]>,
+    version: ProtocolVersion,
+) -> anyhow::Result<Vec<i32>> {
+    let values: Vec<VarInt> = LengthPrefixedVec::<VarInt>::read(buffer, version)?.into();
+    Ok(values.into_iter().map(i32::from).collect())
+}
+
+/// Bits per entry of a paletted container using
+/// global block state IDs instead of a palette.
+pub const DIRECT_BITS_PER_ENTRY: u8 = 15;
+/// Largest bits per entry of a paletted container
+/// with an indirect palette.
+const MAX_INDIRECT_BITS_PER_ENTRY: u8 = 8;
+/// Smallest bits per entry of a paletted container
+/// with an indirect palette.
+const MIN_INDIRECT_BITS_PER_ENTRY: u8 = 4;
+
+/// Writes a paletted block state container, where `data`
+/// contains indices into `palette`.
+///
+/// The bits per entry are chosen from the palette size:
+/// * Palettes of up to 256 entries are written as an indirect palette.
+/// * Larger palettes are dropped, and global IDs are written directly.
+///
+/// Entries are packed into longs without spanning two longs.
+///
+/// Fails, leaving `buffer` unchanged, if the palette is empty
+/// or `data` contains an index out of its bounds.
+pub fn write_paletted_container(
+    buffer: &mut Vec<u8>,
+    palette: &[BlockId],
+    data: &[u16],
+    version: ProtocolVersion,
+) -> anyhow::Result<()> {
+    if palette.is_empty() {
+        bail!("cannot write a paletted container with an empty palette");
+    }
+    if let Some(&index) = data.iter().find(|&&index| index as usize >= palette.len()) {
+        bail!(
+            "palette index {} out of bounds for palette of {} entries",
+            index,
+            palette.len()
+        );
+    }
+
+    let needed_bits = (usize::BITS - (palette.len() - 1).leading_zeros()) as u8;
+    let direct = needed_bits > MAX_INDIRECT_BITS_PER_ENTRY;
+    let bits_per_entry = if direct {
+        DIRECT_BITS_PER_ENTRY
+    } else {
+        needed_bits.max(MIN_INDIRECT_BITS_PER_ENTRY)
+    };
+    bits_per_entry.write(buffer, version);
+
+    let mut packed = PackedArray::new(data.len(), bits_per_entry as usize);
+    if direct {
+        for (i, &index) in data.iter().enumerate() {
+            packed.set(i, palette[index as usize].vanilla_id() as u64);
+        }
+    } else {
+        VarInt::from(palette.len()).write(buffer, version);
+        for block in palette {
+            block.write(buffer, version);
+        }
+        for (i, &index) in data.iter().enumerate() {
+            packed.set(i, index as u64);
+        }
+    }
+
+    VarInt::from(packed.as_u64_slice().len()).write(buffer, version);
+    for &long in packed.as_u64_slice() {
+        long.write(buffer, version);
+    }
+    Ok(())
+}
+
+/// Reads a paletted container of `len` entries
+/// written by [`write_paletted_container`].
+///
+/// Returns the palette and the indices into it. Directly
+/// encoded containers are given a palette of the distinct
+/// block states in order of appearance.
+///
+/// Fails if the bits per entry are neither those of an
+/// indirect palette nor [`DIRECT_BITS_PER_ENTRY`].
+pub fn read_paletted_container(
+    buffer: &mut Cursor<&[u8]>,
+    len: usize,
+    version: ProtocolVersion,
+) -> anyhow::Result<(Vec<BlockId>, Vec<u16>)> {
+    let bits_per_entry = u8::read(buffer, version)?;
+    if !(MIN_INDIRECT_BITS_PER_ENTRY..=MAX_INDIRECT_BITS_PER_ENTRY).contains(&bits_per_entry)
+        && bits_per_entry != DIRECT_BITS_PER_ENTRY
+    {
+        bail!("invalid bits per entry {}", bits_per_entry);
+    }
+
+    let direct = bits_per_entry > MAX_INDIRECT_BITS_PER_ENTRY;
+    let mut palette = if direct {
+        Vec::new()
+    } else {
+        let palette_len: usize = VarInt::read(buffer, version)?.0.try_into()?;
+        if palette_len > MAX_LENGTH {
+            bail!("palette length {} exceeds maximum", palette_len);
+        }
+        (0..palette_len)
+            .map(|_| BlockId::read(buffer, version))
+            .collect::<anyhow::Result<Vec<_>>>()?
+    };
+
+    let bits_per_entry = bits_per_entry as usize;
+    let values_per_long = 64 / bits_per_entry;
+    let num_longs: usize = VarInt::read(buffer, version)?.0.try_into()?;
+    if num_longs != (len + values_per_long - 1) / values_per_long {
+        bail!(
+            "expected {} longs for {} entries of {} bits; got {}",
+            (len + values_per_long - 1) / values_per_long,
+            len,
+            bits_per_entry,
+            num_longs
+        );
+    }
+    let longs = (0..num_longs)
+        .map(|_| u64::read(buffer, version))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mask = (1u64 << bits_per_entry) - 1;
+    let mut data = Vec::with_capacity(len);
+    for index in 0..len {
+        let long = longs[index / values_per_long];
+        let value = (long >> ((index % values_per_long) * bits_per_entry)) & mask;
+        if direct {
+            let block = match BlockId::try_from_vanilla_id(value.try_into()?) {
+                Some(block) => block,
+                None => bail!("invalid block state ID {}", value),
+            };
+            let index = match palette.iter().position(|&b| b == block) {
+                Some(index) => index,
+                None => {
+                    palette.push(block);
+                    palette.len() - 1
+                }
+            };
+            data.push(index as u16);
+        } else {
+            if value as usize >= palette.len() {
+                bail!("palette index {} out of bounds", value);
+            }
+            data.push(value as u16);
+        }
+    }
+
+    Ok((palette, data))
+}
+
+/// Reserves `len` zeroed bytes at the end of `buffer` to be
+/// overwritten later with [`write_at`], e.g. to back-patch a length
+/// once the following body has been written.
+///
+/// Returns the position of the reserved region.
+pub fn write_placeholder(buffer: &mut Vec<u8>, len: usize) -> usize {
+    let position = buffer.len();
+    buffer.resize(position + len, 0);
+    position
+}
+
+/// Overwrites the bytes at `position` with the encoding of `value`.
+///
+/// Fails, leaving `buffer` unchanged, if the encoding would extend
+/// past the end of the buffer. Note that the encoded size of `VarInt`s
+/// depends on their value, so placeholders should be fixed-size.
+pub fn write_at(
+    buffer: &mut Vec<u8>,
+    position: usize,
+    value: &impl Writeable,
+    version: ProtocolVersion,
+) -> anyhow::Result<()> {
+    // Encode at the end, then move the encoding into place.
+    let end = buffer.len();
+    value.write(buffer, version);
+    let written = buffer.len() - end;
+
+    if position + written > end {
+        buffer.truncate(end);
+        bail!(
+            "write of {} bytes at {} exceeds buffer length {}",
+            written,
+            position,
+            end
+        );
+    }
+
+    buffer.copy_within(end.., position);
+    buffer.truncate(end);
+    Ok(())
+}
+
+/// Maximum length of a frame body. Vanilla limits the
+/// length prefix to a 3-byte `VarInt`, so larger frames
+/// can't be sent by legitimate peers.
+pub const MAX_FRAME_LENGTH: usize = 2_097_152;
+
+/// Splits the next complete frame (a `VarInt` length followed by
+/// that many bytes) off `buffer` and returns its body.
+///
+/// Returns `Ok(None)` without moving the cursor if the frame
+/// has not fully arrived yet. Frames declaring a length over
+/// [`MAX_FRAME_LENGTH`] are rejected before they arrive.
+pub fn try_frame<'a>(
+    buffer: &mut Cursor<&'a [u8]>,
+    version: ProtocolVersion,
+) -> anyhow::Result<Option<&'a [u8]>> {
+    let data: &'a [u8] = *buffer.get_ref();
+    let start = (buffer.position() as usize).min(data.len());
+    let remaining = &data[start..];
+
+    // A VarInt is at most 5 bytes and ends with the first byte
+    // that has its continuation bit cleared.
+    let length_complete = remaining.iter().take(5).any(|byte| byte & 0b1000_0000 == 0);
+    if !length_complete {
+        if remaining.len() < 5 {
+            return Ok(None);
+        }
+        bail!("frame length VarInt too long");
+    }
+
+    let mut cursor = Cursor::new(remaining);
+    let length: usize = VarInt::read(&mut cursor, version)?
+        .0
+        .try_into()
+        .context("negative frame length")?;
+    if length > MAX_FRAME_LENGTH {
+        bail!(
+            "frame length {} exceeds maximum of {}",
+            length,
+            MAX_FRAME_LENGTH
+        );
+    }
+    let body_start = cursor.position() as usize;
+    if remaining.len() - body_start < length {
+        return Ok(None);
+    }
+
+    buffer.set_position((start + body_start + length) as u64);
+    Ok(Some(&remaining[body_start..body_start + length]))
+}
+
+/// Returns an iterator over the bodies of the complete frames in
+/// `buffer`, as split off by [`try_frame`].
+///
+/// Iteration stops at the first incomplete frame, leaving the cursor
+/// at its start, or after yielding the first error.
+pub fn frames<'a, 'b>(
+    buffer: &'b mut Cursor<&'a [u8]>,
+    version: ProtocolVersion,
+) -> impl Iterator<Item = anyhow::Result<&'a [u8]>> + 'b {
+    let mut failed = false;
+    iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        let frame = try_frame(buffer, version);
+        failed = frame.is_err();
+        frame.transpose()
+    })
+}
+
 pub struct ShortPrefixedVec<'a, T: ToOwned>(pub Cow<'a, [T]>)
 where
     [T]: ToOwned;
@@ -858,8 +1235,10 @@ impl Readable for BlockId {
     {
         let id = VarInt::read(buffer, version)?.0;
 
-        let block = BlockId::from_vanilla_id(id.try_into()?);
-        Ok(block)
+        match BlockId::try_from_vanilla_id(id.try_into()?) {
+            Some(block) => Ok(block),
+            None => bail!("invalid block state ID {}", id),
+        }
     }
 }
 
@@ -896,3 +1275,348 @@ impl Writeable for Gamemode {
         (id as u8).write(buffer, version);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plugin_message_round_trip() {
+        let mut buffer = Vec::new();
+        write_plugin_message(
+            &mut buffer,
+            "minecraft:brand",
+            &[7, 0, 255, 3],
+            ProtocolVersion::V1_16_2,
+        );
+
+        let mut cursor = Cursor::new(buffer.as_slice());
+        let (channel, payload) =
+            read_plugin_message(&mut cursor, ProtocolVersion::V1_16_2).unwrap();
+        assert_eq!(channel, "minecraft:brand");
+        assert_eq!(payload, &[7, 0, 255, 3]);
+        assert_eq!(cursor.position() as usize, buffer.len());
+    }
+
+    #[test]
+    fn strings_respect_max_length() {
+        let version = ProtocolVersion::V1_16_2;
+        let encode = |s: &str| {
+            let mut buffer = Vec::new();
+            s.to_owned().write(&mut buffer, version);
+            buffer
+        };
+
+        // Four bytes in UTF-8, but two UTF-16 code units.
+        let buffer = encode("🦀🦀");
+        let read = |max_length| read_string(&mut Cursor::new(&buffer[..]), max_length, version);
+        assert_eq!(read(4).unwrap(), "🦀🦀");
+        assert!(read(3).is_err());
+
+        let buffer = encode("username_too_long");
+        assert!(read_string(&mut Cursor::new(&buffer[..]), 16, version).is_err());
+        let read = String::read(&mut Cursor::new(&buffer[..]), version).unwrap();
+        assert_eq!(read, "username_too_long");
+
+        // A length prefix beyond the limit is rejected before reading.
+        let mut buffer = Vec::new();
+        VarInt(65).write(&mut buffer, version);
+        assert!(read_string(&mut Cursor::new(&buffer[..]), 16, version).is_err());
+    }
+
+    #[test]
+    fn var_long_round_trip() {
+        let version = ProtocolVersion::V1_16_2;
+        for &value in &[0, 1, -1, i64::MAX, i64::MIN, 300] {
+            let mut buffer = Vec::new();
+            VarLong(value).write(&mut buffer, version);
+            assert!(buffer.len() <= 10);
+            let read = VarLong::read(&mut Cursor::new(&buffer[..]), version).unwrap();
+            assert_eq!(read, VarLong(value));
+        }
+        let too_long = [0xFFu8; 11];
+        assert!(VarLong::read(&mut Cursor::new(&too_long[..]), version).is_err());
+    }
+
+    #[test]
+    fn optional_round_trip() {
+        let version = ProtocolVersion::V1_16_2;
+        let write_varint = |x: &i32, buffer: &mut Vec<u8>, version: ProtocolVersion| {
+            VarInt(*x).write(buffer, version)
+        };
+        let read_varint = |buffer: &mut Cursor<&[u8]>, version: ProtocolVersion| {
+            VarInt::read(buffer, version).map(|x| x.0)
+        };
+
+        let mut buffer = Vec::new();
+        write_optional(&mut buffer, Some(&300i32), version, write_varint);
+        write_optional(&mut buffer, None, version, write_varint);
+        assert_eq!(buffer, [1, 0xAC, 0x02, 0]);
+
+        let mut cursor = Cursor::new(buffer.as_slice());
+        assert_eq!(
+            read_optional(&mut cursor, version, read_varint).unwrap(),
+            Some(300)
+        );
+        assert_eq!(
+            read_optional(&mut cursor, version, read_varint).unwrap(),
+            None
+        );
+        assert_eq!(cursor.position() as usize, buffer.len());
+    }
+
+    #[test]
+    fn byte_array_round_trip() {
+        let version = ProtocolVersion::V1_16_2;
+        let mut buffer = Vec::new();
+        write_byte_array(&mut buffer, &[4, 8, 15, 16, 23, 42], version);
+        assert_eq!(buffer[0], 6);
+
+        let mut cursor = Cursor::new(buffer.as_slice());
+        assert_eq!(
+            read_byte_array(&mut cursor, 6, version).unwrap(),
+            [4, 8, 15, 16, 23, 42]
+        );
+        assert_eq!(cursor.position() as usize, buffer.len());
+    }
+
+    #[test]
+    fn byte_array_rejects_oversized_length() {
+        let version = ProtocolVersion::V1_16_2;
+        let mut buffer = Vec::new();
+        write_byte_array(&mut buffer, &[0; 32], version);
+        assert!(read_byte_array(&mut Cursor::new(buffer.as_slice()), 31, version).is_err());
+
+        // Declared length far exceeding the data is rejected
+        // by the cap rather than by running out of bytes.
+        let mut buffer = Vec::new();
+        VarInt(i32::MAX).write(&mut buffer, version);
+        let err = read_byte_array(&mut Cursor::new(buffer.as_slice()), 1024, version).unwrap_err();
+        assert!(err.to_string().contains("too large"));
+    }
+
+    #[test]
+    fn var_int_array_round_trip() {
+        let version = ProtocolVersion::V1_16_2;
+        let values = [0, 1, 127, 128, 300, 2_097_151, -1, i32::MAX, i32::MIN];
+
+        let mut buffer = Vec::new();
+        write_var_int_array(&mut buffer, &values, version);
+        // Count, then 1 + 1 + 1 + 2 + 2 + 3 + 5 + 5 + 5 value bytes.
+        assert_eq!(buffer.len(), 1 + 25);
+
+        let mut cursor = Cursor::new(buffer.as_slice());
+        assert_eq!(read_var_int_array(&mut cursor, version).unwrap(), values);
+        assert_eq!(cursor.position() as usize, buffer.len());
+    }
+
+    #[test]
+    fn entity_metadata_round_trip() {
+        let version = ProtocolVersion::V1_16_2;
+        let metadata = EntityMetadata::new()
+            .with(0, 0x20u8)
+            .with(8, 17.5f32)
+            .with(2, Some("Steve".to_owned()))
+            .with_many(&[(3, MetaEntry::String("custom".to_owned()))]);
+
+        let mut buffer = Vec::new();
+        metadata.write(&mut buffer, version);
+        assert_eq!(buffer.last(), Some(&0xFF));
+
+        let mut cursor = Cursor::new(buffer.as_slice());
+        assert_eq!(
+            EntityMetadata::read(&mut cursor, version).unwrap(),
+            metadata
+        );
+        assert_eq!(cursor.position() as usize, buffer.len());
+    }
+
+    #[test]
+    fn try_frame_complete_partial_and_concatenated() {
+        let mut data = Vec::new();
+        write_byte_array(&mut data, &[1, 2, 3], ProtocolVersion::V1_16_2);
+        write_byte_array(&mut data, &[4; 200], ProtocolVersion::V1_16_2);
+
+        // Partial frame: cursor is left untouched.
+        let mut cursor = Cursor::new(&data[..10]);
+        assert_eq!(
+            try_frame(&mut cursor, ProtocolVersion::V1_16_2).unwrap(),
+            Some(&[1, 2, 3][..])
+        );
+        assert_eq!(
+            try_frame(&mut cursor, ProtocolVersion::V1_16_2).unwrap(),
+            None
+        );
+        assert_eq!(cursor.position(), 4);
+
+        // Incomplete length prefix.
+        let mut cursor = Cursor::new(&data[4..5]);
+        assert_eq!(
+            try_frame(&mut cursor, ProtocolVersion::V1_16_2).unwrap(),
+            None
+        );
+        assert_eq!(cursor.position(), 0);
+
+        // Concatenated frames.
+        let mut cursor = Cursor::new(&data[..]);
+        assert_eq!(
+            try_frame(&mut cursor, ProtocolVersion::V1_16_2).unwrap(),
+            Some(&[1, 2, 3][..])
+        );
+        assert_eq!(
+            try_frame(&mut cursor, ProtocolVersion::V1_16_2).unwrap(),
+            Some(&[4; 200][..])
+        );
+        assert_eq!(
+            try_frame(&mut cursor, ProtocolVersion::V1_16_2).unwrap(),
+            None
+        );
+
+        assert!(try_frame(&mut Cursor::new(&[0xFF; 6][..]), ProtocolVersion::V1_16_2).is_err());
+    }
+
+    #[test]
+    fn try_frame_rejects_overlong_frames() {
+        let mut data = Vec::new();
+        VarInt::from(MAX_FRAME_LENGTH).write(&mut data, ProtocolVersion::V1_16_2);
+        assert_eq!(
+            try_frame(&mut Cursor::new(&data[..]), ProtocolVersion::V1_16_2).unwrap(),
+            None
+        );
+
+        // Rejected as soon as the length is known,
+        // without waiting for the body.
+        let mut data = Vec::new();
+        VarInt::from(MAX_FRAME_LENGTH + 1).write(&mut data, ProtocolVersion::V1_16_2);
+        assert!(try_frame(&mut Cursor::new(&data[..]), ProtocolVersion::V1_16_2).is_err());
+    }
+
+    #[test]
+    fn back_patch_placeholder() {
+        let mut buffer = vec![0xAB];
+        let length_pos = write_placeholder(&mut buffer, 4);
+        "hello"
+            .to_owned()
+            .write(&mut buffer, ProtocolVersion::V1_16_2);
+
+        let body_len = (buffer.len() - length_pos - 4) as i32;
+        write_at(&mut buffer, length_pos, &body_len, ProtocolVersion::V1_16_2).unwrap();
+
+        let mut cursor = Cursor::new(&buffer[..]);
+        assert_eq!(
+            u8::read(&mut cursor, ProtocolVersion::V1_16_2).unwrap(),
+            0xAB
+        );
+        assert_eq!(i32::read(&mut cursor, ProtocolVersion::V1_16_2).unwrap(), 6);
+        assert_eq!(
+            String::read(&mut cursor, ProtocolVersion::V1_16_2).unwrap(),
+            "hello"
+        );
+
+        let len = buffer.len();
+        assert!(write_at(&mut buffer, len - 2, &body_len, ProtocolVersion::V1_16_2).is_err());
+        assert_eq!(buffer.len(), len);
+    }
+
+    #[test]
+    fn paletted_container_round_trip() {
+        let version = ProtocolVersion::V1_16_2;
+        let round_trip = |palette: &[BlockId], data: &[u16]| {
+            let mut buffer = Vec::new();
+            write_paletted_container(&mut buffer, palette, data, version).unwrap();
+            let mut cursor = Cursor::new(buffer.as_slice());
+            let (read_palette, read_data) =
+                read_paletted_container(&mut cursor, data.len(), version).unwrap();
+            assert_eq!(cursor.position() as usize, buffer.len());
+            assert_eq!(read_data.len(), data.len());
+            for (&index, &read_index) in data.iter().zip(&read_data) {
+                assert_eq!(palette[index as usize], read_palette[read_index as usize]);
+            }
+            buffer[0]
+        };
+
+        // Single-valued
+        assert_eq!(round_trip(&[BlockId::air()], &[0; 4096]), 4);
+
+        // Indirect
+        let palette = [
+            BlockId::air(),
+            BlockId::stone(),
+            BlockId::dirt(),
+            BlockId::grass_block(),
+            BlockId::water(),
+        ];
+        let data: Vec<u16> = (0..4096).map(|i| (i * 7 % 5) as u16).collect();
+        assert_eq!(round_trip(&palette, &data), 4);
+
+        // Direct
+        let palette: Vec<BlockId> = (0..300).map(BlockId::from_vanilla_id).collect();
+        let data: Vec<u16> = (0..4096).map(|i| (i % 300) as u16).collect();
+        assert_eq!(round_trip(&palette, &data), DIRECT_BITS_PER_ENTRY);
+    }
+
+    #[test]
+    fn paletted_container_rejects_invalid_input() {
+        let version = ProtocolVersion::V1_16_2;
+        let mut buffer = Vec::new();
+        assert!(write_paletted_container(&mut buffer, &[], &[0; 16], version).is_err());
+        assert!(write_paletted_container(
+            &mut buffer,
+            &[BlockId::air(), BlockId::stone()],
+            &[0, 1, 2],
+            version
+        )
+        .is_err());
+        assert!(buffer.is_empty());
+
+        // A directly encoded container holding an invalid block state ID.
+        let mut buffer = Vec::new();
+        DIRECT_BITS_PER_ENTRY.write(&mut buffer, version);
+        let values_per_long = 64 / DIRECT_BITS_PER_ENTRY as usize;
+        VarInt(1).write(&mut buffer, version);
+        let invalid = (1u64 << DIRECT_BITS_PER_ENTRY) - 1;
+        let long: u64 = (0..values_per_long).fold(0, |long, i| {
+            long | invalid << (i * DIRECT_BITS_PER_ENTRY as usize)
+        });
+        long.write(&mut buffer, version);
+        assert!(read_paletted_container(
+            &mut Cursor::new(buffer.as_slice()),
+            values_per_long,
+            version
+        )
+        .is_err());
+
+        // Bits per entry which would divide by zero or overflow,
+        // and the single-valued format of later versions.
+        for &bits_per_entry in &[0u8, 3, 9, 14, 16, 64, 65, 255] {
+            let mut buffer = Vec::new();
+            bits_per_entry.write(&mut buffer, version);
+            VarInt(0).write(&mut buffer, version);
+            VarInt(0).write(&mut buffer, version);
+            assert!(
+                read_paletted_container(&mut Cursor::new(buffer.as_slice()), 16, version).is_err(),
+                "bits per entry {}",
+                bits_per_entry
+            );
+        }
+    }
+
+    #[test]
+    fn frames_stop_at_partial_frame() {
+        let mut data = Vec::new();
+        write_byte_array(&mut data, &[1], ProtocolVersion::V1_16_2);
+        write_byte_array(&mut data, &[], ProtocolVersion::V1_16_2);
+        write_byte_array(&mut data, &[2, 3], ProtocolVersion::V1_16_2);
+        let complete = data.len();
+        write_byte_array(&mut data, &[4, 5, 6], ProtocolVersion::V1_16_2);
+        data.pop();
+
+        let mut cursor = Cursor::new(&data[..]);
+        let bodies: Vec<&[u8]> = frames(&mut cursor, ProtocolVersion::V1_16_2)
+            .collect::<anyhow::Result<_>>()
+            .unwrap();
+        let expected: Vec<&[u8]> = vec![&[1][..], &[][..], &[2, 3][..]];
+        assert_eq!(bodies, expected);
+        assert_eq!(cursor.position() as usize, complete);
+    }
+}
@@ -12,6 +12,9 @@ struct Heightmaps {
     #[serde(rename = "MOTION_BLOCKING")]
     #[serde(serialize_with = "nbt::i64_array")]
     motion_blocking: [i64; 37],
+    #[serde(rename = "WORLD_SURFACE")]
+    #[serde(serialize_with = "nbt::i64_array")]
+    world_surface: [i64; 37],
 }
 
 #[derive(Debug, Clone)]
@@ -107,7 +110,13 @@ fn build_heightmaps(chunk: &Chunk) -> Heightmaps {
     let mut motion_blocking = [0; 37];
     let chunk_motion_blocking = chunk.heightmaps().motion_blocking.as_u64_slice();
     motion_blocking.copy_from_slice(bytemuck::cast_slice::<_, i64>(chunk_motion_blocking));
-    Heightmaps { motion_blocking }
+    let mut world_surface = [0; 37];
+    let chunk_world_surface = chunk.heightmaps().world_surface.as_u64_slice();
+    world_surface.copy_from_slice(bytemuck::cast_slice::<_, i64>(chunk_world_surface));
+    Heightmaps {
+        motion_blocking,
+        world_surface,
+    }
 }
 
 fn encode_section(section: &ChunkSection, buffer: &mut Vec<u8>, version: ProtocolVersion) {
@@ -1,29 +1,61 @@
 use ahash::AHashMap;
 use base::ChunkPosition;
 use common::{
-    events::{EntityRemoveEvent, ViewUpdateEvent},
+    events::{EntityRemoveEvent, TeleportEvent, ViewUpdateEvent},
     view::View,
-    Game,
+    DimensionId, Game,
 };
 use ecs::{SysResult, SystemExecutor};
 use utils::vec_remove_item;
 
 use crate::{ClientId, Server};
 
+type DimensionChunk = (DimensionId, ChunkPosition);
+
 /// Data structure to query which clients should
 /// receive updates from a given chunk, fast.
+///
+/// Chunks are keyed by dimension, so clients only
+/// receive updates from the dimension they are in.
 #[derive(Default)]
 pub struct ChunkSubscriptions {
-    chunks: AHashMap<ChunkPosition, Vec<ClientId>>,
+    chunks: AHashMap<DimensionChunk, Vec<ClientId>>,
 }
 
 impl ChunkSubscriptions {
-    pub fn subscriptions_for(&self, chunk: ChunkPosition) -> &[ClientId] {
+    pub fn subscriptions_for(&self, dimension: DimensionId, chunk: ChunkPosition) -> &[ClientId] {
         self.chunks
-            .get(&chunk)
+            .get(&(dimension, chunk))
             .map(Vec::as_slice)
             .unwrap_or_default()
     }
+
+    fn add(&mut self, chunk: DimensionChunk, client_id: ClientId) {
+        let clients = self.chunks.entry(chunk).or_default();
+        if !clients.contains(&client_id) {
+            clients.push(client_id);
+        }
+    }
+
+    fn remove(&mut self, chunk: DimensionChunk, client_id: ClientId) {
+        if let Some(vec) = self.chunks.get_mut(&chunk) {
+            vec_remove_item(vec, &client_id);
+
+            if vec.is_empty() {
+                self.chunks.remove(&chunk);
+            }
+        }
+    }
+
+    /// Removes all subscriptions of a client in `dimension`.
+    fn remove_dimension(&mut self, dimension: DimensionId, client_id: ClientId) {
+        self.chunks.retain(|&(chunk_dimension, _), clients| {
+            if chunk_dimension == dimension {
+                vec_remove_item(clients, &client_id);
+            }
+            !clients.is_empty()
+        });
+    }
 }
 
 pub fn register(systems: &mut SystemExecutor<Game>) {
@@ -33,41 +65,49 @@ pub fn register(systems: &mut SystemExecutor<Game>) {
 }
 
 fn update_chunk_subscriptions(game: &mut Game, server: &mut Server) -> SysResult {
+    let subscriptions = &mut server.chunk_subscriptions;
+
     // Update players whose views have changed
-    for (_, (event, &client_id)) in game.ecs.query::<(&ViewUpdateEvent, &ClientId)>().iter() {
+    for (_, (event, &client_id, dimension)) in game
+        .ecs
+        .query::<(&ViewUpdateEvent, &ClientId, Option<&DimensionId>)>()
+        .iter()
+    {
+        let dimension = dimension.copied().unwrap_or(DimensionId::OVERWORLD);
         for new_chunk in event.new_view.difference(event.old_view) {
-            server
-                .chunk_subscriptions
-                .chunks
-                .entry(new_chunk)
-                .or_default()
-                .push(client_id);
+            subscriptions.add((dimension, new_chunk), client_id);
         }
         for old_chunk in event.old_view.difference(event.new_view) {
-            remove_subscription(server, old_chunk, client_id);
+            subscriptions.remove((dimension, old_chunk), client_id);
         }
     }
 
-    // Update players that have left
-    for (_, (_event, &client_id, &view)) in game
+    // Move players who changed dimensions
+    for (_, (event, &client_id, &view)) in game
         .ecs
-        .query::<(&EntityRemoveEvent, &ClientId, &View)>()
+        .query::<(&TeleportEvent, &ClientId, &View)>()
         .iter()
     {
+        if !event.changed_dimension() {
+            continue;
+        }
+        subscriptions.remove_dimension(event.previous_dimension, client_id);
         for chunk in view.iter() {
-            remove_subscription(server, chunk, client_id);
+            subscriptions.add((event.dimension, chunk), client_id);
         }
     }
 
-    Ok(())
-}
-
-fn remove_subscription(server: &mut Server, chunk: ChunkPosition, client_id: ClientId) {
-    if let Some(vec) = server.chunk_subscriptions.chunks.get_mut(&chunk) {
-        vec_remove_item(vec, &client_id);
-
-        if vec.is_empty() {
-            server.chunk_subscriptions.chunks.remove(&chunk);
+    // Update players that have left
+    for (_, (_event, &client_id, &view, dimension)) in game
+        .ecs
+        .query::<(&EntityRemoveEvent, &ClientId, &View, Option<&DimensionId>)>()
+        .iter()
+    {
+        let dimension = dimension.copied().unwrap_or(DimensionId::OVERWORLD);
+        for chunk in view.iter() {
+            subscriptions.remove((dimension, chunk), client_id);
         }
     }
+
+    Ok(())
 }
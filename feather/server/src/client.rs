@@ -2,7 +2,9 @@ use std::{
     cell::{Cell, RefCell},
     collections::VecDeque,
     io::Cursor,
+    net::IpAddr,
     sync::Arc,
+    time::Instant,
 };
 
 use ahash::AHashSet;
@@ -12,19 +14,25 @@ use base::{
 };
 use common::{
     chat::{ChatKind, ChatMessage},
-    Window,
+    effects::{ActiveEffect, StatusEffect},
+    DimensionInfo, Window,
 };
 use flume::{Receiver, Sender};
-use packets::server::{Particle, SetSlot, SpawnLivingEntity, UpdateLight, WindowConfirmation};
+use libcraft_core::Dimension;
+use packets::server::{
+    AttachEntity, EntityEffect, Particle, RemoveEntityEffect, SetSlot, SpawnLivingEntity,
+    UpdateLight, WindowConfirmation,
+};
 use parking_lot::RwLock;
 use protocol::{
     packets::{
         self,
         server::{
             AddPlayer, Animation, BlockChange, ChatPosition, ChunkData, ChunkDataKind,
-            DestroyEntities, Disconnect, EntityAnimation, EntityHeadLook, EntityTeleport, JoinGame,
-            KeepAlive, PlayerInfo, PlayerPositionAndLook, PluginMessage, SendEntityMetadata,
-            SpawnPlayer, Title, UnloadChunk, UpdateViewPosition, WindowItems,
+            DestroyEntities, Disconnect, EntityAnimation, EntityHeadLook, EntityPosition,
+            EntityPositionAndRotation, EntityRotation, EntityTeleport, JoinGame, PlayerInfo,
+            PlayerPositionAndLook, PluginMessage, Respawn, SendEntityMetadata, SpawnPlayer, Title,
+            UnloadChunk, UpdateViewPosition, WindowItems,
         },
     },
     ClientPlayPacket, Nbt, ProtocolVersion, ServerPlayPacket, Writeable,
@@ -33,7 +41,12 @@ use quill_common::components::OnGround;
 use uuid::Uuid;
 use vec_arena::Arena;
 
-use crate::{initial_handler::NewPlayer, network_id_registry::NetworkId, Options};
+use crate::{
+    initial_handler::NewPlayer,
+    keepalive::{KeepAlive, KeepAliveAction, UnexpectedKeepAlive},
+    network_id_registry::NetworkId,
+    Options,
+};
 
 /// Max number of chunks to send to a client per tick.
 const MAX_CHUNKS_PER_TICK: usize = 10;
@@ -81,8 +94,10 @@ pub struct Client {
     username: String,
     profile: Vec<ProfileProperty>,
     uuid: Uuid,
+    address: IpAddr,
 
     teleport_id_counter: Cell<i32>,
+    keepalive: RefCell<KeepAlive>,
 
     network_id: NetworkId,
     sent_entities: RefCell<AHashSet<NetworkId>>,
@@ -107,9 +122,11 @@ impl Client {
             options,
             username: player.username,
             teleport_id_counter: Cell::new(0),
+            keepalive: RefCell::new(KeepAlive::new(Instant::now())),
             network_id,
             profile: player.profile,
             uuid: player.uuid,
+            address: player.address,
             sent_entities: RefCell::new(AHashSet::new()),
             knows_position: Cell::new(false),
             known_chunks: RefCell::new(AHashSet::new()),
@@ -143,6 +160,13 @@ impl Client {
         &self.username
     }
 
+    /// Returns the address of the client. Behind a proxy with
+    /// IP forwarding enabled, this is the address of the player
+    /// rather than the proxy.
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
     pub fn received_packets(&self) -> impl Iterator<Item = ClientPlayPacket> + '_ {
         self.received_packets.try_iter()
     }
@@ -179,27 +203,32 @@ impl Client {
         self.sent_entities.borrow().contains(&network_id)
     }
 
-    pub fn send_join_game(&self, gamemode: Gamemode) {
+    /// Sends Join Game, placing the client in `dimension`.
+    ///
+    /// `world_names` are the names of the worlds of all
+    /// registered dimensions.
+    pub fn send_join_game(
+        &self,
+        gamemode: Gamemode,
+        dimension: &DimensionInfo,
+        world_names: Vec<String>,
+    ) {
         log::trace!("Sending Join Game to {}", self.username);
         // Use the dimension codec sent by the default vanilla server. (Data acquired via tools/proxy)
         let dimension_codec = nbt::Blob::from_reader(&mut Cursor::new(include_bytes!(
             "../../../assets/dimension_codec.nbt"
         )))
         .expect("dimension codec asset is malformed");
-        let dimension = nbt::Blob::from_reader(&mut Cursor::new(include_bytes!(
-            "../../../assets/dimension.nbt"
-        )))
-        .expect("dimension asset is malformed");
 
         self.send_packet(JoinGame {
             entity_id: self.network_id.0,
             is_hardcore: false,
             gamemode,
             previous_gamemode: 0,
-            world_names: vec!["world".to_owned()],
+            world_names,
             dimension_codec: Nbt(dimension_codec),
-            dimension: Nbt(dimension),
-            world_name: "world".to_owned(),
+            dimension: Nbt(dimension_type(dimension.kind)),
+            world_name: dimension.world_name.clone(),
             hashed_seed: 0,
             max_players: 0,
             view_distance: self.options.view_distance as i32,
@@ -210,6 +239,34 @@ impl Client {
         });
     }
 
+    /// Moves the client into `dimension`.
+    ///
+    /// The client discards all chunks and entities when
+    /// it receives the Respawn packet, so they have to be sent
+    /// again, followed by the player's own position.
+    pub fn send_respawn(&self, gamemode: Gamemode, dimension: &DimensionInfo) {
+        log::trace!(
+            "Sending Respawn({}) to {}",
+            dimension.world_name,
+            self.username
+        );
+        self.send_packet(Respawn {
+            dimension: Nbt(dimension_type(dimension.kind)),
+            world_name: dimension.world_name.clone(),
+            hashed_seed: 0,
+            gamemode,
+            previous_gamemode: gamemode,
+            is_debug: false,
+            is_flat: false,
+            copy_metadata: true,
+        });
+
+        self.known_chunks.borrow_mut().clear();
+        self.sent_entities.borrow_mut().clear();
+        self.chunk_send_queue.borrow_mut().clear();
+        self.knows_position.set(false);
+    }
+
     pub fn send_brand(&self) {
         let mut data = Vec::new();
         "Feather"
@@ -365,11 +422,13 @@ impl Client {
         });
     }
 
+    /// Sends the packets returned by [`movement_packets`]
+    /// for the entity with the given network ID.
     pub fn update_entity_position(
         &self,
         network_id: NetworkId,
         position: Position,
-        on_ground: OnGround,
+        packets: &[ServerPlayPacket],
     ) {
         if network_id == self.network_id {
             // This entity is the client. Only update
@@ -380,28 +439,63 @@ impl Client {
             }
             return;
         }
-        // Consider using the relative movement packets in the future.
-        // (Entity Teleport works fine, but the relative movement packets
-        // save bandwidth.)
-        self.send_packet(EntityTeleport {
-            entity_id: network_id.0,
-            x: position.x,
-            y: position.y,
-            z: position.z,
-            yaw: position.yaw,
-            pitch: position.pitch,
-            on_ground: on_ground.0,
-        });
-        // Needed for head orientation
-        self.send_packet(EntityHeadLook {
+        for packet in packets {
+            self.send_packet(packet.clone());
+        }
+    }
+
+    /// Attaches a lead from the given entity to `holder`,
+    /// or removes its lead if `holder` is `None`.
+    pub fn send_attach_entity(&self, attached: NetworkId, holder: Option<NetworkId>) {
+        log::trace!(
+            "Attaching {:?} to {:?} on {}",
+            attached,
+            holder,
+            self.username
+        );
+        self.send_packet(attach_entity_packet(attached, holder));
+    }
+
+    /// Applies a status effect to the given entity.
+    pub fn send_entity_effect(&self, network_id: NetworkId, effect: ActiveEffect) {
+        self.send_packet(entity_effect_packet(network_id, effect));
+    }
+
+    /// Removes a status effect from the given entity.
+    pub fn send_remove_entity_effect(&self, network_id: NetworkId, effect: StatusEffect) {
+        self.send_packet(RemoveEntityEffect {
             entity_id: network_id.0,
-            head_yaw: position.yaw,
+            effect_id: effect.id(),
         });
     }
 
-    pub fn send_keepalive(&self) {
-        log::trace!("Sending keepalive to {}", self.username);
-        self.send_packet(KeepAlive { id: 0 });
+    /// Sends a keep-alive if one is due, or disconnects
+    /// the client if it didn't respond to the last one in time.
+    pub fn tick_keepalive(&self, now: Instant) {
+        let action = self.keepalive.borrow_mut().tick(now);
+        match action {
+            KeepAliveAction::Send(id) => {
+                log::trace!("Sending keepalive to {}", self.username);
+                self.send_packet(packets::server::KeepAlive { id });
+            }
+            KeepAliveAction::TimedOut => {
+                log::debug!("{} timed out", self.username);
+                self.disconnect("Timed out");
+            }
+            KeepAliveAction::None => {}
+        }
+    }
+
+    /// Handles a keep-alive response from the client.
+    pub fn handle_keepalive(&self, id: u64) {
+        let result = self.keepalive.borrow_mut().respond(id, Instant::now());
+        match result {
+            Ok(latency) => log::trace!("Latency of {} is {:?}", self.username, latency),
+            Err(UnexpectedKeepAlive(id)) => {
+                log::debug!("{} sent unexpected keepalive {}", self.username, id);
+                self.disconnect("Timed out");
+            }
+        }
     }
 
     pub fn send_entity_animation(&self, network_id: NetworkId, animation: Animation) {
@@ -520,6 +614,13 @@ impl Client {
         });
     }
 
+    pub fn send_entity_metadata(&self, network_id: NetworkId, entries: EntityMetadata) {
+        self.send_packet(SendEntityMetadata {
+            entity_id: network_id.0,
+            entries,
+        });
+    }
+
     fn register_entity(&self, network_id: NetworkId) {
         self.sent_entities.borrow_mut().insert(network_id);
     }
@@ -547,3 +648,217 @@ fn chat_packet(message: ChatMessage) -> packets::server::ChatMessage {
         sender: Uuid::default(),
     }
 }
+
+fn attach_entity_packet(attached: NetworkId, holder: Option<NetworkId>) -> AttachEntity {
+    AttachEntity {
+        attached_entity_id: attached.0,
+        // -1 detaches the lead
+        holding_entity_id: holder.map(|id| id.0).unwrap_or(-1),
+    }
+}
+
+fn entity_effect_packet(network_id: NetworkId, effect: ActiveEffect) -> EntityEffect {
+    EntityEffect {
+        entity_id: network_id.0,
+        effect_id: effect.effect.id(),
+        amplifier: effect.amplifier,
+        duration: effect.duration as i32,
+        // Show particles and icon
+        flags: 0x06,
+    }
+}
+
+/// Largest distance along each axis which fits
+/// in a relative move packet, in blocks.
+const MAX_RELATIVE_MOVE: f64 = i16::MAX as f64 / 4096.0;
+
+/// Returns the smallest packets moving the entity with the given
+/// network ID from `known`, the position last sent to clients,
+/// to `position`, along with the position clients will
+/// know after receiving them.
+///
+/// No packets are returned if the entity hasn't moved or turned.
+/// Movement within `MAX_RELATIVE_MOVE` uses relative move packets;
+/// larger movement uses a teleport. A head look is added if the
+/// yaw changed.
+pub fn movement_packets(
+    network_id: NetworkId,
+    known: Position,
+    position: Position,
+    on_ground: OnGround,
+) -> (Vec<ServerPlayPacket>, Position) {
+    let entity_id = network_id.0;
+    let on_ground = on_ground.0;
+    // Deltas are sent in units of 1/4096 blocks.
+    let delta = |from: f64, to: f64| ((to - from) * 4096.0).round();
+    let (dx, dy, dz) = (
+        delta(known.x, position.x),
+        delta(known.y, position.y),
+        delta(known.z, position.z),
+    );
+    let moved = dx != 0.0 || dy != 0.0 || dz != 0.0;
+    let turned = known.yaw != position.yaw || known.pitch != position.pitch;
+
+    let mut packets = Vec::new();
+    let mut new_known = position;
+    if [dx, dy, dz]
+        .iter()
+        .any(|delta| delta.abs() > MAX_RELATIVE_MOVE * 4096.0)
+    {
+        packets.push(
+            EntityTeleport {
+                entity_id,
+                x: position.x,
+                y: position.y,
+                z: position.z,
+                yaw: position.yaw,
+                pitch: position.pitch,
+                on_ground,
+            }
+            .into(),
+        );
+    } else {
+        // Clients accumulate the rounded deltas,
+        // so track the position they will compute.
+        new_known.x = known.x + dx / 4096.0;
+        new_known.y = known.y + dy / 4096.0;
+        new_known.z = known.z + dz / 4096.0;
+
+        let (delta_x, delta_y, delta_z) = (dx as i16, dy as i16, dz as i16);
+        if moved && turned {
+            packets.push(
+                EntityPositionAndRotation {
+                    entity_id,
+                    delta_x,
+                    delta_y,
+                    delta_z,
+                    yaw: position.yaw,
+                    pitch: position.pitch,
+                    on_ground,
+                }
+                .into(),
+            );
+        } else if moved {
+            packets.push(
+                EntityPosition {
+                    entity_id,
+                    delta_x,
+                    delta_y,
+                    delta_z,
+                    on_ground,
+                }
+                .into(),
+            );
+        } else if turned {
+            packets.push(
+                EntityRotation {
+                    entity_id,
+                    yaw: position.yaw,
+                    pitch: position.pitch,
+                    on_ground,
+                }
+                .into(),
+            );
+        }
+    }
+
+    if known.yaw != position.yaw {
+        packets.push(
+            EntityHeadLook {
+                entity_id,
+                head_yaw: position.yaw,
+            }
+            .into(),
+        );
+    }
+    (packets, new_known)
+}
+
+/// Returns the dimension type sent in Join Game and Respawn,
+/// taken from the dimension codec of the default vanilla server.
+///
+/// Custom dimensions use the type of the vanilla dimension
+/// they are rendered as.
+fn dimension_type(dimension: Dimension) -> nbt::Blob {
+    let bytes: &[u8] = match dimension {
+        Dimension::Overworld => include_bytes!("../../../assets/dimension.nbt"),
+        Dimension::TheNether => include_bytes!("../../../assets/dimension_the_nether.nbt"),
+        Dimension::TheEnd => include_bytes!("../../../assets/dimension_the_end.nbt"),
+    };
+    nbt::Blob::from_reader(&mut Cursor::new(bytes)).expect("dimension asset is malformed")
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+
+    #[test]
+    fn attach_entity_packet_encodes_holder() {
+        let packet = attach_entity_packet(NetworkId(5), Some(NetworkId(8)));
+        assert_eq!(packet.attached_entity_id, 5);
+        assert_eq!(packet.holding_entity_id, 8);
+
+        let mut buffer = Vec::new();
+        packet.write(&mut buffer, ProtocolVersion::V1_16_2);
+        assert_eq!(buffer, [0, 0, 0, 5, 0, 0, 0, 8]);
+
+        let packet = attach_entity_packet(NetworkId(5), None);
+        assert_eq!(packet.holding_entity_id, -1);
+    }
+
+    #[test]
+    fn entity_effect_packet_uses_protocol_id() {
+        let packet = entity_effect_packet(
+            NetworkId(3),
+            ActiveEffect {
+                effect: StatusEffect::Poison,
+                amplifier: 1,
+                duration: 200,
+            },
+        );
+        assert_eq!(packet.entity_id, 3);
+        assert_eq!(packet.effect_id, 19);
+        assert_eq!(packet.amplifier, 1);
+        assert_eq!(packet.duration, 200);
+    }
+
+    #[test]
+    fn dimension_types_load() {
+        assert_ne!(
+            dimension_type(Dimension::Overworld),
+            dimension_type(Dimension::TheNether)
+        );
+        assert_ne!(
+            dimension_type(Dimension::Overworld),
+            dimension_type(Dimension::TheEnd)
+        );
+    }
+
+    #[test]
+    fn small_movement_uses_relative_moves() {
+        let id = NetworkId(7);
+        let mut known = position!(0.0, 64.0, 0.0);
+        let mut position = known;
+        for _ in 0..20 {
+            position.x += 0.13;
+            position.z -= 0.07;
+            let (packets, new_known) = movement_packets(id, known, position, OnGround(true));
+            assert_eq!(packets.len(), 1);
+            assert!(matches!(packets[0], ServerPlayPacket::EntityPosition(_)));
+            known = new_known;
+        }
+        assert!((known.x - position.x).abs() < 1.0 / 4096.0);
+
+        // Unmoved
+        let (packets, _) = movement_packets(id, known, known, OnGround(true));
+        assert!(packets.is_empty());
+
+        // Too far for a relative move
+        position.y += 10.0;
+        let (packets, new_known) = movement_packets(id, known, position, OnGround(false));
+        assert!(matches!(packets[0], ServerPlayPacket::EntityTeleport(_)));
+        assert_eq!(new_known, position);
+    }
+}
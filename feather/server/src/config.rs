@@ -1,10 +1,13 @@
 //! Loads an `Options` from a TOML config.
 
-use std::{fs, net::Ipv4Addr, path::Path, str::FromStr};
+use std::{fs, net::Ipv4Addr, path::Path, str::FromStr, time::Duration};
 
 use anyhow::Context;
 use base::Gamemode;
+use libcraft_core::Dimension;
 use serde::{Deserialize, Deserializer};
+use uuid::Uuid;
+use worldgen::{GeneratorSettings, SKY_LIMIT};
 
 use crate::{favicon::Favicon, Options};
 
@@ -32,7 +35,16 @@ pub struct Config {
     pub server: ServerConfig,
     pub log: Log,
     pub world: World,
+    #[serde(default)]
+    pub worldgen: Worldgen,
+    /// Dimensions added to the overworld, the Nether, and the End.
+    #[serde(default)]
+    pub dimensions: Vec<CustomDimension>,
     pub proxy: Proxy,
+    #[serde(default)]
+    pub query: Query,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
 }
 
 impl Config {
@@ -47,7 +59,7 @@ impl Config {
             } else {
                 self.server.online_mode
             },
-            compression_threshold: if self.network.compression_threshold <= 0 {
+            compression_threshold: if self.network.compression_threshold < 0 {
                 None
             } else {
                 Some(self.network.compression_threshold as usize)
@@ -61,6 +73,23 @@ impl Config {
                 ProxyMode::Velocity => Some(crate::options::ProxyMode::Velocity),
             },
             velocity_secret: self.proxy.velocity_secret.clone(),
+            query_port: if self.query.enabled {
+                Some(self.query.port)
+            } else {
+                None
+            },
+            level_name: self.world.name.clone(),
+            rate_limit: if self.rate_limit.enabled {
+                Some(crate::options::RateLimit {
+                    max_packets_per_second: self.rate_limit.max_packets_per_second,
+                    max_bytes_per_second: self.rate_limit.max_bytes_per_second,
+                    kicks_before_ban: self.rate_limit.kicks_before_ban,
+                    ban_duration: Duration::from_secs(self.rate_limit.ban_minutes * 60),
+                })
+            } else {
+                None
+            },
+            operators: self.server.operators.clone(),
         }
     }
 }
@@ -72,6 +101,44 @@ pub struct Network {
     pub compression_threshold: i32,
 }
 
+/// Settings of the GameSpy4 query protocol.
+#[derive(Debug, Deserialize)]
+pub struct Query {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 25565,
+        }
+    }
+}
+
+/// Limits on the packets received from each connection.
+#[derive(Debug, Deserialize)]
+pub struct RateLimit {
+    pub enabled: bool,
+    pub max_packets_per_second: u32,
+    pub max_bytes_per_second: u64,
+    pub kicks_before_ban: u32,
+    pub ban_minutes: u64,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_packets_per_second: 500,
+            max_bytes_per_second: 1_048_576,
+            kicks_before_ban: 3,
+            ban_minutes: 10,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ServerConfig {
     pub online_mode: bool,
@@ -79,6 +146,10 @@ pub struct ServerConfig {
     pub max_players: u32,
     pub default_gamemode: Gamemode,
     pub view_distance: u32,
+    /// UUIDs of the players allowed to
+    /// run operator commands.
+    #[serde(default)]
+    pub operators: Vec<Uuid>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -90,8 +161,159 @@ pub struct Log {
 #[derive(Debug, Deserialize)]
 pub struct World {
     pub name: String,
-    pub generator: String,
+    pub generator: GeneratorKind,
     pub seed: String,
+    /// Vanilla-style layer string for the flat generator.
+    /// Empty for the default layers.
+    #[serde(default)]
+    pub flat_layers: String,
+    /// Radius, in chunks, of the area around spawn
+    /// generated in the background on startup.
+    #[serde(default)]
+    pub pregen_radius: u32,
+}
+
+impl World {
+    /// Returns the world seed given in the config.
+    ///
+    /// An empty seed is replaced with a random one, and seeds
+    /// which aren't valid integers are hashed like vanilla does.
+    pub fn seed(&self) -> i64 {
+        if self.seed.is_empty() {
+            rand::random()
+        } else {
+            self.seed.parse().unwrap_or_else(|_| hash_seed(&self.seed))
+        }
+    }
+}
+
+/// Hashes a string seed using Java's `String.hashCode`.
+fn hash_seed(seed: &str) -> i64 {
+    seed.encode_utf16()
+        .fold(0i32, |hash, c| hash.wrapping_mul(31).wrapping_add(c as i32)) as i64
+}
+
+/// The generator used for chunks missing from the world save.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GeneratorKind {
+    /// Noise-based terrain with biomes and structures.
+    Default,
+    /// The default generator with much taller terrain.
+    Amplified,
+    /// The default generator with larger biomes.
+    LargeBiomes,
+    /// A superflat world.
+    Flat,
+    /// A world without any blocks.
+    Void,
+}
+
+impl GeneratorKind {
+    /// Returns the generator name stored in level.dat.
+    pub fn name(self) -> &'static str {
+        match self {
+            GeneratorKind::Default => "default",
+            GeneratorKind::Amplified => "amplified",
+            GeneratorKind::LargeBiomes => "largeBiomes",
+            GeneratorKind::Flat => "flat",
+            GeneratorKind::Void => "void",
+        }
+    }
+}
+
+/// A dimension added through the config.
+#[derive(Debug, Deserialize)]
+pub struct CustomDimension {
+    /// ID of the dimension, other than those of the vanilla dimensions.
+    pub id: i32,
+    /// Name of the dimension's world, sent to clients.
+    pub name: String,
+    /// The vanilla dimension, by namespaced ID, whose
+    /// sky and lighting clients render this dimension with.
+    #[serde(default = "default_dimension_kind")]
+    pub kind: Dimension,
+    /// The generator of chunks missing from the dimension's save.
+    pub generator: GeneratorKind,
+}
+
+impl CustomDimension {
+    /// Returns the directory of the dimension's
+    /// save, named like vanilla's.
+    pub fn directory(&self, world_directory: &str) -> String {
+        format!("{}/DIM{}", world_directory, self.id)
+    }
+}
+
+fn default_dimension_kind() -> Dimension {
+    Dimension::Overworld
+}
+
+/// Settings customizing the default, amplified
+/// and large biomes generators.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Worldgen {
+    /// Frequency of the terrain noise. Unset to use the preset's.
+    pub frequency: Option<f32>,
+    /// Factor applied to the height of the terrain.
+    /// Unset to use the preset's.
+    pub amplitude: Option<f32>,
+    pub sea_level: usize,
+    /// Multiplier applied to the number of ore veins.
+    pub ore_rate: f32,
+    pub caves: bool,
+    pub ores: bool,
+    pub lakes: bool,
+    pub trees: bool,
+    pub structures: bool,
+}
+
+impl Default for Worldgen {
+    fn default() -> Self {
+        Self::from_settings(&GeneratorSettings::default())
+    }
+}
+
+impl Worldgen {
+    fn from_settings(settings: &GeneratorSettings) -> Self {
+        Self {
+            frequency: settings.frequency,
+            amplitude: settings.height_scale,
+            sea_level: settings.sea_level,
+            ore_rate: settings.ore_rate,
+            caves: settings.caves,
+            ores: settings.ores,
+            lakes: settings.lakes,
+            trees: settings.trees,
+            structures: settings.structures,
+        }
+    }
+
+    /// Converts the config section into the generator's settings.
+    pub fn to_settings(&self) -> anyhow::Result<GeneratorSettings> {
+        if self.sea_level > SKY_LIMIT {
+            anyhow::bail!("worldgen.sea_level must be at most {}", SKY_LIMIT);
+        }
+        if self.ore_rate < 0.0 {
+            anyhow::bail!("worldgen.ore_rate must not be negative");
+        }
+        if self.frequency.map_or(false, |f| f <= 0.0) || self.amplitude.map_or(false, |a| a <= 0.0)
+        {
+            anyhow::bail!("worldgen.frequency and worldgen.amplitude must be positive");
+        }
+        Ok(GeneratorSettings {
+            frequency: self.frequency,
+            height_scale: self.amplitude,
+            sea_level: self.sea_level,
+            ore_rate: self.ore_rate,
+            caves: self.caves,
+            ores: self.ores,
+            lakes: self.lakes,
+            trees: self.trees,
+            structures: self.structures,
+        })
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -128,4 +350,76 @@ mod tests {
     fn default_config_is_valid() {
         let _config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
     }
+
+    #[test]
+    fn worldgen_settings_are_parsed() {
+        let config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert_eq!(
+            config.worldgen.to_settings().unwrap(),
+            GeneratorSettings::default()
+        );
+
+        let worldgen: Worldgen =
+            toml::from_str("amplitude = 1.5\nsea_level = 40\nore_rate = 2.0\nlakes = false")
+                .unwrap();
+        let settings = worldgen.to_settings().unwrap();
+        assert_eq!(settings.frequency, None);
+        assert_eq!(settings.height_scale, Some(1.5));
+        assert_eq!(settings.sea_level, 40);
+        assert_eq!(settings.ore_rate, 2.0);
+        assert!(!settings.lakes);
+        assert!(settings.caves);
+
+        let worldgen: Worldgen = toml::from_str("sea_level = 300").unwrap();
+        assert!(worldgen.to_settings().is_err());
+    }
+
+    #[test]
+    fn custom_dimensions_are_parsed() {
+        let config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert!(config.dimensions.is_empty());
+
+        let dimension: CustomDimension = toml::from_str(
+            "id = 2\nname = \"world_caves\"\nkind = \"minecraft:the_nether\"\ngenerator = \"void\"",
+        )
+        .unwrap();
+        assert_eq!(dimension.kind, Dimension::TheNether);
+        assert_eq!(dimension.generator, GeneratorKind::Void);
+        assert_eq!(dimension.directory("world"), "world/DIM2");
+
+        let dimension: CustomDimension =
+            toml::from_str("id = 3\nname = \"world_flat\"\ngenerator = \"flat\"").unwrap();
+        assert_eq!(dimension.kind, Dimension::Overworld);
+    }
+
+    #[test]
+    fn operators_are_parsed() {
+        let config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert!(config.server.operators.is_empty());
+
+        let server: ServerConfig = toml::from_str(
+            "online_mode = true\nmotd = \"\"\nmax_players = 1\n\
+             default_gamemode = \"survival\"\nview_distance = 2\n\
+             operators = [\"069a79f4-44e9-4726-a5be-fca90e38aaf5\"]",
+        )
+        .unwrap();
+        assert_eq!(
+            server.operators,
+            [Uuid::from_u128(0x069a79f4_44e9_4726_a5be_fca90e38aaf5)]
+        );
+    }
+
+    #[test]
+    fn seeds_are_parsed_or_hashed() {
+        let world = |seed: &str| World {
+            name: "world".to_owned(),
+            generator: GeneratorKind::Default,
+            seed: seed.to_owned(),
+            flat_layers: String::new(),
+            pregen_radius: 0,
+        };
+        assert_eq!(world("-1234").seed(), -1234);
+        assert_eq!(world("feather").seed(), -979_220_317);
+        assert_eq!(world("feather").seed(), world("feather").seed());
+    }
 }
@@ -1,8 +1,13 @@
-use std::{fmt::Debug, io, net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    fmt::Debug,
+    io,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use base::Text;
 use flume::{Receiver, Sender};
-use futures_lite::FutureExt;
 use io::ErrorKind;
 use protocol::{
     codec::CryptKey, packets::server::Disconnect, ClientPlayPacket, MinecraftCodec, Readable,
@@ -20,9 +25,14 @@ use tokio::{
 use crate::{
     initial_handler::{InitialHandling, NewPlayer},
     options::Options,
-    player_count::PlayerCount,
+    player_count::{OnlinePlayer, PlayerCount},
+    rate_limit::{self, Bans, RateLimitExceeded, RateLimiter},
 };
 
+/// How long to wait for the writer to send
+/// the disconnect to a client kicked for spamming.
+const KICK_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Tokio task which handles a connection and processes
 /// packets.
 ///
@@ -34,8 +44,12 @@ use crate::{
 pub struct Worker {
     reader: Reader,
     writer: Writer,
+    /// Address of the client, which is forwarded
+    /// by the proxy if there is one.
+    address: IpAddr,
     options: Arc<Options>,
     player_count: PlayerCount,
+    bans: Bans,
     packets_to_send_tx: Sender<ServerPlayPacket>,
     received_packets_rx: Receiver<ClientPlayPacket>,
     new_players: Sender<NewPlayer>,
@@ -44,23 +58,30 @@ pub struct Worker {
 impl Worker {
     pub fn new(
         stream: TcpStream,
-        _addr: SocketAddr,
+        addr: SocketAddr,
         options: Arc<Options>,
         player_count: PlayerCount,
+        bans: Bans,
         new_players: Sender<NewPlayer>,
     ) -> Self {
         let (reader, writer) = stream.into_split();
 
         let (received_packets_tx, received_packets_rx) = flume::bounded(32);
         let (packets_to_send_tx, packets_to_send_rx) = flume::unbounded();
-        let reader = Reader::new(reader, received_packets_tx);
+        let rate_limiter = options
+            .rate_limit
+            .clone()
+            .map(|limits| RateLimiter::new(limits, Instant::now()));
+        let reader = Reader::new(reader, received_packets_tx, rate_limiter);
         let writer = Writer::new(writer, packets_to_send_rx);
 
         Self {
             reader,
             writer,
+            address: addr.ip(),
             options,
             player_count,
+            bans,
             packets_to_send_tx,
             received_packets_rx,
             new_players,
@@ -77,7 +98,12 @@ impl Worker {
         let result = crate::initial_handler::handle(&mut self).await;
         match result {
             Ok(result) => self.proceed(result).await,
-            Err(e) => log::debug!("Initial handling failed: {:?}", e),
+            Err(e) => {
+                if let Some(exceeded) = e.downcast_ref::<RateLimitExceeded>() {
+                    record_kick(&self.bans, &self.options, self.address, exceeded);
+                }
+                log::debug!("Initial handling failed: {:?}", e);
+            }
         }
     }
 
@@ -85,6 +111,16 @@ impl Worker {
         match result {
             InitialHandling::Disconnect => (),
             InitialHandling::Join(new_player) => {
+                // Checked again now that a proxy may have forwarded the address.
+                if self.bans.is_banned(self.address, Instant::now()) {
+                    self.write(ServerPlayPacket::Disconnect(Disconnect {
+                        reason: Text::from("You are temporarily banned for spamming.").to_string(),
+                    }))
+                    .await
+                    .ok();
+                    return;
+                }
+
                 if self.player_count.try_add_player().is_err() {
                     self.write(ServerPlayPacket::Disconnect(Disconnect {
                         reason: Text::from("The server is full!").to_string(),
@@ -94,9 +130,13 @@ impl Worker {
                     return;
                 }
 
-                let username = new_player.username.clone();
+                let player = OnlinePlayer {
+                    name: new_player.username.clone(),
+                    id: new_player.uuid,
+                };
+                self.player_count.add_online(player.clone());
                 let _ = self.new_players.send_async(new_player).await;
-                self.split(username);
+                self.split(player);
             }
         }
     }
@@ -105,10 +145,24 @@ impl Worker {
         &self.options
     }
 
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    /// Sets the client address forwarded by a proxy.
+    pub fn set_address(&mut self, address: IpAddr) {
+        self.address = address;
+    }
+
     pub fn player_count(&self) -> u32 {
         self.player_count.get()
     }
 
+    /// Returns the sample of online players shown in the server list.
+    pub fn player_sample(&self) -> Vec<OnlinePlayer> {
+        self.player_count.sample()
+    }
+
     #[allow(unused)]
     pub fn enable_compression(&mut self, threshold: usize) {
         self.reader.codec.enable_compression(threshold);
@@ -128,26 +182,56 @@ impl Worker {
         self.reader.read().await
     }
 
+    /// Reads received bytes into `buf` without consuming them.
+    pub async fn peek(&mut self, buf: &mut [u8]) -> anyhow::Result<usize> {
+        let duration = Duration::from_secs(10);
+        Ok(timeout(duration, self.reader.stream.peek(buf)).await??)
+    }
+
+    /// Writes bytes to the stream without framing them as a packet.
+    pub async fn write_raw(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        self.writer.stream.write_all(bytes).await?;
+        Ok(())
+    }
+
     pub async fn write(&mut self, packet: impl Writeable + Debug) -> anyhow::Result<()> {
         self.writer.write(packet).await
     }
 
-    pub fn split(self, username: String) {
+    pub fn split(self, player: OnlinePlayer) {
         let Self {
             reader,
             writer,
+            address,
+            options,
             player_count,
+            bans,
+            packets_to_send_tx,
             ..
         } = self;
         let reader = tokio::task::spawn(async move { reader.run().await });
-        let writer = tokio::task::spawn(async move { writer.run().await });
+        let mut writer = tokio::task::spawn(async move { writer.run().await });
 
         tokio::task::spawn(async move {
-            let result = reader.race(writer).await.expect("task panicked");
+            let result = tokio::select! {
+                result = reader => result,
+                result = &mut writer => result,
+            }
+            .expect("task panicked");
             if let Err(e) = result {
+                if let Some(exceeded) = e.downcast_ref::<RateLimitExceeded>() {
+                    record_kick(&bans, &options, address, exceeded);
+                    // The writer stops once it has sent the disconnect.
+                    let _ = packets_to_send_tx.send(ServerPlayPacket::Disconnect(Disconnect {
+                        reason: Text::from(rate_limit::KICK_REASON).to_string(),
+                    }));
+                    let _ = timeout(KICK_TIMEOUT, &mut writer).await;
+                }
                 let message = disconnected_message(e);
-                log::debug!("{} lost connection: {}", username, message);
+                log::debug!("{} lost connection: {}", player.name, message);
             }
+            writer.abort();
+            player_count.remove_online(player.id);
             player_count.remove_player();
         });
     }
@@ -161,20 +245,41 @@ impl Worker {
     }
 }
 
+/// Records that a client was kicked for exceeding its rate
+/// limits, banning its address if it was kicked repeatedly.
+fn record_kick(bans: &Bans, options: &Options, address: IpAddr, exceeded: &RateLimitExceeded) {
+    log::info!("Kicking {}, which {}", address, exceeded);
+    if let Some(limits) = &options.rate_limit {
+        if bans.record_kick(address, limits, Instant::now()) {
+            log::warn!(
+                "Banned {} for {:?} after repeated packet spam",
+                address,
+                limits.ban_duration
+            );
+        }
+    }
+}
+
 struct Reader {
     stream: OwnedReadHalf,
     codec: MinecraftCodec,
     buffer: [u8; 512],
     received_packets: Sender<ClientPlayPacket>,
+    rate_limiter: Option<RateLimiter>,
 }
 
 impl Reader {
-    pub fn new(stream: OwnedReadHalf, received_packets: Sender<ClientPlayPacket>) -> Self {
+    pub fn new(
+        stream: OwnedReadHalf,
+        received_packets: Sender<ClientPlayPacket>,
+        rate_limiter: Option<RateLimiter>,
+    ) -> Self {
         Self {
             stream,
             codec: MinecraftCodec::new(),
             buffer: [0; 512],
             received_packets,
+            rate_limiter,
         }
     }
 
@@ -193,6 +298,9 @@ impl Reader {
         // Keep reading bytes and trying to get the packet.
         loop {
             if let Some(packet) = self.codec.next_packet::<P>()? {
+                if let Some(rate_limiter) = &mut self.rate_limiter {
+                    rate_limiter.record_packet(Instant::now())?;
+                }
                 return Ok(packet);
             }
 
@@ -202,6 +310,10 @@ impl Reader {
                 return Err(io::Error::new(ErrorKind::UnexpectedEof, "read 0 bytes").into());
             }
 
+            if let Some(rate_limiter) = &mut self.rate_limiter {
+                rate_limiter.record_bytes(read_bytes, Instant::now())?;
+            }
+
             let bytes = &self.buffer[..read_bytes];
             self.codec.accept(bytes);
         }
@@ -225,9 +337,15 @@ impl Writer {
         }
     }
 
+    /// Writes packets until the server drops the connection
+    /// or a `Disconnect` is sent.
     pub async fn run(mut self) -> anyhow::Result<()> {
         while let Ok(packet) = self.packets_to_send.recv_async().await {
+            let disconnect = matches!(packet, ServerPlayPacket::Disconnect(_));
             self.write(packet).await?;
+            if disconnect {
+                break;
+            }
         }
         Ok(())
     }
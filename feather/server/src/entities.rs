@@ -1,9 +1,10 @@
 use base::{EntityKind, Position};
 use ecs::{EntityBuilder, EntityRef, SysResult};
+use parking_lot::Mutex;
 use quill_common::entity_init::EntityInit;
 use uuid::Uuid;
 
-use crate::{Client, NetworkId};
+use crate::{network_id_registry::EntityIdAllocator, Client, NetworkId};
 
 /// Component that sends the spawn packet for an entity
 /// using its components.
@@ -21,9 +22,13 @@ impl SpawnPacketSender {
 #[derive(Copy, Clone, Debug)]
 pub struct PreviousPosition(pub Position);
 
-pub fn add_entity_components(builder: &mut EntityBuilder, init: &EntityInit) {
+pub fn add_entity_components(
+    builder: &mut EntityBuilder,
+    init: &EntityInit,
+    network_ids: &Mutex<EntityIdAllocator>,
+) {
     if !builder.has::<NetworkId>() {
-        builder.add(NetworkId::new());
+        builder.add(network_ids.lock().allocate());
     }
     builder.add(PreviousPosition(*builder.get::<Position>().unwrap()));
     add_spawn_packet(builder, init);
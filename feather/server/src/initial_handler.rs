@@ -1,6 +1,11 @@
 //! Initial handling of a connection.
 
-use crate::{connection_worker::Worker, favicon::Favicon};
+use crate::{
+    connection_worker::Worker,
+    favicon::Favicon,
+    player_count::OnlinePlayer,
+    rate_limit::{self, RateLimitExceeded},
+};
 use anyhow::bail;
 use base::{ProfileProperty, Text};
 use flume::{Receiver, Sender};
@@ -22,7 +27,7 @@ use rand::rngs::OsRng;
 use rsa::{PaddingScheme, PublicKeyParts, RSAPrivateKey};
 use serde::{Deserialize, Serialize};
 use sha1::Sha1;
-use std::convert::TryInto;
+use std::{convert::TryInto, net::IpAddr};
 use uuid::Uuid;
 
 use self::proxy::ProxyData;
@@ -30,6 +35,7 @@ use self::proxy::ProxyData;
 const SERVER_NAME: &str = "Feather 1.16.5";
 const PROTOCOL_VERSION: i32 = 754;
 
+mod legacy_ping;
 mod proxy;
 
 /// Information for a newly connected player.
@@ -38,6 +44,8 @@ pub struct NewPlayer {
     pub uuid: Uuid,
     pub username: String,
     pub profile: Vec<ProfileProperty>,
+    /// Address of the client, as forwarded by the proxy if there is one.
+    pub address: IpAddr,
 
     pub received_packets: Receiver<ClientPlayPacket>,
     pub packets_to_send: Sender<ServerPlayPacket>,
@@ -55,6 +63,12 @@ pub enum InitialHandling {
 /// Handles a connection until the protocol state is switched to Play;
 /// that is, until we send Login Success. Returns the client's information.
 pub async fn handle(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
+    // Clients before 1.7 ping with a packet that isn't framed like modern ones.
+    if let Some(format) = legacy_ping::detect(worker).await? {
+        legacy_ping::respond(worker, format).await?;
+        return Ok(InitialHandling::Disconnect);
+    }
+
     // Get the handshake packet.
     let handshake = worker.read::<ClientHandshakePacket>().await?;
 
@@ -64,24 +78,37 @@ pub async fn handle(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
         HandshakeState::Status => handle_status(worker).await,
         HandshakeState::Login => {
             if handshake.protocol_version < PROTOCOL_VERSION {
-                worker
-                    .write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
-                        reason: Text::from(
-                            "Invalid protocol! The server is running on version 1.16!",
-                        )
-                        .to_string(),
-                    }))
-                    .await
-                    .ok();
+                disconnect_login(
+                    worker,
+                    "Invalid protocol! The server is running on version 1.16!",
+                )
+                .await;
                 return Ok(InitialHandling::Disconnect);
             }
             let proxy_data =
                 if let Some(crate::options::ProxyMode::Bungeecord) = worker.options().proxy_mode {
-                    Some(proxy::do_bungee_ip_forwarding(&handshake)?)
+                    match proxy::do_bungee_ip_forwarding(&handshake) {
+                        Ok(proxy_data) => Some(proxy_data),
+                        Err(e) => {
+                            disconnect_login(
+                                worker,
+                                "If you wish to use IP forwarding, \
+                                 please enable it in your BungeeCord config as well!",
+                            )
+                            .await;
+                            return Err(e);
+                        }
+                    }
                 } else {
                     None
                 };
-            handle_login(worker, proxy_data).await
+            let result = handle_login(worker, proxy_data).await;
+            if let Err(e) = &result {
+                if e.is::<RateLimitExceeded>() {
+                    disconnect_login(worker, rate_limit::KICK_REASON).await;
+                }
+            }
+            result
         }
     }
 }
@@ -105,6 +132,8 @@ struct Version {
 struct Players {
     max: u32,
     online: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sample: Vec<OnlinePlayer>,
 }
 
 async fn handle_status(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
@@ -118,6 +147,7 @@ async fn handle_status(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
         players: Players {
             max: worker.options().max_players,
             online: worker.player_count(),
+            sample: worker.player_sample(),
         },
         description: Text::from(worker.options().motd.clone()),
         favicon: worker
@@ -160,7 +190,24 @@ async fn handle_login(
 
     // Velocity IP forwarding runs after Login Start is received.
     if let Some(crate::options::ProxyMode::Velocity) = worker.options().proxy_mode {
-        proxy_data = Some(proxy::do_velocity_ip_forwarding(worker).await?);
+        match proxy::do_velocity_ip_forwarding(worker).await {
+            Ok(data) => proxy_data = Some(data),
+            Err(e) => {
+                disconnect_login(worker, "This server requires you to connect with Velocity.")
+                    .await;
+                return Err(e);
+            }
+        }
+    }
+
+    if let Some(proxy_data) = &proxy_data {
+        match proxy_data.client.parse() {
+            Ok(address) => worker.set_address(address),
+            Err(_) => log::warn!(
+                "Proxy forwarded an invalid client address {:?}",
+                proxy_data.client
+            ),
+        }
     }
 
     if worker.options().online_mode {
@@ -211,6 +258,12 @@ static RSA_KEY_ENCODED: Lazy<Vec<u8>> = Lazy::new(|| {
     rsa_der::public_key_to_der(&RSA_KEY.n().to_bytes_be(), &RSA_KEY.e().to_bytes_be())
 });
 
+/// Generates the RSA key used for encryption, so that
+/// the first player to log in doesn't wait for it.
+pub fn init_rsa_key() {
+    Lazy::force(&RSA_KEY_ENCODED);
+}
+
 async fn enable_encryption(
     worker: &mut Worker,
     username: String,
@@ -219,9 +272,13 @@ async fn enable_encryption(
     let shared_secret = do_encryption_handshake(worker).await?;
     worker.enable_encryption(shared_secret);
 
-    let response = authenticate(shared_secret, username).await?;
-
-    finish_login(worker, response).await
+    match authenticate(shared_secret, username).await? {
+        Some(response) => finish_login(worker, response).await,
+        None => {
+            disconnect_login(worker, "Failed to verify username!").await;
+            Ok(InitialHandling::Disconnect)
+        }
+    }
 }
 
 async fn do_encryption_handshake(worker: &mut Worker) -> anyhow::Result<CryptKey> {
@@ -259,17 +316,27 @@ struct AuthResponse {
     properties: Vec<ProfileProperty>,
 }
 
-async fn authenticate(shared_secret: CryptKey, username: String) -> anyhow::Result<AuthResponse> {
+/// Asks the session server whether the player has joined this server.
+/// Returns `None` if the player failed to authenticate.
+async fn authenticate(
+    shared_secret: CryptKey,
+    username: String,
+) -> anyhow::Result<Option<AuthResponse>> {
     let server_hash = compute_server_hash(shared_secret);
 
-    let response: AuthResponse = tokio::task::spawn_blocking(move || {
+    let response = tokio::task::spawn_blocking(move || {
         let url = format!(
             "https://sessionserver.mojang.com/session/minecraft/hasJoined?username={}&serverId={}",
             username, server_hash
         );
         let response = ureq::get(&url).call()?;
 
-        Result::<AuthResponse, anyhow::Error>::Ok(response.into_json()?)
+        // The session server responds with no content
+        // if the client didn't join with this server hash.
+        if response.status() == 204 {
+            return Result::<_, anyhow::Error>::Ok(None);
+        }
+        Ok(Some(response.into_json()?))
     })
     .await??;
 
@@ -308,6 +375,7 @@ async fn finish_login(
         username: response.name,
         uuid: response.id,
         profile: response.properties,
+        address: worker.address(),
         received_packets: worker.received_packets(),
         packets_to_send: worker.packets_to_send(),
     };
@@ -315,6 +383,17 @@ async fn finish_login(
     Ok(InitialHandling::Join(new_player))
 }
 
+/// Disconnects a client during login, ignoring errors
+/// since the connection is closed afterwards anyway.
+async fn disconnect_login(worker: &mut Worker, reason: &'static str) {
+    worker
+        .write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
+            reason: Text::from(reason).to_string(),
+        }))
+        .await
+        .ok();
+}
+
 async fn enable_compression(worker: &mut Worker) -> anyhow::Result<()> {
     if let Some(threshold) = worker.options().compression_threshold {
         let packet = ServerLoginPacket::SetCompression(SetCompression {
@@ -325,3 +404,17 @@ async fn enable_compression(worker: &mut Worker) -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hexdigest_matches_minecraft() {
+        // Test vectors from https://wiki.vg/Protocol_Encryption
+        let digest = |name: &str| hexdigest(Sha1::digest(name.as_bytes()).as_slice());
+        assert_eq!(digest("Notch"), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+        assert_eq!(digest("jeb_"), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+        assert_eq!(digest("simon"), "88e16a1019277b15d58faf0541e11910eb756f6");
+    }
+}
@@ -0,0 +1,188 @@
+//! The server list ping of clients before 1.7.
+//!
+//! See <https://wiki.vg/Server_List_Ping#1.6>.
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::connection_worker::Worker;
+
+use super::SERVER_NAME;
+
+/// ID of the legacy ping packet.
+const PING: u8 = 0xFE;
+/// ID of the plugin message sent by 1.6 clients after the ping.
+const PLUGIN_MESSAGE: u8 = 0xFA;
+/// ID of the kick packet that carries the response.
+const KICK: u8 = 0xFF;
+
+/// Protocol version reported to legacy clients, which
+/// marks the server as incompatible in their server list.
+const LEGACY_PROTOCOL_VERSION: u32 = 127;
+
+/// Maximum length, in UTF-16 code units, of the string in
+/// a kick packet. Legacy clients reject longer strings.
+const MAX_RESPONSE_LENGTH: usize = 256;
+
+/// How long to wait for the bytes which tell
+/// legacy pings apart from modern handshakes.
+const DETECT_TIMEOUT: Duration = Duration::from_millis(500);
+/// Delay between peeks while waiting for those bytes.
+const PEEK_INTERVAL: Duration = Duration::from_millis(5);
+
+/// The response format understood by a legacy client.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Beta 1.8 to 1.3: MOTD and player counts.
+    Beta,
+    /// 1.4 to 1.6: also the protocol and server version.
+    Versioned,
+}
+
+/// Determines whether the connection starts with a legacy
+/// ping, without consuming any bytes.
+///
+/// A modern handshake in a 254-byte frame starts with `0xFE 0x01`
+/// too, so this waits for a third byte, which is the handshake packet
+/// ID, 0, rather than `0xFA`. Clients which send fewer bytes within
+/// `DETECT_TIMEOUT` are detected from the bytes they did send.
+pub async fn detect(worker: &mut Worker) -> anyhow::Result<Option<Format>> {
+    let deadline = Instant::now() + DETECT_TIMEOUT;
+    let mut buf = [0; 3];
+    loop {
+        let read = worker.peek(&mut buf).await?;
+        if read == 0 || is_conclusive(&buf[..read]) || Instant::now() >= deadline {
+            return Ok(detect_format(&buf[..read]));
+        }
+        tokio::time::sleep(PEEK_INTERVAL).await;
+    }
+}
+
+/// Returns whether `bytes` are enough to tell
+/// whether the connection starts with a legacy ping.
+fn is_conclusive(bytes: &[u8]) -> bool {
+    !matches!(bytes, [PING] | [PING, 0x01])
+}
+
+fn detect_format(bytes: &[u8]) -> Option<Format> {
+    match bytes {
+        [PING] => Some(Format::Beta),
+        [PING, 0x01] | [PING, 0x01, PLUGIN_MESSAGE] => Some(Format::Versioned),
+        _ => None,
+    }
+}
+
+/// Responds to a legacy ping with the server's status.
+/// The client closes the connection afterwards.
+pub async fn respond(worker: &mut Worker, format: Format) -> anyhow::Result<()> {
+    let options = worker.options();
+    let response = encode_response(
+        format,
+        &options.motd,
+        worker.player_count(),
+        options.max_players,
+    );
+    worker.write_raw(&response).await
+}
+
+fn encode_response(format: Format, motd: &str, online: u32, max: u32) -> Vec<u8> {
+    let status = |motd: &str| match format {
+        Format::Beta => format!("{}§{}§{}", motd, online, max),
+        Format::Versioned => format!(
+            "§1\0{}\0{}\0{}\0{}\0{}",
+            LEGACY_PROTOCOL_VERSION, SERVER_NAME, motd, online, max
+        ),
+    };
+    // Shorten the MOTD so the whole status fits in a kick packet.
+    let overhead = status("").encode_utf16().count();
+    let motd = truncate_utf16(motd, MAX_RESPONSE_LENGTH.saturating_sub(overhead));
+    let chars: Vec<u16> = status(motd).encode_utf16().collect();
+
+    let mut response = vec![KICK];
+    response.extend_from_slice(&(chars.len() as u16).to_be_bytes());
+    for c in chars {
+        response.extend_from_slice(&c.to_be_bytes());
+    }
+    response
+}
+
+/// Returns the longest prefix of `s` which is
+/// at most `max` UTF-16 code units long.
+fn truncate_utf16(s: &str, max: usize) -> &str {
+    let mut length = 0;
+    for (i, c) in s.char_indices() {
+        length += c.len_utf16();
+        if length > max {
+            return &s[..i];
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_are_detected() {
+        assert_eq!(detect_format(&[0xFE]), Some(Format::Beta));
+        assert_eq!(detect_format(&[0xFE, 0x01]), Some(Format::Versioned));
+        assert_eq!(detect_format(&[0xFE, 0x01, 0xFA]), Some(Format::Versioned));
+        // Handshake in a 254-byte frame
+        assert_eq!(detect_format(&[0xFE, 0x01, 0x00]), None);
+        assert_eq!(detect_format(&[0x10, 0x00, 0xF2]), None);
+    }
+
+    #[test]
+    fn detection_waits_for_ambiguous_prefixes() {
+        assert!(!is_conclusive(&[0xFE]));
+        assert!(!is_conclusive(&[0xFE, 0x01]));
+        assert!(is_conclusive(&[0xFE, 0x01, 0x00]));
+        assert!(is_conclusive(&[0xFE, 0x02]));
+        assert!(is_conclusive(&[0x10]));
+    }
+
+    #[test]
+    fn responses_are_utf16_kick_packets() {
+        assert_eq!(
+            encode_response(Format::Beta, "A§", 1, 20),
+            [
+                0xFF, 0x00, 0x07, 0x00, b'A', 0x00, 0xA7, 0x00, 0xA7, 0x00, b'1', 0x00, 0xA7, 0x00,
+                b'2', 0x00, b'0',
+            ]
+        );
+
+        let response = encode_response(Format::Versioned, "Hi", 3, 16);
+        let chars: Vec<u16> = response[3..]
+            .chunks(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        assert_eq!(
+            String::from_utf16(&chars).unwrap(),
+            format!("§1\u{0}127\u{0}{}\u{0}Hi\u{0}3\u{0}16", SERVER_NAME)
+        );
+        assert_eq!(
+            u16::from_be_bytes([response[1], response[2]]) as usize,
+            chars.len()
+        );
+    }
+
+    #[test]
+    fn long_motds_are_truncated() {
+        let motd = "é😀".repeat(200);
+        for &format in &[Format::Beta, Format::Versioned] {
+            let response = encode_response(format, &motd, 3, 16);
+            let length = u16::from_be_bytes([response[1], response[2]]) as usize;
+            assert!(length <= MAX_RESPONSE_LENGTH);
+            assert_eq!(response.len(), 3 + length * 2);
+
+            let chars: Vec<u16> = response[3..]
+                .chunks(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            // Surrogate pairs aren't split.
+            assert!(String::from_utf16(&chars).unwrap().ends_with("16"));
+        }
+    }
+}
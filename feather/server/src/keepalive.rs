@@ -0,0 +1,122 @@
+//! Keep-alive exchanges, which detect clients that stopped responding.
+
+use std::time::{Duration, Instant};
+
+/// Time between keep-alives sent to a client.
+pub const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+/// Time a client has to respond to a keep-alive
+/// before it is disconnected.
+pub const KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What to do after ticking a [`KeepAlive`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KeepAliveAction {
+    None,
+    /// Send a keep-alive with the given ID.
+    Send(u64),
+    /// The client didn't respond in time.
+    TimedOut,
+}
+
+/// The client responded with an ID that wasn't outstanding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UnexpectedKeepAlive(pub u64);
+
+/// Tracks the keep-alive exchange with a client.
+///
+/// Like vanilla, at most one keep-alive is outstanding at a time.
+#[derive(Debug)]
+pub struct KeepAlive {
+    next_id: u64,
+    /// The ID of the outstanding keep-alive and when it was sent.
+    pending: Option<(u64, Instant)>,
+    last_sent: Instant,
+}
+
+impl KeepAlive {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            next_id: 0,
+            pending: None,
+            last_sent: now,
+        }
+    }
+
+    pub fn tick(&mut self, now: Instant) -> KeepAliveAction {
+        match self.pending {
+            Some((_, sent)) if now.duration_since(sent) >= KEEPALIVE_TIMEOUT => {
+                KeepAliveAction::TimedOut
+            }
+            Some(_) => KeepAliveAction::None,
+            None if now.duration_since(self.last_sent) >= KEEPALIVE_INTERVAL => {
+                let id = self.next_id;
+                self.next_id = self.next_id.wrapping_add(1);
+                self.pending = Some((id, now));
+                self.last_sent = now;
+                KeepAliveAction::Send(id)
+            }
+            None => KeepAliveAction::None,
+        }
+    }
+
+    /// Handles a keep-alive response from the client,
+    /// returning the round-trip time.
+    pub fn respond(&mut self, id: u64, now: Instant) -> Result<Duration, UnexpectedKeepAlive> {
+        match self.pending {
+            Some((pending, sent)) if pending == id => {
+                self.pending = None;
+                Ok(now.duration_since(sent))
+            }
+            _ => Err(UnexpectedKeepAlive(id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keepalives_are_sent_and_answered() {
+        let start = Instant::now();
+        let mut keepalive = KeepAlive::new(start);
+        assert_eq!(keepalive.tick(start), KeepAliveAction::None);
+
+        let sent = start + KEEPALIVE_INTERVAL;
+        assert_eq!(keepalive.tick(sent), KeepAliveAction::Send(0));
+        // Only one keep-alive is outstanding.
+        assert_eq!(
+            keepalive.tick(sent + KEEPALIVE_INTERVAL),
+            KeepAliveAction::None
+        );
+
+        assert_eq!(keepalive.respond(1, sent), Err(UnexpectedKeepAlive(1)));
+        let received = sent + Duration::from_millis(50);
+        assert_eq!(
+            keepalive.respond(0, received),
+            Ok(Duration::from_millis(50))
+        );
+        assert_eq!(keepalive.respond(0, received), Err(UnexpectedKeepAlive(0)));
+
+        assert_eq!(
+            keepalive.tick(sent + KEEPALIVE_INTERVAL),
+            KeepAliveAction::Send(1)
+        );
+    }
+
+    #[test]
+    fn unanswered_keepalives_time_out() {
+        let start = Instant::now();
+        let mut keepalive = KeepAlive::new(start);
+        let sent = start + KEEPALIVE_INTERVAL;
+        assert_eq!(keepalive.tick(sent), KeepAliveAction::Send(0));
+        assert_eq!(
+            keepalive.tick(sent + KEEPALIVE_TIMEOUT - Duration::from_secs(1)),
+            KeepAliveAction::None
+        );
+        assert_eq!(
+            keepalive.tick(sent + KEEPALIVE_TIMEOUT),
+            KeepAliveAction::TimedOut
+        );
+    }
+}
@@ -1,14 +1,16 @@
 #![allow(clippy::unnecessary_wraps)] // systems are required to return Results
 
-use std::{sync::Arc, time::Instant};
+use std::sync::Arc;
 
 use base::Position;
 use chunk_subscriptions::ChunkSubscriptions;
-use common::Game;
+use common::{DimensionId, Game};
 use ecs::SystemExecutor;
 use flume::Receiver;
 use initial_handler::NewPlayer;
 use listener::Listener;
+use network_id_registry::EntityIdAllocator;
+use parking_lot::Mutex;
 
 mod chunk_subscriptions;
 pub mod client;
@@ -17,17 +19,22 @@ mod connection_worker;
 mod entities;
 pub mod favicon;
 mod initial_handler;
+mod keepalive;
 mod listener;
 mod network_id_registry;
 mod options;
 mod packet_handlers;
 mod player_count;
+mod query;
+mod rate_limit;
 mod systems;
 
 pub use client::{Client, ClientId, Clients};
 pub use network_id_registry::NetworkId;
 pub use options::Options;
 use player_count::PlayerCount;
+use query::QueryServer;
+use rate_limit::Bans;
 use systems::view::WaitingChunks;
 
 /// A Minecraft server.
@@ -45,9 +52,9 @@ pub struct Server {
     waiting_chunks: WaitingChunks,
     chunk_subscriptions: ChunkSubscriptions,
 
-    last_keepalive_time: Instant,
-
     player_count: PlayerCount,
+
+    network_ids: Arc<Mutex<EntityIdAllocator>>,
 }
 
 impl Server {
@@ -56,10 +63,20 @@ impl Server {
     /// Must be called within the context of a Tokio runtime.
     pub async fn bind(options: Options) -> anyhow::Result<Self> {
         let options = Arc::new(options);
+        if options.online_mode {
+            log::info!("Generating RSA keypair");
+            tokio::task::spawn_blocking(initial_handler::init_rsa_key).await?;
+        }
         let player_count = PlayerCount::new(options.max_players);
 
         let (new_players_tx, new_players) = flume::bounded(4);
-        Listener::start(Arc::clone(&options), player_count.clone(), new_players_tx).await?;
+        Listener::start(
+            Arc::clone(&options),
+            player_count.clone(),
+            Bans::new(),
+            new_players_tx,
+        )
+        .await?;
 
         log::info!(
             "Server is listening on {}:{}",
@@ -67,22 +84,30 @@ impl Server {
             options.port
         );
 
+        if let Some(port) = options.query_port {
+            QueryServer::start(Arc::clone(&options), player_count.clone()).await?;
+            log::info!("Query is listening on {}:{}", options.bind_address, port);
+        }
+
         Ok(Self {
             options,
             clients: Clients::new(),
             new_players,
             waiting_chunks: WaitingChunks::default(),
             chunk_subscriptions: ChunkSubscriptions::default(),
-            last_keepalive_time: Instant::now(),
             player_count,
+            network_ids: Arc::new(Mutex::new(EntityIdAllocator::new())),
         })
     }
 
     /// Links this server with a `Game` so that players connecting
     /// to the server become part of this `Game`.
     pub fn link_with_game(self, game: &mut Game, systems: &mut SystemExecutor<Game>) {
+        let network_ids = Arc::clone(&self.network_ids);
         systems::register(self, game, systems);
-        game.add_entity_spawn_callback(entities::add_entity_components);
+        game.add_entity_spawn_callback(move |builder, init| {
+            entities::add_entity_components(builder, init, &network_ids)
+        });
     }
 
     /// Gets the number of online players.
@@ -114,11 +139,21 @@ impl Server {
 
     /// Allocates a `NetworkId` for an entity.
     pub fn create_network_id(&mut self) -> NetworkId {
-        NetworkId::new()
+        self.network_ids.lock().allocate()
+    }
+
+    /// Releases the `NetworkId` of a removed entity
+    /// so that it can be reused.
+    pub fn release_network_id(&mut self, network_id: NetworkId) {
+        self.network_ids.lock().release(network_id);
     }
 
     fn create_client(&mut self, player: NewPlayer) -> ClientId {
-        log::debug!("Creating client for {}", player.username);
+        log::debug!(
+            "Creating client for {} ({})",
+            player.username,
+            player.address
+        );
         let network_id = self.create_network_id();
         let client = Client::new(player, Arc::clone(&self.options), network_id);
         self.clients.insert(client)
@@ -132,19 +167,22 @@ impl Server {
     }
 
     /// Sends a packet to all clients currently subscribed
-    /// to the given position. This function should be
+    /// to the given position in `dimension`. This function should be
     /// used for entity updates, block updates, etc—
     /// any packets that need to be sent only to nearby players.
-    pub fn broadcast_nearby_with(&self, position: Position, mut callback: impl FnMut(&Client)) {
-        for &client_id in self.chunk_subscriptions.subscriptions_for(position.chunk()) {
+    pub fn broadcast_nearby_with(
+        &self,
+        dimension: DimensionId,
+        position: Position,
+        mut callback: impl FnMut(&Client),
+    ) {
+        for &client_id in self
+            .chunk_subscriptions
+            .subscriptions_for(dimension, position.chunk())
+        {
             if let Some(client) = self.clients.get(client_id) {
                 callback(client);
             }
         }
     }
-
-    pub fn broadcast_keepalive(&mut self) {
-        self.broadcast_with(|client| client.send_keepalive());
-        self.last_keepalive_time = Instant::now();
-    }
 }
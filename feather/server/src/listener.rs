@@ -1,4 +1,4 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc, time::Instant};
 
 use anyhow::Context;
 use flume::Sender;
@@ -6,7 +6,7 @@ use tokio::net::{TcpListener, TcpStream};
 
 use crate::{
     connection_worker::Worker, initial_handler::NewPlayer, options::Options,
-    player_count::PlayerCount,
+    player_count::PlayerCount, rate_limit::Bans,
 };
 
 /// Listens for and accepts incoming connections.
@@ -14,6 +14,7 @@ pub struct Listener {
     listener: TcpListener,
     options: Arc<Options>,
     player_count: PlayerCount,
+    bans: Bans,
     new_players: Sender<NewPlayer>,
 }
 
@@ -21,6 +22,7 @@ impl Listener {
     pub async fn start(
         options: Arc<Options>,
         player_count: PlayerCount,
+        bans: Bans,
         new_players: Sender<NewPlayer>,
     ) -> anyhow::Result<()> {
         let listener = TcpListener::bind(format!("{}:{}", options.bind_address, options.port))
@@ -31,6 +33,7 @@ impl Listener {
             listener,
             options,
             player_count,
+            bans,
             new_players,
         };
         tokio::task::spawn(async move {
@@ -49,11 +52,19 @@ impl Listener {
     }
 
     async fn accept(&mut self, stream: TcpStream, addr: SocketAddr) {
+        // Behind a proxy, bans are checked once the
+        // client's address has been forwarded.
+        if self.options.proxy_mode.is_none() && self.bans.is_banned(addr.ip(), Instant::now()) {
+            log::debug!("Refusing connection from banned address {}", addr);
+            return;
+        }
+
         let worker = Worker::new(
             stream,
             addr,
             Arc::clone(&self.options),
             self.player_count.clone(),
+            self.bans.clone(),
             self.new_players.clone(),
         );
         worker.start();
@@ -1,42 +1,88 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, fs, io, rc::Rc, sync::Arc};
 
 use anyhow::Context;
+use base::{
+    anvil::level::{LevelData, SuperflatGeneratorOptions},
+    BlockPosition, ChunkPosition,
+};
 use common::{
-    world_source::{flat::FlatWorldSource, region::RegionWorldSource, WorldSource},
-    Game, TickLoop, World,
+    pregen::Pregenerator,
+    world_source::{
+        generating::{GeneratingWorldSource, SharedScheduler},
+        region::{RegionFiles, RegionWorldSource},
+        WorldSource,
+    },
+    DimensionId, DimensionInfo, Game, TickLoop, World,
 };
 use ecs::SystemExecutor;
-use feather_server::Server;
+use feather_server::{
+    config::{self, GeneratorKind},
+    Server,
+};
+use libcraft_core::Dimension;
 use plugin_host::PluginManager;
+use worldgen::{
+    find_spawn, ComposableGenerator, EmptyWorldGenerator, EndWorldGenerator, NetherWorldGenerator,
+    SuperflatWorldGenerator, WorldGenerator, WorldPreset,
+};
 
 mod logging;
+mod worldgen_bench;
 
 const PLUGINS_DIRECTORY: &str = "plugins";
 const WORLD_DIRECTORY: &str = "world";
+const NETHER_DIRECTORY: &str = "world/DIM-1";
+const END_DIRECTORY: &str = "world/DIM1";
+const LEVEL_FILE: &str = "world/level.dat";
 const CONFIG_PATH: &str = "config.toml";
+/// Radius, in chunks, of the area around the origin
+/// searched for the spawn point of a new world.
+const SPAWN_SEARCH_RADIUS: i32 = 8;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     println!("Loading configuration");
-    let config =
-        feather_server::config::load(CONFIG_PATH).context("failed to load configuration file")?;
+    let config = config::load(CONFIG_PATH).context("failed to load configuration file")?;
     logging::init(config.log.level);
 
+    if let Some(count) = bench_worldgen_chunks(std::env::args())? {
+        let seed = config.world.seed();
+        let generator = composable_generator(config.world.generator, &config.worldgen, seed)?;
+        worldgen_bench::run(&generator, count);
+        return Ok(());
+    }
+
     log::info!("Creating server");
     let options = config.to_options();
     let server = Server::bind(options).await?;
 
-    let game = init_game(server)?;
+    let game = init_game(server, &config)?;
 
     run(game);
 
     Ok(())
 }
 
-fn init_game(server: Server) -> anyhow::Result<Game> {
+fn init_game(server: Server, config: &config::Config) -> anyhow::Result<Game> {
     let mut game = Game::new();
     init_systems(&mut game, server);
-    init_world_source(&mut game);
+    let saved_level = load_level()?;
+    let seed = saved_level
+        .as_ref()
+        .map_or_else(|| config.world.seed(), |level| level.seed);
+    let generator = create_generator(
+        config.world.generator,
+        &config.world,
+        &config.worldgen,
+        seed,
+    )?;
+    let level = match saved_level {
+        Some(level) => level,
+        None => create_level(&config.world, seed, &generator)?,
+    };
+    game.spawn = BlockPosition::new(level.spawn_x, level.spawn_y, level.spawn_z);
+    init_world_source(&mut game, &config.world, &level, generator);
+    init_custom_dimensions(&mut game, config, level.seed)?;
     init_plugin_manager(&mut game)?;
     Ok(game)
 }
@@ -55,14 +101,207 @@ fn init_systems(game: &mut Game, server: Server) {
     game.system_executor = Rc::new(RefCell::new(systems));
 }
 
-fn init_world_source(game: &mut Game) {
-    // Load chunks from the world save first,
-    // and fall back to generating a superflat
-    // world otherwise. This is a placeholder:
-    // we don't have proper world generation yet.
-    let world_source =
-        RegionWorldSource::new(WORLD_DIRECTORY).with_fallback(FlatWorldSource::new());
-    game.world = World::with_source(world_source);
+/// Loads the level data of the world save, if
+/// it exists and is valid.
+fn load_level() -> anyhow::Result<Option<LevelData>> {
+    let mut file = match fs::File::open(LEVEL_FILE) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            log::info!("World save not found; creating it");
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // Generating the rest of an existing world with another seed
+    // would leave seams, so don't fall back to the config.
+    let level = LevelData::load_from_file(&mut file).context("failed to parse level.dat")?;
+    Ok(Some(level))
+}
+
+/// Creates and saves the level data for a new world,
+/// finding its spawn point with the given generator.
+fn create_level(
+    world: &config::World,
+    seed: i64,
+    generator: &Arc<dyn WorldGenerator>,
+) -> anyhow::Result<LevelData> {
+    log::info!("Searching for a spawn point");
+    let spawn = find_spawn(Arc::clone(generator), SPAWN_SEARCH_RADIUS).unwrap_or_else(|| {
+        log::warn!("No safe spawn point found near the origin");
+        BlockPosition::new(0, 100, 0)
+    });
+    log::info!("Spawn point is at {}, {}, {}", spawn.x, spawn.y, spawn.z);
+
+    let level = LevelData {
+        seed,
+        spawn_x: spawn.x,
+        spawn_y: spawn.y,
+        spawn_z: spawn.z,
+        generator_name: world.generator.name().to_owned(),
+        ..Default::default()
+    };
+    fs::create_dir_all(WORLD_DIRECTORY)?;
+    level.save_to_file(&mut fs::File::create(LEVEL_FILE)?)?;
+    Ok(level)
+}
+
+fn init_world_source(
+    game: &mut Game,
+    world: &config::World,
+    level: &LevelData,
+    generator: Arc<dyn WorldGenerator>,
+) {
+    let (region_files, scheduler) = register_dimension(
+        game,
+        DimensionId::OVERWORLD,
+        DimensionInfo::new("world", Dimension::Overworld),
+        WORLD_DIRECTORY,
+        generator,
+    );
+
+    let spawn = ChunkPosition::new(level.spawn_x >> 4, level.spawn_z >> 4);
+    let pregenerator = Pregenerator::new(scheduler, region_files, spawn);
+    if world.pregen_radius > 0 {
+        pregenerator.start(world.pregen_radius);
+    }
+    game.insert_resource(pregenerator);
+
+    register_dimension(
+        game,
+        DimensionId::THE_NETHER,
+        DimensionInfo::new("world_nether", Dimension::TheNether),
+        NETHER_DIRECTORY,
+        Arc::new(NetherWorldGenerator::new(level.seed as u64)),
+    );
+    register_dimension(
+        game,
+        DimensionId::THE_END,
+        DimensionInfo::new("world_the_end", Dimension::TheEnd),
+        END_DIRECTORY,
+        Arc::new(EndWorldGenerator::new(level.seed as u64)),
+    );
+}
+
+/// Registers the dimensions listed in the config.
+fn init_custom_dimensions(
+    game: &mut Game,
+    config: &config::Config,
+    seed: i64,
+) -> anyhow::Result<()> {
+    for dimension in &config.dimensions {
+        let id = DimensionId(dimension.id);
+        if game.dimensions.contains(id) {
+            anyhow::bail!(
+                "dimension {} ({}) in config reuses the ID of another dimension",
+                dimension.id,
+                dimension.name
+            );
+        }
+        let generator =
+            create_generator(dimension.generator, &config.world, &config.worldgen, seed)
+                .with_context(|| format!("invalid generator for dimension {}", dimension.name))?;
+        register_dimension(
+            game,
+            id,
+            DimensionInfo::new(dimension.name.clone(), dimension.kind),
+            &dimension.directory(WORLD_DIRECTORY),
+            generator,
+        );
+    }
+    Ok(())
+}
+
+/// Registers a dimension whose chunks are loaded from the world
+/// save in `directory`, or generated if they are missing.
+///
+/// Returns the region files and scheduler used by the dimension's
+/// world source, for other users of its world save.
+fn register_dimension(
+    game: &mut Game,
+    id: DimensionId,
+    info: DimensionInfo,
+    directory: &str,
+    generator: Arc<dyn WorldGenerator>,
+) -> (RegionFiles, SharedScheduler) {
+    let region_files = RegionFiles::new(directory);
+    let scheduler = SharedScheduler::new(Arc::clone(&generator));
+    let world_source = RegionWorldSource::with_files(region_files.clone())
+        .with_fallback(GeneratingWorldSource::with_scheduler(scheduler.clone()));
+    game.dimensions.register(
+        id,
+        info.with_generator(generator),
+        World::with_source(world_source),
+    );
+    (region_files, scheduler)
+}
+
+fn create_generator(
+    kind: GeneratorKind,
+    world: &config::World,
+    worldgen: &config::Worldgen,
+    seed: i64,
+) -> anyhow::Result<Arc<dyn WorldGenerator>> {
+    log::info!("Using {:?} world generator with seed {}", kind, seed);
+    let generator: Arc<dyn WorldGenerator> = match kind {
+        GeneratorKind::Default | GeneratorKind::Amplified | GeneratorKind::LargeBiomes => {
+            Arc::new(composable_generator(kind, worldgen, seed)?)
+        }
+        GeneratorKind::Flat => {
+            let options = if world.flat_layers.is_empty() {
+                SuperflatGeneratorOptions::default()
+            } else {
+                world
+                    .flat_layers
+                    .parse()
+                    .context("invalid flat_layers in config")?
+            };
+            Arc::new(SuperflatWorldGenerator { options })
+        }
+        GeneratorKind::Void => Arc::new(EmptyWorldGenerator {}),
+    };
+    Ok(generator)
+}
+
+/// Creates the `ComposableGenerator` for the given
+/// generator, which must be one of its presets.
+fn composable_generator(
+    kind: GeneratorKind,
+    worldgen: &config::Worldgen,
+    seed: i64,
+) -> anyhow::Result<ComposableGenerator> {
+    let preset = match kind {
+        GeneratorKind::Default => WorldPreset::Default,
+        GeneratorKind::Amplified => WorldPreset::Amplified,
+        GeneratorKind::LargeBiomes => WorldPreset::LargeBiomes,
+        kind => anyhow::bail!(
+            "the {} generator is not a composable generator",
+            kind.name()
+        ),
+    };
+    let settings = worldgen
+        .to_settings()
+        .context("invalid [worldgen] config")?;
+    Ok(ComposableGenerator::with_settings(
+        preset,
+        &settings,
+        seed as u64,
+    ))
+}
+
+/// Parses the `--bench-worldgen [chunks]` flag, which
+/// benchmarks the generator instead of starting the server.
+fn bench_worldgen_chunks(mut args: impl Iterator<Item = String>) -> anyhow::Result<Option<u32>> {
+    if !args.any(|arg| arg == "--bench-worldgen") {
+        return Ok(None);
+    }
+    match args.next() {
+        Some(count) if !count.starts_with("--") => count
+            .parse()
+            .map(Some)
+            .context("invalid chunk count for --bench-worldgen"),
+        _ => Ok(Some(worldgen_bench::DEFAULT_CHUNKS)),
+    }
 }
 
 fn init_plugin_manager(game: &mut Game) -> anyhow::Result<()> {
@@ -94,3 +333,30 @@ fn create_tick_loop(mut game: Game) -> TickLoop {
         false
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn bench_worldgen_flag() {
+        let parse = |a: &[&str]| bench_worldgen_chunks(args(a)).unwrap();
+        assert_eq!(parse(&["feather-server"]), None);
+        assert_eq!(
+            parse(&["feather-server", "--bench-worldgen"]),
+            Some(worldgen_bench::DEFAULT_CHUNKS)
+        );
+        assert_eq!(
+            parse(&["feather-server", "--bench-worldgen", "64"]),
+            Some(64)
+        );
+        assert!(bench_worldgen_chunks(args(&["feather-server", "--bench-worldgen", "x"])).is_err());
+    }
+}
@@ -1,17 +1,65 @@
-use std::sync::atomic::{AtomicI32, Ordering};
-
 /// An entity's ID used by the protocol
 /// in `entity_id` fields.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct NetworkId(pub i32);
 
-impl NetworkId {
-    /// Creates a new, unique network ID.
-    pub(crate) fn new() -> Self {
-        static NEXT: AtomicI32 = AtomicI32::new(0);
-        // In theory, this can overflow if the server
-        // creates 4 billion entities. The hope is that
-        // old entities will have died out at that point.
-        Self(NEXT.fetch_add(1, Ordering::SeqCst))
+/// Allocates unique `NetworkId`s.
+///
+/// IDs of removed entities are released back
+/// into a free list and reused, so that long-running
+/// servers don't exhaust the 32-bit ID space.
+#[derive(Debug, Default)]
+pub struct EntityIdAllocator {
+    /// The smallest ID which has never been allocated.
+    next: i32,
+    /// Released IDs available for reuse.
+    free: Vec<i32>,
+}
+
+impl EntityIdAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates an ID, reusing a released one if possible.
+    pub fn allocate(&mut self) -> NetworkId {
+        if let Some(id) = self.free.pop() {
+            return NetworkId(id);
+        }
+
+        let id = self.next;
+        self.next = self
+            .next
+            .checked_add(1)
+            .expect("all network IDs are in use");
+        NetworkId(id)
+    }
+
+    /// Releases an ID so that it can be allocated again.
+    ///
+    /// The ID must not be used by any entity afterward.
+    pub fn release(&mut self, id: NetworkId) {
+        debug_assert!(id.0 < self.next && !self.free.contains(&id.0));
+        self.free.push(id.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn released_ids_are_reused() {
+        let mut allocator = EntityIdAllocator::new();
+        let a = allocator.allocate();
+        let b = allocator.allocate();
+        assert_ne!(a, b);
+
+        allocator.release(a);
+        assert_eq!(allocator.allocate(), a);
+
+        let c = allocator.allocate();
+        assert_ne!(c, a);
+        assert_ne!(c, b);
     }
 }
@@ -1,4 +1,7 @@
+use std::time::Duration;
+
 use base::Gamemode;
+use uuid::Uuid;
 
 use crate::favicon::Favicon;
 
@@ -35,6 +38,20 @@ pub struct Options {
 
     /// Packet size threshold at which to compress data
     pub compression_threshold: Option<usize>,
+
+    /// UDP port to answer query requests on,
+    /// or `None` if the query protocol is disabled.
+    pub query_port: Option<u16>,
+    /// Name of the world, reported as the map by the query protocol.
+    pub level_name: String,
+
+    /// Limits on the packets received from each connection,
+    /// or `None` if they are unlimited.
+    pub rate_limit: Option<RateLimit>,
+
+    /// UUIDs of the players allowed to run
+    /// operator commands, such as `/pregen`.
+    pub operators: Vec<Uuid>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -42,3 +59,14 @@ pub enum ProxyMode {
     Bungeecord,
     Velocity,
 }
+
+/// Limits on the packets received from each connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_packets_per_second: u32,
+    pub max_bytes_per_second: u64,
+    /// Number of kicks within `ban_duration` after
+    /// which the client's address is banned.
+    pub kicks_before_ban: u32,
+    pub ban_duration: Duration,
+}
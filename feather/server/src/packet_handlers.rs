@@ -14,8 +14,9 @@ use protocol::{
 };
 use quill_common::components::Name;
 
-use crate::{NetworkId, Server};
+use crate::{ClientId, NetworkId, Server};
 
+mod commands;
 mod interaction;
 pub mod inventory;
 mod movement;
@@ -42,9 +43,9 @@ pub fn handle_packet(
             movement::handle_player_movement(player, packet)
         }
 
-        ClientPlayPacket::Animation(packet) => handle_animation(server, player, packet),
+        ClientPlayPacket::Animation(packet) => handle_animation(game, server, player_id, packet),
 
-        ClientPlayPacket::ChatMessage(packet) => handle_chat_message(game, player, packet),
+        ClientPlayPacket::ChatMessage(packet) => handle_chat_message(game, server, player, packet),
 
         ClientPlayPacket::PlayerDigging(packet) => handle_player_digging(game, packet, player_id),
 
@@ -65,6 +66,7 @@ pub fn handle_packet(
         }
 
         ClientPlayPacket::ClientSettings(packet) => handle_client_settings(server, player, packet),
+        ClientPlayPacket::KeepAlive(packet) => handle_keepalive(server, player, packet),
 
         ClientPlayPacket::TeleportConfirm(_)
         | ClientPlayPacket::QueryBlockNbt(_)
@@ -78,7 +80,6 @@ pub fn handle_packet(
         | ClientPlayPacket::EditBook(_)
         | ClientPlayPacket::QueryEntityNbt(_)
         | ClientPlayPacket::GenerateStructure(_)
-        | ClientPlayPacket::KeepAlive(_)
         | ClientPlayPacket::LockDifficulty(_)
         | ClientPlayPacket::VehicleMove(_)
         | ClientPlayPacket::SteerBoat(_)
@@ -105,11 +106,14 @@ pub fn handle_packet(
 }
 
 fn handle_animation(
+    game: &Game,
     server: &mut Server,
-    player: EntityRef,
+    player_id: Entity,
     packet: client::Animation,
 ) -> SysResult {
+    let player = game.ecs.entity(player_id)?;
     let pos = *player.get::<Position>()?;
+    let dimension = game.dimension_of(player_id);
     let network_id = *player.get::<NetworkId>()?;
 
     let animation = match packet.hand {
@@ -117,19 +121,35 @@ fn handle_animation(
         Hand::Off => Animation::SwingOffhand,
     };
 
-    server.broadcast_nearby_with(pos, |client| {
+    server.broadcast_nearby_with(dimension, pos, |client| {
         client.send_entity_animation(network_id, animation.clone())
     });
     Ok(())
 }
 
-fn handle_chat_message(game: &Game, player: EntityRef, packet: client::ChatMessage) -> SysResult {
+fn handle_chat_message(
+    game: &Game,
+    server: &Server,
+    player: EntityRef,
+    packet: client::ChatMessage,
+) -> SysResult {
+    if let Some(command) = packet.message.strip_prefix('/') {
+        return commands::handle_command(game, server, player, command);
+    }
+
     let name = player.get::<Name>()?;
     let message = Text::translate_with("chat.type.text", vec![name.to_string(), packet.message]);
     game.broadcast_chat(ChatKind::PlayerChat, message);
     Ok(())
 }
 
+fn handle_keepalive(server: &Server, player: EntityRef, packet: client::KeepAlive) -> SysResult {
+    if let Some(client) = server.clients.get(*player.get::<ClientId>()?) {
+        client.handle_keepalive(packet.id);
+    }
+    Ok(())
+}
+
 fn handle_client_settings(
     server: &mut Server,
     player: EntityRef,
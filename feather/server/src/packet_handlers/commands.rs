@@ -0,0 +1,57 @@
+//! Handles commands sent by players as chat messages.
+
+use common::{pregen::Pregenerator, ChatBox, Game};
+use ecs::{EntityRef, SysResult};
+use uuid::Uuid;
+
+use crate::Server;
+
+/// Maximum radius, in chunks, accepted by `/pregen`.
+const MAX_PREGEN_RADIUS: u32 = 256;
+
+/// Reply to players running operator commands
+/// without being an operator.
+const NO_PERMISSION: &str = "You don't have permission to use this command";
+
+/// Runs a command, without its leading slash,
+/// and replies to the player who sent it.
+pub fn handle_command(game: &Game, server: &Server, player: EntityRef, command: &str) -> SysResult {
+    let mut args = command.split_whitespace();
+    let reply = match args.next() {
+        Some("pregen") if !is_operator(server, &player)? => NO_PERMISSION.to_owned(),
+        Some("pregen") => pregen(game, args.next())?,
+        Some(name) => format!("Unknown command: {}", name),
+        None => "Unknown command".to_owned(),
+    };
+    player.get_mut::<ChatBox>()?.send_system(reply);
+    Ok(())
+}
+
+/// Returns whether `player` is listed as an operator in the config.
+fn is_operator(server: &Server, player: &EntityRef) -> anyhow::Result<bool> {
+    let uuid = *player.get::<Uuid>()?;
+    Ok(server.options.operators.contains(&uuid))
+}
+
+/// `/pregen <radius>`: pre-generates the chunks around spawn.
+fn pregen(game: &Game, radius: Option<&str>) -> anyhow::Result<String> {
+    let radius = match radius.map(str::parse::<u32>) {
+        Some(Ok(radius)) if radius <= MAX_PREGEN_RADIUS => radius,
+        _ => {
+            return Ok(format!(
+                "Usage: /pregen <radius>, with a radius of at most {} chunks",
+                MAX_PREGEN_RADIUS
+            ))
+        }
+    };
+
+    let pregenerator = game.resources.get::<Pregenerator>()?;
+    if pregenerator.start(radius) {
+        Ok(format!(
+            "Pre-generating chunks within {} chunks of spawn; see the console for progress",
+            radius
+        ))
+    } else {
+        Ok("Chunks are already being pre-generated".to_owned())
+    }
+}
@@ -53,7 +53,7 @@ pub fn handle_player_block_placement(
     );
 
     let block_kind = {
-        let result = game.block(packet.position);
+        let result = game.block(game.dimension_of(player), packet.position);
         match result {
             Some(block) => block.kind(),
             None => {
@@ -110,11 +110,12 @@ pub fn handle_player_block_placement(
 /// * Shooting arrows.
 /// * Eating.
 /// * Swapping items between the main and off hand.
-pub fn handle_player_digging(game: &mut Game, packet: PlayerDigging, _player: Entity) -> SysResult {
+pub fn handle_player_digging(game: &mut Game, packet: PlayerDigging, player: Entity) -> SysResult {
     log::trace!("Got player digging with status {:?}", packet.status);
     match packet.status {
         PlayerDiggingStatus::StartDigging | PlayerDiggingStatus::CancelDigging => {
-            game.break_block(packet.position);
+            let dimension = game.dimension_of(player);
+            game.break_block(dimension, packet.position);
             Ok(())
         }
         _ => Ok(()),
@@ -3,10 +3,26 @@ use std::sync::{
     Arc,
 };
 
+use parking_lot::Mutex;
+use rand::seq::SliceRandom;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Maximum number of players in the server list sample.
+const MAX_SAMPLE_SIZE: usize = 12;
+
+/// An online player, as listed in the server list sample.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OnlinePlayer {
+    pub name: String,
+    pub id: Uuid,
+}
+
 #[derive(Debug)]
 pub struct MaxPlayersReached;
 
-/// Maintains the server player count.
+/// Maintains the server player count and the
+/// list of online players.
 ///
 /// Can be cloned to create a new handle.
 #[derive(Clone)]
@@ -20,6 +36,7 @@ impl PlayerCount {
             inner: Arc::new(Inner {
                 count: AtomicU32::new(0),
                 max_players,
+                online: Mutex::new(Vec::new()),
             }),
         }
     }
@@ -50,11 +67,38 @@ impl PlayerCount {
     pub fn get(&self) -> u32 {
         self.inner.count.load(Ordering::Acquire)
     }
+
+    /// Lists a player who joined in the server list sample.
+    pub fn add_online(&self, player: OnlinePlayer) {
+        self.inner.online.lock().push(player);
+    }
+
+    /// Removes a player who left from the server list sample.
+    pub fn remove_online(&self, id: Uuid) {
+        self.inner.online.lock().retain(|player| player.id != id);
+    }
+
+    /// Returns all online players.
+    pub fn online(&self) -> Vec<OnlinePlayer> {
+        self.inner.online.lock().clone()
+    }
+
+    /// Returns up to 12 random online players
+    /// to show in the server list.
+    pub fn sample(&self) -> Vec<OnlinePlayer> {
+        self.inner
+            .online
+            .lock()
+            .choose_multiple(&mut rand::thread_rng(), MAX_SAMPLE_SIZE)
+            .cloned()
+            .collect()
+    }
 }
 
 struct Inner {
     count: AtomicU32,
     max_players: u32,
+    online: Mutex<Vec<OnlinePlayer>>,
 }
 
 #[cfg(test)]
@@ -76,6 +120,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sample() {
+        let count = PlayerCount::new(100);
+        let players: Vec<_> = (0..20)
+            .map(|i| OnlinePlayer {
+                name: format!("player{}", i),
+                id: Uuid::from_u128(i),
+            })
+            .collect();
+        for player in &players {
+            count.add_online(player.clone());
+        }
+
+        let sample = count.sample();
+        assert_eq!(sample.len(), MAX_SAMPLE_SIZE);
+        assert!(sample.iter().all(|player| players.contains(player)));
+
+        for player in &players[1..] {
+            count.remove_online(player.id);
+        }
+        assert_eq!(count.sample(), vec![players[0].clone()]);
+    }
+
     #[test]
     fn no_race_conditions() {
         let threads = 8;
@@ -0,0 +1,309 @@
+//! The GameSpy4 query protocol, used by server lists and
+//! monitoring tools to get the server's status over UDP.
+//!
+//! See <https://wiki.vg/Query>.
+
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use tokio::net::UdpSocket;
+
+use crate::{options::Options, player_count::PlayerCount};
+
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+const HANDSHAKE: u8 = 9;
+const STAT: u8 = 0;
+
+/// How long a challenge token stays valid. Expired
+/// tokens are pruned at the same interval.
+const CHALLENGE_LIFETIME: Duration = Duration::from_secs(30);
+
+const GAME_TYPE: &str = "SMP";
+const GAME_ID: &str = "MINECRAFT";
+const VERSION: &str = "1.16.5";
+const PLUGINS: &str = "Feather";
+
+/// A parsed query request.
+#[derive(Debug, PartialEq, Eq)]
+struct Request {
+    session_id: i32,
+    kind: RequestKind,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RequestKind {
+    Handshake,
+    BasicStat { challenge: i32 },
+    FullStat { challenge: i32 },
+}
+
+impl Request {
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 7 || bytes[..2] != MAGIC {
+            return None;
+        }
+        let session_id = i32::from_be_bytes(bytes[3..7].try_into().unwrap());
+        let payload = &bytes[7..];
+        let kind = match (bytes[2], payload.len()) {
+            (HANDSHAKE, 0) => RequestKind::Handshake,
+            (STAT, 4) | (STAT, 8) => {
+                let challenge = i32::from_be_bytes(payload[..4].try_into().unwrap());
+                if payload.len() == 4 {
+                    RequestKind::BasicStat { challenge }
+                } else {
+                    RequestKind::FullStat { challenge }
+                }
+            }
+            _ => return None,
+        };
+        Some(Self { session_id, kind })
+    }
+}
+
+/// The status reported by stat responses.
+#[derive(Debug)]
+struct Status {
+    motd: String,
+    map: String,
+    online: u32,
+    max_players: u32,
+    host_port: u16,
+    host_ip: String,
+    players: Vec<String>,
+}
+
+impl Status {
+    fn encode_basic(&self, response: &mut Vec<u8>) {
+        for value in &[
+            self.motd.as_str(),
+            GAME_TYPE,
+            self.map.as_str(),
+            self.online.to_string().as_str(),
+            self.max_players.to_string().as_str(),
+        ] {
+            write_string(response, value);
+        }
+        response.extend_from_slice(&self.host_port.to_le_bytes());
+        write_string(response, &self.host_ip);
+    }
+
+    fn encode_full(&self, response: &mut Vec<u8>) {
+        response.extend_from_slice(b"splitnum\0\x80\0");
+        for (key, value) in &[
+            ("hostname", self.motd.as_str()),
+            ("gametype", GAME_TYPE),
+            ("game_id", GAME_ID),
+            ("version", VERSION),
+            ("plugins", PLUGINS),
+            ("map", self.map.as_str()),
+            ("numplayers", self.online.to_string().as_str()),
+            ("maxplayers", self.max_players.to_string().as_str()),
+            ("hostport", self.host_port.to_string().as_str()),
+            ("hostip", self.host_ip.as_str()),
+        ] {
+            write_string(response, key);
+            write_string(response, value);
+        }
+        response.push(0);
+
+        response.extend_from_slice(b"\x01player_\0\0");
+        for player in &self.players {
+            write_string(response, player);
+        }
+        response.push(0);
+    }
+}
+
+fn write_string(response: &mut Vec<u8>, value: &str) {
+    response.extend_from_slice(value.as_bytes());
+    response.push(0);
+}
+
+/// Answers query requests on a UDP socket.
+pub struct QueryServer {
+    socket: UdpSocket,
+    options: Arc<Options>,
+    player_count: PlayerCount,
+    /// Challenge tokens handed out to each address,
+    /// along with the time they were issued.
+    challenges: HashMap<SocketAddr, (i32, Instant)>,
+}
+
+impl QueryServer {
+    /// Binds to the query port and starts answering requests.
+    pub async fn start(options: Arc<Options>, player_count: PlayerCount) -> anyhow::Result<()> {
+        let port = match options.query_port {
+            Some(port) => port,
+            None => return Ok(()),
+        };
+        let socket = UdpSocket::bind(format!("{}:{}", options.bind_address, port))
+            .await
+            .context("failed to bind to query port")?;
+
+        let server = QueryServer {
+            socket,
+            options,
+            player_count,
+            challenges: HashMap::new(),
+        };
+        tokio::task::spawn(async move {
+            server.run().await;
+        });
+
+        Ok(())
+    }
+
+    async fn run(mut self) {
+        let mut buffer = [0; 1460];
+        let mut prune = tokio::time::interval(CHALLENGE_LIFETIME);
+        loop {
+            let received = tokio::select! {
+                received = self.socket.recv_from(&mut buffer) => received,
+                _ = prune.tick() => {
+                    self.prune_challenges(Instant::now());
+                    continue;
+                }
+            };
+            let (length, addr) = match received {
+                Ok(received) => received,
+                Err(e) => {
+                    log::debug!("Failed to receive query request: {}", e);
+                    continue;
+                }
+            };
+            let response = Request::parse(&buffer[..length])
+                .and_then(|request| self.respond(request, addr, Instant::now()));
+            if let Some(response) = response {
+                if let Err(e) = self.socket.send_to(&response, addr).await {
+                    log::debug!("Failed to send query response to {}: {}", addr, e);
+                }
+            }
+        }
+    }
+
+    /// Returns the response to a request, or `None` if
+    /// the request had an invalid challenge token.
+    fn respond(&mut self, request: Request, addr: SocketAddr, now: Instant) -> Option<Vec<u8>> {
+        let mut response = Vec::new();
+        match request.kind {
+            RequestKind::Handshake => {
+                let challenge = rand::random::<i32>() & i32::MAX;
+                self.challenges.insert(addr, (challenge, now));
+
+                response.push(HANDSHAKE);
+                response.extend_from_slice(&request.session_id.to_be_bytes());
+                write_string(&mut response, &challenge.to_string());
+            }
+            RequestKind::BasicStat { challenge } | RequestKind::FullStat { challenge } => {
+                match self.challenges.get(&addr) {
+                    Some(&(expected, issued))
+                        if expected == challenge && now - issued < CHALLENGE_LIFETIME => {}
+                    _ => return None,
+                }
+
+                response.push(STAT);
+                response.extend_from_slice(&request.session_id.to_be_bytes());
+                let status = self.status();
+                if let RequestKind::FullStat { .. } = request.kind {
+                    status.encode_full(&mut response);
+                } else {
+                    status.encode_basic(&mut response);
+                }
+            }
+        }
+        Some(response)
+    }
+
+    /// Forgets the challenge tokens which have expired.
+    fn prune_challenges(&mut self, now: Instant) {
+        self.challenges
+            .retain(|_, &mut (_, issued)| now - issued < CHALLENGE_LIFETIME);
+    }
+
+    fn status(&self) -> Status {
+        Status {
+            motd: self.options.motd.clone(),
+            map: self.options.level_name.clone(),
+            online: self.player_count.get(),
+            max_players: self.options.max_players,
+            host_port: self.options.port,
+            host_ip: self.options.bind_address.clone(),
+            players: self
+                .player_count
+                .online()
+                .into_iter()
+                .map(|player| player.name)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_parsed() {
+        let handshake = [0xFE, 0xFD, 0x09, 0x00, 0x00, 0x00, 0x01];
+        assert_eq!(
+            Request::parse(&handshake),
+            Some(Request {
+                session_id: 1,
+                kind: RequestKind::Handshake
+            })
+        );
+
+        let basic = [
+            0xFE, 0xFD, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x91, 0x29, 0x5B,
+        ];
+        assert_eq!(
+            Request::parse(&basic).unwrap().kind,
+            RequestKind::BasicStat {
+                challenge: 9_513_307
+            }
+        );
+
+        let mut full = basic.to_vec();
+        full.extend_from_slice(&[0; 4]);
+        assert_eq!(
+            Request::parse(&full).unwrap().kind,
+            RequestKind::FullStat {
+                challenge: 9_513_307
+            }
+        );
+
+        assert_eq!(Request::parse(&[0xFE, 0xFD, 0x09]), None);
+        assert_eq!(Request::parse(&full[..10]), None);
+    }
+
+    #[test]
+    fn full_stat_lists_players() {
+        let status = Status {
+            motd: "A Feather server".to_owned(),
+            map: "world".to_owned(),
+            online: 2,
+            max_players: 16,
+            host_port: 25565,
+            host_ip: "0.0.0.0".to_owned(),
+            players: vec!["Notch".to_owned(), "jeb_".to_owned()],
+        };
+
+        let mut basic = Vec::new();
+        status.encode_basic(&mut basic);
+        assert_eq!(
+            basic,
+            b"A Feather server\0SMP\0world\x002\x0016\0\xDD\x630.0.0.0\0".to_vec()
+        );
+
+        let mut full = Vec::new();
+        status.encode_full(&mut full);
+        assert!(full.starts_with(b"splitnum\0\x80\0hostname\0A Feather server\0"));
+        assert!(full.ends_with(b"hostip\x000.0.0.0\0\0\x01player_\0\0Notch\0jeb_\0\0"));
+    }
+}
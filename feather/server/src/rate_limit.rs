@@ -0,0 +1,250 @@
+//! Rate limiting of received packets, which protects
+//! the server against clients spamming packets.
+//!
+//! Each connection has a [`RateLimiter`] counting the packets and
+//! bytes it receives each second. Connections over the limits are
+//! kicked, and addresses kicked too often are temporarily banned
+//! through the shared [`Bans`].
+
+use std::{
+    collections::HashMap,
+    fmt,
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use crate::options::RateLimit;
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Reason shown to clients kicked for exceeding their rate limits.
+pub const KICK_REASON: &str = "Kicked for spamming";
+
+/// Error returned when a connection exceeds its rate limits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RateLimitExceeded {
+    Packets,
+    Bytes,
+}
+
+impl fmt::Display for RateLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RateLimitExceeded::Packets => f.write_str("sent too many packets per second"),
+            RateLimitExceeded::Bytes => f.write_str("sent too many bytes per second"),
+        }
+    }
+}
+
+impl std::error::Error for RateLimitExceeded {}
+
+/// Counts the packets and bytes received by a connection.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limits: RateLimit,
+    window_start: Instant,
+    packets: u32,
+    bytes: u64,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimit, now: Instant) -> Self {
+        Self {
+            limits,
+            window_start: now,
+            packets: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Records received bytes.
+    pub fn record_bytes(&mut self, bytes: usize, now: Instant) -> Result<(), RateLimitExceeded> {
+        self.roll_window(now);
+        self.bytes += bytes as u64;
+        if self.bytes > self.limits.max_bytes_per_second {
+            return Err(RateLimitExceeded::Bytes);
+        }
+        Ok(())
+    }
+
+    /// Records a received packet.
+    pub fn record_packet(&mut self, now: Instant) -> Result<(), RateLimitExceeded> {
+        self.roll_window(now);
+        self.packets += 1;
+        if self.packets > self.limits.max_packets_per_second {
+            return Err(RateLimitExceeded::Packets);
+        }
+        Ok(())
+    }
+
+    fn roll_window(&mut self, now: Instant) {
+        if now.duration_since(self.window_start) >= WINDOW {
+            self.window_start = now;
+            self.packets = 0;
+            self.bytes = 0;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Offender {
+    /// Times at which the address was kicked, within the ban duration.
+    kicks: Vec<Instant>,
+    banned_until: Option<Instant>,
+}
+
+/// Tracks addresses kicked for exceeding the rate limits
+/// and bans those kicked repeatedly.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone, Default)]
+pub struct Bans {
+    offenders: Arc<Mutex<HashMap<IpAddr, Offender>>>,
+}
+
+impl Bans {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that an address was kicked. Returns whether
+    /// the address is now banned.
+    pub fn record_kick(&self, address: IpAddr, limits: &RateLimit, now: Instant) -> bool {
+        let mut offenders = self.offenders.lock();
+        Self::forget_expired(&mut offenders, limits, now);
+
+        let offender = offenders.entry(address).or_default();
+        offender.kicks.push(now);
+
+        if offender.kicks.len() >= limits.kicks_before_ban as usize {
+            offender.kicks.clear();
+            offender.banned_until = Some(now + limits.ban_duration);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Forgets kicks older than the ban duration, and offenders
+    /// left with neither recent kicks nor a current ban.
+    fn forget_expired(offenders: &mut HashMap<IpAddr, Offender>, limits: &RateLimit, now: Instant) {
+        offenders.retain(|_, offender| {
+            offender
+                .kicks
+                .retain(|&kicked| now.duration_since(kicked) < limits.ban_duration);
+            let banned = matches!(offender.banned_until, Some(until) if now < until);
+            if !banned {
+                offender.banned_until = None;
+            }
+            banned || !offender.kicks.is_empty()
+        });
+    }
+
+    /// Determines whether an address is banned.
+    pub fn is_banned(&self, address: IpAddr, now: Instant) -> bool {
+        let mut offenders = self.offenders.lock();
+        let offender = match offenders.get_mut(&address) {
+            Some(offender) => offender,
+            None => return false,
+        };
+        let banned_until = offender.banned_until;
+        match banned_until {
+            Some(until) if now < until => true,
+            Some(_) => {
+                offender.banned_until = None;
+                if offender.kicks.is_empty() {
+                    offenders.remove(&address);
+                }
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    fn limits() -> RateLimit {
+        RateLimit {
+            max_packets_per_second: 3,
+            max_bytes_per_second: 100,
+            kicks_before_ban: 2,
+            ban_duration: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn limits_apply_per_second() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(limits(), start);
+        for _ in 0..3 {
+            limiter.record_packet(start).unwrap();
+        }
+        assert_eq!(
+            limiter.record_packet(start),
+            Err(RateLimitExceeded::Packets)
+        );
+
+        let next = start + WINDOW;
+        limiter.record_packet(next).unwrap();
+        limiter.record_bytes(100, next).unwrap();
+        assert_eq!(limiter.record_bytes(1, next), Err(RateLimitExceeded::Bytes));
+    }
+
+    #[test]
+    fn repeated_kicks_ban() {
+        let bans = Bans::new();
+        let address = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let start = Instant::now();
+        let limits = limits();
+
+        assert!(!bans.record_kick(address, &limits, start));
+        assert!(!bans.is_banned(address, start));
+        assert!(bans.record_kick(address, &limits, start + Duration::from_secs(30)));
+        assert!(bans.is_banned(address, start + Duration::from_secs(30)));
+        assert!(!bans.is_banned(other, start + Duration::from_secs(30)));
+
+        // Bans expire.
+        assert!(!bans.is_banned(address, start + Duration::from_secs(90)));
+
+        // Kicks older than the ban duration are forgotten.
+        let later = start + Duration::from_secs(200);
+        assert!(!bans.record_kick(address, &limits, later));
+        assert!(!bans.record_kick(address, &limits, later + Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn expired_offenders_are_forgotten() {
+        let bans = Bans::new();
+        let kicked = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let banned = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 3));
+        let start = Instant::now();
+        let limits = limits();
+
+        assert!(!bans.record_kick(kicked, &limits, start));
+        assert!(!bans.record_kick(banned, &limits, start));
+        assert!(bans.record_kick(banned, &limits, start + Duration::from_secs(30)));
+        assert_eq!(bans.offenders.lock().len(), 2);
+
+        // The single kick has expired, but the ban has not.
+        assert!(!bans.record_kick(other, &limits, start + Duration::from_secs(60)));
+        assert_eq!(bans.offenders.lock().len(), 2);
+        assert!(bans.is_banned(banned, start + Duration::from_secs(60)));
+
+        // Once everything has expired, only the newest offender is kept.
+        let later = start + Duration::from_secs(200);
+        assert!(!bans.record_kick(other, &limits, later));
+        let offenders = bans.offenders.lock();
+        assert_eq!(offenders.len(), 1);
+        assert!(offenders.contains_key(&other));
+    }
+}
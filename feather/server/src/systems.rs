@@ -10,7 +10,7 @@ mod plugin_message;
 mod tablist;
 pub mod view;
 
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 use common::Game;
 use ecs::{SysResult, SystemExecutor};
@@ -66,11 +66,12 @@ fn handle_packets(game: &mut Game, server: &mut Server) -> SysResult {
     Ok(())
 }
 
-/// Sends out keepalive packets at an interval.
+/// Sends out keepalive packets at an interval and
+/// disconnects clients that don't respond to them.
 fn send_keepalives(_game: &mut Game, server: &mut Server) -> SysResult {
-    let interval = Duration::from_secs(5);
-    if server.last_keepalive_time + interval < Instant::now() {
-        server.broadcast_keepalive();
+    let now = Instant::now();
+    for client in server.clients.iter() {
+        client.tick_keepalive(now);
     }
     Ok(())
 }
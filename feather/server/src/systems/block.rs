@@ -55,15 +55,19 @@ fn broadcast_block_change_chunk_overwrite(
         sections.entry(chunk).or_default().push(section + 1); // + 1 to account for the void air chunk
     }
 
+    let world = match game.dimensions.world(event.dimension()) {
+        Some(world) => world,
+        None => return,
+    };
     for (chunk_pos, sections) in sections {
-        let chunk = game.world.chunk_map().chunk_handle_at(chunk_pos);
+        let chunk = world.chunk_map().chunk_handle_at(chunk_pos);
         if let Some(chunk) = chunk {
             let position = position!(
                 (chunk_pos.x * CHUNK_WIDTH as i32) as f64,
                 0.0,
                 (chunk_pos.z * CHUNK_WIDTH as i32) as f64,
             );
-            server.broadcast_nearby_with(position, |client| {
+            server.broadcast_nearby_with(event.dimension(), position, |client| {
                 client.overwrite_chunk_sections(&chunk, sections.clone());
             })
         }
@@ -72,9 +76,9 @@ fn broadcast_block_change_chunk_overwrite(
 
 fn broadcast_block_change_simple(event: &BlockChangeEvent, game: &Game, server: &mut Server) {
     for pos in event.iter_changed_blocks() {
-        let new_block = game.block(pos);
+        let new_block = game.block(event.dimension(), pos);
         if let Some(new_block) = new_block {
-            server.broadcast_nearby_with(pos.position(), |client| {
+            server.broadcast_nearby_with(event.dimension(), pos.position(), |client| {
                 client.send_block_change(pos, new_block)
             });
         }
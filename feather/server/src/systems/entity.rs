@@ -1,33 +1,186 @@
 //! Sends entity-related packets to clients.
 //! Spawn packets, position updates, equipment, animations, etc.
 
-use base::Position;
-use common::Game;
-use ecs::{SysResult, SystemExecutor};
+use base::{
+    metadata::{META_INDEX_LIVING_BED_POSITION, META_INDEX_POSE},
+    EntityMetadata, Position,
+};
+use common::{
+    events::{
+        EffectChange, EffectChangeEvent, EntityRemoveEvent, LeashChangeEvent, SleepChangeEvent,
+    },
+    leash::LeashComponent,
+    sleeping::SleepingComponent,
+    DimensionId, Game,
+};
+use ecs::{Entity, SysResult, SystemExecutor};
 use quill_common::components::OnGround;
 
-use crate::{entities::PreviousPosition, NetworkId, Server};
+use crate::{client::movement_packets, entities::PreviousPosition, Client, NetworkId, Server};
 
 mod spawn_packet;
 
 pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
     spawn_packet::register(game, systems);
-    systems.group::<Server>().add_system(send_entity_movement);
+    systems
+        .group::<Server>()
+        .add_system(send_entity_movement)
+        .add_system(send_leash_updates)
+        .add_system(send_sleep_updates)
+        .add_system(send_effect_updates)
+        .add_system(release_network_ids);
 }
 
 /// Sends entity movement packets.
+///
+/// `PreviousPosition` tracks the position last sent to
+/// clients, so only the change since then is sent.
 fn send_entity_movement(game: &mut Game, server: &mut Server) -> SysResult {
-    for (_, (&position, prev_position, &on_ground, &network_id)) in game
+    for (_, (&position, prev_position, &on_ground, &network_id, dimension)) in game
         .ecs
-        .query::<(&Position, &mut PreviousPosition, &OnGround, &NetworkId)>()
+        .query::<(
+            &Position,
+            &mut PreviousPosition,
+            &OnGround,
+            &NetworkId,
+            Option<&DimensionId>,
+        )>()
         .iter()
     {
         if position != prev_position.0 {
-            server.broadcast_nearby_with(position, |client| {
-                client.update_entity_position(network_id, position, on_ground);
+            let dimension = dimension.copied().unwrap_or(DimensionId::OVERWORLD);
+            let (packets, known) =
+                movement_packets(network_id, prev_position.0, position, on_ground);
+            server.broadcast_nearby_with(dimension, position, |client| {
+                client.update_entity_position(network_id, position, &packets);
             });
-            prev_position.0 = position;
+            prev_position.0 = known;
         }
     }
     Ok(())
 }
+
+/// Sends Attach Entity packets for entities whose leash changed.
+fn send_leash_updates(game: &mut Game, server: &mut Server) -> SysResult {
+    let changed: Vec<Entity> = game
+        .ecs
+        .query::<&LeashChangeEvent>()
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect();
+    for entity in changed {
+        send_attach(game, server, entity)?;
+    }
+    Ok(())
+}
+
+/// Sends the current leash state of `entity` to nearby clients.
+///
+/// If the entity is not leashed, its lead is removed on the client.
+pub fn send_attach(game: &Game, server: &Server, entity: Entity) -> SysResult {
+    let position = *game.ecs.get::<Position>(entity)?;
+    let dimension = game.dimension_of(entity);
+    let network_id = *game.ecs.get::<NetworkId>(entity)?;
+    let holder = leash_holder(game, entity);
+
+    server.broadcast_nearby_with(dimension, position, |client| {
+        client.send_attach_entity(network_id, holder)
+    });
+    Ok(())
+}
+
+/// Sends the lead of `entity` to a client which was just sent the
+/// entity, if it is leashed. Must be called after the spawn packets
+/// of both the entity and its holder have been sent.
+pub(crate) fn send_leash_to(game: &Game, client: &Client, entity: Entity) -> SysResult {
+    if let Some(holder) = leash_holder(game, entity) {
+        let network_id = *game.ecs.get::<NetworkId>(entity)?;
+        client.send_attach_entity(network_id, Some(holder));
+    }
+    Ok(())
+}
+
+/// Returns the network ID of the entity `entity` is leashed to, if any.
+fn leash_holder(game: &Game, entity: Entity) -> Option<NetworkId> {
+    let leash = game.ecs.get::<LeashComponent>(entity).ok()?;
+    let holder = leash.holding_entity()?;
+    game.ecs.get::<NetworkId>(holder).ok().map(|id| *id)
+}
+
+/// Sends the pose and bed position metadata
+/// of entities which fell asleep or woke up.
+fn send_sleep_updates(game: &mut Game, server: &mut Server) -> SysResult {
+    for (entity, (_, &position, &network_id)) in game
+        .ecs
+        .query::<(&SleepChangeEvent, &Position, &NetworkId)>()
+        .iter()
+    {
+        let metadata = sleep_metadata(game, entity);
+        server.broadcast_nearby_with(game.dimension_of(entity), position, |client| {
+            client.send_entity_metadata(network_id, metadata.clone())
+        });
+    }
+    Ok(())
+}
+
+/// Sends the pose and bed position of `entity` to a client
+/// which was just sent the entity, if it is sleeping.
+pub(crate) fn send_sleep_to(game: &Game, client: &Client, entity: Entity) -> SysResult {
+    if game.ecs.get::<SleepingComponent>(entity).is_ok() {
+        let network_id = *game.ecs.get::<NetworkId>(entity)?;
+        client.send_entity_metadata(network_id, sleep_metadata(game, entity));
+    }
+    Ok(())
+}
+
+/// Returns the sleep-related entries of the metadata of `entity`.
+fn sleep_metadata(game: &Game, entity: Entity) -> EntityMetadata {
+    let mut entries = EntityMetadata::new();
+    if let Ok(metadata) = game.ecs.get::<EntityMetadata>(entity) {
+        for &index in &[META_INDEX_POSE, META_INDEX_LIVING_BED_POSITION] {
+            if let Some(entry) = metadata.get(index) {
+                entries.values.insert(index, entry);
+            }
+        }
+    }
+    entries
+}
+
+/// Sends Entity Effect and Remove Entity Effect packets
+/// for entities whose status effects changed.
+fn send_effect_updates(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (event, &position, &network_id, dimension)) in game
+        .ecs
+        .query::<(
+            &EffectChangeEvent,
+            &Position,
+            &NetworkId,
+            Option<&DimensionId>,
+        )>()
+        .iter()
+    {
+        let dimension = dimension.copied().unwrap_or(DimensionId::OVERWORLD);
+        server.broadcast_nearby_with(dimension, position, |client| {
+            for &change in &event.changes {
+                match change {
+                    EffectChange::Added(effect) => client.send_entity_effect(network_id, effect),
+                    EffectChange::Removed(effect) => {
+                        client.send_remove_entity_effect(network_id, effect)
+                    }
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Releases the network IDs of removed entities.
+///
+/// Registered after the systems which send
+/// Destroy Entities packets using these IDs.
+fn release_network_ids(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (_, &network_id)) in game.ecs.query::<(&EntityRemoveEvent, &NetworkId)>().iter() {
+        server.release_network_id(network_id);
+    }
+    Ok(())
+}
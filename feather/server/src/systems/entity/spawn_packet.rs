@@ -1,13 +1,17 @@
 use ahash::AHashSet;
 use anyhow::Context;
-use base::Position;
+use base::{ChunkPosition, Position};
 use common::{
-    events::{ChunkCrossEvent, EntityCreateEvent, EntityRemoveEvent, ViewUpdateEvent},
-    Game,
+    events::{
+        ChunkCrossEvent, EntityCreateEvent, EntityRemoveEvent, TeleportEvent, ViewUpdateEvent,
+    },
+    view::View,
+    DimensionId, Game,
 };
-use ecs::{SysResult, SystemExecutor};
+use ecs::{Entity, SysResult, SystemExecutor};
 
-use crate::{entities::SpawnPacketSender, ClientId, NetworkId, Server};
+use super::{send_leash_to, send_sleep_to};
+use crate::{entities::SpawnPacketSender, Client, ClientId, NetworkId, Server};
 
 pub fn register(_game: &mut Game, systems: &mut SystemExecutor<Game>) {
     systems
@@ -15,7 +19,8 @@ pub fn register(_game: &mut Game, systems: &mut SystemExecutor<Game>) {
         .add_system(update_visible_entities)
         .add_system(send_entities_when_created)
         .add_system(unload_entities_when_removed)
-        .add_system(update_entities_on_chunk_cross);
+        .add_system(update_entities_on_chunk_cross)
+        .add_system(update_entities_on_dimension_change);
 }
 
 /// System to spawn entities on clients when they become visible,
@@ -26,25 +31,15 @@ pub fn update_visible_entities(game: &mut Game, server: &mut Server) -> SysResul
             Some(client) => client,
             None => continue,
         };
+        let dimension = game.dimension_of(player);
 
         // Send newly visible entities
-        for &new_chunk in &event.new_chunks {
-            for &entity_id in game.chunk_entities.entities_in_chunk(new_chunk) {
-                if entity_id != player {
-                    let entity_ref = game.ecs.entity(entity_id)?;
-                    if let Ok(spawn_packet) = entity_ref.get::<SpawnPacketSender>() {
-                        spawn_packet
-                            .send(&entity_ref, client)
-                            .context("failed to send spawn packet")?;
-                    }
-                }
-            }
-        }
+        send_entities_in_chunks(game, client, player, dimension, &event.new_chunks)?;
 
         // Unload entities no longer visible
         for &old_chunk in &event.old_chunks {
             for &entity_id in game.chunk_entities.entities_in_chunk(old_chunk) {
-                if entity_id != player {
+                if entity_id != player && game.dimension_of(entity_id) == dimension {
                     if let Ok(network_id) = game.ecs.get::<NetworkId>(entity_id) {
                         client.unload_entity(*network_id);
                     }
@@ -56,6 +51,40 @@ pub fn update_visible_entities(game: &mut Game, server: &mut Server) -> SysResul
     Ok(())
 }
 
+/// Sends the entities in `dimension` and `chunks` to `client`,
+/// except for the client's own `player`.
+///
+/// Leads are sent once all entities have been spawned,
+/// so that leash knots in these chunks are known to the client.
+fn send_entities_in_chunks(
+    game: &Game,
+    client: &Client,
+    player: Entity,
+    dimension: DimensionId,
+    chunks: &[ChunkPosition],
+) -> SysResult {
+    let entities: Vec<Entity> = chunks
+        .iter()
+        .flat_map(|&chunk| game.chunk_entities.entities_in_chunk(chunk))
+        .copied()
+        .filter(|&entity| entity != player && game.dimension_of(entity) == dimension)
+        .collect();
+
+    for &entity_id in &entities {
+        let entity_ref = game.ecs.entity(entity_id)?;
+        if let Ok(spawn_packet) = entity_ref.get::<SpawnPacketSender>() {
+            spawn_packet
+                .send(&entity_ref, client)
+                .context("failed to send spawn packet")?;
+            send_sleep_to(game, client, entity_id)?;
+        }
+    }
+    for &entity_id in &entities {
+        send_leash_to(game, client, entity_id)?;
+    }
+    Ok(())
+}
+
 /// System to send an entity to clients when it is created.
 fn send_entities_when_created(game: &mut Game, server: &mut Server) -> SysResult {
     for (entity, (_event, &position, spawn_packet)) in game
@@ -64,7 +93,8 @@ fn send_entities_when_created(game: &mut Game, server: &mut Server) -> SysResult
         .iter()
     {
         let entity_ref = game.ecs.entity(entity)?;
-        server.broadcast_nearby_with(position, |client| {
+        let dimension = game.dimension_of(entity);
+        server.broadcast_nearby_with(dimension, position, |client| {
             spawn_packet
                 .send(&entity_ref, client)
                 .expect("failed to create spawn packet")
@@ -76,12 +106,15 @@ fn send_entities_when_created(game: &mut Game, server: &mut Server) -> SysResult
 
 /// System to unload an entity on clients when it is removed.
 fn unload_entities_when_removed(game: &mut Game, server: &mut Server) -> SysResult {
-    for (_, (_event, &position, &network_id)) in game
+    for (entity, (_event, &position, &network_id)) in game
         .ecs
         .query::<(&EntityRemoveEvent, &Position, &NetworkId)>()
         .iter()
     {
-        server.broadcast_nearby_with(position, |client| client.unload_entity(network_id));
+        let dimension = game.dimension_of(entity);
+        server.broadcast_nearby_with(dimension, position, |client| {
+            client.unload_entity(network_id)
+        });
     }
 
     Ok(())
@@ -94,15 +127,16 @@ fn update_entities_on_chunk_cross(game: &mut Game, server: &mut Server) -> SysRe
         .query::<(&ChunkCrossEvent, &SpawnPacketSender, &NetworkId)>()
         .iter()
     {
+        let dimension = game.dimension_of(entity);
         let old_clients: AHashSet<_> = server
             .chunk_subscriptions
-            .subscriptions_for(event.old_chunk)
+            .subscriptions_for(dimension, event.old_chunk)
             .iter()
             .copied()
             .collect();
         let new_clients: AHashSet<_> = server
             .chunk_subscriptions
-            .subscriptions_for(event.new_chunk)
+            .subscriptions_for(dimension, event.new_chunk)
             .iter()
             .copied()
             .collect();
@@ -117,9 +151,73 @@ fn update_entities_on_chunk_cross(game: &mut Game, server: &mut Server) -> SysRe
         for send_client in new_clients.difference(&old_clients) {
             if let Some(client) = server.clients.get(*send_client) {
                 spawn_packet.send(&entity_ref, client)?;
+                send_sleep_to(game, client, entity)?;
+                send_leash_to(game, client, entity)?;
             }
         }
     }
 
     Ok(())
 }
+
+/// System to move entities which changed dimensions
+/// from the clients in their old dimension to those in
+/// their new dimension.
+///
+/// Players who changed dimensions are also sent the
+/// entities visible in their new dimension.
+fn update_entities_on_dimension_change(game: &mut Game, server: &mut Server) -> SysResult {
+    for (entity, (event, &chunk, &network_id, spawn_packet, client_id, view)) in game
+        .ecs
+        .query::<(
+            &TeleportEvent,
+            &ChunkPosition,
+            &NetworkId,
+            Option<&SpawnPacketSender>,
+            Option<&ClientId>,
+            Option<&View>,
+        )>()
+        .iter()
+    {
+        if !event.changed_dimension() {
+            continue;
+        }
+        // `chunk` is the chunk the entity was last indexed in;
+        // crossing into its new chunk is handled on the next tick.
+        let own_client = client_id.copied();
+
+        for &old_client in server
+            .chunk_subscriptions
+            .subscriptions_for(event.previous_dimension, chunk)
+        {
+            if let Some(client) = server.clients.get(old_client) {
+                client.unload_entity(network_id);
+            }
+        }
+
+        let entity_ref = game.ecs.entity(entity)?;
+        if let Some(spawn_packet) = spawn_packet {
+            for &new_client in server
+                .chunk_subscriptions
+                .subscriptions_for(event.dimension, chunk)
+            {
+                if Some(new_client) == own_client {
+                    continue;
+                }
+                if let Some(client) = server.clients.get(new_client) {
+                    spawn_packet.send(&entity_ref, client)?;
+                    send_sleep_to(game, client, entity)?;
+                    send_leash_to(game, client, entity)?;
+                }
+            }
+        }
+
+        if let (Some(client), Some(view)) = (own_client.and_then(|id| server.clients.get(id)), view)
+        {
+            let chunks: Vec<ChunkPosition> = view.iter().collect();
+            send_entities_in_chunks(game, client, entity, event.dimension, &chunks)?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use crate::Server;
 use base::{Particle, Position};
-use common::Game;
+use common::{DimensionId, Game};
 use ecs::{SysResult, SystemExecutor};
 
 pub fn register(systems: &mut SystemExecutor<Game>) {
@@ -10,8 +10,13 @@ pub fn register(systems: &mut SystemExecutor<Game>) {
 fn send_particle_packets(game: &mut Game, server: &mut Server) -> SysResult {
     let mut entities = Vec::new();
 
-    for (entity, (&particle, &position)) in game.ecs.query::<(&Particle, &Position)>().iter() {
-        server.broadcast_nearby_with(position, |client| {
+    for (entity, (&particle, &position, dimension)) in game
+        .ecs
+        .query::<(&Particle, &Position, Option<&DimensionId>)>()
+        .iter()
+    {
+        let dimension = dimension.copied().unwrap_or(DimensionId::OVERWORLD);
+        server.broadcast_nearby_with(dimension, position, |client| {
             client.send_particle(&particle, &position);
         });
 
@@ -1,10 +1,10 @@
-use base::{Inventory, Position, Text};
+use base::{position, Inventory, Text};
 use common::{
     chat::{ChatKind, ChatPreference},
     entities::player::HotbarSlot,
     view::View,
     window::BackingWindow,
-    ChatBox, Game, Window,
+    ChatBox, DimensionId, Game, Window,
 };
 use ecs::{SysResult, SystemExecutor};
 use quill_common::{components::Name, entity_init::EntityInit};
@@ -26,10 +26,20 @@ fn poll_new_players(game: &mut Game, server: &mut Server) -> SysResult {
 
 fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId) -> SysResult {
     let client = server.clients.get(client_id).unwrap();
-    client.send_join_game(server.options.default_gamemode);
+    let overworld = game
+        .dimensions
+        .info(DimensionId::OVERWORLD)
+        .expect("the overworld is not registered");
+    client.send_join_game(
+        server.options.default_gamemode,
+        overworld,
+        game.dimensions.world_names(),
+    );
     client.send_brand();
 
-    let mut builder = game.create_entity_builder(Position::default(), EntityInit::Player);
+    let spawn = game.spawn;
+    let position = position!(spawn.x as f64 + 0.5, spawn.y as f64, spawn.z as f64 + 0.5);
+    let mut builder = game.create_entity_builder(position, EntityInit::Player);
 
     let inventory = Inventory::player();
     let window = Window::new(BackingWindow::Player {
@@ -41,10 +51,7 @@ fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId)
     builder
         .add(client.network_id())
         .add(client_id)
-        .add(View::new(
-            Position::default().chunk(),
-            server.options.view_distance,
-        ))
+        .add(View::new(position.chunk(), server.options.view_distance))
         .add(server.options.default_gamemode)
         .add(Name::new(client.username()))
         .add(client.uuid())
@@ -4,10 +4,11 @@
 //! determined based on the player's [`View`].
 
 use ahash::AHashMap;
-use base::{ChunkPosition, Position};
+use base::{ChunkPosition, Gamemode, Position};
 use common::{
-    events::{ChunkLoadEvent, ViewUpdateEvent},
-    Game,
+    events::{ChunkLoadEvent, TeleportEvent, ViewUpdateEvent},
+    view::View,
+    DimensionId, Game,
 };
 use ecs::{Entity, SysResult, SystemExecutor};
 
@@ -17,29 +18,35 @@ pub fn register(_game: &mut Game, systems: &mut SystemExecutor<Game>) {
     systems
         .group::<Server>()
         .add_system(send_new_chunks)
+        .add_system(resend_chunks_on_dimension_change)
         .add_system(send_loaded_chunks);
 }
 
 /// Stores the players waiting on chunks that are currently being loaded.
 #[derive(Default)]
-pub struct WaitingChunks(AHashMap<ChunkPosition, Vec<Entity>>);
+pub struct WaitingChunks(AHashMap<(DimensionId, ChunkPosition), Vec<Entity>>);
 
 impl WaitingChunks {
-    pub fn drain_players_waiting_for(&mut self, chunk: ChunkPosition) -> Vec<Entity> {
-        self.0.remove(&chunk).unwrap_or_default()
+    pub fn drain_players_waiting_for(
+        &mut self,
+        dimension: DimensionId,
+        chunk: ChunkPosition,
+    ) -> Vec<Entity> {
+        self.0.remove(&(dimension, chunk)).unwrap_or_default()
     }
 
-    pub fn insert(&mut self, player: Entity, chunk: ChunkPosition) {
-        self.0.entry(chunk).or_default().push(player);
+    pub fn insert(&mut self, player: Entity, dimension: DimensionId, chunk: ChunkPosition) {
+        self.0.entry((dimension, chunk)).or_default().push(player);
     }
 }
 
 fn send_new_chunks(game: &mut Game, server: &mut Server) -> SysResult {
-    for (player, (&client_id, event, &position)) in game
+    for (player, (&client_id, event, &position, dimension)) in game
         .ecs
-        .query::<(&ClientId, &ViewUpdateEvent, &Position)>()
+        .query::<(&ClientId, &ViewUpdateEvent, &Position, Option<&DimensionId>)>()
         .iter()
     {
+        let dimension = dimension.copied().unwrap_or(DimensionId::OVERWORLD);
         let client = server.clients.get(client_id).unwrap();
         client.update_own_chunk(event.new_view.center());
         update_chunks(
@@ -48,6 +55,7 @@ fn send_new_chunks(game: &mut Game, server: &mut Server) -> SysResult {
             client,
             event,
             position,
+            dimension,
             &mut server.waiting_chunks,
         )?;
     }
@@ -60,14 +68,22 @@ fn update_chunks(
     client: &Client,
     event: &ViewUpdateEvent,
     position: Position,
+    dimension: DimensionId,
     waiting_chunks: &mut WaitingChunks,
 ) -> SysResult {
     // Send chunks that are in the new view but not the old view.
+    let world = match game.dimensions.world(dimension) {
+        Some(world) => world,
+        None => {
+            log::warn!("Player is in unregistered {}", dimension);
+            return Ok(());
+        }
+    };
     for &pos in &event.new_chunks {
-        if let Some(chunk) = game.world.chunk_map().chunk_handle_at(pos) {
+        if let Some(chunk) = world.chunk_map().chunk_handle_at(pos) {
             client.send_chunk(&chunk);
         } else {
-            waiting_chunks.insert(player, pos);
+            waiting_chunks.insert(player, dimension, pos);
         }
     }
 
@@ -81,13 +97,48 @@ fn update_chunks(
     Ok(())
 }
 
+/// Respawns players who changed dimensions and
+/// sends them the chunks of their new dimension.
+fn resend_chunks_on_dimension_change(game: &mut Game, server: &mut Server) -> SysResult {
+    for (player, (&client_id, event, &view, &position, &gamemode)) in game
+        .ecs
+        .query::<(&ClientId, &TeleportEvent, &View, &Position, &Gamemode)>()
+        .iter()
+    {
+        if !event.changed_dimension() {
+            continue;
+        }
+        let info = match game.dimensions.info(event.dimension) {
+            Some(info) => info,
+            None => {
+                log::warn!("Player teleported into unregistered {}", event.dimension);
+                continue;
+            }
+        };
+        let client = server.clients.get(client_id).unwrap();
+        client.send_respawn(gamemode, info);
+        client.update_own_chunk(view.center());
+
+        let world = game.world_for(event.dimension);
+        for pos in view.iter() {
+            if let Some(chunk) = world.chunk_map().chunk_handle_at(pos) {
+                client.send_chunk(&chunk);
+            } else {
+                server.waiting_chunks.insert(player, event.dimension, pos);
+            }
+        }
+        spawn_client_if_needed(client, position);
+    }
+    Ok(())
+}
+
 /// Sends newly loaded chunks to players currently
 /// waiting for those chunks to load.
 fn send_loaded_chunks(game: &mut Game, server: &mut Server) -> SysResult {
     for (_, event) in game.ecs.query::<&ChunkLoadEvent>().iter() {
         for player in server
             .waiting_chunks
-            .drain_players_waiting_for(event.position)
+            .drain_players_waiting_for(event.dimension, event.position)
         {
             if let Ok(client_id) = game.ecs.get::<ClientId>(player) {
                 if let Some(client) = server.clients.get(*client_id) {
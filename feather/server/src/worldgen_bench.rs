@@ -0,0 +1,72 @@
+//! The `--bench-worldgen` mode, which generates chunks with the
+//! configured generator and reports the time spent in each stage.
+
+use std::{collections::HashMap, time::Instant};
+
+use base::{Chunk, ChunkPosition};
+use worldgen::{ChunkRegion, ComposableGenerator, StageTimings};
+
+/// Number of chunks generated if no count is given.
+pub const DEFAULT_CHUNKS: u32 = 256;
+
+/// Generates `count` chunks in a square around the origin, then
+/// populates those whose neighbors were all generated.
+///
+/// Population works on copies of the chunks, so every
+/// chunk is populated from the same generated terrain.
+pub fn run(generator: &ComposableGenerator, count: u32) {
+    let side = (count as f64).sqrt().ceil() as i32;
+    let positions: Vec<_> = (0..side)
+        .flat_map(|z| (0..side).map(move |x| ChunkPosition::new(x - side / 2, z - side / 2)))
+        .take(count as usize)
+        .collect();
+
+    println!("Generating {} chunks", positions.len());
+    let mut timings = StageTimings::new();
+    let start = Instant::now();
+    let chunks: HashMap<ChunkPosition, Chunk> = positions
+        .iter()
+        .map(|&pos| (pos, generator.generate_chunk_timed(pos, &mut timings)))
+        .collect();
+    let generation = start.elapsed();
+
+    let mut populated = 0;
+    let start = Instant::now();
+    for &pos in &positions {
+        let region: Option<Vec<Chunk>> = (-1..=1)
+            .flat_map(|z| (-1..=1).map(move |x| ChunkPosition::new(pos.x + x, pos.z + z)))
+            .map(|neighbor| chunks.get(&neighbor).cloned())
+            .collect();
+        if let Some(region) = region {
+            generator.populate_timed(&mut ChunkRegion::new(region), &mut timings);
+            populated += 1;
+        }
+    }
+    let population = start.elapsed();
+
+    println!(
+        "Generated {} chunks in {:.2?} ({:.1} chunks/s)",
+        chunks.len(),
+        generation,
+        chunks.len() as f64 / generation.as_secs_f64()
+    );
+    println!(
+        "Populated {} chunks in {:.2?}, including copying their regions",
+        populated, population
+    );
+    println!();
+    println!(
+        "{:<12} {:>12} {:>14} {:>7}",
+        "stage", "total", "per chunk", "share"
+    );
+    let total = timings.total();
+    for (stage, duration) in timings.iter() {
+        println!(
+            "{:<12} {:>12} {:>14} {:>6.1}%",
+            format!("{:?}", stage),
+            format!("{:.2?}", duration),
+            format!("{:.2?}", duration / chunks.len().max(1) as u32),
+            100.0 * duration.as_secs_f64() / total.as_secs_f64()
+        );
+    }
+}
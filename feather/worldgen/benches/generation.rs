@@ -0,0 +1,50 @@
+//! Benchmarks for the overworld generator.
+//!
+//! Run with `cargo bench -p feather-worldgen`. For a breakdown
+//! of the time spent in each stage, run the server
+//! with `--bench-worldgen` instead.
+
+use base::ChunkPosition;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use feather_worldgen::{ChunkRegion, ComposableGenerator, WorldGenerator, WorldPreset};
+
+const SEED: u64 = 0x5EED;
+
+fn generate_chunk(c: &mut Criterion) {
+    for &(name, preset) in &[
+        ("default", WorldPreset::Default),
+        ("amplified", WorldPreset::Amplified),
+        ("large_biomes", WorldPreset::LargeBiomes),
+    ] {
+        let generator = ComposableGenerator::with_preset(preset, SEED);
+        let mut x = 0;
+        c.bench_function(&format!("generate_chunk/{}", name), |b| {
+            b.iter(|| {
+                // Move along so no chunk is generated twice in a row.
+                x += 1;
+                black_box(generator.generate_chunk(ChunkPosition::new(x, 0)))
+            })
+        });
+    }
+}
+
+fn populate(c: &mut Criterion) {
+    let generator = ComposableGenerator::default_with_seed(SEED);
+    let chunks: Vec<_> = (-1..=1)
+        .flat_map(|z| (-1..=1).map(move |x| ChunkPosition::new(x, z)))
+        .map(|pos| generator.generate_chunk(pos))
+        .collect();
+    c.bench_function("populate", |b| {
+        b.iter_batched(
+            || ChunkRegion::new(chunks.clone()),
+            |mut region| {
+                generator.populate(&mut region);
+                region
+            },
+            criterion::BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, generate_chunk, populate);
+criterion_main!(benches);
@@ -0,0 +1,79 @@
+//! Aquifers: water filling caves below sea level.
+
+use crate::{
+    noise::{index, Wrapped3DPerlinNoise},
+    SKY_LIMIT,
+};
+use base::BlockId;
+
+/// Fills air blocks below each column's aquifer level with water.
+///
+/// The aquifer level is `sea_level` lowered by `aquifer_noise`,
+/// sampled at sea level, so that some caves below sea level
+/// stay dry. The noise should have a low frequency and
+/// the default chunk size. `blocks` must be indexable by `noise::index`.
+/// Sea levels above `SKY_LIMIT` are clamped to it.
+pub fn apply_aquifers(
+    blocks: &mut [BlockId],
+    sea_level: u32,
+    aquifer_noise: &Wrapped3DPerlinNoise,
+) {
+    let sea_level = sea_level.min(SKY_LIMIT as u32);
+    let noise = aquifer_noise.generate();
+
+    for x in 0..16 {
+        for z in 0..16 {
+            let offset = noise[index(x, sea_level as usize, z)].abs();
+            let level = (sea_level as f32 - offset).max(0.0) as usize;
+
+            for y in 0..level {
+                let block = &mut blocks[index(x, y, z)];
+                if block.is_air() {
+                    *block = BlockId::water();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn low_caves_fill_with_water() {
+        let mut blocks = vec![BlockId::stone(); 16 * 256 * 16];
+        for x in 4..12 {
+            for z in 4..12 {
+                for &y in &[10, 11, 100, 101] {
+                    blocks[index(x, y, z)] = BlockId::air();
+                }
+            }
+        }
+
+        let noise = Wrapped3DPerlinNoise::new(9)
+            .with_frequency(0.005)
+            .with_amplitude(8.0);
+        apply_aquifers(&mut blocks, 64, &noise);
+
+        for x in 4..12 {
+            for z in 4..12 {
+                assert_eq!(blocks[index(x, 10, z)], BlockId::water());
+                assert_eq!(blocks[index(x, 11, z)], BlockId::water());
+                assert_eq!(blocks[index(x, 100, z)], BlockId::air());
+                assert_eq!(blocks[index(x, 101, z)], BlockId::air());
+            }
+        }
+        assert_eq!(blocks[index(0, 10, 0)], BlockId::stone());
+    }
+
+    #[test]
+    fn sea_level_above_sky_limit_is_clamped() {
+        let mut blocks = vec![BlockId::air(); 16 * 256 * 16];
+        let noise = Wrapped3DPerlinNoise::new(9).with_amplitude(0.0);
+        apply_aquifers(&mut blocks, 300, &noise);
+
+        assert_eq!(blocks[index(0, 0, 0)], BlockId::water());
+        assert_eq!(blocks[index(15, SKY_LIMIT - 1, 15)], BlockId::water());
+    }
+}
@@ -0,0 +1,145 @@
+//! Biome selection based on temperature and humidity noise.
+
+use crate::noise::NoiseSeed;
+use crate::{BiomeGenerator, ChunkBiomes};
+use base::{Biome, ChunkPosition};
+use simdnoise::NoiseBuilder;
+
+/// Temperature and humidity values below this are "cold" and "dry".
+const LOW_THRESHOLD: f32 = -0.33;
+/// Temperature and humidity values above this are "hot" and "humid".
+const HIGH_THRESHOLD: f32 = 0.33;
+
+/// Picks a biome from a temperature and humidity value,
+/// each within `[-1.0, 1.0]`.
+///
+/// The classification matrix is:
+///
+/// | temperature \ humidity | dry          | moderate    | humid       |
+/// |------------------------|--------------|-------------|-------------|
+/// | cold                   | SnowyTundra  | SnowyTaiga  | Taiga       |
+/// | temperate              | Plains       | Forest      | Swamp       |
+/// | hot                    | Desert       | Savanna     | Jungle      |
+///
+/// Values outside of `[-1.0, 1.0]` are treated as the nearest extreme.
+pub fn classify_biome(temperature: f32, humidity: f32) -> Biome {
+    const MATRIX: [[Biome; 3]; 3] = [
+        [Biome::SnowyTundra, Biome::SnowyTaiga, Biome::Taiga],
+        [Biome::Plains, Biome::Forest, Biome::Swamp],
+        [Biome::Desert, Biome::Savanna, Biome::Jungle],
+    ];
+
+    MATRIX[band(temperature)][band(humidity)]
+}
+
+fn band(value: f32) -> usize {
+    if value < LOW_THRESHOLD {
+        0
+    } else if value > HIGH_THRESHOLD {
+        2
+    } else {
+        1
+    }
+}
+
+/// Classifies each of the 256 columns of a chunk
+/// given its temperature and humidity samples.
+///
+/// Both slices are indexed by `(z << 4) | x`.
+///
+/// # Panics
+/// Panics if either slice does not have length 256.
+pub fn generate_biome_map(temperature: &[f32], humidity: &[f32]) -> ChunkBiomes {
+    assert_eq!(temperature.len(), 16 * 16);
+    assert_eq!(humidity.len(), 16 * 16);
+
+    let mut biomes = ChunkBiomes::from_array([Biome::Plains; 16 * 16]);
+    for x in 0..16 {
+        for z in 0..16 {
+            let index = (z << 4) | x;
+            biomes.set_biome_at(x, z, classify_biome(temperature[index], humidity[index]));
+        }
+    }
+    biomes
+}
+
+/// Biome grid generator which samples two layers of 2D noise,
+/// temperature and humidity, and picks biomes using `classify_biome`.
+#[derive(Debug)]
+pub struct ClimateBiomeGenerator {
+    /// Frequency of the temperature and humidity noise.
+    frequency: f32,
+    /// Factor applied to raw noise values before classification.
+    amplitude: f32,
+}
+
+impl Default for ClimateBiomeGenerator {
+    fn default() -> Self {
+        Self {
+            frequency: 0.002,
+            amplitude: 40.0,
+        }
+    }
+}
+
+impl ClimateBiomeGenerator {
+    /// Sets the frequency of the temperature and humidity noise.
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Sets the factor applied to raw noise values before classification.
+    pub fn with_amplitude(mut self, amplitude: f32) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    fn sample(&self, chunk: ChunkPosition, seed: NoiseSeed, n: i32) -> Vec<f32> {
+        let (x_offset, z_offset) = seed.shift((chunk.x * 16) as f32, (chunk.z * 16) as f32);
+        let mut noise = NoiseBuilder::fbm_2d_offset(x_offset, 16, z_offset, 16)
+            .with_seed(seed.get(n))
+            .with_octaves(3)
+            .with_freq(self.frequency)
+            .generate()
+            .0;
+        noise.iter_mut().for_each(|value| *value *= self.amplitude);
+        noise
+    }
+}
+
+impl BiomeGenerator for ClimateBiomeGenerator {
+    fn generate_for_chunk(&self, chunk: ChunkPosition, seed: u64) -> ChunkBiomes {
+        let seed = NoiseSeed::new(seed);
+        let temperature = self.sample(chunk, seed, 0);
+        let humidity = self.sample(chunk, seed, 1);
+
+        generate_biome_map(&temperature, &humidity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extremes() {
+        assert_eq!(classify_biome(1.0, -1.0), Biome::Desert);
+        assert_eq!(classify_biome(1.0, 1.0), Biome::Jungle);
+        assert_eq!(classify_biome(-1.0, -1.0), Biome::SnowyTundra);
+        assert_eq!(classify_biome(-5.0, 0.0), Biome::SnowyTaiga);
+        assert_eq!(classify_biome(0.0, 0.0), Biome::Forest);
+    }
+
+    #[test]
+    fn biome_map_classifies_each_column() {
+        let mut temperature = vec![0.0; 256];
+        let humidity = vec![-1.0; 256];
+        temperature[(3 << 4) | 5] = 1.0;
+
+        let biomes = generate_biome_map(&temperature, &humidity);
+
+        assert_eq!(biomes.biome_at(5, 3), Biome::Desert);
+        assert_eq!(biomes.biome_at(3, 5), Biome::Plains);
+    }
+}
@@ -0,0 +1,161 @@
+//! Biome generation using a stack of zoom layers,
+//! similar to the pre-1.18 vanilla layer system.
+
+use super::classify_biome;
+use crate::{BiomeGenerator, ChunkBiomes};
+use base::{Biome, ChunkPosition};
+
+/// Number of times the base layer is zoomed in.
+/// Each zoom halves the size of a cell, so base cells
+/// span `2^ZOOM_LEVELS` blocks.
+const ZOOM_LEVELS: u32 = 8;
+
+/// Chance, out of 100, that a base cell is ocean.
+const OCEAN_CHANCE: u64 = 15;
+
+/// Biome grid generator which starts from a coarse grid of
+/// random climates and repeatedly zooms it in. Each zoom
+/// doubles the resolution and picks every new cell from one of
+/// its parent's neighbors at random, giving biome borders an
+/// irregular shape.
+///
+/// Every cell depends only on the seed and its coordinates,
+/// so chunks agree with their neighbors along borders.
+#[derive(Debug, Default)]
+pub struct LayeredBiomeGenerator;
+
+impl BiomeGenerator for LayeredBiomeGenerator {
+    fn generate_for_chunk(&self, chunk: ChunkPosition, seed: u64) -> ChunkBiomes {
+        let area = generate_area(seed, ZOOM_LEVELS, chunk.x * 16, chunk.z * 16, 16, 16);
+
+        let mut biomes = ChunkBiomes::from_array([Biome::Plains; 16 * 16]);
+        for x in 0..16 {
+            for z in 0..16 {
+                biomes.set_biome_at(x, z, area[z * 16 + x]);
+            }
+        }
+        biomes
+    }
+}
+
+/// Generates a `width` x `height` area of cells at the given
+/// layer, with its lowest corner at `(x, z)`.
+///
+/// Returns cells indexed by `z * width + x`.
+fn generate_area(seed: u64, level: u32, x: i32, z: i32, width: usize, height: usize) -> Vec<Biome> {
+    if level == 0 {
+        let mut area = Vec::with_capacity(width * height);
+        for dz in 0..height as i32 {
+            for dx in 0..width as i32 {
+                area.push(base_biome(seed, x + dx, z + dz));
+            }
+        }
+        return area;
+    }
+
+    // The parent area covers every cell, plus one extra
+    // cell in each direction for the neighbors of odd cells.
+    let parent_x = x >> 1;
+    let parent_z = z >> 1;
+    let parent_width = (((x + width as i32 - 1) >> 1) - parent_x + 2) as usize;
+    let parent_height = (((z + height as i32 - 1) >> 1) - parent_z + 2) as usize;
+    let parent = generate_area(
+        seed,
+        level - 1,
+        parent_x,
+        parent_z,
+        parent_width,
+        parent_height,
+    );
+    let parent_at = |px: i32, pz: i32| {
+        parent[(pz - parent_z) as usize * parent_width + (px - parent_x) as usize]
+    };
+
+    let mut area = Vec::with_capacity(width * height);
+    for dz in 0..height as i32 {
+        for dx in 0..width as i32 {
+            let (cx, cz) = (x + dx, z + dz);
+            let (px, pz) = (cx >> 1, cz >> 1);
+            let random = layer_random(seed, level as u64, cx, cz);
+
+            let candidates = [
+                parent_at(px, pz),
+                parent_at(px + 1, pz),
+                parent_at(px, pz + 1),
+                parent_at(px + 1, pz + 1),
+            ];
+            let biome = match (cx & 1, cz & 1) {
+                (0, 0) => candidates[0],
+                (1, 0) => candidates[(random % 2) as usize],
+                (0, 1) => candidates[(random % 2) as usize * 2],
+                _ => candidates[(random % 4) as usize],
+            };
+            area.push(biome);
+        }
+    }
+    area
+}
+
+/// Picks the biome of a cell in the base layer.
+fn base_biome(seed: u64, x: i32, z: i32) -> Biome {
+    let random = layer_random(seed, 0, x, z);
+    if random % 100 < OCEAN_CHANCE {
+        return Biome::Ocean;
+    }
+
+    let temperature = unit_float(random >> 8);
+    let humidity = unit_float(random >> 32);
+    classify_biome(temperature, humidity)
+}
+
+/// Maps the low 24 bits of `bits` to `[-1.0, 1.0]`.
+fn unit_float(bits: u64) -> f32 {
+    (bits & 0xFF_FFFF) as f32 / 0xFF_FFFF as f32 * 2.0 - 1.0
+}
+
+/// Deterministic random value for a cell of a layer.
+fn layer_random(seed: u64, layer: u64, x: i32, z: i32) -> u64 {
+    let mut value = seed
+        .wrapping_add(layer.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .wrapping_add((x as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F))
+        .wrapping_add((z as u64).wrapping_mul(0x1656_67B1_9E37_79F9));
+    // splitmix64 finalizer
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_agree_with_larger_areas() {
+        let seed = 5678;
+        let area = generate_area(seed, ZOOM_LEVELS, -16, -16, 32, 32);
+
+        for (chunk_x, chunk_z) in [(-1, -1), (0, -1), (-1, 0), (0, 0)].iter() {
+            let biomes = LayeredBiomeGenerator
+                .generate_for_chunk(ChunkPosition::new(*chunk_x, *chunk_z), seed);
+            for x in 0..16 {
+                for z in 0..16 {
+                    let area_x = ((chunk_x + 1) * 16) as usize + x;
+                    let area_z = ((chunk_z + 1) * 16) as usize + z;
+                    assert_eq!(biomes.biome_at(x, z), area[area_z * 32 + area_x]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zooming_keeps_biomes_coherent() {
+        let area = generate_area(42, ZOOM_LEVELS, 0, 0, 64, 64);
+
+        // Cells span 256 blocks in the base layer,
+        // so most neighboring columns share a biome.
+        let same = (0..63 * 64)
+            .filter(|&i| i % 64 != 63 && area[i] == area[i + 1])
+            .count();
+        assert!(same > 63 * 63 * 9 / 10);
+    }
+}
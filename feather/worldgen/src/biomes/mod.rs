@@ -1,7 +1,13 @@
 //! Biome grid creation.
 
+mod climate;
 mod distorted_voronoi;
+mod layered;
+mod spawns;
 mod two_level;
 
+pub use climate::{classify_biome, generate_biome_map, ClimateBiomeGenerator};
 pub use distorted_voronoi::DistortedVoronoiBiomeGenerator;
+pub use layered::LayeredBiomeGenerator;
+pub use spawns::{column_mob_spawns, mob_spawns, MobSpawns, SpawnCategory, SpawnEntry};
 pub use two_level::TwoLevelBiomeGenerator;
@@ -0,0 +1,410 @@
+//! The mobs which may spawn naturally in each biome.
+//!
+//! Lists follow vanilla's biome spawn settings. Natural
+//! spawning in `feather-common` rolls its packs from them.
+
+use base::{Biome, Chunk, EntityKind};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use std::collections::HashMap;
+use EntityKind::*;
+use SpawnCategory::*;
+
+/// The groups of mobs which are spawned separately,
+/// each with its own cap and spawn conditions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SpawnCategory {
+    /// Animals spawning on grass in daylight.
+    Passive,
+    /// Monsters spawning in the dark.
+    Hostile,
+    /// Fish, squid and dolphins spawning in water.
+    Water,
+}
+
+/// A mob which may spawn in a biome.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SpawnEntry {
+    pub kind: EntityKind,
+    /// Weight of the mob relative to the others in its category.
+    pub weight: u32,
+    /// Minimum number of mobs spawned in a group.
+    pub min_group: u32,
+    /// Maximum number of mobs spawned in a group.
+    pub max_group: u32,
+}
+
+const fn entry(kind: EntityKind, weight: u32, min_group: u32, max_group: u32) -> SpawnEntry {
+    SpawnEntry {
+        kind,
+        weight,
+        min_group,
+        max_group,
+    }
+}
+
+/// The mobs which may spawn in a biome, by category.
+#[derive(Clone, Debug, Default)]
+pub struct MobSpawns {
+    passive: Vec<SpawnEntry>,
+    hostile: Vec<SpawnEntry>,
+    water: Vec<SpawnEntry>,
+}
+
+impl MobSpawns {
+    /// Returns the mobs of the given category.
+    pub fn get(&self, category: SpawnCategory) -> &[SpawnEntry] {
+        match category {
+            SpawnCategory::Passive => &self.passive,
+            SpawnCategory::Hostile => &self.hostile,
+            SpawnCategory::Water => &self.water,
+        }
+    }
+
+    /// Chooses a mob of the given category at random,
+    /// weighted by `SpawnEntry::weight`. Returns `None`
+    /// if no mob of the category spawns in the biome.
+    pub fn choose(&self, category: SpawnCategory, rng: &mut impl Rng) -> Option<&SpawnEntry> {
+        let entries = self.get(category);
+        let total: u32 = entries.iter().map(|entry| entry.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rng.gen_range(0, total);
+        entries.iter().find(|entry| {
+            if roll < entry.weight {
+                true
+            } else {
+                roll -= entry.weight;
+                false
+            }
+        })
+    }
+
+    fn with(mut self, category: SpawnCategory, entries: &[SpawnEntry]) -> Self {
+        self.list_mut(category).extend_from_slice(entries);
+        self
+    }
+
+    /// Replaces the weight of `kind` in the given category,
+    /// like vanilla does for biome variants of common monsters.
+    fn with_weight(mut self, category: SpawnCategory, kind: EntityKind, weight: u32) -> Self {
+        let list = self.list_mut(category);
+        if let Some(entry) = list.iter_mut().find(|entry| entry.kind == kind) {
+            entry.weight = weight;
+        }
+        self
+    }
+
+    fn list_mut(&mut self, category: SpawnCategory) -> &mut Vec<SpawnEntry> {
+        match category {
+            SpawnCategory::Passive => &mut self.passive,
+            SpawnCategory::Hostile => &mut self.hostile,
+            SpawnCategory::Water => &mut self.water,
+        }
+    }
+}
+
+/// Returns the mobs which may spawn in the given biome.
+pub fn mob_spawns(biome: Biome) -> &'static MobSpawns {
+    static SPAWNS: Lazy<HashMap<Biome, MobSpawns>> = Lazy::new(|| {
+        (0..=u8::MAX as u32)
+            .filter_map(Biome::from_id)
+            .map(|biome| (biome, biome_spawns(biome)))
+            .collect()
+    });
+    &SPAWNS[&biome]
+}
+
+/// Returns the mobs which may spawn in the given column of a chunk.
+pub fn column_mob_spawns(chunk: &Chunk, x: usize, z: usize) -> &'static MobSpawns {
+    mob_spawns(chunk.biomes().get(x / 4, 0, z / 4))
+}
+
+const FARM_ANIMALS: &[SpawnEntry] = &[
+    entry(Sheep, 12, 4, 4),
+    entry(Pig, 10, 4, 4),
+    entry(Chicken, 10, 4, 4),
+    entry(Cow, 8, 4, 4),
+];
+
+const MONSTERS: &[SpawnEntry] = &[
+    entry(Spider, 100, 4, 4),
+    entry(Zombie, 95, 4, 4),
+    entry(ZombieVillager, 5, 1, 1),
+    entry(Skeleton, 100, 4, 4),
+    entry(Creeper, 100, 4, 4),
+    entry(Slime, 100, 4, 4),
+    entry(Enderman, 10, 1, 4),
+    entry(Witch, 5, 1, 1),
+];
+
+const OCEAN_MONSTERS: &[SpawnEntry] = &[entry(Drowned, 5, 1, 1)];
+const RIVER_MONSTERS: &[SpawnEntry] = &[entry(Drowned, 100, 1, 1)];
+
+const STRIDERS: &[SpawnEntry] = &[entry(Strider, 60, 1, 2)];
+
+fn biome_spawns(biome: Biome) -> MobSpawns {
+    let overworld = MobSpawns::default().with(Hostile, MONSTERS);
+    let farm = overworld.clone().with(Passive, FARM_ANIMALS);
+    match biome {
+        Biome::Plains | Biome::SunflowerPlains => {
+            farm.with(Passive, &[entry(Horse, 5, 2, 6), entry(Donkey, 1, 1, 3)])
+        }
+        Biome::Forest | Biome::WoodedHills => farm.with(Passive, &[entry(Wolf, 5, 4, 4)]),
+        Biome::FlowerForest => farm.with(Passive, &[entry(Rabbit, 4, 2, 3)]),
+        Biome::BirchForest
+        | Biome::BirchForestHills
+        | Biome::TallBirchForest
+        | Biome::TallBirchHills
+        | Biome::DarkForest
+        | Biome::DarkForestHills
+        | Biome::Badlands
+        | Biome::BadlandsPlateau
+        | Biome::WoodedBadlandsPlateau
+        | Biome::ErodedBadlands
+        | Biome::ModifiedBadlandsPlateau
+        | Biome::ModifiedWoodedBadlandsPlateau
+        | Biome::Swamp
+        | Biome::SwampHills
+        | Biome::StoneShore => farm,
+        Biome::Taiga
+        | Biome::TaigaHills
+        | Biome::TaigaMountains
+        | Biome::SnowyTaiga
+        | Biome::SnowyTaigaHills
+        | Biome::SnowyTaigaMountains => farm.with(
+            Passive,
+            &[
+                entry(Wolf, 8, 4, 4),
+                entry(Rabbit, 4, 2, 3),
+                entry(Fox, 8, 2, 4),
+            ],
+        ),
+        Biome::GiantTreeTaiga
+        | Biome::GiantTreeTaigaHills
+        | Biome::GiantSpruceTaiga
+        | Biome::GiantSpruceTaigaHills => farm
+            .with(
+                Passive,
+                &[
+                    entry(Wolf, 8, 4, 4),
+                    entry(Rabbit, 4, 2, 3),
+                    entry(Fox, 8, 2, 4),
+                ],
+            )
+            .with_weight(Hostile, Zombie, 100)
+            .with_weight(Hostile, ZombieVillager, 25),
+        Biome::Mountains
+        | Biome::MountainEdge
+        | Biome::WoodedMountains
+        | Biome::GravellyMountains
+        | Biome::ModifiedGravellyMountains => farm.with(Passive, &[entry(Llama, 5, 4, 6)]),
+        Biome::Savanna | Biome::ShatteredSavanna => {
+            farm.with(Passive, &[entry(Horse, 1, 2, 6), entry(Donkey, 1, 1, 1)])
+        }
+        Biome::SavannaPlateau | Biome::ShatteredSavannaPlateau => farm.with(
+            Passive,
+            &[
+                entry(Horse, 1, 2, 6),
+                entry(Donkey, 1, 1, 1),
+                entry(Llama, 8, 4, 4),
+            ],
+        ),
+        Biome::Jungle
+        | Biome::JungleHills
+        | Biome::JungleEdge
+        | Biome::ModifiedJungle
+        | Biome::ModifiedJungleEdge => farm.with(
+            Passive,
+            &[
+                entry(Parrot, 40, 1, 2),
+                entry(Panda, 1, 1, 2),
+                entry(Ocelot, 2, 1, 3),
+            ],
+        ),
+        Biome::BambooJungle | Biome::BambooJungleHills => farm.with(
+            Passive,
+            &[
+                entry(Parrot, 40, 1, 2),
+                entry(Panda, 80, 1, 2),
+                entry(Ocelot, 2, 1, 1),
+            ],
+        ),
+        Biome::Desert | Biome::DesertHills | Biome::DesertLakes => overworld
+            .with(Passive, &[entry(Rabbit, 4, 2, 3)])
+            .with_weight(Hostile, Zombie, 19)
+            .with_weight(Hostile, ZombieVillager, 1)
+            .with(Hostile, &[entry(Husk, 80, 4, 4)]),
+        Biome::SnowyTundra | Biome::SnowyMountains | Biome::IceSpikes => overworld
+            .with(
+                Passive,
+                &[entry(Rabbit, 10, 2, 3), entry(PolarBear, 1, 1, 2)],
+            )
+            .with_weight(Hostile, Skeleton, 20)
+            .with(Hostile, &[entry(Stray, 80, 4, 4)]),
+        Biome::Beach => farm.with(Passive, &[entry(Turtle, 5, 2, 5)]),
+        Biome::SnowyBeach => overworld,
+        Biome::MushroomFields | Biome::MushroomFieldShore => {
+            MobSpawns::default().with(Passive, &[entry(Mooshroom, 8, 4, 8)])
+        }
+        Biome::River | Biome::FrozenRiver => overworld
+            .with(Hostile, RIVER_MONSTERS)
+            .with(Water, &[entry(Squid, 2, 1, 4), entry(Salmon, 5, 1, 5)]),
+        Biome::Ocean | Biome::DeepOcean => overworld.with(Hostile, OCEAN_MONSTERS).with(
+            Water,
+            &[
+                entry(Squid, 1, 1, 4),
+                entry(Cod, 10, 3, 6),
+                entry(Dolphin, 1, 1, 2),
+            ],
+        ),
+        Biome::WarmOcean | Biome::DeepWarmOcean => overworld.with(Hostile, OCEAN_MONSTERS).with(
+            Water,
+            &[
+                entry(Pufferfish, 15, 1, 3),
+                entry(TropicalFish, 25, 8, 8),
+                entry(Dolphin, 2, 1, 2),
+            ],
+        ),
+        Biome::LukewarmOcean | Biome::DeepLukewarmOcean => {
+            overworld.with(Hostile, OCEAN_MONSTERS).with(
+                Water,
+                &[
+                    entry(Squid, 10, 1, 4),
+                    entry(Cod, 15, 3, 6),
+                    entry(Pufferfish, 5, 1, 3),
+                    entry(TropicalFish, 25, 8, 8),
+                    entry(Dolphin, 2, 1, 2),
+                ],
+            )
+        }
+        Biome::ColdOcean | Biome::DeepColdOcean => overworld.with(Hostile, OCEAN_MONSTERS).with(
+            Water,
+            &[
+                entry(Squid, 3, 1, 4),
+                entry(Cod, 15, 3, 6),
+                entry(Salmon, 15, 1, 5),
+            ],
+        ),
+        Biome::FrozenOcean | Biome::DeepFrozenOcean => overworld
+            .with(Passive, &[entry(PolarBear, 1, 1, 2)])
+            .with(Hostile, OCEAN_MONSTERS)
+            .with(Water, &[entry(Squid, 1, 1, 4), entry(Salmon, 15, 1, 5)]),
+        Biome::NetherWastes => MobSpawns::default().with(Passive, STRIDERS).with(
+            Hostile,
+            &[
+                entry(Ghast, 50, 4, 4),
+                entry(ZombifiedPiglin, 100, 4, 4),
+                entry(MagmaCube, 2, 4, 4),
+                entry(Enderman, 1, 4, 4),
+                entry(Piglin, 15, 4, 4),
+            ],
+        ),
+        Biome::SoulSandValley => MobSpawns::default().with(Passive, STRIDERS).with(
+            Hostile,
+            &[
+                entry(Skeleton, 20, 5, 5),
+                entry(Ghast, 50, 4, 4),
+                entry(Enderman, 1, 4, 4),
+            ],
+        ),
+        Biome::CrimsonForest => MobSpawns::default().with(Passive, STRIDERS).with(
+            Hostile,
+            &[
+                entry(ZombifiedPiglin, 1, 2, 4),
+                entry(Hoglin, 9, 3, 4),
+                entry(Piglin, 5, 3, 4),
+            ],
+        ),
+        Biome::WarpedForest => MobSpawns::default()
+            .with(Passive, STRIDERS)
+            .with(Hostile, &[entry(Enderman, 1, 4, 4)]),
+        Biome::BasaltDeltas => MobSpawns::default().with(Passive, STRIDERS).with(
+            Hostile,
+            &[entry(Ghast, 40, 1, 1), entry(MagmaCube, 100, 2, 5)],
+        ),
+        Biome::TheEnd
+        | Biome::SmallEndIslands
+        | Biome::EndMidlands
+        | Biome::EndHighlands
+        | Biome::EndBarrens => MobSpawns::default().with(Hostile, &[entry(Enderman, 10, 4, 4)]),
+        Biome::TheVoid => MobSpawns::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn biomes_have_fitting_mobs() {
+        let plains = mob_spawns(Biome::Plains);
+        assert!(plains.get(Passive).iter().any(|e| e.kind == Cow));
+        assert!(plains.get(Hostile).iter().any(|e| e.kind == Zombie));
+        assert!(plains.get(Water).is_empty());
+
+        let ocean = mob_spawns(Biome::Ocean);
+        assert!(ocean.get(Water).iter().any(|e| e.kind == Cod));
+
+        let desert = mob_spawns(Biome::Desert);
+        let weight = |kind| {
+            desert
+                .get(Hostile)
+                .iter()
+                .find(|e| e.kind == kind)
+                .unwrap()
+                .weight
+        };
+        assert_eq!(weight(Zombie), 19);
+        assert_eq!(weight(Husk), 80);
+
+        assert!(mob_spawns(Biome::MushroomFields).get(Hostile).is_empty());
+        assert!(mob_spawns(Biome::NetherWastes)
+            .get(Hostile)
+            .iter()
+            .all(|e| e.kind != Zombie));
+    }
+
+    #[test]
+    fn columns_use_their_biome() {
+        let mut chunk =
+            Chunk::new_with_default_biome(base::ChunkPosition::new(0, 0), Biome::Plains);
+        chunk.biomes_mut().set(3, 0, 0, Biome::Ocean);
+        assert!(column_mob_spawns(&chunk, 0, 0).get(Water).is_empty());
+        assert!(!column_mob_spawns(&chunk, 13, 2).get(Water).is_empty());
+    }
+
+    #[test]
+    fn every_biome_has_valid_groups() {
+        for biome in (0..=255).filter_map(Biome::from_id) {
+            let spawns = mob_spawns(biome);
+            for &category in &[Passive, Hostile, Water] {
+                for entry in spawns.get(category) {
+                    assert!(entry.weight > 0, "{:?} in {:?}", entry.kind, biome);
+                    assert!(entry.min_group >= 1 && entry.min_group <= entry.max_group);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn choose_respects_weights() {
+        let mut rng = XorShiftRng::seed_from_u64(0);
+        let spawns = mob_spawns(Biome::Desert);
+        let mut husks = 0;
+        for _ in 0..1000 {
+            let entry = spawns.choose(Hostile, &mut rng).unwrap();
+            if entry.kind == Husk {
+                husks += 1;
+            }
+        }
+        // Husks make up 80 of the 515 total weight.
+        assert!(husks > 100 && husks < 200, "{}", husks);
+        assert!(mob_spawns(Biome::TheVoid)
+            .choose(Hostile, &mut rng)
+            .is_none());
+    }
+}
@@ -28,14 +28,26 @@ static BIOME_GROUPS: Lazy<Vec<Vec<Biome>>> = Lazy::new(|| {
 /// to use. This technique allows similar biomes to be grouped
 /// together and prevents unrelated biomes from being neighbors.
 #[derive(Default)]
-pub struct TwoLevelBiomeGenerator;
+pub struct TwoLevelBiomeGenerator {
+    /// Number of times the Voronoi cells are doubled in size.
+    extra_zoom: u32,
+}
+
+impl TwoLevelBiomeGenerator {
+    /// Doubles the size of biomes `levels` times, like the
+    /// extra zoom layers of vanilla's "large biomes" world type.
+    pub fn with_extra_zoom(mut self, levels: u32) -> Self {
+        self.extra_zoom = levels;
+        self
+    }
+}
 
 impl BiomeGenerator for TwoLevelBiomeGenerator {
     fn generate_for_chunk(&self, chunk: ChunkPosition, seed: u64) -> ChunkBiomes {
         // Voronoi used to determine biome group
-        let mut group_voronoi = VoronoiGrid::new(1024, seed);
+        let mut group_voronoi = VoronoiGrid::new(1024 << self.extra_zoom, seed);
         // Voronoi used to determine biome within group
-        let mut local_voronoi = VoronoiGrid::new(256, seed + 1);
+        let mut local_voronoi = VoronoiGrid::new(256 << self.extra_zoom, seed + 1);
 
         let mut biomes = ChunkBiomes::from_array([Biome::Plains; 16 * 16]); // Will be overridden
 
@@ -73,3 +85,29 @@ impl BiomeGenerator for TwoLevelBiomeGenerator {
         biomes
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Number of biome changes between neighboring
+    /// chunks along a line of chunks.
+    fn biome_changes(generator: &TwoLevelBiomeGenerator) -> usize {
+        let biomes: Vec<Biome> = (0..512)
+            .map(|x| {
+                generator
+                    .generate_for_chunk(ChunkPosition::new(x, 0), 42)
+                    .biome_at(8, 8)
+            })
+            .collect();
+        biomes.windows(2).filter(|pair| pair[0] != pair[1]).count()
+    }
+
+    #[test]
+    fn extra_zoom_enlarges_biomes() {
+        let default = biome_changes(&TwoLevelBiomeGenerator::default());
+        let large = biome_changes(&TwoLevelBiomeGenerator::default().with_extra_zoom(2));
+        assert!(default > 0);
+        assert!(large < default);
+    }
+}
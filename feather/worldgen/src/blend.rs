@@ -0,0 +1,165 @@
+//! Smooth interpolation of biomes, used to avoid
+//! hard edges where biomes meet.
+
+use crate::NearbyBiomes;
+use base::Biome;
+use rand::Rng;
+
+/// Computes per-column biome weights by averaging the
+/// biomes within a radius, weighted by distance.
+///
+/// Since the weights are computed from `NearbyBiomes`, which
+/// includes the neighboring chunks, they are continuous
+/// across chunk borders.
+#[derive(Copy, Clone, Debug)]
+pub struct BiomeBlender {
+    radius: i32,
+}
+
+impl BiomeBlender {
+    /// Creates a blender averaging biomes within `radius` columns.
+    ///
+    /// # Panics
+    /// Panics if `radius > 15`, since `NearbyBiomes`
+    /// only contains a margin of one chunk.
+    pub fn new(radius: u32) -> Self {
+        assert!(
+            radius <= 15,
+            "blend radius must fit within neighboring chunks"
+        );
+        Self {
+            radius: radius as i32,
+        }
+    }
+
+    /// Returns the biome weights for the column at `(x, z)`,
+    /// relative to the center chunk of `biomes`.
+    pub fn weights_at(&self, biomes: &NearbyBiomes, x: i32, z: i32) -> BiomeWeights {
+        let mut weights = BiomeWeights::default();
+        let falloff = (self.radius + 1) as f32;
+        for dx in -self.radius..=self.radius {
+            for dz in -self.radius..=self.radius {
+                let distance = ((dx * dx + dz * dz) as f32).sqrt();
+                let weight = falloff - distance;
+                if weight > 0.0 {
+                    weights.add(biomes.biome_at(x + dx, z + dz), weight);
+                }
+            }
+        }
+        weights.normalize();
+        weights
+    }
+}
+
+/// Weights of the biomes around a column, summing to 1.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BiomeWeights {
+    weights: Vec<(Biome, f32)>,
+}
+
+impl BiomeWeights {
+    /// Returns the weight of `biome`, or zero
+    /// if it isn't near the column.
+    pub fn weight_of(&self, biome: Biome) -> f32 {
+        self.weights
+            .iter()
+            .find(|(b, _)| *b == biome)
+            .map_or(0.0, |(_, weight)| *weight)
+    }
+
+    /// Returns the biomes near the column and their weights.
+    pub fn iter(&self) -> impl Iterator<Item = (Biome, f32)> + '_ {
+        self.weights.iter().copied()
+    }
+
+    /// Returns the average of `f` over the biomes, by weight.
+    pub fn average(&self, f: impl Fn(Biome) -> f32) -> f32 {
+        self.iter().map(|(biome, weight)| f(biome) * weight).sum()
+    }
+
+    /// Picks a biome at random with probability equal to its weight.
+    pub fn sample(&self, rng: &mut impl Rng) -> Biome {
+        let mut threshold: f32 = rng.gen();
+        for &(biome, weight) in &self.weights {
+            if threshold < weight {
+                return biome;
+            }
+            threshold -= weight;
+        }
+        // Rounding error
+        self.weights.last().expect("no biomes").0
+    }
+
+    fn add(&mut self, biome: Biome, weight: f32) {
+        match self.weights.iter_mut().find(|(b, _)| *b == biome) {
+            Some((_, total)) => *total += weight,
+            None => self.weights.push((biome, weight)),
+        }
+    }
+
+    fn normalize(&mut self) {
+        let sum: f32 = self.weights.iter().map(|(_, weight)| weight).sum();
+        for (_, weight) in &mut self.weights {
+            *weight /= sum;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChunkBiomes;
+
+    /// Builds the `NearbyBiomes` for the chunk at X `chunk_x`
+    /// in a world which is plains for X < 16 and desert otherwise.
+    fn split_world(chunk_x: i32) -> NearbyBiomes {
+        let mut biomes = NearbyBiomes::from_vec(
+            (0..9)
+                .map(|_| ChunkBiomes::from_array([Biome::Plains; 16 * 16]))
+                .collect(),
+        );
+        for x in -15..32 {
+            for z in -15..32 {
+                let biome = if chunk_x * 16 + x < 16 {
+                    Biome::Plains
+                } else {
+                    Biome::Desert
+                };
+                biomes.set_biome_at(x, z, biome);
+            }
+        }
+        biomes
+    }
+
+    #[test]
+    fn weights_are_continuous_across_chunk_border() {
+        let blender = BiomeBlender::new(4);
+        let west = split_world(0);
+        let east = split_world(1);
+
+        // The same column seen from either chunk.
+        assert_eq!(
+            blender.weights_at(&west, 15, 8),
+            blender.weights_at(&east, -1, 8)
+        );
+
+        // Weights change gradually across the border.
+        let mut previous = 1.0;
+        for x in 10..22 {
+            let weights = if x < 16 {
+                blender.weights_at(&west, x, 8)
+            } else {
+                blender.weights_at(&east, x - 16, 8)
+            };
+            let plains = weights.weight_of(Biome::Plains);
+            approx::assert_relative_eq!(
+                plains + weights.weight_of(Biome::Desert),
+                1.0,
+                max_relative = 1e-5
+            );
+            assert!(plains <= previous && previous - plains < 0.3);
+            previous = plains;
+        }
+        assert!(previous < 0.01);
+    }
+}
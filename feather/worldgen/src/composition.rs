@@ -1,40 +1,160 @@
 //! Composition generator, used to populate chunks with blocks
 //! based on the density and biome values.
 
-use crate::{block_index, util, ChunkBiomes, CompositionGenerator, SEA_LEVEL};
+use crate::blend::BiomeBlender;
+use crate::{block_index, painter, util, CompositionGenerator, NearbyBiomes, SEA_LEVEL};
 use base::{Biome, BlockId, Chunk, ChunkPosition};
 use bitvec::order::LocalBits;
 use bitvec::slice::BitSlice;
-use rand::{Rng, SeedableRng};
+use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
 use std::cmp::min;
 
 /// A composition generator which generates basic
 /// terrain based on biome values.
-#[derive(Debug, Default)]
-pub struct BasicCompositionGenerator;
+#[derive(Debug)]
+pub struct BasicCompositionGenerator {
+    /// Rules overriding the default soil blocks.
+    surface_rules: Vec<SurfaceRule>,
+    /// Blender used to choose the biome for surface rules, if enabled.
+    blender: Option<BiomeBlender>,
+    /// Highest Y coordinate filled with water.
+    sea_level: usize,
+}
+
+impl Default for BasicCompositionGenerator {
+    fn default() -> Self {
+        Self {
+            surface_rules: Vec::new(),
+            blender: None,
+            sea_level: SEA_LEVEL,
+        }
+    }
+}
+
+impl BasicCompositionGenerator {
+    /// Sets the sea level. Air at or below the sea level is
+    /// filled with water, which freezes at the surface in
+    /// cold biomes.
+    pub fn with_sea_level(mut self, sea_level: usize) -> Self {
+        self.sea_level = sea_level;
+        self
+    }
+
+    /// Returns the sea level.
+    pub fn sea_level(&self) -> usize {
+        self.sea_level
+    }
+
+    /// Sets the surface rules. For each solid block, the first
+    /// matching rule determines the block; if none match,
+    /// the biome's default soil is used.
+    pub fn with_surface_rules(mut self, rules: Vec<SurfaceRule>) -> Self {
+        self.surface_rules = rules;
+        self
+    }
+
+    /// Matches surface rules against a biome picked at random for
+    /// each column, with probabilities given by the weights from
+    /// `blender`. This dithers the edges between biomes' surfaces
+    /// instead of leaving a straight line.
+    pub fn with_biome_blender(mut self, blender: BiomeBlender) -> Self {
+        self.blender = Some(blender);
+        self
+    }
+}
+
+/// A rule placing a block at certain depths below
+/// the surface, optionally only in certain biomes.
+///
+/// Depth is counted in solid blocks from the surface,
+/// so the top block has depth 0.
+#[derive(Debug, Clone)]
+pub struct SurfaceRule {
+    /// Biomes in which this rule applies, or `None` for all biomes.
+    biomes: Option<Vec<Biome>>,
+    /// The minimum depth, inclusive.
+    min_depth: usize,
+    /// The maximum depth, inclusive.
+    max_depth: usize,
+    /// The block to place.
+    block: BlockId,
+}
+
+impl SurfaceRule {
+    /// Creates a rule placing `block` in all biomes at depths
+    /// 0 through 3, i.e. in place of the top soil and the
+    /// soil underneath it.
+    pub fn new(block: BlockId) -> Self {
+        Self {
+            biomes: None,
+            min_depth: 0,
+            max_depth: 3,
+            block,
+        }
+    }
+
+    /// Restricts this rule to the given biomes.
+    pub fn in_biomes(mut self, biomes: &[Biome]) -> Self {
+        self.biomes = Some(biomes.to_vec());
+        self
+    }
+
+    /// Sets the depths, inclusive, at which this rule applies.
+    pub fn with_depth(mut self, min: usize, max: usize) -> Self {
+        self.min_depth = min;
+        self.max_depth = max;
+        self
+    }
+
+    fn matches(&self, biome: Biome, depth: usize) -> bool {
+        (self.min_depth..=self.max_depth).contains(&depth)
+            && self
+                .biomes
+                .as_ref()
+                .map_or(true, |biomes| biomes.contains(&biome))
+    }
+}
 
 impl CompositionGenerator for BasicCompositionGenerator {
     fn generate_for_chunk(
         &self,
         chunk: &mut Chunk,
-        _pos: ChunkPosition,
-        biomes: &ChunkBiomes,
+        pos: ChunkPosition,
+        biomes: &NearbyBiomes,
         density: &BitSlice<LocalBits, u8>,
         seed: u64,
     ) {
-        // For each column in the chunk, go from top to
-        // bottom. The first time a block density value is set to `true`,
-        // set it and the next three blocks to dirt. After that, use
-        // stone.
+        let mut rng = XorShiftRng::seed_from_u64(util::shuffle_seed_for_chunk(seed, pos));
+
+        // Fill each column with stone and water from the density
+        // map, then paint its surface with the biome's soil.
         for x in 0..16 {
             for z in 0..16 {
-                basic_composition_for_column(x, z, chunk, density, seed, biomes.biome_at(x, z));
+                let biome = biomes.biome_at(x, z);
+                let rule_biome = match &self.blender {
+                    Some(blender) => blender
+                        .weights_at(biomes, x as i32, z as i32)
+                        .sample(&mut rng),
+                    None => biome,
+                };
+                basic_composition_for_column(
+                    x,
+                    z,
+                    chunk,
+                    density,
+                    seed,
+                    biome,
+                    rule_biome,
+                    &self.surface_rules,
+                    self.sea_level,
+                );
             }
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn basic_composition_for_column(
     x: usize,
     z: usize,
@@ -42,10 +162,24 @@ fn basic_composition_for_column(
     density: &BitSlice<LocalBits, u8>,
     seed: u64,
     biome: Biome,
+    rule_biome: Biome,
+    surface_rules: &[SurfaceRule],
+    sea_level: usize,
 ) {
-    basic_composition_for_solid_biome(x, z, chunk, density, seed, biome);
+    basic_composition_for_solid_biome(
+        x,
+        z,
+        chunk,
+        density,
+        seed,
+        biome,
+        rule_biome,
+        surface_rules,
+        sea_level,
+    );
 }
 
+#[allow(clippy::too_many_arguments)]
 fn basic_composition_for_solid_biome(
     x: usize,
     z: usize,
@@ -53,65 +187,71 @@ fn basic_composition_for_solid_biome(
     density: &BitSlice<LocalBits, u8>,
     seed: u64,
     biome: Biome,
+    rule_biome: Biome,
+    surface_rules: &[SurfaceRule],
+    sea_level: usize,
 ) {
-    let mut rng =
-        XorShiftRng::seed_from_u64(util::shuffle_seed_for_column(seed, chunk.position(), x, z));
-
-    let top_soil = top_soil_block(biome);
-
-    let mut topsoil_remaining = -1;
+    let mut depth = 0; // Solid blocks since the last air block
     let mut water_level = 0; // `level` block data starts at 0 and skips to min(8+n, 15) for each level of water downward
     for y in (0..256).rev() {
-        let mut block = BlockId::air();
-
         let is_solid = density[block_index(x, y, z)];
 
-        let mut skip = false;
-
-        if biome == Biome::Ocean {
-            if y <= SEA_LEVEL && !is_solid {
-                block = BlockId::water().with_water_level(water_level);
-                if water_level == 0 {
-                    water_level = 8;
-                } else {
-                    water_level = min(water_level + 1, 15);
-                }
-                skip = true;
-            } else if y >= SEA_LEVEL {
-                continue; // Leave at air - no blocks above sea level in ocean
-            }
-        }
-
-        if !skip {
-            if y <= rng.gen_range(0, 4) {
-                block = BlockId::bedrock();
+        let block = if y <= sea_level && !is_solid {
+            let block = if y == sea_level && is_frozen_biome(biome) {
+                BlockId::ice()
             } else {
-                block = if is_solid {
-                    if topsoil_remaining == -1 {
-                        topsoil_remaining = 3;
-                        top_soil
-                    } else if topsoil_remaining > 0 {
-                        let block = underneath_top_soil_block(biome);
-                        topsoil_remaining -= 1;
-                        block
-                    } else {
-                        BlockId::stone()
-                    }
-                } else {
-                    topsoil_remaining = -1;
-                    BlockId::air()
-                };
+                BlockId::water().with_water_level(water_level)
+            };
+            if water_level == 0 {
+                water_level = 8;
+            } else {
+                water_level = min(water_level + 1, 15);
             }
-        }
+            depth = 0;
+            block
+        } else if biome == Biome::Ocean && y >= sea_level {
+            continue; // Leave at air - no blocks above sea level in ocean
+        } else if is_solid {
+            let rule = surface_rules
+                .iter()
+                .find(|rule| rule.matches(rule_biome, depth));
+            depth += 1;
+            rule.map_or(BlockId::stone(), |rule| rule.block)
+        } else {
+            depth = 0;
+            BlockId::air()
+        };
 
         if !block.is_air() {
             chunk.set_block_at(x, y, z, block);
         }
     }
+
+    // Soil and bedrock go wherever surface rules left stone.
+    let mut rng =
+        XorShiftRng::seed_from_u64(util::shuffle_seed_for_column(seed, chunk.position(), x, z));
+    painter::paint_column(chunk, x, z, biome, &mut rng);
+}
+
+/// Returns whether water at sea level freezes in the given biome.
+fn is_frozen_biome(biome: Biome) -> bool {
+    matches!(
+        biome,
+        Biome::SnowyTundra
+            | Biome::IceSpikes
+            | Biome::SnowyTaiga
+            | Biome::SnowyTaigaHills
+            | Biome::SnowyTaigaMountains
+            | Biome::SnowyMountains
+            | Biome::SnowyBeach
+            | Biome::FrozenRiver
+            | Biome::FrozenOcean
+            | Biome::DeepFrozenOcean
+    )
 }
 
 /// Returns the top soil block for the given biome.
-fn top_soil_block(biome: Biome) -> BlockId {
+pub(crate) fn top_soil_block(biome: Biome) -> BlockId {
     match biome {
         Biome::SnowyTundra
         | Biome::IceSpikes
@@ -135,7 +275,7 @@ fn top_soil_block(biome: Biome) -> BlockId {
 }
 
 /// Returns the block under the top soil block for the given biome.
-fn underneath_top_soil_block(biome: Biome) -> BlockId {
+pub(crate) fn underneath_top_soil_block(biome: Biome) -> BlockId {
     match biome {
         Biome::SnowyBeach => BlockId::snow_block(),
         Biome::GravellyMountains | Biome::ModifiedGravellyMountains => BlockId::gravel(),
@@ -176,9 +316,20 @@ mod tests {
         }
 
         let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
-        basic_composition_for_column(x, z, &mut chunk, &density[..], 435, Biome::Plains);
+        basic_composition_for_column(
+            x,
+            z,
+            &mut chunk,
+            &density[..],
+            435,
+            Biome::Plains,
+            Biome::Plains,
+            &[],
+            0,
+        );
 
-        for y in 4..=28 {
+        assert_eq!(chunk.block_at(x, 0, z).unwrap(), BlockId::bedrock());
+        for y in 5..=28 {
             assert_eq!(chunk.block_at(x, y, z).unwrap(), BlockId::stone());
         }
 
@@ -196,4 +347,91 @@ mod tests {
 
         assert_eq!(chunk.block_at(x, 64, z).unwrap(), BlockId::grass_block());
     }
+
+    #[test]
+    fn surface_rules_replace_soil() {
+        let mut density = BitVec::from_vec(vec![0u8; 16 * 256 * 16 / 8]);
+        for x in 0..2 {
+            for y in 0..=64 {
+                density.set(block_index(x, y, 0), true);
+            }
+        }
+        let rules = [SurfaceRule::new(BlockId::sand()).in_biomes(&[Biome::Desert])];
+
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        basic_composition_for_column(
+            0,
+            0,
+            &mut chunk,
+            &density[..],
+            435,
+            Biome::Desert,
+            Biome::Desert,
+            &rules,
+            0,
+        );
+        basic_composition_for_column(
+            1,
+            0,
+            &mut chunk,
+            &density[..],
+            435,
+            Biome::Plains,
+            Biome::Plains,
+            &rules,
+            0,
+        );
+
+        for y in 61..=64 {
+            assert_eq!(chunk.block_at(0, y, 0).unwrap(), BlockId::sand());
+        }
+        assert_eq!(chunk.block_at(0, 60, 0).unwrap(), BlockId::stone());
+
+        assert_eq!(chunk.block_at(1, 64, 0).unwrap(), BlockId::grass_block());
+        for y in 61..=63 {
+            assert_eq!(chunk.block_at(1, y, 0).unwrap(), BlockId::dirt());
+        }
+    }
+
+    #[test]
+    fn air_below_sea_level_fills_with_water() {
+        let mut density = BitVec::from_vec(vec![0u8; 16 * 256 * 16 / 8]);
+        for x in 0..2 {
+            for y in 0..=40 {
+                density.set(block_index(x, y, 0), true);
+            }
+        }
+
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        for &(x, biome) in [(0, Biome::Plains), (1, Biome::FrozenOcean)].iter() {
+            basic_composition_for_column(
+                x,
+                0,
+                &mut chunk,
+                &density[..],
+                435,
+                biome,
+                biome,
+                &[],
+                SEA_LEVEL,
+            );
+        }
+
+        for y in 41..SEA_LEVEL {
+            assert_eq!(
+                chunk.block_at(0, y, 0).unwrap().kind(),
+                BlockId::water().kind()
+            );
+            assert_eq!(
+                chunk.block_at(1, y, 0).unwrap().kind(),
+                BlockId::water().kind()
+            );
+        }
+        assert_eq!(
+            chunk.block_at(0, SEA_LEVEL, 0).unwrap().kind(),
+            BlockId::water().kind()
+        );
+        assert_eq!(chunk.block_at(1, SEA_LEVEL, 0).unwrap(), BlockId::ice());
+        assert!(chunk.block_at(0, SEA_LEVEL + 1, 0).unwrap().is_air());
+    }
 }
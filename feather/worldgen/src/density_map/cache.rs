@@ -0,0 +1,138 @@
+//! A least-recently-used cache of density columns.
+
+use super::density::DENSITY_HEIGHT;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// Default number of columns kept by a `ColumnCache`,
+/// enough for the borders of a few hundred chunks.
+const DEFAULT_CAPACITY: usize = 4096;
+
+/// The densities of each subchunk in a column.
+pub type Column = [f32; DENSITY_HEIGHT];
+
+/// Identifies a column by the seed it was generated with
+/// and its absolute subchunk coordinates.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ColumnKey {
+    pub seed: u64,
+    pub x: i32,
+    pub z: i32,
+}
+
+/// Caches the density columns along chunk borders, which
+/// are shared by neighboring chunks, so they're only
+/// computed once when the neighbors are generated in turn.
+///
+/// Once full, the least recently used column is evicted.
+#[derive(Debug)]
+pub struct ColumnCache {
+    capacity: usize,
+    columns: Mutex<Columns>,
+}
+
+#[derive(Debug, Default)]
+struct Columns {
+    /// Each column and the time it was last used.
+    entries: HashMap<ColumnKey, (Column, u64)>,
+    /// Keys ordered by the time they were last used.
+    recency: BTreeMap<u64, ColumnKey>,
+    clock: u64,
+}
+
+impl Default for ColumnCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl ColumnCache {
+    /// Creates a cache holding up to `capacity` columns.
+    /// A capacity of zero disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            columns: Mutex::new(Columns::default()),
+        }
+    }
+
+    /// Returns the cached column, marking it as recently used.
+    pub fn get(&self, key: ColumnKey) -> Option<Column> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let mut columns = self.columns.lock().unwrap();
+        let time = columns.tick();
+        let (column, last_used) = columns.entries.get_mut(&key)?;
+        let column = *column;
+        let previous = std::mem::replace(last_used, time);
+        columns.recency.remove(&previous);
+        columns.recency.insert(time, key);
+        Some(column)
+    }
+
+    /// Caches a column, evicting the least recently
+    /// used one if the cache is full.
+    pub fn insert(&self, key: ColumnKey, column: Column) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut columns = self.columns.lock().unwrap();
+        let time = columns.tick();
+        if let Some((_, previous)) = columns.entries.insert(key, (column, time)) {
+            columns.recency.remove(&previous);
+        }
+        columns.recency.insert(time, key);
+
+        while columns.entries.len() > self.capacity {
+            let oldest = *columns.recency.keys().next().unwrap();
+            let key = columns.recency.remove(&oldest).unwrap();
+            columns.entries.remove(&key);
+        }
+    }
+
+    /// Returns the number of cached columns.
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.columns.lock().unwrap().entries.len()
+    }
+}
+
+impl Columns {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(x: i32) -> ColumnKey {
+        ColumnKey { seed: 0, x, z: 0 }
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() {
+        let cache = ColumnCache::new(2);
+        cache.insert(key(0), [0.0; DENSITY_HEIGHT]);
+        cache.insert(key(1), [1.0; DENSITY_HEIGHT]);
+        assert!(cache.get(key(0)).is_some());
+        cache.insert(key(2), [2.0; DENSITY_HEIGHT]);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(key(0)), Some([0.0; DENSITY_HEIGHT]));
+        assert_eq!(cache.get(key(1)), None);
+        assert_eq!(cache.get(key(2)), Some([2.0; DENSITY_HEIGHT]));
+        assert_eq!(cache.get(ColumnKey { seed: 1, ..key(2) }), None);
+    }
+
+    #[test]
+    fn zero_capacity_disables_caching() {
+        let cache = ColumnCache::new(0);
+        cache.insert(key(0), [0.0; DENSITY_HEIGHT]);
+        assert_eq!(cache.get(key(0)), None);
+        assert_eq!(cache.len(), 0);
+    }
+}
@@ -3,7 +3,11 @@
 //! Over the 2D height map generator, this has the advantage that terrain
 //! is more interesting; overhangs and the like will be able to generate.
 
-use crate::{block_index, noise, DensityMapGenerator, NearbyBiomes, NoiseLerper};
+use super::cache::{Column, ColumnCache, ColumnKey};
+use crate::blend::BiomeBlender;
+use crate::noise::{ColumnNoise, NoiseSeed};
+use crate::spline::Spline;
+use crate::{block_index, noise, DensityMapGenerator, NearbyBiomes, NoiseLerper, SEA_LEVEL};
 use base::{Biome, ChunkPosition};
 use bitvec::order::LocalBits;
 use bitvec::vec::BitVec;
@@ -23,7 +27,112 @@ use simdnoise::NoiseBuilder;
 /// * Depending on the density value from the noise, decide
 /// whether the position is solid or air.
 #[derive(Debug, Default)]
-pub struct DensityMapGeneratorImpl;
+pub struct DensityMapGeneratorImpl {
+    /// Continentalness noise and the spline mapping it
+    /// to a terrain height offset, if enabled.
+    continentalness: Option<(ColumnNoise, Spline)>,
+    /// Erosion noise and the spline mapping it to
+    /// a factor applied to the amplitude, if enabled.
+    erosion: Option<(ColumnNoise, Spline)>,
+    /// Frequency and maximum amplitude of the
+    /// jaggedness noise, if enabled.
+    jaggedness: Option<(f32, f32)>,
+    /// Height above which all blocks are air, if enabled.
+    air_ceiling: Option<u32>,
+    /// Blender used to average biome parameters, if enabled.
+    /// Otherwise, biomes are smoothed over 5x5 cells.
+    blender: Option<BiomeBlender>,
+    /// Frequency of the density noises, if not the default.
+    frequency: Option<f32>,
+    /// Factor applied to the height of the terrain, if not the default.
+    height_scale: Option<f32>,
+    /// Density columns shared with neighboring chunks.
+    column_cache: ColumnCache,
+}
+
+impl DensityMapGeneratorImpl {
+    /// Shifts the terrain midpoint of each column by
+    /// `spline.sample(continentalness)` blocks, where
+    /// continentalness is sampled from `noise`.
+    ///
+    /// This allows large-scale features like oceans
+    /// and inland plateaus independent of biomes.
+    pub fn with_continentalness_spline(mut self, noise: ColumnNoise, spline: Spline) -> Self {
+        self.continentalness = Some((noise, spline));
+        self
+    }
+
+    /// Multiplies the amplitude of each column by
+    /// `spline.sample(erosion)`, where erosion is sampled
+    /// from `noise`.
+    ///
+    /// Larger factors steepen the vertical density gradient,
+    /// flattening the terrain, so the spline should map high
+    /// erosion to large factors and low erosion to small ones
+    /// to allow peaks. This composes with continentalness, which
+    /// moves the terrain up and down rather than flattening it.
+    pub fn with_erosion(mut self, noise: ColumnNoise, spline: Spline) -> Self {
+        self.erosion = Some((noise, spline));
+        self
+    }
+
+    /// Adds a high-frequency noise with amplitude up to `max`,
+    /// scaled from zero at the terrain midpoint to `max` at the
+    /// top of the column. This roughens peaks while leaving
+    /// valleys smooth.
+    pub fn with_jaggedness(mut self, noise_freq: f32, max: f32) -> Self {
+        self.jaggedness = Some((noise_freq, max));
+        self
+    }
+
+    /// Forces all blocks above `start_y` to be air, so that the
+    /// top of the world is open sky rather than a solid ceiling.
+    ///
+    /// Density is ramped toward air over the `AIR_CEILING_RAMP`
+    /// blocks below `start_y` so that terrain tapers off smoothly.
+    pub fn with_air_ceiling(mut self, start_y: u32) -> Self {
+        self.air_ceiling = Some(start_y);
+        self
+    }
+
+    /// Averages the amplitude and midpoint of each column
+    /// over the biome weights computed by `blender`.
+    pub fn with_biome_blender(mut self, blender: BiomeBlender) -> Self {
+        self.blender = Some(blender);
+        self
+    }
+
+    /// Sets the frequency of the 3D density noises,
+    /// which defaults to `DENSITY_FREQUENCY`. Lower
+    /// frequencies give broader hills and overhangs.
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = Some(frequency);
+        self
+    }
+
+    /// Multiplies the height of the terrain by `scale`.
+    ///
+    /// This flattens the vertical density gradient and lifts
+    /// the midpoint of columns above sea level, so hills and
+    /// mountains grow while oceans stay at the same depth.
+    /// Vanilla's "amplified" world type works similarly.
+    pub fn with_height_scale(mut self, scale: f32) -> Self {
+        self.height_scale = Some(scale);
+        self
+    }
+
+    /// Sets the number of density columns cached for reuse by
+    /// neighboring chunks. A capacity of zero disables the cache.
+    ///
+    /// Columns are keyed by their position and the seed, so the
+    /// biomes passed for a chunk must not change between calls.
+    /// The cache is unused if jaggedness is enabled, since the
+    /// jaggedness noise is scaled by the range of each batch.
+    pub fn with_column_cache(mut self, capacity: usize) -> Self {
+        self.column_cache = ColumnCache::new(capacity);
+        self
+    }
+}
 
 impl DensityMapGenerator for DensityMapGeneratorImpl {
     fn generate_for_chunk(
@@ -34,7 +143,7 @@ impl DensityMapGenerator for DensityMapGeneratorImpl {
     ) -> BitVec<LocalBits, u8> {
         let mut density = BitVec::from_vec(vec![0u8; 16 * 256 * 16 / 8]);
 
-        let uninterpolated_densities = generate_density(chunk, &biomes, seed);
+        let uninterpolated_densities = generate_density(self, chunk, &biomes, seed);
         let noise = NoiseLerper::new(&uninterpolated_densities)
             .with_offset(chunk.x, chunk.z)
             .generate();
@@ -44,7 +153,10 @@ impl DensityMapGenerator for DensityMapGeneratorImpl {
                 for z in 0..16 {
                     let value = noise[noise::index(x, y, z)];
 
-                    let is_solid = value < 0.0;
+                    let below_ceiling = self
+                        .air_ceiling
+                        .map_or(true, |start_y| y <= start_y as usize);
+                    let is_solid = value < 0.0 && below_ceiling;
                     let index = block_index(x, y, z);
                     density.set(index, is_solid);
                 }
@@ -55,8 +167,17 @@ impl DensityMapGenerator for DensityMapGeneratorImpl {
     }
 }
 
+/// Number of blocks below the air ceiling over
+/// which density is ramped toward air.
+const AIR_CEILING_RAMP: f32 = 16.0;
+/// Density added at and above the air ceiling.
+const AIR_CEILING_DENSITY: f32 = 100.0;
+
+/// Default frequency of the 3D density noises.
+const DENSITY_FREQUENCY: f32 = 0.2;
+
 const DENSITY_WIDTH: usize = 5;
-const DENSITY_HEIGHT: usize = 33;
+pub(super) const DENSITY_HEIGHT: usize = 33;
 
 /// Generates a 5x33x5 density array to pass to `NoiseLerper`.
 ///
@@ -70,179 +191,378 @@ const DENSITY_HEIGHT: usize = 33;
 /// The density values emitted from this function should
 /// be considered solid if less than 0 and air if greater
 /// than 0. This is contrary to what might seem logical.
-fn generate_density(chunk: ChunkPosition, biomes: &NearbyBiomes, seed: u64) -> Vec<f32> {
+///
+/// Columns on the chunk's borders are shared with its neighbors,
+/// so they're taken from the generator's `ColumnCache` if possible.
+fn generate_density(
+    generator: &DensityMapGeneratorImpl,
+    chunk: ChunkPosition,
+    biomes: &NearbyBiomes,
+    seed: u64,
+) -> Vec<f32> {
+    let cache = match generator.jaggedness {
+        None => Some(&generator.column_cache),
+        Some(_) => None,
+    };
+    let key = |subx: usize, subz: usize| ColumnKey {
+        seed,
+        x: chunk.x * (DENSITY_WIDTH as i32 - 1) + subx as i32,
+        z: chunk.z * (DENSITY_WIDTH as i32 - 1) + subz as i32,
+    };
+
+    let mut result = vec![0.0; DENSITY_WIDTH * DENSITY_HEIGHT * DENSITY_WIDTH];
+    let mut missing = Vec::new();
+    for subx in 0..DENSITY_WIDTH {
+        for subz in 0..DENSITY_WIDTH {
+            match cache.and_then(|cache| cache.get(key(subx, subz))) {
+                Some(column) => {
+                    for (suby, &value) in column.iter().enumerate() {
+                        result[density_index(subx, suby, subz)] = value;
+                    }
+                }
+                None => missing.push((subx, subz)),
+            }
+        }
+    }
+
+    if !missing.is_empty() {
+        sample_columns(generator, chunk, biomes, seed, &missing, &mut result);
+        if let Some(cache) = cache {
+            for &(subx, subz) in &missing {
+                let mut column: Column = [0.0; DENSITY_HEIGHT];
+                for (suby, value) in column.iter_mut().enumerate() {
+                    *value = result[density_index(subx, suby, subz)];
+                }
+                cache.insert(key(subx, subz), column);
+            }
+        }
+    }
+
+    result
+}
+
+/// Returns the index of a subchunk in a density array.
+fn density_index(subx: usize, suby: usize, subz: usize) -> usize {
+    DENSITY_WIDTH * suby + subx + DENSITY_WIDTH * DENSITY_HEIGHT * subz
+}
+
+/// Computes the densities of the given columns of
+/// subchunks, writing them into `result`.
+///
+/// Noise is sampled over the smallest square of
+/// columns containing all of them.
+fn sample_columns(
+    generator: &DensityMapGeneratorImpl,
+    chunk: ChunkPosition,
+    biomes: &NearbyBiomes,
+    seed: u64,
+    columns: &[(usize, usize)],
+    result: &mut [f32],
+) {
     // TODO: generate based on biome
 
-    let x_offset = (chunk.x * (DENSITY_WIDTH as i32 - 1)) as f32;
+    let min_x = columns.iter().map(|&(subx, _)| subx).min().unwrap_or(0);
+    let min_z = columns.iter().map(|&(_, subz)| subz).min().unwrap_or(0);
+    let len = columns
+        .iter()
+        .map(|&(subx, subz)| (subx - min_x).max(subz - min_z) + 1)
+        .max()
+        .unwrap_or(0);
+
+    let x_offset = (chunk.x * (DENSITY_WIDTH as i32 - 1) + min_x as i32) as f32;
     let y_offset = 0.0;
-    let z_offset = (chunk.z * (DENSITY_WIDTH as i32 - 1)) as f32;
-    let len = DENSITY_WIDTH;
+    let z_offset = (chunk.z * (DENSITY_WIDTH as i32 - 1) + min_z as i32) as f32;
     let height = DENSITY_HEIGHT;
 
-    let noise_seed = seed as i32;
+    let noise_seed = NoiseSeed::new(seed);
+    // Only the noise sampled here is shifted;
+    // `ColumnNoise` shifts its own coordinates.
+    let (noise_x, noise_z) = noise_seed.shift(x_offset, z_offset);
+    let frequency = generator.frequency.unwrap_or(DENSITY_FREQUENCY);
+    let height_scale = generator.height_scale.unwrap_or(1.0);
 
     // Generate various noises.
-    let choice_noise = NoiseBuilder::fbm_3d_offset(x_offset, len, y_offset, height, z_offset, len)
-        .with_seed(noise_seed)
+    let choice_noise = NoiseBuilder::fbm_3d_offset(noise_x, len, y_offset, height, noise_z, len)
+        .with_seed(noise_seed.get(0))
         .with_octaves(2)
         .with_freq(0.001)
         .generate()
         .0;
-    let density_noise_1 =
-        NoiseBuilder::fbm_3d_offset(x_offset, len, y_offset, height, z_offset, len)
-            .with_seed(noise_seed + 1)
-            .with_octaves(2)
-            .with_freq(0.2)
-            .generate()
-            .0;
-    let density_noise_2 =
-        NoiseBuilder::fbm_3d_offset(x_offset, len, y_offset, height, z_offset, len)
-            .with_seed(noise_seed + 2)
-            .with_octaves(2)
-            .with_freq(0.2)
-            .generate()
-            .0;
+    let density_noise_1 = NoiseBuilder::fbm_3d_offset(noise_x, len, y_offset, height, noise_z, len)
+        .with_seed(noise_seed.get(1))
+        .with_octaves(2)
+        .with_freq(frequency)
+        .generate()
+        .0;
+    let density_noise_2 = NoiseBuilder::fbm_3d_offset(noise_x, len, y_offset, height, noise_z, len)
+        .with_seed(noise_seed.get(2))
+        .with_octaves(2)
+        .with_freq(frequency)
+        .generate()
+        .0;
     // Additional 2D height noise for extra detail.
-    let height_noise = NoiseBuilder::fbm_2d_offset(x_offset, len, z_offset, len)
-        .with_seed(noise_seed + 3)
+    let height_noise = NoiseBuilder::fbm_2d_offset(noise_x, len, noise_z, len)
+        .with_seed(noise_seed.get(3))
         .with_octaves(2)
         .with_freq(0.001)
         .generate()
         .0;
-
-    let mut result = vec![0.0; DENSITY_WIDTH * DENSITY_HEIGHT * DENSITY_WIDTH];
+    // Optional continentalness noise, mapped through its spline below.
+    let continentalness = generator
+        .continentalness
+        .as_ref()
+        .map(|(noise, spline)| (noise.generate(seed, x_offset, z_offset, len), spline));
+    // Optional erosion noise, mapped through its spline below.
+    let erosion = generator
+        .erosion
+        .as_ref()
+        .map(|(noise, spline)| (noise.generate(seed, x_offset, z_offset, len), spline));
+    // Optional high-frequency jaggedness noise.
+    let jaggedness = generator.jaggedness.map(|(frequency, max)| {
+        NoiseBuilder::gradient_3d_offset(noise_x, len, y_offset, height, noise_z, len)
+            .with_seed(noise_seed.get(4))
+            .with_freq(frequency)
+            .generate_scaled(-max, max)
+    });
 
     // Loop through subchunks and generate density for each.
-    for subx in 0..DENSITY_WIDTH {
-        for subz in 0..DENSITY_WIDTH {
-            // TODO: average nearby biome parameters
-            let (amplitude, midpoint) = column_parameters(&biomes, subx, subz);
+    for &(subx, subz) in columns {
+        let column = (subz - min_z) * len + (subx - min_x);
+        let (mut amplitude, mut midpoint) = match &generator.blender {
+            Some(blender) => blended_column_parameters(blender, biomes, subx, subz),
+            None => column_parameters(&biomes, subx, subz),
+        };
+        if let Some((noise, spline)) = &continentalness {
+            midpoint += spline.sample(noise[column]);
+        }
+        if let Some((noise, spline)) = &erosion {
+            amplitude *= spline.sample(noise[column]);
+        }
+        if midpoint > SEA_LEVEL as f32 {
+            midpoint = SEA_LEVEL as f32 + (midpoint - SEA_LEVEL as f32) * height_scale;
+        }
+        amplitude /= height_scale;
 
-            let height = height_noise[(subz * len) + subx] * 25.0;
+        let height = height_noise[column] * 25.0 * height_scale;
 
-            // Loop through Y axis of this subchunk column.
-            for suby in 0..DENSITY_HEIGHT {
-                // Linear gradient used to offset based on height.
-                let mut height_offset = ((suby as f32 * 8.0) - midpoint) * amplitude;
+        // Loop through Y axis of this subchunk column.
+        for suby in 0..DENSITY_HEIGHT {
+            // Linear gradient used to offset based on height.
+            let mut height_offset = ((suby as f32 * 8.0) - midpoint) * amplitude;
 
-                // If we are below the midpoint, increase the slope of the gradient.
-                // This creates smoother terrain.
-                if height_offset < 0.0 {
-                    height_offset *= 4.0;
-                }
+            // If we are below the midpoint, increase the slope of the gradient.
+            // This creates smoother terrain.
+            if height_offset < 0.0 {
+                height_offset *= 4.0;
+            }
 
-                // When we are near sky limit, decrease
-                // the slope. This ensures that very tall
-                // mountains don't artificially cut off
-                // at Y=256.
-                if suby > 26 {
-                    height_offset += (suby as f32 - 28.0) / 4.0;
-                }
+            // When we are near sky limit, decrease
+            // the slope. This ensures that very tall
+            // mountains don't artificially cut off
+            // at Y=256.
+            if suby > 26 {
+                height_offset += (suby as f32 - 28.0) / 4.0;
+            }
 
-                let index = DENSITY_WIDTH * suby + subx + DENSITY_WIDTH * DENSITY_HEIGHT * subz;
+            let index = density_index(subx, suby, subz);
+            let noise_index = len * suby + (subx - min_x) + len * DENSITY_HEIGHT * (subz - min_z);
 
-                let choice = choice_noise[index] * 100.0;
-                let density_1 = density_noise_1[index] * 50.0;
-                let density_2 = density_noise_2[index] * 50.0;
+            let choice = choice_noise[noise_index] * 100.0;
+            let density_1 = density_noise_1[noise_index] * 50.0;
+            let density_2 = density_noise_2[noise_index] * 50.0;
+
+            // Average between two density values based on choice weight.
+            result[index] = lerp(density_1, density_2, choice) + height_offset + height;
+
+            if let Some(jaggedness) = &jaggedness {
+                // Zero at and below the midpoint, rising to
+                // full strength at the top of the column.
+                let weight = ((suby as f32 * 8.0) - midpoint) / (256.0 - midpoint);
+                result[index] += jaggedness[noise_index] * weight.max(0.0).min(1.0);
+            }
 
-                // Average between two density values based on choice weight.
-                result[index] = lerp(density_1, density_2, choice) + height_offset + height;
+            if let Some(start_y) = generator.air_ceiling {
+                let ramp_start = start_y as f32 - AIR_CEILING_RAMP;
+                let weight = (suby as f32 * 8.0 - ramp_start) / AIR_CEILING_RAMP;
+                result[index] += AIR_CEILING_DENSITY * weight.clamp(0.0, 1.0);
             }
         }
     }
-
-    result
 }
 
-/// Elevation height field, used to weight
-/// the averaging of nearby biome heights.
-static ELEVATION_WEIGHT: Lazy<[[f32; 19]; 19]> = Lazy::new(|| {
-    let mut array = [[0.0; 19]; 19];
+/// Width of the cells in which biomes are sampled when
+/// smoothing terrain parameters, in blocks.
+const BIOME_CELL: i32 = 4;
+/// Distance in cells of the biomes averaged by
+/// `column_parameters`, giving a 5x5 neighborhood.
+const SMOOTHING_RADIUS: i32 = 2;
+
+/// Weights of the cells around a column, falling off
+/// with the distance to the center cell.
+static SMOOTHING_WEIGHTS: Lazy<[[f32; 5]; 5]> = Lazy::new(|| {
+    let mut array = [[0.0; 5]; 5];
     for (x, values) in array.iter_mut().enumerate() {
         for (z, value) in values.iter_mut().enumerate() {
-            let mut x_squared = x as i32 - 9;
-            x_squared *= x_squared;
-            let mut z_sqaured = z as i32 - 9;
-            z_sqaured *= z_sqaured;
-            *value = 10.0 / (x_squared as f32 + z_sqaured as f32 + 0.2).sqrt();
+            let dx = x as i32 - SMOOTHING_RADIUS;
+            let dz = z as i32 - SMOOTHING_RADIUS;
+            *value = 10.0 / ((dx * dx + dz * dz) as f32 + 0.2).sqrt();
         }
     }
     array
 });
 
 /// Computes the target amplitude and midpoint for the
-/// given column, using a 9x9 grid of biomes
-/// around the column to determine a weighted average.
+/// given column by smoothing the depth and scale of the
+/// biomes in the 5x5 cells around it.
+///
+/// Like vanilla, deep biomes weigh more than shallow ones, and
+/// biomes higher than the column's own weigh half as much, so
+/// oceans next to mountains slope up rather than meeting them
+/// at a cliff.
 ///
 /// The X and Z parameters are the coordinates of the subchunk
 /// within the chunk, not the block coordinate.
 fn column_parameters(biomes: &NearbyBiomes, x: usize, z: usize) -> (f32, f32) {
     let x = x as i32 * (DENSITY_WIDTH as i32 - 1);
     let z = z as i32 * (DENSITY_WIDTH as i32 - 1);
+    let (center_depth, _) = biome_terrain(biomes.biome_at(x, z));
 
-    let mut sum_amplitudes = 0.0;
-    let mut sum_midpoints = 0.0;
+    let mut sum_depths = 0.0;
+    let mut sum_scales = 0.0;
     let mut sum_weights = 0.0;
+    for cell_x in -SMOOTHING_RADIUS..=SMOOTHING_RADIUS {
+        for cell_z in -SMOOTHING_RADIUS..=SMOOTHING_RADIUS {
+            let biome = biomes.biome_at(x + cell_x * BIOME_CELL, z + cell_z * BIOME_CELL);
+            let (depth, scale) = biome_terrain(biome);
+
+            let mut weight = SMOOTHING_WEIGHTS[(cell_x + SMOOTHING_RADIUS) as usize]
+                [(cell_z + SMOOTHING_RADIUS) as usize]
+                / (depth + 2.0);
+            if depth > center_depth {
+                weight /= 2.0;
+            }
 
-    // Loop through columns in 9x9 grid and compute weighted average of amplitudes
-    // and midpoints.
-    for block_x in -9..=9 {
-        for block_z in -9..=9 {
-            let abs_x = x + block_x;
-            let abs_z = z + block_z;
-
-            let biome = biomes.biome_at(abs_x, abs_z);
-            let (amplitude, midpoint) = biome_parameters(biome);
-
-            let weight = ELEVATION_WEIGHT[(block_x + 9) as usize][(block_z + 9) as usize];
-
-            sum_amplitudes += amplitude * weight;
-            sum_midpoints += midpoint * weight;
+            sum_depths += depth * weight;
+            sum_scales += scale * weight;
             sum_weights += weight;
         }
     }
 
-    sum_amplitudes /= sum_weights;
-    sum_midpoints /= sum_weights;
+    terrain_parameters(sum_depths / sum_weights, sum_scales / sum_weights)
+}
+
+/// Computes the target amplitude and midpoint for the
+/// given column using the weights from `blender`.
+///
+/// The X and Z parameters are the coordinates of the subchunk
+/// within the chunk, not the block coordinate.
+fn blended_column_parameters(
+    blender: &BiomeBlender,
+    biomes: &NearbyBiomes,
+    x: usize,
+    z: usize,
+) -> (f32, f32) {
+    let x = x as i32 * (DENSITY_WIDTH as i32 - 1);
+    let z = z as i32 * (DENSITY_WIDTH as i32 - 1);
 
-    (sum_amplitudes, sum_midpoints)
+    let weights = blender.weights_at(biomes, x, z);
+    let depth = weights.average(|biome| biome_terrain(biome).0);
+    let scale = weights.average(|biome| biome_terrain(biome).1);
+    terrain_parameters(depth, scale)
 }
 
-/// Returns the amplitude and midpoint for a given biome
-/// type as a tuple in that order.
+/// Midpoint of a column with a depth of zero.
+const BASE_MIDPOINT: f32 = 60.0;
+/// Blocks the midpoint is raised per unit of depth.
+const DEPTH_BLOCKS: f32 = 16.0;
+/// Amplitude of a column is `SCALE_AMPLITUDE / (scale + SCALE_BIAS)`.
+const SCALE_AMPLITUDE: f32 = 0.018;
+/// Keeps the amplitude finite for biomes with a scale of zero.
+const SCALE_BIAS: f32 = 0.01;
+
+/// Converts a depth and scale into the amplitude
+/// and midpoint of a column, in that order.
 ///
-/// All original values were taken from Cuberite's source,
-/// so all credit for this function goes to their team
-/// for the presumably highly laborious effort
-/// involved in finding these values.
-fn biome_parameters(biome: Biome) -> (f32, f32) {
+/// Depth raises the terrain (or lowers it, when negative),
+/// while scale makes it more varied by flattening the
+/// vertical density gradient.
+fn terrain_parameters(depth: f32, scale: f32) -> (f32, f32) {
+    let amplitude = SCALE_AMPLITUDE / (scale.max(0.0) + SCALE_BIAS);
+    let midpoint = BASE_MIDPOINT + depth * DEPTH_BLOCKS;
+    (amplitude, midpoint)
+}
+
+/// Returns the depth and scale of a given biome
+/// as a tuple in that order.
+///
+/// Values follow vanilla's biome definitions, so deserts and
+/// plains are flat, oceans are deep, and mountains and
+/// plateaus stand well above the surrounding terrain.
+fn biome_terrain(biome: Biome) -> (f32, f32) {
     match biome {
-        Biome::Beach => (0.2, 60.0),
-        Biome::BirchForest => (0.1, 64.0),
-        Biome::BirchForestHills => (0.075, 64.0),
-        Biome::TallBirchHills => (0.075, 68.0),
-        Biome::TallBirchForest => (0.1, 64.0),
-        Biome::SnowyBeach => (0.3, 62.0),
-        Biome::Taiga => (0.3, 62.0),
-        Biome::TaigaHills => (0.075, 68.0),
-        Biome::DesertHills => (0.075, 68.0),
-        Biome::DeepOcean => (0.17, 35.0),
-        Biome::Desert => (0.15, 62.0),
-        Biome::Mountains => (0.045, 75.0),
-        Biome::MountainEdge => (0.1, 70.0),
-        Biome::WoodedMountains => (0.04, 80.0),
-        Biome::FlowerForest => (0.1, 64.0),
-        Biome::Forest => (0.1, 64.0),
-        Biome::Jungle => (0.1, 63.0),
-        Biome::Ocean => (0.12, 45.0),
-        Biome::Plains => (0.3, 62.0),
-        Biome::Savanna => (0.3, 62.0),
-        Biome::SavannaPlateau => (0.3, 85.0),
-        Biome::StoneShore => (0.075, 60.0),
-        Biome::SunflowerPlains => (0.3, 62.0),
-        Biome::Swamp => (0.25, 59.0),
-        // TODO: finish this list
-        _ => (0.3, 62.0),
+        Biome::Plains
+        | Biome::SunflowerPlains
+        | Biome::Desert
+        | Biome::SnowyTundra
+        | Biome::Savanna => (0.125, 0.05),
+        Biome::DesertLakes => (0.225, 0.25),
+        Biome::IceSpikes => (0.425, 0.45),
+        Biome::ShatteredSavanna => (0.3625, 1.225),
+        Biome::Forest
+        | Biome::FlowerForest
+        | Biome::BirchForest
+        | Biome::TallBirchForest
+        | Biome::DarkForest
+        | Biome::Jungle
+        | Biome::ModifiedJungle
+        | Biome::BambooJungle
+        | Biome::JungleEdge
+        | Biome::ModifiedJungleEdge
+        | Biome::Badlands
+        | Biome::ErodedBadlands => (0.1, 0.2),
+        Biome::Taiga | Biome::SnowyTaiga | Biome::GiantTreeTaiga | Biome::GiantSpruceTaiga => {
+            (0.2, 0.2)
+        }
+        Biome::TaigaMountains | Biome::SnowyTaigaMountains => (0.3, 0.4),
+        Biome::DesertHills
+        | Biome::WoodedHills
+        | Biome::TaigaHills
+        | Biome::JungleHills
+        | Biome::BambooJungleHills
+        | Biome::BirchForestHills
+        | Biome::SnowyTaigaHills
+        | Biome::GiantTreeTaigaHills
+        | Biome::GiantSpruceTaigaHills
+        | Biome::SnowyMountains => (0.45, 0.3),
+        Biome::TallBirchHills | Biome::DarkForestHills => (0.55, 0.5),
+        Biome::Mountains
+        | Biome::WoodedMountains
+        | Biome::GravellyMountains
+        | Biome::ModifiedGravellyMountains => (1.0, 0.5),
+        Biome::MountainEdge => (0.8, 0.3),
+        Biome::SavannaPlateau | Biome::BadlandsPlateau | Biome::WoodedBadlandsPlateau => {
+            (1.5, 0.025)
+        }
+        Biome::ShatteredSavannaPlateau => (1.05, 1.2125),
+        Biome::ModifiedBadlandsPlateau | Biome::ModifiedWoodedBadlandsPlateau => (0.45, 0.3),
+        Biome::MushroomFields => (0.2, 0.3),
+        Biome::MushroomFieldShore => (0.0, 0.025),
+        Biome::Beach | Biome::SnowyBeach => (0.0, 0.025),
+        Biome::StoneShore => (0.1, 0.8),
+        Biome::Swamp => (-0.2, 0.1),
+        Biome::SwampHills => (-0.1, 0.3),
+        Biome::River | Biome::FrozenRiver => (-0.5, 0.0),
+        Biome::Ocean
+        | Biome::FrozenOcean
+        | Biome::WarmOcean
+        | Biome::LukewarmOcean
+        | Biome::ColdOcean => (-1.0, 0.1),
+        Biome::DeepOcean
+        | Biome::DeepWarmOcean
+        | Biome::DeepLukewarmOcean
+        | Biome::DeepColdOcean
+        | Biome::DeepFrozenOcean => (-1.8, 0.1),
+        _ => (0.1, 0.2),
     }
 }
 
@@ -259,3 +579,194 @@ fn lerp(a: f32, b: f32, weight: f32) -> f32 {
 
     a + (b - a) * weight
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChunkBiomes;
+
+    fn uniform(biome: Biome) -> NearbyBiomes {
+        NearbyBiomes::from_vec(
+            (0..9)
+                .map(|_| ChunkBiomes::from_array([biome; 16 * 16]))
+                .collect(),
+        )
+    }
+
+    fn plains() -> NearbyBiomes {
+        uniform(Biome::Plains)
+    }
+
+    /// Sum of absolute second differences along Y
+    /// in the given range of subchunks.
+    fn roughness(densities: &[f32], suby: std::ops::Range<usize>) -> f32 {
+        let mut roughness = 0.0;
+        for subx in 0..DENSITY_WIDTH {
+            for subz in 0..DENSITY_WIDTH {
+                let at = |y: usize| {
+                    densities[DENSITY_WIDTH * y + subx + DENSITY_WIDTH * DENSITY_HEIGHT * subz]
+                };
+                for y in suby.clone() {
+                    roughness += (at(y + 1) - 2.0 * at(y) + at(y - 1)).abs();
+                }
+            }
+        }
+        roughness
+    }
+
+    #[test]
+    fn jaggedness_roughens_peaks_only() {
+        let chunk = ChunkPosition::new(3, -2);
+        let biomes = plains();
+        let smooth = generate_density(&DensityMapGeneratorImpl::default(), chunk, &biomes, 42);
+        let jagged = generate_density(
+            &DensityMapGeneratorImpl::default().with_jaggedness(0.5, 30.0),
+            chunk,
+            &biomes,
+            42,
+        );
+
+        // Plains have a midpoint of 62, i.e. subchunk 7.75.
+        for subx in 0..DENSITY_WIDTH {
+            for subz in 0..DENSITY_WIDTH {
+                for suby in 0..=7 {
+                    let index = DENSITY_WIDTH * suby + subx + DENSITY_WIDTH * DENSITY_HEIGHT * subz;
+                    approx::assert_relative_eq!(smooth[index], jagged[index]);
+                }
+            }
+        }
+
+        assert!(roughness(&jagged, 20..32) > roughness(&smooth, 20..32));
+    }
+
+    /// Mean and variance of the surface height over
+    /// all columns in a few chunks of the given biome.
+    fn surface_stats(generator: &DensityMapGeneratorImpl, biome: Biome) -> (f32, f32) {
+        let mut heights = Vec::new();
+        for chunk_x in 0..3 {
+            let chunk = ChunkPosition::new(chunk_x, 1);
+            let density = generator.generate_for_chunk(chunk, &uniform(biome), 42);
+            for x in 0..16 {
+                for z in 0..16 {
+                    let height = (0..256)
+                        .rev()
+                        .find(|&y| density[block_index(x, y, z)])
+                        .unwrap_or(0);
+                    heights.push(height as f32);
+                }
+            }
+        }
+        let mean = heights.iter().sum::<f32>() / heights.len() as f32;
+        let variance =
+            heights.iter().map(|h| (h - mean) * (h - mean)).sum::<f32>() / heights.len() as f32;
+        (mean, variance)
+    }
+
+    /// Variance of the surface height over all columns in a few plains chunks.
+    fn surface_variance(generator: &DensityMapGeneratorImpl) -> f32 {
+        surface_stats(generator, Biome::Plains).1
+    }
+
+    #[test]
+    fn terrain_follows_biomes() {
+        // A generator per biome, as cached columns
+        // assume the biomes at a position never change.
+        let stats = |biome| surface_stats(&DensityMapGeneratorImpl::default(), biome);
+        let (desert_mean, desert_variance) = stats(Biome::Desert);
+        let (mountains_mean, mountains_variance) = stats(Biome::Mountains);
+        let (ocean_mean, _) = stats(Biome::DeepOcean);
+
+        assert!(mountains_mean > desert_mean);
+        assert!(mountains_variance > desert_variance);
+        assert!(ocean_mean < SEA_LEVEL as f32);
+        assert!(ocean_mean < desert_mean);
+    }
+
+    #[test]
+    fn biome_borders_are_smoothed() {
+        // Ocean for X < 8, mountains otherwise.
+        let mut biomes = uniform(Biome::Ocean);
+        for x in 8..32 {
+            for z in -16..32 {
+                biomes.set_biome_at(x, z, Biome::Mountains);
+            }
+        }
+
+        let raw_step = terrain_parameters(biome_terrain(Biome::Mountains).0, 0.0).1
+            - terrain_parameters(biome_terrain(Biome::Ocean).0, 0.0).1;
+        let midpoints: Vec<f32> = (0..DENSITY_WIDTH)
+            .map(|subx| column_parameters(&biomes, subx, 2).1)
+            .collect();
+        for pair in midpoints.windows(2) {
+            assert!(pair[1] >= pair[0]);
+            assert!(pair[1] - pair[0] < raw_step / 2.0);
+        }
+    }
+
+    #[test]
+    fn erosion_flattens_terrain() {
+        // Constant splines, so every column is
+        // eroded equally regardless of the noise.
+        let erosion = |factor: f32| {
+            DensityMapGeneratorImpl::default().with_erosion(
+                ColumnNoise::new(5),
+                Spline::new(vec![(-1.0, factor), (1.0, factor)]),
+            )
+        };
+        let high_erosion = surface_variance(&erosion(4.0));
+        let low_erosion = surface_variance(&erosion(0.25));
+        assert!(high_erosion < low_erosion);
+    }
+
+    #[test]
+    fn height_scale_amplifies_terrain() {
+        let default = surface_variance(&DensityMapGeneratorImpl::default());
+        let amplified =
+            surface_variance(&DensityMapGeneratorImpl::default().with_height_scale(2.5));
+        assert!(amplified > default);
+    }
+
+    #[test]
+    fn air_ceiling_clears_top_of_world() {
+        // Tiny amplitude, so terrain is solid up to the sky limit.
+        let tall = || {
+            DensityMapGeneratorImpl::default().with_erosion(
+                ColumnNoise::new(5),
+                Spline::new(vec![(-1.0, 0.01), (1.0, 0.01)]),
+            )
+        };
+        let solid_above = |generator: &DensityMapGeneratorImpl, start_y: usize| {
+            let density = generator.generate_for_chunk(ChunkPosition::new(2, 2), &plains(), 7);
+            (start_y + 1..256)
+                .flat_map(|y| (0..16).flat_map(move |x| (0..16).map(move |z| (x, y, z))))
+                .filter(|&(x, y, z)| density[block_index(x, y, z)])
+                .count()
+        };
+
+        assert!(solid_above(&tall(), 100) > 0);
+        assert_eq!(solid_above(&tall().with_air_ceiling(100), 100), 0);
+    }
+
+    #[test]
+    fn cached_columns_match_computed_ones() {
+        let cached = DensityMapGeneratorImpl::default();
+        let uncached = DensityMapGeneratorImpl::default().with_column_cache(0);
+        let biomes = plains();
+        for &(x, z) in &[(0, 0), (1, 0), (0, 1), (1, 1), (-1, 0)] {
+            let chunk = ChunkPosition::new(x, z);
+            assert_eq!(
+                generate_density(&cached, chunk, &biomes, 42),
+                generate_density(&uncached, chunk, &biomes, 42)
+            );
+        }
+
+        // The border between (0, 0) and (1, 0) is cached.
+        let key = ColumnKey {
+            seed: 42,
+            x: 4,
+            z: 2,
+        };
+        assert!(cached.column_cache.get(key).is_some());
+        assert!(uncached.column_cache.get(key).is_none());
+    }
+}
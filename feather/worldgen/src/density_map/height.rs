@@ -1,6 +1,7 @@
 //! Implements a basic height map generator using 2D Perlin noise.
 //! A superior generator would use 3D noise to allow for overhangs.
 
+use crate::noise::NoiseSeed;
 use crate::{block_index, DensityMapGenerator, NearbyBiomes, OCEAN_DEPTH, SKY_LIMIT};
 use base::{Biome, ChunkPosition};
 use bitvec::order::LocalBits;
@@ -20,16 +21,16 @@ impl DensityMapGenerator for HeightMapGenerator {
         biomes: &NearbyBiomes,
         seed: u64,
     ) -> BitVec<LocalBits, u8> {
-        let x_offset = (chunk.x * 16) as f32;
-        let y_offset = (chunk.z * 16) as f32;
+        let seed = NoiseSeed::new(seed);
+        let (x_offset, y_offset) = seed.shift((chunk.x * 16) as f32, (chunk.z * 16) as f32);
 
         let dim = 16;
         let (elevation, _, _) = NoiseBuilder::gradient_2d_offset(x_offset, dim, y_offset, dim)
-            .with_seed(seed as i32)
+            .with_seed(seed.get(0))
             .with_freq(0.01)
             .generate();
         let (detail, _, _) = NoiseBuilder::gradient_2d_offset(x_offset, dim, y_offset, dim)
-            .with_seed(seed as i32 + 1)
+            .with_seed(seed.get(1))
             .generate();
 
         let mut density_map = BitVec::from_vec(vec![0u8; 16 * 256 * 16 / 8]);
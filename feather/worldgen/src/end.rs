@@ -0,0 +1,200 @@
+//! Generator for the End: a main island around the origin,
+//! surrounded by a ring of void and then scattered outer islands.
+
+use crate::noise::ColumnNoise;
+use crate::util::feature_random;
+use crate::WorldGenerator;
+use base::{Biome, BlockId, Chunk, ChunkPosition};
+use rand::Rng;
+
+/// Radius of the main island.
+const MAIN_RADIUS: f32 = 100.0;
+/// Distance from the origin at which outer islands start.
+const OUTER_START: f32 = 1000.0;
+/// Height of the middle of each island.
+const ISLAND_Y: f32 = 56.0;
+/// Maximum height of an island's surface above `ISLAND_Y`.
+const SURFACE_HEIGHT: f32 = 8.0;
+/// Maximum depth of an island's underside below `ISLAND_Y`.
+const UNDERSIDE_DEPTH: f32 = 40.0;
+/// Width of the grid cells which may each contain an outer island.
+const CELL_SIZE: i32 = 64;
+/// Chance for a cell to contain an outer island.
+const ISLAND_CHANCE: f64 = 0.3;
+/// Maximum radius of an outer island.
+const MAX_ISLAND_RADIUS: i32 = 24;
+/// Salt passed to `feature_random` for outer islands.
+const ISLAND_SALT: u64 = 0xE4D;
+
+/// World generator for the End.
+///
+/// Each island's strength falls off linearly with the distance
+/// to its center, and is roughened by a 2D noise. Columns with
+/// a positive strength are filled with end stone, with a flat
+/// top and a conical underside.
+pub struct EndWorldGenerator {
+    seed: u64,
+    roughness: ColumnNoise,
+}
+
+impl EndWorldGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            roughness: ColumnNoise::new(11).with_frequency(0.05).with_octaves(2),
+        }
+    }
+}
+
+impl WorldGenerator for EndWorldGenerator {
+    fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+        let (min_x, min_z) = (position.x * 16, position.z * 16);
+        let biome = if distance(min_x + 8, min_z + 8) < OUTER_START {
+            Biome::TheEnd
+        } else {
+            Biome::EndHighlands
+        };
+        let mut chunk = Chunk::new_with_default_biome(position, biome);
+
+        let roughness = self
+            .roughness
+            .generate(self.seed, min_x as f32, min_z as f32, 16);
+        let islands = outer_islands_near(self.seed, min_x, min_z);
+
+        for x in 0..16 {
+            for z in 0..16 {
+                let (world_x, world_z) = (min_x + x as i32, min_z + z as i32);
+                let strength = column_strength(world_x, world_z, &islands)
+                    + roughness[z * 16 + x].max(-1.0).min(1.0) * 0.15;
+                if strength <= 0.0 {
+                    continue;
+                }
+
+                let strength = strength.min(1.0);
+                let top = (ISLAND_Y + strength * SURFACE_HEIGHT) as usize;
+                let bottom = (ISLAND_Y - strength * UNDERSIDE_DEPTH) as usize;
+                for y in bottom..=top {
+                    chunk.set_block_at(x, y, z, BlockId::end_stone());
+                }
+            }
+        }
+
+        chunk.recalculate_heightmaps();
+        chunk
+    }
+}
+
+/// An outer island, given by its center and radius in blocks.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Island {
+    x: i32,
+    z: i32,
+    radius: i32,
+}
+
+/// Returns the island in the given grid cell, if any.
+///
+/// Islands are derived from the seed and cell alone, so
+/// neighboring chunks agree on islands crossing their borders.
+fn outer_island(seed: u64, cell_x: i32, cell_z: i32) -> Option<Island> {
+    let mut rng = feature_random(seed, cell_x, cell_z, ISLAND_SALT);
+    if !rng.gen_bool(ISLAND_CHANCE) {
+        return None;
+    }
+    let island = Island {
+        x: cell_x * CELL_SIZE + rng.gen_range(0, CELL_SIZE),
+        z: cell_z * CELL_SIZE + rng.gen_range(0, CELL_SIZE),
+        radius: rng.gen_range(8, MAX_ISLAND_RADIUS + 1),
+    };
+
+    // Keep the void ring around the main island clear.
+    if distance(island.x, island.z) - (island.radius as f32) < OUTER_START {
+        return None;
+    }
+    Some(island)
+}
+
+/// Returns the outer islands which may overlap the chunk
+/// whose lowest corner is at `(min_x, min_z)`.
+fn outer_islands_near(seed: u64, min_x: i32, min_z: i32) -> Vec<Island> {
+    let cell = |coordinate: i32| coordinate.div_euclid(CELL_SIZE);
+    let mut islands = Vec::new();
+    for cell_x in cell(min_x - MAX_ISLAND_RADIUS)..=cell(min_x + 15 + MAX_ISLAND_RADIUS) {
+        for cell_z in cell(min_z - MAX_ISLAND_RADIUS)..=cell(min_z + 15 + MAX_ISLAND_RADIUS) {
+            islands.extend(outer_island(seed, cell_x, cell_z));
+        }
+    }
+    islands
+}
+
+/// Returns the strength of the strongest island at a column,
+/// from 1 at an island's center to 0 at its edge, or
+/// negative outside of all islands.
+fn column_strength(x: i32, z: i32, islands: &[Island]) -> f32 {
+    let main = 1.0 - distance(x, z) / MAIN_RADIUS;
+    islands
+        .iter()
+        .map(|island| {
+            let distance = distance(x - island.x, z - island.z);
+            1.0 - distance / island.radius as f32
+        })
+        .fold(main, f32::max)
+}
+
+fn distance(x: i32, z: i32) -> f32 {
+    ((x as f32).powi(2) + (z as f32).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 8;
+
+    fn end_stone(chunk: &Chunk) -> usize {
+        let mut count = 0;
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..256 {
+                    if chunk.block_at(x, y, z) == Some(BlockId::end_stone()) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn main_island_and_void_ring() {
+        let generator = EndWorldGenerator::new(SEED);
+
+        let center = generator.generate_chunk(ChunkPosition::new(0, 0));
+        assert_eq!(
+            center.block_at(0, ISLAND_Y as usize, 0),
+            Some(BlockId::end_stone())
+        );
+        assert_eq!(center.block_at(0, 10, 0), Some(BlockId::air()));
+
+        let ring = generator.generate_chunk(ChunkPosition::new(30, -20));
+        assert_eq!(end_stone(&ring), 0);
+    }
+
+    #[test]
+    fn outer_islands_are_generated() {
+        let island = (16..48)
+            .flat_map(|x| (16..48).map(move |z| (x, z)))
+            .find_map(|(x, z)| outer_island(SEED, x, z))
+            .expect("no outer islands");
+        assert!(distance(island.x, island.z) >= OUTER_START);
+
+        let generator = EndWorldGenerator::new(SEED);
+        let position = ChunkPosition::new(island.x.div_euclid(16), island.z.div_euclid(16));
+        let chunk = generator.generate_chunk(position);
+        let (x, z) = (island.x.rem_euclid(16), island.z.rem_euclid(16));
+        assert_eq!(
+            chunk.block_at(x as usize, ISLAND_Y as usize, z as usize),
+            Some(BlockId::end_stone())
+        );
+    }
+}
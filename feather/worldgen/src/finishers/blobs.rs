@@ -0,0 +1,174 @@
+use super::ores::{OreHeight, OreVein};
+use crate::util::feature_random;
+use crate::{ChunkBiomes, FinishingGenerator, GenerationStage, TopBlocks};
+use base::{BlockId, Chunk};
+use rand::Rng;
+use std::f32::consts::PI;
+
+/// Vanilla-like distributions of dirt, gravel,
+/// and the stone variants.
+fn default_blobs() -> Vec<OreVein> {
+    let blob =
+        |block, count, max| OreVein::new(block, count, 33, OreHeight::Uniform { min: 0, max });
+    vec![
+        blob(BlockId::dirt(), 10, 256),
+        blob(BlockId::gravel(), 8, 256),
+        blob(BlockId::granite(), 10, 80),
+        blob(BlockId::diorite(), 10, 80),
+        blob(BlockId::andesite(), 10, 80),
+    ]
+}
+
+/// Finisher which places large pockets of dirt, gravel,
+/// granite, diorite, and andesite in stone, so that the
+/// underground isn't uniform.
+///
+/// Runs in the ore stage before the `OreFinisher`. Like ore
+/// veins, blobs only replace stone and are kept within the chunk.
+pub struct StoneBlobFinisher {
+    blobs: Vec<OreVein>,
+}
+
+impl Default for StoneBlobFinisher {
+    fn default() -> Self {
+        Self::new(default_blobs())
+    }
+}
+
+impl StoneBlobFinisher {
+    /// Creates a `StoneBlobFinisher` placing the given blobs.
+    pub fn new(blobs: Vec<OreVein>) -> Self {
+        Self { blobs }
+    }
+}
+
+impl FinishingGenerator for StoneBlobFinisher {
+    fn name(&self) -> &str {
+        "stone_blobs"
+    }
+
+    fn generate_for_chunk(
+        &self,
+        chunk: &mut Chunk,
+        _biomes: &ChunkBiomes,
+        _top_blocks: &TopBlocks,
+        seed: u64,
+    ) {
+        let pos = chunk.position();
+        for blob in &self.blobs {
+            let salt = blob.block.vanilla_id() as u64;
+            let mut rng = feature_random(seed, pos.x, pos.z, salt);
+            for _ in 0..blob.count {
+                place_blob(chunk, blob, &mut rng);
+            }
+        }
+    }
+
+    fn stage(&self) -> GenerationStage {
+        GenerationStage::Ores
+    }
+}
+
+/// Places a blob like vanilla's ore feature: a chain of spheres
+/// along a random horizontal segment, widest at its middle.
+fn place_blob(chunk: &mut Chunk, blob: &OreVein, rng: &mut impl Rng) {
+    let size = blob.size as f32;
+    let center_x = rng.gen_range(0, 16) as f32;
+    let center_y = blob.height.sample(rng) as f32;
+    let center_z = rng.gen_range(0, 16) as f32;
+
+    let angle = rng.gen::<f32>() * PI;
+    let (dx, dz) = (angle.sin() * size / 8.0, angle.cos() * size / 8.0);
+    let (start_y, end_y) = (
+        center_y + rng.gen_range(-2, 1) as f32,
+        center_y + rng.gen_range(-2, 1) as f32,
+    );
+
+    for i in 0..blob.size {
+        let t = i as f32 / size;
+        let x = center_x + dx * (1.0 - 2.0 * t);
+        let y = start_y + (end_y - start_y) * t;
+        let z = center_z + dz * (1.0 - 2.0 * t);
+        let spread = rng.gen::<f32>() * size / 16.0;
+        let radius = ((PI * t).sin() + 1.0) * spread / 2.0 + 0.5;
+        fill_sphere(chunk, (x, y, z), radius, blob.block);
+    }
+}
+
+/// Replaces the stone within `radius` of `center`, clipped to the chunk.
+fn fill_sphere(chunk: &mut Chunk, center: (f32, f32, f32), radius: f32, block: BlockId) {
+    let (cx, cy, cz) = center;
+    let range = |c: f32, max: f32| {
+        let min = (c - radius).floor().max(0.0) as usize;
+        let max = (c + radius).ceil().min(max) as usize;
+        min..=max
+    };
+    for x in range(cx, 15.0) {
+        for y in range(cy, 255.0) {
+            for z in range(cz, 15.0) {
+                let (ox, oy, oz) = (
+                    x as f32 + 0.5 - cx,
+                    y as f32 + 0.5 - cy,
+                    z as f32 + 0.5 - cz,
+                );
+                if ox * ox + oy * oy + oz * oz > radius * radius {
+                    continue;
+                }
+                if chunk.block_at(x, y, z) == Some(BlockId::stone()) {
+                    chunk.set_block_at(x, y, z, block);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::{Biome, ChunkPosition};
+
+    #[test]
+    fn blobs_vary_the_stone() {
+        let mut chunk = Chunk::new(ChunkPosition::new(3, -8));
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..70 {
+                    chunk.set_block_at(x, y, z, BlockId::stone());
+                }
+            }
+        }
+        StoneBlobFinisher::default().generate_for_chunk(
+            &mut chunk,
+            &ChunkBiomes::from_array([Biome::Plains; 256]),
+            &TopBlocks::new(),
+            12,
+        );
+
+        let mut counts = [0; 5];
+        let kinds = [
+            BlockId::dirt(),
+            BlockId::gravel(),
+            BlockId::granite(),
+            BlockId::diorite(),
+            BlockId::andesite(),
+        ];
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..256 {
+                    let block = chunk.block_at(x, y, z).unwrap();
+                    if y >= 70 {
+                        // Blobs don't replace air.
+                        assert!(block.is_air());
+                    }
+                    if let Some(i) = kinds.iter().position(|&kind| kind == block) {
+                        counts[i] += 1;
+                    }
+                }
+            }
+        }
+        // Granite, diorite, and andesite are common below Y=80.
+        assert!(counts[2..].iter().all(|&count| count > 0), "{:?}", counts);
+        // Most of the underground is still stone.
+        assert!(counts.iter().sum::<usize>() < 16 * 16 * 70 / 2);
+    }
+}
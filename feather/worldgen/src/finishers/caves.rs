@@ -0,0 +1,204 @@
+use crate::noise::{index, ColumnNoise, Wrapped3DPerlinNoise};
+use crate::{ChunkBiomes, FinishingGenerator, GenerationStage, TopBlocks};
+use base::{BlockId, Chunk};
+
+/// Carver which cuts tunnels and ravines into solid terrain.
+///
+/// Tunnels are carved where two uncorrelated 3D noises are
+/// both close to zero, which produces long winding "spaghetti"
+/// caves. Ravines are carved where a 2D noise is close to zero,
+/// forming narrow trenches spanning the whole depth range.
+///
+/// Noise is sampled in world coordinates, so caves
+/// continue seamlessly across chunk borders.
+pub struct CaveCarver {
+    /// Maximum distance of both noises from zero
+    /// for a block to be carved. Larger values give wider,
+    /// more frequent tunnels.
+    threshold: f32,
+    /// Maximum distance of the ravine noise from zero for
+    /// a column to be carved. Zero disables ravines.
+    ravine_width: f32,
+    /// Lowest Y coordinate which may be carved.
+    min_y: usize,
+    /// Highest Y coordinate which may be carved.
+    max_y: usize,
+    /// Frequency of the tunnel noise.
+    frequency: f32,
+}
+
+impl Default for CaveCarver {
+    fn default() -> Self {
+        Self {
+            threshold: 0.08,
+            ravine_width: 0.015,
+            min_y: 5,
+            max_y: 56,
+            frequency: 0.03,
+        }
+    }
+}
+
+impl CaveCarver {
+    /// Sets the tunnel threshold. Larger values carve more caves.
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets the width of ravines. Zero disables ravines.
+    pub fn with_ravine_width(mut self, ravine_width: f32) -> Self {
+        self.ravine_width = ravine_width;
+        self
+    }
+
+    /// Sets the range of Y coordinates, inclusive, which may be carved.
+    ///
+    /// # Panics
+    /// Panics if `min_y > max_y` or `max_y` is above the world.
+    pub fn with_depth_limits(mut self, min_y: usize, max_y: usize) -> Self {
+        assert!(min_y <= max_y && max_y < 256);
+        self.min_y = min_y;
+        self.max_y = max_y;
+        self
+    }
+
+    /// Sets the frequency of the tunnel noise.
+    /// Lower frequencies give longer, straighter tunnels.
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+}
+
+impl FinishingGenerator for CaveCarver {
+    fn name(&self) -> &str {
+        "caves"
+    }
+
+    fn generate_for_chunk(
+        &self,
+        chunk: &mut Chunk,
+        _biomes: &ChunkBiomes,
+        _top_blocks: &TopBlocks,
+        seed: u64,
+    ) {
+        let pos = chunk.position();
+        let tunnel_noise = Wrapped3DPerlinNoise::new(seed)
+            .with_offset(pos.x, pos.z)
+            .with_frequency(self.frequency)
+            .with_amplitude(1.0);
+        let first = tunnel_noise.generate();
+        let second = tunnel_noise.generate_with_seed(seed.wrapping_add(1));
+        let ravines = ColumnNoise::new(3)
+            .with_frequency(0.01)
+            .with_octaves(1)
+            .generate(seed, (pos.x * 16) as f32, (pos.z * 16) as f32, 16);
+
+        for x in 0..16 {
+            for z in 0..16 {
+                let ravine = ravines[z * 16 + x].abs() < self.ravine_width;
+
+                // Carve top-down so that we can check for water above.
+                for y in (self.min_y..=self.max_y).rev() {
+                    let i = index(x, y, z);
+                    let tunnel =
+                        first[i].abs() < self.threshold && second[i].abs() < self.threshold;
+                    if !(tunnel || ravine) {
+                        continue;
+                    }
+
+                    let block = chunk.block_at(x, y, z).unwrap();
+                    if !can_carve(block) || is_liquid(chunk.block_at(x, y + 1, z)) {
+                        continue;
+                    }
+                    chunk.set_block_at(x, y, z, BlockId::air());
+                }
+            }
+        }
+    }
+
+    fn stage(&self) -> GenerationStage {
+        GenerationStage::Caves
+    }
+}
+
+fn can_carve(block: BlockId) -> bool {
+    !block.is_air() && block != BlockId::bedrock() && !block.is_fluid()
+}
+
+/// Carving below water would leave it floating,
+/// so blocks beneath liquids are kept.
+fn is_liquid(block: Option<BlockId>) -> bool {
+    block.map_or(false, |block| block.is_fluid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::ChunkPosition;
+
+    fn solid_chunk() -> Chunk {
+        let mut chunk = Chunk::new(ChunkPosition::new(3, -2));
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set_block_at(x, 0, z, BlockId::bedrock());
+                for y in 1..80 {
+                    chunk.set_block_at(x, y, z, BlockId::stone());
+                }
+            }
+        }
+        chunk
+    }
+
+    fn carve(carver: &CaveCarver) -> Chunk {
+        let mut chunk = solid_chunk();
+        carver.generate_for_chunk(
+            &mut chunk,
+            &ChunkBiomes::from_array([base::Biome::Plains; 256]),
+            &TopBlocks::new(),
+            4321,
+        );
+        chunk
+    }
+
+    #[test]
+    fn carves_within_depth_limits() {
+        let chunk = carve(
+            &CaveCarver::default()
+                .with_threshold(0.3)
+                .with_depth_limits(10, 40),
+        );
+
+        let mut carved = 0;
+        for x in 0..16 {
+            for z in 0..16 {
+                assert_eq!(chunk.block_at(x, 0, z), Some(BlockId::bedrock()));
+                for y in 1..80 {
+                    let air = chunk.block_at(x, y, z) == Some(BlockId::air());
+                    if air {
+                        assert!((10..=40).contains(&y), "carved outside limits at y={}", y);
+                        carved += 1;
+                    }
+                }
+            }
+        }
+        assert!(carved > 0);
+    }
+
+    #[test]
+    fn zero_threshold_carves_nothing() {
+        let chunk = carve(
+            &CaveCarver::default()
+                .with_threshold(0.0)
+                .with_ravine_width(0.0),
+        );
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 1..80 {
+                    assert_eq!(chunk.block_at(x, y, z), Some(BlockId::stone()));
+                }
+            }
+        }
+    }
+}
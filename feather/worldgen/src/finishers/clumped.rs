@@ -10,6 +10,10 @@ use std::{cmp, iter};
 pub struct ClumpedFoliageFinisher;
 
 impl FinishingGenerator for ClumpedFoliageFinisher {
+    fn name(&self) -> &str {
+        "clumped_foliage"
+    }
+
     fn generate_for_chunk(
         &self,
         chunk: &mut Chunk,
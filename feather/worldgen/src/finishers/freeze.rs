@@ -0,0 +1,147 @@
+use crate::noise::Wrapped2DPerlinNoise;
+use crate::{ChunkRegion, GenerationStage, Populator, SEA_LEVEL};
+use base::{Biome, BlockId, SimplifiedBlockKind};
+
+/// Temperature below which water freezes and snow settles.
+const FREEZING: f32 = 0.15;
+
+/// Temperature lost per block above sea level.
+const ALTITUDE_FALLOFF: f32 = 0.05 / 30.0;
+
+/// Maximum number of blocks the temperature noise
+/// moves the altitude at which it starts to freeze.
+const NOISE_HEIGHT: f32 = 4.0;
+
+/// Populator which freezes the surface in cold places, placing
+/// snow on exposed ground and turning surface water into ice.
+///
+/// The temperature of a column is the temperature of its biome,
+/// lowered with altitude above sea level. A 2D noise varies the
+/// altitude at which it starts to freeze, so the snow line on
+/// mountains isn't perfectly flat.
+///
+/// Runs after the other populators so that snow settles on trees.
+#[derive(Default)]
+pub struct FreezePopulator;
+
+impl Populator for FreezePopulator {
+    fn populate(&self, region: &mut ChunkRegion, seed: u64) {
+        let pos = region.position();
+        let noise = Wrapped2DPerlinNoise::new(seed)
+            .with_offset(pos.x, pos.z)
+            .with_frequency(0.05)
+            .generate();
+
+        for x in 0..16 {
+            for z in 0..16 {
+                let biome = match region.biome_at(x, z) {
+                    Some(biome) => biome,
+                    None => continue,
+                };
+                let top = match (0..256).rev().find(|&y| !is_air(region, x, y, z)) {
+                    Some(top) => top,
+                    None => continue,
+                };
+
+                let noise = noise[((z as usize) << 4) | x as usize];
+                if temperature(biome, top + 1, noise) >= FREEZING {
+                    continue;
+                }
+
+                let block = region.block_at(x, top, z).unwrap();
+                if is_water(block) {
+                    region.set_block_at(x, top, z, BlockId::ice());
+                } else if can_hold_snow(block) {
+                    if block.kind() == BlockId::grass_block().kind() {
+                        region.set_block_at(x, top, z, block.with_snowy(true));
+                    }
+                    region.set_block_at(x, top + 1, z, BlockId::snow());
+                }
+            }
+        }
+    }
+
+    fn stage(&self) -> GenerationStage {
+        GenerationStage::Surface
+    }
+}
+
+/// Returns the temperature of a block at height `y` in the given
+/// biome. `noise` should be within `[-1, 1]`.
+fn temperature(biome: Biome, y: i32, noise: f32) -> f32 {
+    let height = y as f32 - SEA_LEVEL as f32 + noise * NOISE_HEIGHT;
+    biome.temperature() - height.max(0.0) * ALTITUDE_FALLOFF
+}
+
+fn is_air(region: &ChunkRegion, x: i32, y: i32, z: i32) -> bool {
+    region.block_at(x, y, z).map_or(true, BlockId::is_air)
+}
+
+fn is_water(block: BlockId) -> bool {
+    block.is_fluid() && !block.is_lava()
+}
+
+/// Returns whether snow can be placed on top of the given block.
+/// Leaves hold snow even though they aren't opaque.
+fn can_hold_snow(block: BlockId) -> bool {
+    block.is_solid()
+        && (block.is_opaque() || block.simplified_kind() == SimplifiedBlockKind::Leaves)
+        && block != BlockId::ice()
+        && block != BlockId::packed_ice()
+        && block.kind() != BlockId::snow().kind()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::{Chunk, ChunkPosition};
+
+    fn populate(biome: Biome, ground: usize, top: BlockId) -> ChunkRegion {
+        let mut chunks = Vec::new();
+        for z in -1..=1 {
+            for x in -1..=1 {
+                let mut chunk = Chunk::new_with_default_biome(ChunkPosition::new(x, z), biome);
+                for x in 0..16 {
+                    for z in 0..16 {
+                        chunk.set_block_at(x, ground, z, top);
+                    }
+                }
+                chunks.push(chunk);
+            }
+        }
+        let mut region = ChunkRegion::new(chunks);
+        FreezePopulator.populate(&mut region, 3);
+        region
+    }
+
+    #[test]
+    fn cold_biomes_freeze() {
+        let region = populate(Biome::SnowyTundra, 62, BlockId::water());
+        assert_eq!(region.block_at(4, 62, 9), Some(BlockId::ice()));
+
+        let region = populate(Biome::SnowyTundra, 70, BlockId::grass_block());
+        assert_eq!(region.block_at(4, 71, 9), Some(BlockId::snow()));
+
+        let region = populate(Biome::SnowyTundra, 70, BlockId::spruce_leaves());
+        assert_eq!(region.block_at(4, 71, 9), Some(BlockId::snow()));
+        assert_eq!(
+            region.block_at(4, 70, 9),
+            Some(BlockId::grass_block().with_snowy(true))
+        );
+
+        // Only the center chunk is populated.
+        assert_eq!(region.block_at(-4, 71, 9), Some(BlockId::air()));
+    }
+
+    #[test]
+    fn warm_biomes_freeze_only_at_altitude() {
+        let region = populate(Biome::Plains, 62, BlockId::water());
+        assert_eq!(region.block_at(4, 62, 9), Some(BlockId::water()));
+
+        let region = populate(Biome::Mountains, 80, BlockId::stone());
+        assert_eq!(region.block_at(4, 81, 9), Some(BlockId::air()));
+
+        let region = populate(Biome::Mountains, 200, BlockId::stone());
+        assert_eq!(region.block_at(4, 201, 9), Some(BlockId::snow()));
+    }
+}
@@ -0,0 +1,323 @@
+use crate::util::feature_random;
+use crate::{ChunkRegion, GenerationStage, Populator};
+use base::BlockId;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+
+/// Salt passed to `feature_random` for lake placement.
+const LAKE_SALT: u64 = 0x1A4E;
+
+/// Horizontal size of the box containing a lake.
+const LAKE_WIDTH: i32 = 16;
+/// Vertical size of the box containing a lake.
+const LAKE_HEIGHT: i32 = 8;
+/// Layers of the box filled with liquid. Layers
+/// above are carved out to air.
+const LIQUID_DEPTH: i32 = 4;
+
+/// Lowest Y coordinate of an underground lava lake.
+const MIN_LAVA_Y: i32 = 8;
+
+/// Populator which carves small water lakes into the
+/// surface and lava lakes into the ground below it.
+///
+/// Each lake is the union of a few ellipsoids within a
+/// 16x8x16 box centered near the chunk, so lakes extend into
+/// the neighboring chunks of the region. Lakes which would
+/// leak are skipped. Lava lakes are lined with stone and
+/// water lakes with dirt.
+pub struct LakePopulator {
+    /// One in `water_rarity` chunks has a water lake.
+    water_rarity: u32,
+    /// One in `lava_rarity` chunks has a lava lake.
+    lava_rarity: u32,
+}
+
+impl Default for LakePopulator {
+    fn default() -> Self {
+        Self {
+            water_rarity: 4,
+            lava_rarity: 8,
+        }
+    }
+}
+
+impl LakePopulator {
+    /// Places a water lake in one in `rarity` chunks.
+    /// A rarity of zero disables water lakes.
+    pub fn with_water_rarity(mut self, rarity: u32) -> Self {
+        self.water_rarity = rarity;
+        self
+    }
+
+    /// Places a lava lake in one in `rarity` chunks.
+    /// A rarity of zero disables lava lakes.
+    pub fn with_lava_rarity(mut self, rarity: u32) -> Self {
+        self.lava_rarity = rarity;
+        self
+    }
+}
+
+impl Populator for LakePopulator {
+    fn populate(&self, region: &mut ChunkRegion, seed: u64) {
+        let pos = region.position();
+        let mut rng = feature_random(seed, pos.x, pos.z, LAKE_SALT);
+
+        if self.water_rarity > 0 && rng.gen_range(0, self.water_rarity) == 0 {
+            let (x, z) = lake_corner(&mut rng);
+            let shape = LakeShape::random(&mut rng);
+            if let Some(surface) = region.top_solid_block(x + LAKE_WIDTH / 2, z + LAKE_WIDTH / 2) {
+                let y = surface - LIQUID_DEPTH + 1;
+                if y > 0 {
+                    place_lake(region, &shape, x, y, z, Liquid::Water);
+                }
+            }
+        }
+
+        if self.lava_rarity > 0 && rng.gen_range(0, self.lava_rarity) == 0 {
+            let (x, z) = lake_corner(&mut rng);
+            let shape = LakeShape::random(&mut rng);
+            let roll: f64 = rng.gen();
+            if let Some(surface) = region.top_solid_block(x + LAKE_WIDTH / 2, z + LAKE_WIDTH / 2) {
+                // Keep the lake buried under a few blocks of ground.
+                let max_y = surface - LAKE_HEIGHT * 2;
+                if max_y > MIN_LAVA_Y {
+                    let y = MIN_LAVA_Y + ((max_y - MIN_LAVA_Y) as f64 * roll) as i32;
+                    place_lake(region, &shape, x, y, z, Liquid::Lava);
+                }
+            }
+        }
+    }
+
+    fn stage(&self) -> GenerationStage {
+        GenerationStage::Lakes
+    }
+}
+
+/// The liquid filling a lake.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Liquid {
+    Water,
+    Lava,
+}
+
+impl Liquid {
+    fn block(self) -> BlockId {
+        match self {
+            Liquid::Water => BlockId::water(),
+            Liquid::Lava => BlockId::lava(),
+        }
+    }
+
+    /// Returns the block lining the bottom and sides of the lake.
+    fn border(self) -> BlockId {
+        match self {
+            Liquid::Water => BlockId::dirt(),
+            Liquid::Lava => BlockId::stone(),
+        }
+    }
+}
+
+/// Returns the minimum X and Z coordinates of a lake's
+/// box, such that the box is centered within the chunk.
+fn lake_corner(rng: &mut XorShiftRng) -> (i32, i32) {
+    (
+        rng.gen_range(0, 16) - LAKE_WIDTH / 2,
+        rng.gen_range(0, 16) - LAKE_WIDTH / 2,
+    )
+}
+
+/// The positions within a lake's box which are part of the lake.
+struct LakeShape {
+    cells: Vec<bool>,
+}
+
+impl LakeShape {
+    /// Generates the shape as a union of four to seven ellipsoids.
+    fn random(rng: &mut XorShiftRng) -> Self {
+        let mut cells = vec![false; (LAKE_WIDTH * LAKE_HEIGHT * LAKE_WIDTH) as usize];
+        let width = LAKE_WIDTH as f64;
+        let height = LAKE_HEIGHT as f64;
+
+        for _ in 0..rng.gen_range(4, 8) {
+            let size_x = rng.gen::<f64>() * 6.0 + 3.0;
+            let size_y = rng.gen::<f64>() * 4.0 + 2.0;
+            let size_z = rng.gen::<f64>() * 6.0 + 3.0;
+            let center_x = rng.gen::<f64>() * (width - size_x - 2.0) + 1.0 + size_x / 2.0;
+            let center_y = rng.gen::<f64>() * (height - size_y - 4.0) + 2.0 + size_y / 2.0;
+            let center_z = rng.gen::<f64>() * (width - size_z - 2.0) + 1.0 + size_z / 2.0;
+
+            // Leave a margin of one block so the border
+            // can be checked and lined inside the box.
+            for x in 1..LAKE_WIDTH - 1 {
+                for y in 1..LAKE_HEIGHT - 1 {
+                    for z in 1..LAKE_WIDTH - 1 {
+                        let dx = (x as f64 - center_x) / (size_x / 2.0);
+                        let dy = (y as f64 - center_y) / (size_y / 2.0);
+                        let dz = (z as f64 - center_z) / (size_z / 2.0);
+                        if dx * dx + dy * dy + dz * dz < 1.0 {
+                            cells[Self::index(x, y, z)] = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        Self { cells }
+    }
+
+    fn contains(&self, x: i32, y: i32, z: i32) -> bool {
+        (0..LAKE_WIDTH).contains(&x)
+            && (0..LAKE_HEIGHT).contains(&y)
+            && (0..LAKE_WIDTH).contains(&z)
+            && self.cells[Self::index(x, y, z)]
+    }
+
+    /// Returns whether the given position is outside
+    /// the lake but next to it.
+    fn is_border(&self, x: i32, y: i32, z: i32) -> bool {
+        !self.contains(x, y, z)
+            && (self.contains(x - 1, y, z)
+                || self.contains(x + 1, y, z)
+                || self.contains(x, y - 1, z)
+                || self.contains(x, y + 1, z)
+                || self.contains(x, y, z - 1)
+                || self.contains(x, y, z + 1))
+    }
+
+    fn index(x: i32, y: i32, z: i32) -> usize {
+        ((y * LAKE_WIDTH + z) * LAKE_WIDTH + x) as usize
+    }
+}
+
+/// Iterates over all positions in a lake's box.
+fn box_positions() -> impl Iterator<Item = (i32, i32, i32)> {
+    (0..LAKE_WIDTH)
+        .flat_map(|x| (0..LAKE_HEIGHT).flat_map(move |y| (0..LAKE_WIDTH).map(move |z| (x, y, z))))
+}
+
+/// Places a lake whose box has its minimum corner at the given
+/// position. Does nothing if the lake would leak.
+fn place_lake(region: &mut ChunkRegion, shape: &LakeShape, x: i32, y: i32, z: i32, liquid: Liquid) {
+    // The border must not contain liquid above the lake's
+    // surface, and must be solid below it.
+    for (dx, dy, dz) in box_positions() {
+        if !shape.is_border(dx, dy, dz) {
+            continue;
+        }
+        let block = match region.block_at(x + dx, y + dy, z + dz) {
+            Some(block) => block,
+            None => return,
+        };
+        if dy >= LIQUID_DEPTH && block.is_fluid() {
+            return;
+        }
+        if dy < LIQUID_DEPTH && !block.is_solid() && block != liquid.block() {
+            return;
+        }
+    }
+
+    for (dx, dy, dz) in box_positions() {
+        if shape.contains(dx, dy, dz) {
+            let block = if dy < LIQUID_DEPTH {
+                liquid.block()
+            } else {
+                BlockId::air()
+            };
+            region.set_block_at(x + dx, y + dy, z + dz, block);
+        }
+    }
+
+    for (dx, dy, dz) in box_positions() {
+        let (bx, by, bz) = (x + dx, y + dy, z + dz);
+        if shape.is_border(dx, dy, dz) && dy < LIQUID_DEPTH {
+            if region.block_at(bx, by, bz).map_or(false, BlockId::is_solid) {
+                region.set_block_at(bx, by, bz, liquid.border());
+            }
+        } else if liquid == Liquid::Water
+            && shape.contains(dx, dy, dz)
+            && dy >= LIQUID_DEPTH
+            && region.block_at(bx, by - 1, bz) == Some(BlockId::dirt())
+        {
+            // Ground exposed around the lake grows grass.
+            region.set_block_at(bx, by - 1, bz, BlockId::grass_block());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::{Biome, Chunk, ChunkPosition};
+
+    /// A region of stone up to `surface`.
+    fn stone_region(surface: usize) -> ChunkRegion {
+        let mut chunks = Vec::new();
+        for z in -1..=1 {
+            for x in -1..=1 {
+                let mut chunk =
+                    Chunk::new_with_default_biome(ChunkPosition::new(x, z), Biome::Plains);
+                for x in 0..16 {
+                    for y in 0..=surface {
+                        for z in 0..16 {
+                            chunk.set_block_at(x, y, z, BlockId::stone());
+                        }
+                    }
+                }
+                chunks.push(chunk);
+            }
+        }
+        ChunkRegion::new(chunks)
+    }
+
+    fn count(region: &ChunkRegion, block: BlockId) -> usize {
+        (-16..32)
+            .flat_map(|x| (0..256).flat_map(move |y| (-16..32).map(move |z| (x, y, z))))
+            .filter(|&(x, y, z)| region.block_at(x, y, z) == Some(block))
+            .count()
+    }
+
+    #[test]
+    fn lakes_are_placed() {
+        let water = LakePopulator::default()
+            .with_water_rarity(1)
+            .with_lava_rarity(0);
+        let mut region = stone_region(70);
+        water.populate(&mut region, 5);
+        assert!(count(&region, BlockId::water()) > 0);
+        assert!(count(&region, BlockId::dirt()) > 0);
+        assert_eq!(count(&region, BlockId::lava()), 0);
+
+        let lava = LakePopulator::default()
+            .with_water_rarity(0)
+            .with_lava_rarity(1);
+        let mut region = stone_region(70);
+        lava.populate(&mut region, 5);
+        assert!(count(&region, BlockId::lava()) > 0);
+        assert_eq!(count(&region, BlockId::water()), 0);
+
+        // Lava lakes stay underground.
+        for x in -16..32 {
+            for z in -16..32 {
+                assert_eq!(region.top_solid_block(x, z), Some(70));
+            }
+        }
+    }
+
+    #[test]
+    fn leaking_lakes_are_skipped() {
+        // A gap below the surface, which any lake would leak into.
+        let mut region = stone_region(70);
+        for x in -16..32 {
+            for z in -16..32 {
+                region.set_block_at(x, 68, z, BlockId::air());
+                region.set_block_at(x, 69, z, BlockId::air());
+            }
+        }
+        LakePopulator::default()
+            .with_water_rarity(1)
+            .with_lava_rarity(0)
+            .populate(&mut region, 5);
+        assert_eq!(count(&region, BlockId::water()), 0);
+    }
+}
@@ -1,9 +1,24 @@
-//! Various finishers for world generation, such as grass, snow, and trees.
+//! Various finishers for world generation, such as caves, ores, stone blobs, grass, and kelp,
+//! along with the populators for lakes, trees, snow, and ice.
 
+mod blobs;
+mod caves;
 mod clumped;
+mod freeze;
+mod lakes;
+mod ocean;
+mod ores;
 mod single;
-mod snow;
+mod surface;
+mod trees;
 
+pub use blobs::StoneBlobFinisher;
+pub use caves::CaveCarver;
 pub use clumped::ClumpedFoliageFinisher;
+pub use freeze::FreezePopulator;
+pub use lakes::LakePopulator;
+pub use ocean::{decorate_ocean_floor, OceanFloorFinisher};
+pub use ores::{OreFinisher, OreHeight, OreVein};
 pub use single::SingleFoliageFinisher;
-pub use snow::SnowFinisher;
+pub use surface::{decorate_surface, SurfaceDecorationFinisher};
+pub use trees::TreePopulator;
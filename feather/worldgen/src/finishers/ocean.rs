@@ -0,0 +1,292 @@
+use crate::util::shuffle_seed_for_chunk;
+use crate::{ChunkBiomes, FinishingGenerator, TopBlocks};
+use base::{Biome, BlockId, BlockKind, Chunk, HalfUpperLower};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+/// Patches of sand, gravel, and clay per chunk, with their
+/// maximum radius, as (block, patches, radius).
+const PATCHES: [(fn() -> BlockId, u32, i32); 3] = [
+    (BlockId::sand, 3, 6),
+    (BlockId::gravel, 1, 5),
+    (BlockId::clay, 1, 3),
+];
+
+/// Maximum height of a kelp stalk.
+const MAX_KELP_HEIGHT: usize = 16;
+
+/// Finisher which decorates ocean floors with patches of
+/// sand, gravel, and clay, then plants kelp and seagrass.
+///
+/// Only columns in ocean biomes whose floor
+/// is covered by water are decorated.
+#[derive(Default)]
+pub struct OceanFloorFinisher;
+
+impl FinishingGenerator for OceanFloorFinisher {
+    fn name(&self) -> &str {
+        "ocean_floor"
+    }
+
+    fn generate_for_chunk(
+        &self,
+        chunk: &mut Chunk,
+        biomes: &ChunkBiomes,
+        top_blocks: &TopBlocks,
+        seed: u64,
+    ) {
+        let mut rng = XorShiftRng::seed_from_u64(shuffle_seed_for_chunk(seed, chunk.position()));
+        decorate_ocean_floor(chunk, biomes, top_blocks, &mut rng);
+    }
+}
+
+/// Places floor patches and underwater plants in each ocean column.
+pub fn decorate_ocean_floor(
+    chunk: &mut Chunk,
+    biomes: &ChunkBiomes,
+    top_blocks: &TopBlocks,
+    rng: &mut impl Rng,
+) {
+    for &(block, patches, radius) in &PATCHES {
+        for _ in 0..patches {
+            let center_x = rng.gen_range(0, 16);
+            let center_z = rng.gen_range(0, 16);
+            let radius = rng.gen_range(2, radius + 1);
+            place_patch(
+                chunk,
+                biomes,
+                top_blocks,
+                center_x,
+                center_z,
+                radius,
+                block(),
+            );
+        }
+    }
+
+    for x in 0..16 {
+        for z in 0..16 {
+            let biome = biomes.biome_at(x, z);
+            if !is_ocean(biome) {
+                continue;
+            }
+            let floor = match ocean_floor(chunk, top_blocks, x, z) {
+                Some(floor) => floor,
+                None => continue,
+            };
+            let depth = water_depth(chunk, x, floor + 1, z);
+
+            if has_kelp(biome) && depth > 2 && rng.gen_range(0, 10) == 0 {
+                // Keep the top of the kelp underwater.
+                let max_height = (depth - 1).min(MAX_KELP_HEIGHT);
+                let height = rng.gen_range(1, max_height + 1);
+                for y in floor + 1..floor + height {
+                    chunk.set_block_at(x, y, z, BlockId::kelp_plant());
+                }
+                let top = BlockId::kelp().with_age_0_25(rng.gen_range(0, 25));
+                chunk.set_block_at(x, floor + height, z, top);
+            } else if has_seagrass(biome) && rng.gen_range(0, 3) == 0 {
+                if depth > 2 && rng.gen_range(0, 4) == 0 {
+                    let tall = BlockId::tall_seagrass();
+                    chunk.set_block_at(x, floor + 1, z, tall);
+                    chunk.set_block_at(
+                        x,
+                        floor + 2,
+                        z,
+                        tall.with_half_upper_lower(HalfUpperLower::Upper),
+                    );
+                } else {
+                    chunk.set_block_at(x, floor + 1, z, BlockId::seagrass());
+                }
+            }
+        }
+    }
+}
+
+/// Replaces the floor blocks within `radius` of the given column,
+/// and the blocks below them, with `block`.
+fn place_patch(
+    chunk: &mut Chunk,
+    biomes: &ChunkBiomes,
+    top_blocks: &TopBlocks,
+    center_x: i32,
+    center_z: i32,
+    radius: i32,
+    block: BlockId,
+) {
+    // Patches are clipped to the chunk, like
+    // the clumps of `ClumpedFoliageFinisher`.
+    for x in (center_x - radius).max(0)..=(center_x + radius).min(15) {
+        for z in (center_z - radius).max(0)..=(center_z + radius).min(15) {
+            let (dx, dz) = (x - center_x, z - center_z);
+            if dx * dx + dz * dz > radius * radius {
+                continue;
+            }
+            let (x, z) = (x as usize, z as usize);
+            if !is_ocean(biomes.biome_at(x, z)) {
+                continue;
+            }
+            if let Some(floor) = ocean_floor(chunk, top_blocks, x, z) {
+                for y in floor.saturating_sub(1)..=floor {
+                    if chunk.block_at(x, y, z).map_or(false, is_replaceable_floor) {
+                        chunk.set_block_at(x, y, z, block);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the Y coordinate of the solid block below the
+/// water, or the ice above it, in the given column, if any.
+fn ocean_floor(chunk: &Chunk, top_blocks: &TopBlocks, x: usize, z: usize) -> Option<usize> {
+    let top = top_blocks.top_block_at(x, z);
+    let surface = chunk.block_at(x, top, z);
+    if !surface.map_or(false, |block| {
+        is_water(block) || block.kind() == BlockKind::Ice
+    }) {
+        return None;
+    }
+    (1..top)
+        .rev()
+        .find(|&y| !chunk.block_at(x, y, z).map_or(false, is_water))
+        .filter(|&y| {
+            chunk
+                .block_at(x, y, z)
+                .map_or(false, |block| block.is_solid())
+        })
+}
+
+/// Returns the number of water blocks starting at `y` and going up.
+fn water_depth(chunk: &Chunk, x: usize, y: usize, z: usize) -> usize {
+    (y..256)
+        .take_while(|&y| chunk.block_at(x, y, z).map_or(false, is_water))
+        .count()
+}
+
+fn is_water(block: BlockId) -> bool {
+    block.kind() == BlockKind::Water
+}
+
+fn is_replaceable_floor(block: BlockId) -> bool {
+    matches!(
+        block.kind(),
+        BlockKind::Dirt
+            | BlockKind::GrassBlock
+            | BlockKind::Sand
+            | BlockKind::Gravel
+            | BlockKind::Clay
+    )
+}
+
+fn is_ocean(biome: Biome) -> bool {
+    matches!(
+        biome,
+        Biome::Ocean
+            | Biome::FrozenOcean
+            | Biome::WarmOcean
+            | Biome::LukewarmOcean
+            | Biome::ColdOcean
+            | Biome::DeepOcean
+            | Biome::DeepWarmOcean
+            | Biome::DeepLukewarmOcean
+            | Biome::DeepColdOcean
+            | Biome::DeepFrozenOcean
+    )
+}
+
+/// Kelp grows in temperate oceans, but neither warm nor frozen ones.
+fn has_kelp(biome: Biome) -> bool {
+    matches!(
+        biome,
+        Biome::Ocean
+            | Biome::LukewarmOcean
+            | Biome::ColdOcean
+            | Biome::DeepOcean
+            | Biome::DeepLukewarmOcean
+            | Biome::DeepColdOcean
+    )
+}
+
+fn has_seagrass(biome: Biome) -> bool {
+    is_ocean(biome) && !matches!(biome, Biome::FrozenOcean | Biome::DeepFrozenOcean)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::ChunkPosition;
+
+    /// A chunk of stone up to Y=40 with a dirt floor,
+    /// under water up to Y=63, in the given biome.
+    fn flooded_chunk(biome: Biome) -> (Chunk, TopBlocks) {
+        let mut chunk = Chunk::new_with_default_biome(ChunkPosition::new(0, 0), biome);
+        let mut top_blocks = TopBlocks::new();
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..40 {
+                    chunk.set_block_at(x, y, z, BlockId::stone());
+                }
+                chunk.set_block_at(x, 40, z, BlockId::dirt());
+                for y in 41..64 {
+                    chunk.set_block_at(x, y, z, BlockId::water());
+                }
+                top_blocks.set_top_block_at(x, z, 63);
+            }
+        }
+        (chunk, top_blocks)
+    }
+
+    fn count(chunk: &Chunk, kinds: &[BlockKind]) -> usize {
+        (0..16)
+            .flat_map(|x| (0..16).flat_map(move |z| (0..256).map(move |y| (x, y, z))))
+            .filter(|&(x, y, z)| kinds.contains(&chunk.block_at(x, y, z).unwrap().kind()))
+            .count()
+    }
+
+    #[test]
+    fn decorates_ocean_floor() {
+        let (mut chunk, top_blocks) = flooded_chunk(Biome::Ocean);
+        let biomes = ChunkBiomes::from_array([Biome::Ocean; 16 * 16]);
+        decorate_ocean_floor(
+            &mut chunk,
+            &biomes,
+            &top_blocks,
+            &mut XorShiftRng::seed_from_u64(3),
+        );
+
+        assert!(
+            count(
+                &chunk,
+                &[BlockKind::Sand, BlockKind::Gravel, BlockKind::Clay]
+            ) > 0
+        );
+        assert!(count(&chunk, &[BlockKind::Kelp]) > 0);
+        assert!(count(&chunk, &[BlockKind::Seagrass, BlockKind::TallSeagrass]) > 0);
+
+        for x in 0..16 {
+            for z in 0..16 {
+                // Plants stay underwater, on the floor.
+                assert!(chunk.block_at(x, 64, z).unwrap().is_air());
+                let floor = chunk.block_at(x, 40, z).unwrap();
+                assert!(floor.is_solid());
+                // Stone is never replaced.
+                assert_eq!(chunk.block_at(x, 38, z), Some(BlockId::stone()));
+            }
+        }
+    }
+
+    #[test]
+    fn only_decorates_oceans() {
+        let (mut chunk, top_blocks) = flooded_chunk(Biome::Plains);
+        let biomes = ChunkBiomes::from_array([Biome::Plains; 16 * 16]);
+        decorate_ocean_floor(
+            &mut chunk,
+            &biomes,
+            &top_blocks,
+            &mut XorShiftRng::seed_from_u64(3),
+        );
+        assert_eq!(count(&chunk, &[BlockKind::Dirt]), 256);
+        assert_eq!(count(&chunk, &[BlockKind::Water]), 256 * 23);
+    }
+}
@@ -0,0 +1,316 @@
+use crate::util::feature_random;
+use crate::{ChunkBiomes, FinishingGenerator, GenerationStage, TopBlocks};
+use base::{BlockId, Chunk};
+use rand::Rng;
+
+/// How the Y coordinates of an ore's veins are distributed.
+#[derive(Copy, Clone, Debug)]
+pub enum OreHeight {
+    /// Uniformly distributed within `[min, max)`.
+    Uniform { min: i32, max: i32 },
+    /// Triangular distribution peaking at `center`
+    /// and reaching zero `spread` blocks away.
+    Triangle { center: i32, spread: i32 },
+}
+
+impl OreHeight {
+    /// Determines whether the distribution covers any Y coordinates.
+    pub fn is_valid(self) -> bool {
+        match self {
+            OreHeight::Uniform { min, max } => min < max,
+            OreHeight::Triangle { spread, .. } => spread > 0,
+        }
+    }
+
+    /// Samples a Y coordinate, clamped to the chunk's height.
+    /// Invalid distributions always yield their lower bound.
+    pub(super) fn sample(self, rng: &mut impl Rng) -> usize {
+        let y = match self {
+            OreHeight::Uniform { min, max } if min < max => rng.gen_range(min, max),
+            OreHeight::Uniform { min, .. } => min,
+            OreHeight::Triangle { center, spread } if spread > 0 => {
+                rng.gen_range(0, spread) + rng.gen_range(0, spread) + center - spread
+            }
+            OreHeight::Triangle { center, .. } => center,
+        };
+        y.clamp(0, 255) as usize
+    }
+}
+
+/// An ore placed by the [`OreFinisher`], or a blob
+/// placed by the [`StoneBlobFinisher`](super::StoneBlobFinisher).
+#[derive(Copy, Clone, Debug)]
+pub struct OreVein {
+    pub block: BlockId,
+    /// Number of veins attempted per chunk.
+    pub count: u32,
+    /// Maximum number of blocks in a vein.
+    pub size: u32,
+    pub height: OreHeight,
+}
+
+impl OreVein {
+    /// Creates an `OreVein`.
+    ///
+    /// # Panics
+    /// Panics if `height` doesn't cover any Y coordinates.
+    pub fn new(block: BlockId, count: u32, size: u32, height: OreHeight) -> Self {
+        assert!(height.is_valid(), "invalid ore height {:?}", height);
+        Self {
+            block,
+            count,
+            size,
+            height,
+        }
+    }
+}
+
+/// Vanilla-like ore distributions.
+fn default_ores() -> Vec<OreVein> {
+    vec![
+        OreVein::new(
+            BlockId::coal_ore(),
+            20,
+            17,
+            OreHeight::Uniform { min: 0, max: 128 },
+        ),
+        OreVein::new(
+            BlockId::iron_ore(),
+            20,
+            9,
+            OreHeight::Uniform { min: 0, max: 64 },
+        ),
+        OreVein::new(
+            BlockId::gold_ore(),
+            2,
+            9,
+            OreHeight::Uniform { min: 0, max: 32 },
+        ),
+        OreVein::new(
+            BlockId::redstone_ore(),
+            8,
+            8,
+            OreHeight::Uniform { min: 0, max: 16 },
+        ),
+        OreVein::new(
+            BlockId::diamond_ore(),
+            1,
+            8,
+            OreHeight::Uniform { min: 0, max: 16 },
+        ),
+        OreVein::new(
+            BlockId::lapis_ore(),
+            1,
+            7,
+            OreHeight::Triangle {
+                center: 16,
+                spread: 16,
+            },
+        ),
+    ]
+}
+
+/// Finisher which places ore veins in stone.
+///
+/// Each ore uses its own RNG derived from the seed and
+/// chunk position, so veins are reproducible and don't
+/// depend on which other ores are configured.
+pub struct OreFinisher {
+    ores: Vec<OreVein>,
+    /// Multiplier applied to each ore's vein count.
+    rate: f32,
+}
+
+impl Default for OreFinisher {
+    fn default() -> Self {
+        Self::new(default_ores())
+    }
+}
+
+impl OreFinisher {
+    /// Creates an `OreFinisher` placing the given ores.
+    pub fn new(ores: Vec<OreVein>) -> Self {
+        Self { ores, rate: 1.0 }
+    }
+
+    /// Multiplies the number of veins of every ore by `rate`,
+    /// rounding to the nearest vein. A rate of zero disables ores.
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.rate = rate.max(0.0);
+        self
+    }
+}
+
+impl FinishingGenerator for OreFinisher {
+    fn name(&self) -> &str {
+        "ores"
+    }
+
+    fn generate_for_chunk(
+        &self,
+        chunk: &mut Chunk,
+        _biomes: &ChunkBiomes,
+        _top_blocks: &TopBlocks,
+        seed: u64,
+    ) {
+        let pos = chunk.position();
+        for ore in &self.ores {
+            let salt = ore.block.vanilla_id() as u64;
+            let mut rng = feature_random(seed, pos.x, pos.z, salt);
+            let count = (ore.count as f32 * self.rate).round() as u32;
+            for _ in 0..count {
+                place_vein(chunk, ore, &mut rng);
+            }
+        }
+    }
+
+    fn stage(&self) -> GenerationStage {
+        GenerationStage::Ores
+    }
+}
+
+/// Places a vein as a random walk starting at a random
+/// position, replacing only stone. Veins are kept within
+/// the chunk.
+fn place_vein(chunk: &mut Chunk, ore: &OreVein, rng: &mut impl Rng) {
+    let mut x = rng.gen_range(0, 16) as i32;
+    let mut y = ore.height.sample(rng) as i32;
+    let mut z = rng.gen_range(0, 16) as i32;
+
+    for _ in 0..ore.size {
+        let (bx, by, bz) = (x as usize, y as usize, z as usize);
+        if chunk.block_at(bx, by, bz) == Some(BlockId::stone()) {
+            chunk.set_block_at(bx, by, bz, ore.block);
+        }
+
+        x = (x + rng.gen_range(-1, 2)).max(0).min(15);
+        y = (y + rng.gen_range(-1, 2)).max(0).min(255);
+        z = (z + rng.gen_range(-1, 2)).max(0).min(15);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::{Biome, ChunkPosition};
+
+    fn generate(seed: u64) -> Chunk {
+        generate_with(&OreFinisher::default(), seed)
+    }
+
+    fn generate_with(finisher: &OreFinisher, seed: u64) -> Chunk {
+        let mut chunk = Chunk::new(ChunkPosition::new(-4, 9));
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..70 {
+                    chunk.set_block_at(x, y, z, BlockId::stone());
+                }
+            }
+        }
+        finisher.generate_for_chunk(
+            &mut chunk,
+            &ChunkBiomes::from_array([Biome::Plains; 256]),
+            &TopBlocks::new(),
+            seed,
+        );
+        chunk
+    }
+
+    #[test]
+    fn ores_respect_depth_and_host_rock() {
+        let chunk = generate(77);
+        let mut iron = 0;
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..256 {
+                    let block = chunk.block_at(x, y, z).unwrap();
+                    if y >= 70 {
+                        assert!(block.is_air());
+                    }
+                    if block == BlockId::iron_ore() {
+                        iron += 1;
+                    }
+                    if block == BlockId::diamond_ore() || block == BlockId::redstone_ore() {
+                        // Veins may wander a few blocks past their starting range.
+                        assert!(y < 16 + 8, "deep ore at y={}", y);
+                    }
+                }
+            }
+        }
+        assert!(iron > 0);
+    }
+
+    #[test]
+    fn ores_are_reproducible() {
+        let (a, b) = (generate(77), generate(77));
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..70 {
+                    assert_eq!(a.block_at(x, y, z), b.block_at(x, y, z));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rate_scales_vein_count() {
+        let ores = |chunk: &Chunk| {
+            let mut count = 0;
+            for x in 0..16 {
+                for z in 0..16 {
+                    for y in 0..70 {
+                        if chunk.block_at(x, y, z) != Some(BlockId::stone()) {
+                            count += 1;
+                        }
+                    }
+                }
+            }
+            count
+        };
+        let normal = ores(&generate(77));
+        let doubled = ores(&generate_with(&OreFinisher::default().with_rate(2.0), 77));
+        let disabled = ores(&generate_with(&OreFinisher::default().with_rate(0.0), 77));
+        assert!(doubled > normal);
+        assert_eq!(disabled, 0);
+    }
+
+    #[test]
+    fn heights_stay_within_the_chunk() {
+        let mut rng = feature_random(0, 0, 0, 0);
+        let heights = [
+            OreHeight::Triangle {
+                center: 4,
+                spread: 16,
+            },
+            OreHeight::Triangle {
+                center: 250,
+                spread: 16,
+            },
+            OreHeight::Uniform { min: -20, max: 300 },
+            OreHeight::Uniform { min: 8, max: 8 },
+            OreHeight::Triangle {
+                center: 8,
+                spread: 0,
+            },
+        ];
+        for &height in &heights {
+            for _ in 0..1000 {
+                assert!(height.sample(&mut rng) <= 255);
+            }
+        }
+        assert_eq!(heights[3].sample(&mut rng), 8);
+        assert!(!heights[3].is_valid());
+        assert!(!heights[4].is_valid());
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_height_range_is_rejected() {
+        OreVein::new(
+            BlockId::coal_ore(),
+            1,
+            1,
+            OreHeight::Uniform { min: 10, max: 10 },
+        );
+    }
+}
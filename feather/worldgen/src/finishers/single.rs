@@ -9,6 +9,10 @@ use rand_xorshift::XorShiftRng;
 pub struct SingleFoliageFinisher;
 
 impl FinishingGenerator for SingleFoliageFinisher {
+    fn name(&self) -> &str {
+        "single_foliage"
+    }
+
     fn generate_for_chunk(
         &self,
         chunk: &mut Chunk,
@@ -0,0 +1,121 @@
+use crate::util::shuffle_seed_for_chunk;
+use crate::{ChunkBiomes, FinishingGenerator, TopBlocks};
+use base::{Biome, BlockId, Chunk};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+/// Finisher which decorates grass blocks with
+/// tall grass, flowers, and snow depending on the biome.
+#[derive(Default)]
+pub struct SurfaceDecorationFinisher;
+
+impl FinishingGenerator for SurfaceDecorationFinisher {
+    fn name(&self) -> &str {
+        "surface_decoration"
+    }
+
+    fn generate_for_chunk(
+        &self,
+        chunk: &mut Chunk,
+        biomes: &ChunkBiomes,
+        top_blocks: &TopBlocks,
+        seed: u64,
+    ) {
+        let mut rng = XorShiftRng::seed_from_u64(shuffle_seed_for_chunk(seed, chunk.position()));
+        decorate_surface(chunk, biomes, top_blocks, &mut rng);
+    }
+}
+
+/// Places decorations on top of each grass-topped column.
+///
+/// Columns topped by anything other than grass, such as
+/// water or stone, are left untouched.
+pub fn decorate_surface(
+    chunk: &mut Chunk,
+    biomes: &ChunkBiomes,
+    top_blocks: &TopBlocks,
+    rng: &mut impl Rng,
+) {
+    for x in 0..16 {
+        for z in 0..16 {
+            let top = top_blocks.top_block_at(x, z);
+            if top >= 255
+                || chunk.block_at(x, top, z) != Some(BlockId::grass_block())
+                || chunk.block_at(x, top + 1, z) != Some(BlockId::air())
+            {
+                continue;
+            }
+
+            if let Some(block) = decoration(biomes.biome_at(x, z), rng) {
+                chunk.set_block_at(x, top + 1, z, block);
+            }
+        }
+    }
+}
+
+/// Picks the decoration, if any, for a grass block in the given biome.
+fn decoration(biome: Biome, rng: &mut impl Rng) -> Option<BlockId> {
+    let (grass_chance, flower_chance) = match biome {
+        Biome::SnowyTundra | Biome::SnowyTaiga | Biome::SnowyTaigaMountains => {
+            return Some(BlockId::snow())
+        }
+        Biome::Plains | Biome::SunflowerPlains => (6, 40),
+        Biome::FlowerForest => (10, 4),
+        Biome::Forest | Biome::BirchForest => (10, 30),
+        Biome::Savanna | Biome::Jungle => (4, 0),
+        _ => (12, 0),
+    };
+
+    if flower_chance != 0 && rng.gen_range(0, flower_chance) == 0 {
+        let flowers = [
+            BlockId::dandelion(),
+            BlockId::poppy(),
+            BlockId::oxeye_daisy(),
+            BlockId::cornflower(),
+        ];
+        Some(flowers[rng.gen_range(0, flowers.len())])
+    } else if rng.gen_range(0, grass_chance) == 0 {
+        Some(BlockId::grass())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::ChunkPosition;
+
+    #[test]
+    fn decorates_grass_but_not_stone() {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        let mut top_blocks = TopBlocks::new();
+        for x in 0..16 {
+            for z in 0..16 {
+                let block = if x < 8 {
+                    BlockId::grass_block()
+                } else {
+                    BlockId::stone()
+                };
+                chunk.set_block_at(x, 64, z, block);
+                top_blocks.set_top_block_at(x, z, 64);
+            }
+        }
+        let biomes = ChunkBiomes::from_array([Biome::Plains; 16 * 16]);
+
+        decorate_surface(
+            &mut chunk,
+            &biomes,
+            &top_blocks,
+            &mut XorShiftRng::seed_from_u64(52),
+        );
+
+        let decorated = |xs: std::ops::Range<usize>| {
+            xs.flat_map(|x| (0..16).map(move |z| (x, z)))
+                .filter(|&(x, z)| chunk.block_at(x, 65, z) != Some(BlockId::air()))
+                .count()
+        };
+        assert!(decorated(0..8) > 0);
+        assert_eq!(decorated(8..16), 0);
+    }
+}
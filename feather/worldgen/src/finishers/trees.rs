@@ -0,0 +1,277 @@
+use crate::util::feature_random;
+use crate::{ChunkRegion, GenerationStage, Populator};
+use base::{Biome, BlockId};
+use rand::Rng;
+
+/// Salt passed to `feature_random` for tree placement.
+const TREE_SALT: u64 = 0x7EE5;
+
+/// Number of tree candidates rolled per chunk. The biome's
+/// density decides which of them actually grow.
+const CANDIDATES_PER_CHUNK: usize = 16;
+
+/// Horizontal radius of a tree's leaves.
+const LEAF_RADIUS: i32 = 2;
+
+/// Populator which grows trees on grass and dirt depending on the biome.
+///
+/// Trees near chunk borders have their leaves placed
+/// in the neighboring chunks of the region.
+#[derive(Default)]
+pub struct TreePopulator;
+
+impl Populator for TreePopulator {
+    fn populate(&self, region: &mut ChunkRegion, seed: u64) {
+        let pos = region.position();
+        let mut rng = feature_random(seed, pos.x, pos.z, TREE_SALT);
+        for _ in 0..CANDIDATES_PER_CHUNK {
+            let x = rng.gen_range(0, 16);
+            let z = rng.gen_range(0, 16);
+            let roll: f32 = rng.gen();
+            let trunk_height = rng.gen_range(4, 7);
+
+            let kind = match region.biome_at(x, z).and_then(tree_kind) {
+                Some((kind, density)) if roll < density => kind,
+                _ => continue,
+            };
+
+            let ground = match ground_at(region, x, z) {
+                Some(ground) if can_grow_on(region.block_at(x, ground, z)) => ground,
+                _ => continue,
+            };
+
+            grow_tree(region, kind, x, ground + 1, z, trunk_height);
+        }
+    }
+
+    fn stage(&self) -> GenerationStage {
+        GenerationStage::Trees
+    }
+}
+
+/// The kinds of trees which can be grown.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum TreeKind {
+    Oak,
+    Birch,
+    Spruce,
+    Jungle,
+    Acacia,
+    DarkOak,
+}
+
+impl TreeKind {
+    fn log(self) -> BlockId {
+        match self {
+            TreeKind::Oak => BlockId::oak_log(),
+            TreeKind::Birch => BlockId::birch_log(),
+            TreeKind::Spruce => BlockId::spruce_log(),
+            TreeKind::Jungle => BlockId::jungle_log(),
+            TreeKind::Acacia => BlockId::acacia_log(),
+            TreeKind::DarkOak => BlockId::dark_oak_log(),
+        }
+    }
+
+    fn leaves(self) -> BlockId {
+        match self {
+            TreeKind::Oak => BlockId::oak_leaves(),
+            TreeKind::Birch => BlockId::birch_leaves(),
+            TreeKind::Spruce => BlockId::spruce_leaves(),
+            TreeKind::Jungle => BlockId::jungle_leaves(),
+            TreeKind::Acacia => BlockId::acacia_leaves(),
+            TreeKind::DarkOak => BlockId::dark_oak_leaves(),
+        }
+    }
+}
+
+/// Returns the kind of tree growing in a biome and the
+/// fraction of candidates which become trees, if any.
+fn tree_kind(biome: Biome) -> Option<(TreeKind, f32)> {
+    match biome {
+        Biome::Plains | Biome::SunflowerPlains => Some((TreeKind::Oak, 0.02)),
+        Biome::Forest | Biome::FlowerForest | Biome::WoodedHills => Some((TreeKind::Oak, 0.6)),
+        Biome::Swamp | Biome::SwampHills => Some((TreeKind::Oak, 0.15)),
+        Biome::Mountains | Biome::WoodedMountains => Some((TreeKind::Spruce, 0.05)),
+        Biome::BirchForest
+        | Biome::BirchForestHills
+        | Biome::TallBirchForest
+        | Biome::TallBirchHills => Some((TreeKind::Birch, 0.6)),
+        Biome::Taiga
+        | Biome::TaigaHills
+        | Biome::TaigaMountains
+        | Biome::SnowyTaiga
+        | Biome::SnowyTaigaHills
+        | Biome::SnowyTaigaMountains
+        | Biome::GiantTreeTaiga
+        | Biome::GiantTreeTaigaHills
+        | Biome::GiantSpruceTaiga
+        | Biome::GiantSpruceTaigaHills => Some((TreeKind::Spruce, 0.5)),
+        Biome::Jungle
+        | Biome::JungleHills
+        | Biome::ModifiedJungle
+        | Biome::BambooJungle
+        | Biome::BambooJungleHills => Some((TreeKind::Jungle, 0.8)),
+        Biome::JungleEdge | Biome::ModifiedJungleEdge => Some((TreeKind::Jungle, 0.2)),
+        Biome::Savanna
+        | Biome::SavannaPlateau
+        | Biome::ShatteredSavanna
+        | Biome::ShatteredSavannaPlateau => Some((TreeKind::Acacia, 0.05)),
+        Biome::DarkForest | Biome::DarkForestHills => Some((TreeKind::DarkOak, 0.9)),
+        _ => None,
+    }
+}
+
+/// Returns the height of the ground in a column, below any snow.
+fn ground_at(region: &ChunkRegion, x: i32, z: i32) -> Option<i32> {
+    let top = region.top_solid_block(x, z)?;
+    if region.block_at(x, top, z) == Some(BlockId::snow()) {
+        Some(top - 1)
+    } else {
+        Some(top)
+    }
+}
+
+fn can_grow_on(ground: Option<BlockId>) -> bool {
+    ground == Some(BlockId::grass_block())
+        || ground == Some(BlockId::dirt())
+        || ground == Some(BlockId::podzol())
+}
+
+/// Grows a tree with its trunk at `(x, y, z)`, relative to the center chunk.
+fn grow_tree(region: &mut ChunkRegion, kind: TreeKind, x: i32, y: i32, z: i32, trunk_height: i32) {
+    let top = y + trunk_height;
+    if top + 1 > 255 {
+        return;
+    }
+
+    // Two wide layers around the top of the trunk,
+    // then two narrow layers above them.
+    for leaf_y in top - 2..=top + 1 {
+        let radius = if leaf_y < top { LEAF_RADIUS } else { 1 };
+        for dx in -radius..=radius {
+            for dz in -radius..=radius {
+                let corner = dx.abs() == radius && dz.abs() == radius;
+                if corner && (radius == LEAF_RADIUS || leaf_y == top + 1) {
+                    continue;
+                }
+                set_if_replaceable(region, x + dx, leaf_y, z + dz, kind.leaves(), false);
+            }
+        }
+    }
+
+    for trunk_y in y..top {
+        set_if_replaceable(region, x, trunk_y, z, kind.log(), true);
+    }
+}
+
+/// Sets a block if it is currently air, or, for trunks,
+/// leaves, snow, or plants placed by the finishers.
+fn set_if_replaceable(
+    region: &mut ChunkRegion,
+    x: i32,
+    y: i32,
+    z: i32,
+    block: BlockId,
+    trunk: bool,
+) {
+    let replaceable = match region.block_at(x, y, z) {
+        Some(existing) if existing.is_air() => true,
+        Some(existing) => {
+            trunk
+                && (existing == BlockId::snow()
+                    || is_leaves(existing)
+                    || !(existing.is_solid() || existing.is_fluid()))
+        }
+        None => false,
+    };
+    if replaceable {
+        region.set_block_at(x, y, z, block);
+    }
+}
+
+fn is_leaves(block: BlockId) -> bool {
+    [
+        TreeKind::Oak,
+        TreeKind::Birch,
+        TreeKind::Spruce,
+        TreeKind::Jungle,
+        TreeKind::Acacia,
+        TreeKind::DarkOak,
+    ]
+    .iter()
+    .any(|kind| kind.leaves().kind() == block.kind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::{Chunk, ChunkPosition};
+
+    const GROUND: i32 = 63;
+
+    fn populate(position: ChunkPosition) -> ChunkRegion {
+        let mut chunks = Vec::new();
+        for z in -1..=1 {
+            for x in -1..=1 {
+                let mut chunk = Chunk::new_with_default_biome(
+                    ChunkPosition::new(position.x + x, position.z + z),
+                    Biome::DarkForest,
+                );
+                for x in 0..16 {
+                    for z in 0..16 {
+                        chunk.set_block_at(x, GROUND as usize, z, BlockId::grass_block());
+                    }
+                }
+                chunks.push(chunk);
+            }
+        }
+        let mut region = ChunkRegion::new(chunks);
+        TreePopulator.populate(&mut region, 99);
+        region
+    }
+
+    fn is_tree_block(block: Option<BlockId>) -> bool {
+        block.map_or(false, |block| {
+            block.kind() == BlockId::dark_oak_log().kind() || is_leaves(block)
+        })
+    }
+
+    #[test]
+    fn trees_grow_in_forests() {
+        let region = populate(ChunkPosition::new(0, 0));
+        let logs = (0..16)
+            .flat_map(|x| (0..16).map(move |z| (x, z)))
+            .filter(|&(x, z)| region.block_at(x, GROUND + 1, z) == Some(BlockId::dark_oak_log()))
+            .count();
+        assert!(logs > 0);
+
+        // Trees are only rooted in the center chunk.
+        assert_ne!(
+            region.chunk(1, 0).block_at(8, GROUND as usize + 1, 8),
+            Some(BlockId::dark_oak_log())
+        );
+    }
+
+    #[test]
+    fn trees_continue_across_chunk_borders() {
+        let mut checked = 0;
+        for chunk_z in 0..4 {
+            let region = populate(ChunkPosition::new(0, chunk_z));
+
+            for x in 14..16 {
+                for z in 0..16 {
+                    let is_log = |y| region.block_at(x, y, z) == Some(BlockId::dark_oak_log());
+                    if !is_log(GROUND + 1) {
+                        continue;
+                    }
+                    let top = (GROUND + 1..).find(|&y| !is_log(y)).unwrap();
+
+                    // The lower leaf layers reach two blocks from the trunk.
+                    assert!(is_tree_block(region.block_at(x + 2, top - 2, z)));
+                    checked += 1;
+                }
+            }
+        }
+        assert!(checked > 0);
+    }
+}
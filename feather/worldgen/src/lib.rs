@@ -5,29 +5,71 @@
 //! Generation is primarily based around the `ComposableGenerator`,
 //! which allows configuration of a world generator pipeline.
 
+mod aquifer;
 mod biomes;
+mod blend;
 mod composition;
 mod density_map;
+mod end;
 mod finishers;
+mod nether;
 pub mod noise;
+mod painter;
+mod population;
+pub mod router;
+mod seed;
+mod settings;
+mod spawn;
+pub mod spline;
+mod stage;
+pub mod structures;
 mod superflat;
+mod timings;
 mod util;
+pub mod validate;
 pub mod voronoi;
 
-use base::{Biome, BlockId, Chunk, ChunkPosition};
-pub use biomes::{DistortedVoronoiBiomeGenerator, TwoLevelBiomeGenerator};
+pub use aquifer::apply_aquifers;
+use base::{Biome, Chunk, ChunkPosition};
+pub use biomes::{
+    classify_biome, column_mob_spawns, generate_biome_map, mob_spawns, ClimateBiomeGenerator,
+    DistortedVoronoiBiomeGenerator, LayeredBiomeGenerator, MobSpawns, SpawnCategory, SpawnEntry,
+    TwoLevelBiomeGenerator,
+};
 use bitvec::vec::BitVec;
 use bitvec::{order::LocalBits, slice::BitSlice};
-pub use composition::BasicCompositionGenerator;
+pub use blend::{BiomeBlender, BiomeWeights};
+pub use composition::{BasicCompositionGenerator, SurfaceRule};
 pub use density_map::{DensityMapGeneratorImpl, HeightMapGenerator};
-use finishers::{ClumpedFoliageFinisher, SingleFoliageFinisher, SnowFinisher};
-pub use noise::NoiseLerper;
+pub use end::EndWorldGenerator;
+pub use finishers::{
+    decorate_ocean_floor, decorate_surface, CaveCarver, FreezePopulator, LakePopulator,
+    OceanFloorFinisher, OreFinisher, OreHeight, OreVein, StoneBlobFinisher,
+    SurfaceDecorationFinisher, TreePopulator,
+};
+use finishers::{ClumpedFoliageFinisher, SingleFoliageFinisher};
+pub use nether::{NetherWorldGenerator, LAVA_LEVEL};
+pub use noise::{
+    ColumnNoise, NoiseLerper, OctaveNoise, Wrapped2DPerlinNoise, Wrapped3DPerlinNoise,
+};
 use num_traits::ToPrimitive;
-use rand::{Rng, SeedableRng};
-use rand_xorshift::XorShiftRng;
-use smallvec::SmallVec;
+use once_cell::sync::OnceCell;
+pub use painter::paint_surface;
+pub use population::{ChunkRegion, PopulationScheduler, Populator};
+pub use router::{DensityFunction, NoiseRouter};
+pub use seed::{GenerationStage, SeedSplitter};
+pub use settings::GeneratorSettings;
+pub use spawn::find_spawn;
+pub use spline::Spline;
+pub use stage::{CompositionStage, FinisherStage, GenContext, GenStage, StructureStage};
 use std::fmt;
+pub use structures::{
+    stronghold_positions, structure_chunk, BoundingBox, DesertTemple, Dungeon, Mineshaft,
+    StrongholdStage, Strongholds, Structure, StructurePiece, StructureType, Template, Village,
+};
 pub use superflat::SuperflatWorldGenerator;
+pub use timings::StageTimings;
+pub use util::feature_random;
 
 /// Sea-level height.
 pub const SEA_LEVEL: usize = 64;
@@ -39,6 +81,10 @@ const OCEAN_DEPTH: usize = 30;
 pub trait WorldGenerator: Send + Sync {
     /// Generates the chunk at the given position.
     fn generate_chunk(&self, position: ChunkPosition) -> Chunk;
+
+    /// Populates the center chunk of `region` once its
+    /// neighbors have been generated. See `PopulationScheduler`.
+    fn populate(&self, _region: &mut ChunkRegion) {}
 }
 
 pub struct EmptyWorldGenerator {}
@@ -49,6 +95,18 @@ impl WorldGenerator for EmptyWorldGenerator {
     }
 }
 
+/// A preset for the overworld `ComposableGenerator`,
+/// corresponding to vanilla's world types.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WorldPreset {
+    /// The default world type.
+    Default,
+    /// Terrain several times taller than in default worlds.
+    Amplified,
+    /// Biomes four times larger than in default worlds.
+    LargeBiomes,
+}
+
 /// A "composable" world generator.
 ///
 /// This generator will generate the world based
@@ -58,8 +116,15 @@ impl WorldGenerator for EmptyWorldGenerator {
 /// The pipeline stages are as follows:
 /// * Biomes - generates a biome grid.
 /// * Terrain density - generates the terrain density values using Perlin noise.
-/// * Terrain composition - sets the correct block types based on the biome and terrain density.
-/// * Finishing generators - generates final elements, such as grass, snow, and trees.
+/// * An ordered list of `GenStage`s, by default:
+///   * Composition - sets the correct block types based on the biome and terrain density.
+///   * Finishing generators - generates final elements, such as caves, ores, and grass.
+///   * Structures - places dungeons, temples, and other structures overlapping the chunk.
+/// * Populators - run once the neighboring chunks have been generated, adding
+///   features which cross chunk borders, such as lakes and trees, then snow and ice.
+///
+/// Stages can be inserted, replaced, or removed by name, so
+/// alternate generators can reuse the rest of the pipeline.
 ///
 /// This generator is based on [this document](http://cuberite.xoft.cz/docs/Generator.html).
 pub struct ComposableGenerator {
@@ -67,17 +132,19 @@ pub struct ComposableGenerator {
     biome: Box<dyn BiomeGenerator>,
     /// The height map generator.
     density_map: Box<dyn DensityMapGenerator>,
-    /// The composition generator.
-    composition: Box<dyn CompositionGenerator>,
-    /// A vector of finishing generators used
-    /// by this composable generator.
-    finishers: SmallVec<[Box<dyn FinishingGenerator>; 8]>,
-    /// The world seed.
-    seed: u64,
+    /// Stages run in order once the density map is generated.
+    stages: Vec<Box<dyn GenStage>>,
+    /// Populators run once the neighboring chunks have been generated.
+    populators: Vec<Box<dyn Populator>>,
+    /// Seeds for each stage, derived from the world seed.
+    seeds: SeedSplitter,
+    /// Stronghold positions, computed on the first lookup.
+    strongholds: OnceCell<Strongholds>,
 }
 
 impl ComposableGenerator {
-    /// Creates a new `ComposableGenerator` with the given stages.
+    /// Creates a new `ComposableGenerator` running
+    /// `composition` followed by `finishers`.
     pub fn new<B, D, C, F>(
         biome: B,
         density_map: D,
@@ -91,53 +158,231 @@ impl ComposableGenerator {
         C: CompositionGenerator + 'static,
         F: IntoIterator<Item = Box<dyn FinishingGenerator>>,
     {
+        let mut stages: Vec<Box<dyn GenStage>> = vec![Box::new(CompositionStage::new(composition))];
+        stages.extend(
+            finishers
+                .into_iter()
+                .map(|finisher| Box::new(FinisherStage::new(finisher)) as Box<dyn GenStage>),
+        );
         Self {
             biome: Box::new(biome),
             density_map: Box::new(density_map),
-            composition: Box::new(composition),
-            finishers: finishers.into_iter().collect(),
-            seed,
+            stages,
+            populators: Vec::new(),
+            seeds: SeedSplitter::new(seed),
+            strongholds: OnceCell::new(),
+        }
+    }
+
+    /// Sets the structures placed by this generator,
+    /// replacing the "structures" stage or adding
+    /// it after the other stages.
+    pub fn with_structures<S>(self, structures: S) -> Self
+    where
+        S: IntoIterator<Item = Box<dyn StructureType>>,
+    {
+        let stage = StructureStage::new(structures);
+        if self.has_stage(stage.name()) {
+            self.with_stage_replaced("structures", stage)
+        } else {
+            self.with_stage(stage)
         }
     }
 
+    /// Adds a stage after all others.
+    pub fn with_stage(mut self, stage: impl GenStage + 'static) -> Self {
+        self.stages.push(Box::new(stage));
+        self
+    }
+
+    /// Inserts a stage before the stage named `name`.
+    ///
+    /// # Panics
+    /// Panics if there is no stage named `name`.
+    pub fn with_stage_before(mut self, name: &str, stage: impl GenStage + 'static) -> Self {
+        let index = self.stage_index(name);
+        self.stages.insert(index, Box::new(stage));
+        self
+    }
+
+    /// Inserts a stage after the stage named `name`.
+    ///
+    /// # Panics
+    /// Panics if there is no stage named `name`.
+    pub fn with_stage_after(mut self, name: &str, stage: impl GenStage + 'static) -> Self {
+        let index = self.stage_index(name);
+        self.stages.insert(index + 1, Box::new(stage));
+        self
+    }
+
+    /// Replaces the stage named `name`.
+    ///
+    /// # Panics
+    /// Panics if there is no stage named `name`.
+    pub fn with_stage_replaced(mut self, name: &str, stage: impl GenStage + 'static) -> Self {
+        let index = self.stage_index(name);
+        self.stages[index] = Box::new(stage);
+        self
+    }
+
+    /// Removes the stage named `name`, if any.
+    pub fn without_stage(mut self, name: &str) -> Self {
+        self.stages.retain(|stage| stage.name() != name);
+        self
+    }
+
+    /// Determines whether this generator has a stage named `name`.
+    pub fn has_stage(&self, name: &str) -> bool {
+        self.stages.iter().any(|stage| stage.name() == name)
+    }
+
+    /// Returns the names of the stages of this generator, in order.
+    pub fn stage_names(&self) -> impl Iterator<Item = &str> {
+        self.stages.iter().map(|stage| stage.name())
+    }
+
+    /// Returns the start chunk of the stronghold closest to
+    /// `position`, or `None` if this generator has no strongholds.
+    pub fn nearest_stronghold(&self, position: ChunkPosition) -> Option<ChunkPosition> {
+        if !self.has_stage("strongholds") {
+            return None;
+        }
+        let seed = self.seeds.stage_seed(GenerationStage::Structures);
+        let strongholds = self.strongholds.get_or_init(|| Strongholds::new(seed));
+        Some(strongholds.nearest(position))
+    }
+
+    fn stage_index(&self, name: &str) -> usize {
+        self.stages
+            .iter()
+            .position(|stage| stage.name() == name)
+            .unwrap_or_else(|| panic!("no worldgen stage named {}", name))
+    }
+
+    /// Sets the populators run by this generator.
+    pub fn with_populators<P>(mut self, populators: P) -> Self
+    where
+        P: IntoIterator<Item = Box<dyn Populator>>,
+    {
+        self.populators = populators.into_iter().collect();
+        self
+    }
+
     /// A default composable generator, used
     /// for worlds with "default" world type.
     pub fn default_with_seed(seed: u64) -> Self {
-        let finishers: Vec<Box<dyn FinishingGenerator>> = vec![
-            Box::new(SnowFinisher::default()),
-            Box::new(SingleFoliageFinisher::default()),
-            Box::new(ClumpedFoliageFinisher::default()),
-        ];
-        Self::new(
-            TwoLevelBiomeGenerator::default(),
-            DensityMapGeneratorImpl::default(),
-            BasicCompositionGenerator::default(),
-            finishers,
-            seed,
-        )
+        Self::with_preset(WorldPreset::Default, seed)
     }
-}
 
-impl WorldGenerator for ComposableGenerator {
-    fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
-        let mut seed_shuffler = XorShiftRng::seed_from_u64(self.seed);
+    /// A composable generator for the given world type.
+    pub fn with_preset(preset: WorldPreset, seed: u64) -> Self {
+        Self::with_settings(preset, &GeneratorSettings::default(), seed)
+    }
 
-        // Generate biomes for 3x3 grid of chunks around current chunk.
-        let biome_seed = seed_shuffler.gen();
+    /// A composable generator for the given world type,
+    /// customized by `settings`.
+    pub fn with_settings(preset: WorldPreset, settings: &GeneratorSettings, seed: u64) -> Self {
+        let (biome, mut density_map) = match preset {
+            WorldPreset::Default => (
+                TwoLevelBiomeGenerator::default(),
+                DensityMapGeneratorImpl::default(),
+            ),
+            WorldPreset::Amplified => (
+                TwoLevelBiomeGenerator::default(),
+                DensityMapGeneratorImpl::default()
+                    .with_frequency(0.15)
+                    .with_height_scale(2.5),
+            ),
+            WorldPreset::LargeBiomes => (
+                TwoLevelBiomeGenerator::default().with_extra_zoom(2),
+                DensityMapGeneratorImpl::default(),
+            ),
+        };
+        if let Some(frequency) = settings.frequency {
+            density_map = density_map.with_frequency(frequency);
+        }
+        if let Some(height_scale) = settings.height_scale {
+            density_map = density_map.with_height_scale(height_scale);
+        }
 
-        let mut biomes = vec![];
+        let mut finishers: Vec<Box<dyn FinishingGenerator>> = Vec::new();
+        if settings.caves {
+            finishers.push(Box::new(CaveCarver::default()));
+        }
+        if settings.ores {
+            finishers.push(Box::new(StoneBlobFinisher::default()));
+            finishers.push(Box::new(
+                OreFinisher::default().with_rate(settings.ore_rate),
+            ));
+        }
+        finishers.push(Box::new(SingleFoliageFinisher::default()));
+        finishers.push(Box::new(ClumpedFoliageFinisher::default()));
+        finishers.push(Box::new(SurfaceDecorationFinisher::default()));
+        finishers.push(Box::new(OceanFloorFinisher::default()));
+
+        let mut populators: Vec<Box<dyn Populator>> = Vec::new();
+        if settings.lakes {
+            populators.push(Box::new(LakePopulator::default()));
+        }
+        if settings.trees {
+            populators.push(Box::new(TreePopulator::default()));
+        }
+        populators.push(Box::new(FreezePopulator::default()));
 
-        for z in -1..=1 {
-            for x in -1..=1 {
-                let pos = ChunkPosition::new(position.x + x, position.z + z);
-                biomes.push(self.biome.generate_for_chunk(pos, biome_seed));
-            }
+        let generator = Self::new(
+            biome,
+            density_map,
+            BasicCompositionGenerator::default().with_sea_level(settings.sea_level),
+            finishers,
+            seed,
+        )
+        .with_populators(populators);
+        if settings.structures {
+            let structures: Vec<Box<dyn StructureType>> = vec![
+                Box::new(Dungeon::default()),
+                Box::new(Mineshaft::default()),
+                Box::new(DesertTemple::default()),
+                Box::new(Village::default()),
+            ];
+            generator
+                .with_structures(structures)
+                .with_stage(StrongholdStage::default())
+        } else {
+            generator
         }
-        let biomes = NearbyBiomes::from_vec(biomes);
+    }
 
-        let density_map =
-            self.density_map
-                .generate_for_chunk(position, &biomes, seed_shuffler.gen());
+    /// Generates the chunk at the given position, adding
+    /// the time spent in each stage to `timings`.
+    pub fn generate_chunk_timed(
+        &self,
+        position: ChunkPosition,
+        timings: &mut StageTimings,
+    ) -> Chunk {
+        // Generate biomes for 3x3 grid of chunks around current chunk.
+        let biome_seed = self.seeds.stage_seed(GenerationStage::Biomes);
+
+        let biomes = timings.time(GenerationStage::Biomes, || {
+            let mut biomes = vec![];
+            for z in -1..=1 {
+                for x in -1..=1 {
+                    let pos = ChunkPosition::new(position.x + x, position.z + z);
+                    biomes.push(self.biome.generate_for_chunk(pos, biome_seed));
+                }
+            }
+            NearbyBiomes::from_vec(biomes)
+        });
+
+        let density_map = timings.time(GenerationStage::Terrain, || {
+            self.density_map.generate_for_chunk(
+                position,
+                &biomes,
+                self.seeds.stage_seed(GenerationStage::Terrain),
+            )
+        });
+        validate::enforce("terrain density", || {
+            validate::check_density(density_map.as_bitslice())
+        });
 
         let mut chunk = Chunk::new(position);
 
@@ -147,41 +392,39 @@ impl WorldGenerator for ComposableGenerator {
             }
         }
 
-        self.composition.generate_for_chunk(
-            &mut chunk,
+        let ctx = GenContext::new(
             position,
-            &biomes.biomes[4], // Center chunk
+            &biomes,
             density_map.as_bitslice(),
-            seed_shuffler.gen(),
+            self.seeds,
+            &*self.biome,
         );
-
-        // Calculate top blocks in chunk.
-        // TODO: perhaps this should be moved to `Chunk`?
-        let mut top_blocks = TopBlocks::new();
-        for x in 0..16 {
-            for z in 0..16 {
-                for y in (0..256).rev() {
-                    if chunk.block_at(x, y, z).unwrap() != BlockId::air() {
-                        top_blocks.set_top_block_at(x, z, y);
-                        break;
-                    }
-                }
-            }
+        for stage in &self.stages {
+            timings.time(stage.stage(), || stage.apply(&mut chunk, &ctx));
+            validate::enforce(stage.name(), || validate::check_chunk(&chunk, true));
         }
 
-        chunk.recalculate_heightmaps();
+        chunk
+    }
 
-        // Finishers.
-        for finisher in &self.finishers {
-            finisher.generate_for_chunk(
-                &mut chunk,
-                &biomes.biomes[4],
-                &top_blocks,
-                seed_shuffler.gen(),
-            );
+    /// Populates the center chunk of `region`, adding
+    /// the time spent in each stage to `timings`.
+    pub fn populate_timed(&self, region: &mut ChunkRegion, timings: &mut StageTimings) {
+        for populator in &self.populators {
+            timings.time(populator.stage(), || {
+                populator.populate(region, self.seeds.stage_seed(populator.stage()))
+            });
         }
+    }
+}
 
-        chunk
+impl WorldGenerator for ComposableGenerator {
+    fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+        self.generate_chunk_timed(position, &mut StageTimings::new())
+    }
+
+    fn populate(&self, region: &mut ChunkRegion) {
+        self.populate_timed(region, &mut StageTimings::new())
     }
 }
 
@@ -211,12 +454,13 @@ pub trait DensityMapGenerator: Send + Sync {
 /// based on the given density map and biomes.
 pub trait CompositionGenerator: Send + Sync {
     /// Populates the given chunk with blocks based on the given
-    /// biomes and density map.
+    /// biomes and density map. `biomes` includes the neighboring
+    /// chunks so that generators can blend across borders.
     fn generate_for_chunk(
         &self,
         chunk: &mut Chunk,
         pos: ChunkPosition,
-        biomes: &ChunkBiomes,
+        biomes: &NearbyBiomes,
         density: &BitSlice<LocalBits, u8>,
         seed: u64,
     );
@@ -226,8 +470,12 @@ pub trait CompositionGenerator: Send + Sync {
 /// which can add finishing elements to chunks,
 /// such as grass, trees, and snow.
 pub trait FinishingGenerator: Send + Sync {
+    /// Returns the name of the `GenStage` running this finisher.
+    fn name(&self) -> &str;
+
     /// Populates the given chunk with any
-    /// finishing blocks.
+    /// finishing blocks. `seed` is the seed of the
+    /// finisher's stage.
     fn generate_for_chunk(
         &self,
         chunk: &mut Chunk,
@@ -235,6 +483,11 @@ pub trait FinishingGenerator: Send + Sync {
         top_blocks: &TopBlocks,
         seed: u64,
     );
+
+    /// Returns the stage whose seed is passed to this finisher.
+    fn stage(&self) -> GenerationStage {
+        GenerationStage::Decoration
+    }
 }
 
 /// Returns an index into a one-dimensional array
@@ -358,6 +611,14 @@ impl ChunkBiomes {
         self.biomes[index] = biome;
     }
 
+    /// Returns the mobs which may spawn in the given column.
+    ///
+    /// # Panics
+    /// Panics if `x >= 16 | z >= 16`.
+    pub fn mob_spawns_at(&self, x: usize, z: usize) -> &'static MobSpawns {
+        mob_spawns(self.biome_at(x, z))
+    }
+
     fn index(x: usize, z: usize) -> usize {
         (x << 4) | z
     }
@@ -386,6 +647,7 @@ impl BiomeGenerator for StaticBiomeGenerator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use base::BlockId;
 
     #[test]
     #[ignore] // TODO (1.16): account for new 3D biomes
@@ -421,6 +683,120 @@ mod tests {
         }
     }
 
+    #[test]
+    fn settings_toggle_features() {
+        let full = ComposableGenerator::with_preset(WorldPreset::Default, 0);
+        let settings = GeneratorSettings {
+            caves: false,
+            ores: false,
+            lakes: false,
+            trees: false,
+            structures: false,
+            ..Default::default()
+        };
+        let bare = ComposableGenerator::with_settings(WorldPreset::Default, &settings, 0);
+        assert!(full.nearest_stronghold(ChunkPosition::new(0, 0)).is_some());
+        assert_eq!(bare.nearest_stronghold(ChunkPosition::new(0, 0)), None);
+        assert_eq!(full.stage_names().count(), bare.stage_names().count() + 5);
+        assert_eq!(full.populators.len(), bare.populators.len() + 2);
+        for stage in &["caves", "stone_blobs", "ores", "structures", "strongholds"] {
+            assert!(full.has_stage(stage));
+            assert!(!bare.has_stage(stage));
+        }
+
+        // Neither carved nor mineralized.
+        let chunk = bare.generate_chunk(ChunkPosition::new(0, 0));
+        for x in 0..16 {
+            for z in 0..16 {
+                // Above the bedrock and below the deepest oceans.
+                for y in 5..20 {
+                    assert_eq!(chunk.block_at(x, y, z), Some(BlockId::stone()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generated_chunks_are_painted() {
+        let settings = GeneratorSettings {
+            caves: false,
+            structures: false,
+            ..Default::default()
+        };
+        let generator = ComposableGenerator::with_settings(WorldPreset::Default, &settings, 0);
+        for &(chunk_x, chunk_z) in &[(0, 0), (7, -12), (-30, 41)] {
+            let chunk = generator.generate_chunk(ChunkPosition::new(chunk_x, chunk_z));
+            for x in 0..16 {
+                for z in 0..16 {
+                    assert_eq!(chunk.block_at(x, 0, z), Some(BlockId::bedrock()));
+                    for y in 5..256 {
+                        assert_ne!(chunk.block_at(x, y, z), Some(BlockId::bedrock()));
+                    }
+
+                    // No bare stone surfaces, except where stone is the soil.
+                    if chunk.biomes().get(x / 4, 0, z / 4) == Biome::StoneShore {
+                        continue;
+                    }
+                    for y in 0..255 {
+                        let below = chunk.block_at(x, y, z).unwrap();
+                        let above = chunk.block_at(x, y + 1, z).unwrap();
+                        assert!(
+                            !(below == BlockId::stone() && above.is_air()),
+                            "bare stone at {}, {}, {} in chunk {}, {}",
+                            x,
+                            y,
+                            z,
+                            chunk_x,
+                            chunk_z
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Places a block high above the terrain of the origin column.
+    struct MarkerStage(&'static str, BlockId);
+
+    impl GenStage for MarkerStage {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn stage(&self) -> GenerationStage {
+            GenerationStage::Decoration
+        }
+
+        fn apply(&self, chunk: &mut Chunk, _ctx: &GenContext) {
+            chunk.set_block_at(0, 200, 0, self.1);
+        }
+    }
+
+    #[test]
+    fn stages_can_be_inserted_and_replaced() {
+        let generator = ComposableGenerator::default_with_seed(0)
+            .with_stage(MarkerStage("last", BlockId::stone()))
+            .with_stage_after("composition", MarkerStage("first", BlockId::glass()))
+            .with_stage_before("first", MarkerStage("zeroth", BlockId::dirt()));
+        let names: Vec<&str> = generator.stage_names().collect();
+        assert_eq!(&names[..4], &["composition", "zeroth", "first", "caves"]);
+        assert_eq!(names.last(), Some(&"last"));
+
+        let block = |generator: &ComposableGenerator| {
+            generator
+                .generate_chunk(ChunkPosition::new(0, 0))
+                .block_at(0, 200, 0)
+        };
+        assert_eq!(block(&generator), Some(BlockId::stone()));
+
+        let generator = generator.without_stage("last");
+        assert_eq!(block(&generator), Some(BlockId::glass()));
+
+        let generator =
+            generator.with_stage_replaced("first", MarkerStage("first", BlockId::air()));
+        assert_eq!(block(&generator), Some(BlockId::dirt()));
+    }
+
     #[test]
     pub fn test_worldgen_empty() {
         let chunk_pos = ChunkPosition { x: 1, z: 2 };
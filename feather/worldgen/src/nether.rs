@@ -0,0 +1,187 @@
+//! Generator for the Nether: a cavernous layer of netherrack
+//! between bedrock floor and ceiling, with a lava ocean and
+//! glowstone hanging from the ceiling.
+
+use crate::noise::{index, Wrapped3DPerlinNoise};
+use crate::util::{feature_random, shuffle_seed_for_chunk};
+use crate::WorldGenerator;
+use base::{Biome, BlockId, Chunk, ChunkPosition};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+/// Highest Y coordinate filled with lava.
+pub const LAVA_LEVEL: usize = 31;
+/// Height of the Nether's bedrock ceiling.
+const CEILING: usize = 127;
+/// Maximum thickness of the bedrock floor and ceiling.
+const BEDROCK_THICKNESS: usize = 4;
+/// Distance from the floor over which terrain becomes solid.
+const FLOOR_FADE: f32 = 24.0;
+/// Distance from the ceiling over which terrain becomes solid.
+const CEILING_FADE: f32 = 16.0;
+/// Salt passed to `feature_random` for glowstone clusters.
+const GLOWSTONE_SALT: u64 = 0x6C0;
+/// Number of glowstone clusters attempted per chunk.
+const GLOWSTONE_ATTEMPTS: usize = 10;
+/// Number of blocks attempted per glowstone cluster.
+const GLOWSTONE_CLUSTER_SIZE: usize = 60;
+
+/// World generator for the Nether.
+///
+/// Terrain is carved from 3D noise which is biased towards
+/// solid near the floor and ceiling, so caverns open up in
+/// the middle of the dimension. Open space at or below
+/// `LAVA_LEVEL` is filled with lava.
+pub struct NetherWorldGenerator {
+    seed: u64,
+}
+
+impl NetherWorldGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl WorldGenerator for NetherWorldGenerator {
+    fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+        let mut chunk = Chunk::new_with_default_biome(position, Biome::NetherWastes);
+        let noise = Wrapped3DPerlinNoise::new(self.seed)
+            .with_offset(position.x, position.z)
+            .with_frequency(0.03)
+            .with_amplitude(1.0)
+            .generate();
+        let mut rng = XorShiftRng::seed_from_u64(shuffle_seed_for_chunk(self.seed, position));
+
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..=CEILING {
+                    let block = if y <= rng.gen_range(0, BEDROCK_THICKNESS)
+                        || y >= CEILING - rng.gen_range(0, BEDROCK_THICKNESS)
+                    {
+                        BlockId::bedrock()
+                    } else if noise[index(x, y, z)] + solid_bias(y) > 0.0 {
+                        BlockId::netherrack()
+                    } else if y <= LAVA_LEVEL {
+                        BlockId::lava()
+                    } else {
+                        continue;
+                    };
+                    chunk.set_block_at(x, y, z, block);
+                }
+            }
+        }
+
+        let mut rng = feature_random(self.seed, position.x, position.z, GLOWSTONE_SALT);
+        for _ in 0..GLOWSTONE_ATTEMPTS {
+            place_glowstone(&mut chunk, &mut rng);
+        }
+
+        chunk.recalculate_heightmaps();
+        chunk
+    }
+}
+
+/// Returns the value added to the noise at height `y`. Positive
+/// values make blocks more likely to be solid.
+fn solid_bias(y: usize) -> f32 {
+    let y = y as f32;
+    let floor = ((FLOOR_FADE - y) / FLOOR_FADE).max(0.0);
+    let ceiling = ((y - (CEILING as f32 - CEILING_FADE)) / CEILING_FADE).max(0.0);
+    1.5 * (floor + ceiling) - 0.1
+}
+
+/// Grows a glowstone cluster downwards from the underside
+/// of a random netherrack ceiling. Clusters are kept within
+/// the chunk.
+fn place_glowstone(chunk: &mut Chunk, rng: &mut impl Rng) {
+    let x = rng.gen_range(0, 16);
+    let z = rng.gen_range(0, 16);
+    let start = rng.gen_range(LAVA_LEVEL + 8, CEILING - BEDROCK_THICKNESS);
+
+    // Find the nearest ceiling above the start.
+    let y = match (start..CEILING).find(|&y| {
+        chunk.block_at(x, y, z) == Some(BlockId::air())
+            && chunk.block_at(x, y + 1, z) == Some(BlockId::netherrack())
+    }) {
+        Some(y) => y,
+        None => return,
+    };
+    chunk.set_block_at(x, y, z, BlockId::glowstone());
+
+    for _ in 0..GLOWSTONE_CLUSTER_SIZE {
+        let bx = (x as i32 + rng.gen_range(-3, 4)).max(0).min(15) as usize;
+        let by = y - rng.gen_range(0, 8).min(y);
+        let bz = (z as i32 + rng.gen_range(-3, 4)).max(0).min(15) as usize;
+        if chunk.block_at(bx, by, bz) != Some(BlockId::air()) {
+            continue;
+        }
+
+        // Only attach to a single neighboring glowstone block,
+        // which gives the cluster its branching shape.
+        let attached = [
+            (0, 1, 0),
+            (0, -1, 0),
+            (1, 0, 0),
+            (-1, 0, 0),
+            (0, 0, 1),
+            (0, 0, -1),
+        ]
+        .iter()
+        .filter(|&&(dx, dy, dz)| {
+            let (nx, ny, nz) = (bx as i32 + dx, by as i32 + dy, bz as i32 + dz);
+            (0..16).contains(&nx)
+                && (0..16).contains(&nz)
+                && chunk.block_at(nx as usize, ny as usize, nz as usize)
+                    == Some(BlockId::glowstone())
+        })
+        .count();
+        if attached == 1 {
+            chunk.set_block_at(bx, by, bz, BlockId::glowstone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(chunk: &Chunk, block: BlockId, ys: impl Iterator<Item = usize> + Clone) -> usize {
+        let mut count = 0;
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in ys.clone() {
+                    if chunk.block_at(x, y, z) == Some(block) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn nether_terrain_layout() {
+        let generator = NetherWorldGenerator::new(5);
+        let chunk = generator.generate_chunk(ChunkPosition::new(3, -7));
+
+        assert_eq!(count(&chunk, BlockId::bedrock(), 0..1), 256);
+        assert_eq!(count(&chunk, BlockId::bedrock(), CEILING..CEILING + 1), 256);
+        assert!(count(&chunk, BlockId::netherrack(), 0..CEILING) > 0);
+        assert_eq!(count(&chunk, BlockId::lava(), LAVA_LEVEL + 1..256), 0);
+        assert_eq!(count(&chunk, BlockId::air(), 0..=LAVA_LEVEL), 0);
+        assert_eq!(
+            count(&chunk, BlockId::air(), CEILING + 1..256),
+            16 * 16 * (255 - CEILING)
+        );
+    }
+
+    #[test]
+    fn glowstone_hangs_from_ceilings() {
+        let generator = NetherWorldGenerator::new(5);
+        let glowstone: usize = (0..8)
+            .map(|x| generator.generate_chunk(ChunkPosition::new(x, 0)))
+            .map(|chunk| count(&chunk, BlockId::glowstone(), LAVA_LEVEL..CEILING))
+            .sum();
+        assert!(glowstone > 0);
+    }
+}
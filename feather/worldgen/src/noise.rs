@@ -1,4 +1,514 @@
+use crate::router::SampledChunk;
+use crate::validate;
 use num_traits::ToPrimitive;
+use simdnoise::NoiseBuilder;
+use std::{future::Future, sync::Arc};
+
+/// Looks up a density modifier by absolute block X, Y, and Z.
+pub type DensitySampler = Arc<dyn Fn(i32, i32, i32) -> f32 + Send + Sync>;
+
+/// A 64-bit world seed in the form taken by simdnoise.
+///
+/// simdnoise only accepts 32-bit seeds, so the seed is mixed
+/// with splitmix64 and folded into 32 bits, and the remaining
+/// entropy is used to shift the sampled coordinates. Distinct
+/// 64-bit seeds therefore don't produce identical noise, even
+/// if they share their lower 32 bits.
+///
+/// The shift is a whole number of samples, so neighboring
+/// chunks sampled with the same seed still line up.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NoiseSeed {
+    seed: i32,
+    shift_x: f32,
+    shift_z: f32,
+}
+
+impl NoiseSeed {
+    pub fn new(seed: u64) -> Self {
+        let mixed = splitmix64(seed);
+        let shift = splitmix64(mixed);
+        Self {
+            seed: (mixed ^ (mixed >> 32)) as i32,
+            shift_x: (shift & 0xFFFF) as f32,
+            shift_z: ((shift >> 16) & 0xFFFF) as f32,
+        }
+    }
+
+    /// Returns the 32-bit seed for the `n`th noise
+    /// sampled by a generator.
+    pub fn get(&self, n: i32) -> i32 {
+        self.seed.wrapping_add(n)
+    }
+
+    /// Shifts sample coordinates along X and Z.
+    pub fn shift(&self, x: f32, z: f32) -> (f32, f32) {
+        (x + self.shift_x, z + self.shift_z)
+    }
+}
+
+/// The splitmix64 finalizer, which maps each
+/// 64-bit value to a well-distributed one.
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Wrapper over simdnoise's 3D gradient noise which only
+/// samples noise every `scale` blocks and applies linear
+/// interpolation (using `NoiseLerper`) in between.
+///
+/// The seed is stored in the struct, but can be
+/// overridden for a single call using `generate_with_seed`.
+#[derive(Debug, Clone)]
+pub struct Wrapped3DPerlinNoise {
+    /// The seed for the noise.
+    seed: u64,
+    /// The size of the chunk to generate along X and Z axes.
+    size_horizontal: u32,
+    /// The size of the chunk to generate along the Y axis.
+    size_vertical: u32,
+    /// The offset along the X axis to generate.
+    offset_x: i32,
+    /// The offset along the Z axis to generate.
+    offset_z: i32,
+    /// The scale along the X and Z axes. Must be a divisor of size_horizontal.
+    scale_horizontal: u32,
+    /// The scale along the Y axis. Must be a divisor of size_vertical.
+    scale_vertical: u32,
+    /// The frequency of the noise.
+    frequency: f32,
+    /// The amplitude of the noise. Values are within `[-amplitude, amplitude]`.
+    amplitude: f32,
+}
+
+impl Wrapped3DPerlinNoise {
+    /// Initializes with default settings and the given seed.
+    ///
+    /// Default settings are intended to match the size
+    /// of chunks. Horizontal and vertical size and scale
+    /// are initialized to sane defaults.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            size_horizontal: 16,
+            size_vertical: 256,
+            offset_x: 0,
+            offset_z: 0,
+            scale_horizontal: 4,
+            scale_vertical: 8,
+            frequency: 0.02,
+            amplitude: 400.0,
+        }
+    }
+
+    /// Sets the size of the chunk to be generated.
+    pub fn with_size(mut self, xz: u32, y: u32) -> Self {
+        self.size_horizontal = xz;
+        self.size_vertical = y;
+        self
+    }
+
+    /// Sets the X and Z offsets.
+    ///
+    /// # Notes
+    /// * The X and Z offsets are multiplied by the horizontal size
+    /// to obtain the offset in absolute coordinates.
+    /// (This means there is no need to multiply the chunk coordinate by 16.)
+    pub fn with_offset(mut self, x: i32, z: i32) -> Self {
+        self.offset_x = x;
+        self.offset_z = z;
+        self
+    }
+
+    /// Sets the scale of the noise. Linear interpolation
+    /// is used between values based on this scale.
+    pub fn with_scale(mut self, horizontal: u32, vertical: u32) -> Self {
+        self.scale_horizontal = horizontal;
+        self.scale_vertical = vertical;
+        self
+    }
+
+    /// Sets the frequency of the noise.
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Sets the amplitude of the noise.
+    pub fn with_amplitude(mut self, amplitude: f32) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    /// Returns the seed used by `generate`.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Generates a linear-interpolated block of noise
+    /// using the stored seed.
+    /// The returned vector will have length `size_horizontal^2 * size_vertical`,
+    /// indexable by `((y << 12) | z << 4) | x`.
+    pub fn generate(&self) -> Vec<f32> {
+        self.generate_with_seed(self.seed)
+    }
+
+    /// Generates a linear-interpolated block of noise
+    /// like `generate`, but using the provided seed
+    /// instead of the stored one.
+    ///
+    /// This allows sampling the same noise shape at a different
+    /// seed without cloning the builder; `self` is left unchanged.
+    pub fn generate_with_seed(&self, seed: u64) -> Vec<f32> {
+        let densities = self.uninterpolated_densities(seed);
+
+        NoiseLerper::new(&densities)
+            .with_size(self.size_horizontal, self.size_vertical)
+            .with_offset(self.offset_x, self.offset_z)
+            .with_scale(self.scale_horizontal, self.scale_vertical)
+            .generate()
+    }
+
+    /// Generates noise like `generate` and wraps it as a
+    /// density function for use in a `NoiseRouter`.
+    ///
+    /// Assumes the default chunk size, so that the offset
+    /// is the chunk position.
+    pub fn generate_density_function(&self) -> SampledChunk {
+        SampledChunk::new(self.generate(), self.offset_x, self.offset_z)
+    }
+
+    /// Generates noise like `generate`, but on tokio's
+    /// blocking thread pool so that the CPU-bound work
+    /// doesn't stall the async executor.
+    ///
+    /// Must be awaited within a tokio runtime.
+    pub fn generate_async(self) -> impl Future<Output = Vec<f32>> {
+        async move {
+            tokio::task::spawn_blocking(move || self.generate())
+                .await
+                .expect("noise generation task panicked")
+        }
+    }
+
+    /// Samples the raw noise at each subchunk corner, in the
+    /// layout expected by `NoiseLerper`.
+    fn uninterpolated_densities(&self, seed: u64) -> Vec<f32> {
+        let length = (self.size_horizontal / self.scale_horizontal + 1) as usize;
+        let height = (self.size_vertical / self.scale_vertical + 1) as usize;
+
+        let x_offset = (self.offset_x * (length as i32 - 1)) as f32;
+        let z_offset = (self.offset_z * (length as i32 - 1)) as f32;
+
+        let seed = NoiseSeed::new(seed);
+        let (x_offset, z_offset) = seed.shift(x_offset, z_offset);
+
+        NoiseBuilder::gradient_3d_offset(x_offset, length, 0.0, height, z_offset, length)
+            .with_seed(seed.get(0))
+            .with_freq(self.frequency)
+            .generate_scaled(-self.amplitude, self.amplitude)
+    }
+}
+
+/// Wrapper over simdnoise's 2D gradient noise which samples a
+/// coarse grid and bilinearly interpolates between the samples,
+/// like `Wrapped3DPerlinNoise` does in 3D.
+///
+/// Neighboring chunks share the samples along their common edge,
+/// so the noise is continuous across chunk borders. Suited to
+/// per-column values such as surface height, temperature,
+/// and humidity.
+#[derive(Debug, Clone)]
+pub struct Wrapped2DPerlinNoise {
+    /// The seed for the noise.
+    seed: u64,
+    /// The chunk offset along the X axis.
+    offset_x: i32,
+    /// The chunk offset along the Z axis.
+    offset_z: i32,
+    /// The distance between samples. Must be a divisor of 16.
+    scale: u32,
+    /// The frequency of the noise.
+    frequency: f32,
+    /// The amplitude of the noise. Values are within `[-amplitude, amplitude]`.
+    amplitude: f32,
+}
+
+impl Wrapped2DPerlinNoise {
+    /// Initializes with default settings and the given seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            offset_x: 0,
+            offset_z: 0,
+            scale: 4,
+            frequency: 0.02,
+            amplitude: 1.0,
+        }
+    }
+
+    /// Sets the chunk offsets along the X and Z axes.
+    pub fn with_offset(mut self, x: i32, z: i32) -> Self {
+        self.offset_x = x;
+        self.offset_z = z;
+        self
+    }
+
+    /// Sets the distance between samples.
+    ///
+    /// # Panics
+    /// Panics if `scale` is not a divisor of 16.
+    pub fn with_scale(mut self, scale: u32) -> Self {
+        assert!(scale > 0 && 16 % scale == 0, "scale must divide 16");
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the frequency of the noise.
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Sets the amplitude of the noise.
+    pub fn with_amplitude(mut self, amplitude: f32) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    /// Returns the seed used by `generate`.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Generates a 16x16 block of interpolated noise using the
+    /// stored seed, indexable by `(z << 4) | x`.
+    pub fn generate(&self) -> Vec<f32> {
+        self.generate_with_seed(self.seed)
+    }
+
+    /// Generates noise like `generate`, but using the
+    /// provided seed instead of the stored one.
+    pub fn generate_with_seed(&self, seed: u64) -> Vec<f32> {
+        let cells = (16 / self.scale) as usize;
+        let length = cells + 1;
+        let x_offset = (self.offset_x * cells as i32) as f32;
+        let z_offset = (self.offset_z * cells as i32) as f32;
+        let seed = NoiseSeed::new(seed);
+        let (x_offset, z_offset) = seed.shift(x_offset, z_offset);
+
+        let samples = NoiseBuilder::gradient_2d_offset(x_offset, length, z_offset, length)
+            .with_seed(seed.get(0))
+            .with_freq(self.frequency)
+            .generate_scaled(-self.amplitude, self.amplitude);
+        let sample = |x: usize, z: usize| samples[z * length + x];
+
+        let scale = self.scale as usize;
+        let mut buf = vec![0.0; 16 * 16];
+        for z in 0..16 {
+            let (cell_z, tz) = (z / scale, (z % scale) as f32 / scale as f32);
+            for x in 0..16 {
+                let (cell_x, tx) = (x / scale, (x % scale) as f32 / scale as f32);
+                let lower = lerp(sample(cell_x, cell_z), sample(cell_x + 1, cell_z), tx);
+                let upper = lerp(
+                    sample(cell_x, cell_z + 1),
+                    sample(cell_x + 1, cell_z + 1),
+                    tx,
+                );
+                buf[(z << 4) | x] = lerp(lower, upper, tz);
+            }
+        }
+        validate::enforce("2D noise", || validate::check_noise(&buf));
+        buf
+    }
+}
+
+/// Several octaves of `Wrapped3DPerlinNoise` summed together.
+///
+/// Each octave multiplies the previous octave's frequency by the
+/// lacunarity and its amplitude by the persistence, so higher
+/// octaves add progressively finer, weaker detail. Octaves use
+/// consecutive seeds starting at the base noise's seed.
+#[derive(Debug, Clone)]
+pub struct OctaveNoise {
+    /// The first octave. Its size, offset, and scale
+    /// are shared by all octaves.
+    base: Wrapped3DPerlinNoise,
+    /// The number of octaves.
+    octaves: u32,
+    /// Factor applied to the amplitude of each successive octave.
+    persistence: f32,
+    /// Factor applied to the frequency of each successive octave.
+    lacunarity: f32,
+}
+
+impl OctaveNoise {
+    /// Stacks octaves on top of `base`, initialized with
+    /// four octaves, a persistence of 0.5 and a lacunarity of 2.
+    pub fn new(base: Wrapped3DPerlinNoise) -> Self {
+        Self {
+            base,
+            octaves: 4,
+            persistence: 0.5,
+            lacunarity: 2.0,
+        }
+    }
+
+    /// Sets the number of octaves.
+    ///
+    /// # Panics
+    /// Panics if `octaves` is zero.
+    pub fn with_octaves(mut self, octaves: u32) -> Self {
+        assert!(octaves > 0, "at least one octave is required");
+        self.octaves = octaves;
+        self
+    }
+
+    /// Sets the factor applied to the amplitude of each successive octave.
+    pub fn with_persistence(mut self, persistence: f32) -> Self {
+        self.persistence = persistence;
+        self
+    }
+
+    /// Sets the factor applied to the frequency of each successive octave.
+    pub fn with_lacunarity(mut self, lacunarity: f32) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    /// Returns the largest possible absolute value of the summed
+    /// noise, useful for normalizing it.
+    pub fn max_amplitude(&self) -> f32 {
+        self.octave_parameters()
+            .map(|(_, amplitude)| amplitude.abs())
+            .sum()
+    }
+
+    /// Generates each octave and returns their sum,
+    /// in the same layout as `Wrapped3DPerlinNoise::generate`.
+    pub fn generate(&self) -> Vec<f32> {
+        let mut sum: Option<Vec<f32>> = None;
+        for (octave, (frequency, amplitude)) in self.octave_parameters().enumerate() {
+            let noise = self
+                .base
+                .clone()
+                .with_frequency(frequency)
+                .with_amplitude(amplitude)
+                .generate_with_seed(self.base.seed.wrapping_add(octave as u64));
+            match &mut sum {
+                Some(sum) => sum
+                    .iter_mut()
+                    .zip(&noise)
+                    .for_each(|(sum, value)| *sum += value),
+                None => sum = Some(noise),
+            }
+        }
+        sum.expect("at least one octave")
+    }
+
+    /// Returns the frequency and amplitude of each octave.
+    fn octave_parameters(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        (0..self.octaves).map(move |octave| {
+            (
+                self.base.frequency * self.lacunarity.powi(octave as i32),
+                self.base.amplitude * self.persistence.powi(octave as i32),
+            )
+        })
+    }
+}
+
+/// 2D fractal noise sampled once per column, used for
+/// per-column terrain parameters such as continentalness.
+#[derive(Debug, Clone)]
+pub struct ColumnNoise {
+    /// Added to the world seed so that different
+    /// column noises are uncorrelated.
+    seed_offset: i32,
+    /// The frequency of the noise.
+    frequency: f32,
+    /// The number of fractal octaves.
+    octaves: u8,
+    /// Factor applied to raw noise values.
+    amplitude: f32,
+}
+
+impl ColumnNoise {
+    /// Initializes with default settings and the given seed offset.
+    pub fn new(seed_offset: i32) -> Self {
+        Self {
+            seed_offset,
+            frequency: 0.005,
+            octaves: 3,
+            amplitude: 1.0,
+        }
+    }
+
+    /// Sets the frequency of the noise.
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Sets the number of fractal octaves.
+    pub fn with_octaves(mut self, octaves: u8) -> Self {
+        self.octaves = octaves;
+        self
+    }
+
+    /// Sets the factor applied to raw noise values.
+    pub fn with_amplitude(mut self, amplitude: f32) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    /// Samples a `width`x`width` grid of columns starting at
+    /// `(x_offset, z_offset)`, indexed by `(z * width) + x`.
+    pub fn generate(&self, seed: u64, x_offset: f32, z_offset: f32, width: usize) -> Vec<f32> {
+        let seed = NoiseSeed::new(seed);
+        let (x_offset, z_offset) = seed.shift(x_offset, z_offset);
+        let mut noise = NoiseBuilder::fbm_2d_offset(x_offset, width, z_offset, width)
+            .with_seed(seed.get(self.seed_offset))
+            .with_octaves(self.octaves)
+            .with_freq(self.frequency)
+            .generate()
+            .0;
+        noise.iter_mut().for_each(|value| *value *= self.amplitude);
+        noise
+    }
+
+    /// Samples a `period`x`period` grid of columns, indexed
+    /// by `(z * period) + x`, which tiles seamlessly when repeated.
+    ///
+    /// Each value is blended with the copies shifted by `period`
+    /// along X and Z, weighted by its distance to the tile edges.
+    /// This is intended for textures and previews; unlike `generate`,
+    /// the output does not line up with neighboring chunks.
+    pub fn generate_tileable(&self, seed: u64, period: u32) -> Vec<f32> {
+        let period = period as usize;
+        // Covers [-period, period) along both axes.
+        let width = period * 2;
+        let samples = self.generate(seed, -(period as f32), -(period as f32), width);
+        let sample = |x: usize, z: usize| samples[z * width + x];
+
+        let mut tile = Vec::with_capacity(period * period);
+        for z in 0..period {
+            let wz = z as f32 / period as f32;
+            for x in 0..period {
+                let wx = x as f32 / period as f32;
+                let (sx, sz) = (x + period, z + period);
+                let value = sample(sx, sz) * (1.0 - wx) * (1.0 - wz)
+                    + sample(x, sz) * wx * (1.0 - wz)
+                    + sample(sx, z) * (1.0 - wx) * wz
+                    + sample(x, z) * wx * wz;
+                tile.push(value);
+            }
+        }
+        tile
+    }
+}
 
 /// Struct for applying linear interpolation to a 3D
 /// density array.
@@ -17,6 +527,9 @@ pub struct NoiseLerper<'a> {
     scale_horizontal: u32,
     /// The scale along the Y axis. Must be a divisor of size_vertical.
     scale_vertical: u32,
+    /// Per-block density modifier added to each
+    /// density value before interpolation.
+    biome_density: Option<DensitySampler>,
 }
 
 impl<'a> NoiseLerper<'a> {
@@ -35,6 +548,7 @@ impl<'a> NoiseLerper<'a> {
             offset_z: 0,
             scale_horizontal: 4,
             scale_vertical: 8,
+            biome_density: None,
         }
     }
 
@@ -61,7 +575,17 @@ impl<'a> NoiseLerper<'a> {
     /// is used between values based on this scale.
     pub fn with_scale(mut self, horizontal: u32, vertical: u32) -> Self {
         self.scale_horizontal = horizontal;
-        self.size_vertical = vertical;
+        self.scale_vertical = vertical;
+        self
+    }
+
+    /// Sets a sampler whose value at each density point,
+    /// in absolute block coordinates, is added to the density
+    /// before interpolation.
+    ///
+    /// This allows cave shapes to vary by 3D biome.
+    pub fn with_3d_biome_density(mut self, sampler: DensitySampler) -> Self {
+        self.biome_density = Some(sampler);
         self
     }
 
@@ -69,24 +593,9 @@ impl<'a> NoiseLerper<'a> {
     /// The returned vector will have length `size_horizontal^2 * size_vertical`,
     /// indexable by `((y << 12) | z << 4) | x`.
     pub fn generate(&self) -> Vec<f32> {
-        // If AVX2 is available, use it. Otherwise,
-        // default to a scalar impl.
-        // TODO: support SSE41, other SIMD instruction sets
-
-        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-        {
-            if is_x86_feature_detected!("avx2") {
-                return self.generate_avx2();
-            }
-        }
-
-        self.generate_fallback()
-    }
-
-    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-    fn generate_avx2(&self) -> Vec<f32> {
-        // TODO: implement this. (Premature optimization is bad!)
-        self.generate_fallback()
+        let noise = self.generate_fallback();
+        validate::enforce("noise interpolation", || validate::check_noise(&noise));
+        noise
     }
 
     fn generate_fallback(&self) -> Vec<f32> {
@@ -103,7 +612,14 @@ impl<'a> NoiseLerper<'a> {
 
         // Density noise, with one value every `scale` blocks along each axis.
         // Indexing into this vector is done using `self.uninterpolated_index(x, y, z)`.
-        let densities = self.densities;
+        let modified_densities;
+        let densities = match &self.biome_density {
+            Some(sampler) => {
+                modified_densities = self.apply_biome_density(sampler);
+                &modified_densities
+            }
+            None => self.densities,
+        };
 
         // Buffer to emit final noise into.
         // TODO: consider using Vec::set_len to avoid zeroing it out
@@ -189,6 +705,28 @@ impl<'a> NoiseLerper<'a> {
         buf
     }
 
+    /// Returns the density values with `sampler` added to each.
+    fn apply_biome_density(&self, sampler: &DensitySampler) -> Vec<f32> {
+        let length = self.size_horizontal / self.scale_horizontal + 1;
+        let height = self.size_vertical / self.scale_vertical + 1;
+
+        let origin_x = self.offset_x * self.size_horizontal as i32;
+        let origin_z = self.offset_z * self.size_horizontal as i32;
+
+        let mut densities = self.densities.to_vec();
+        for x in 0..length {
+            for y in 0..height {
+                for z in 0..length {
+                    let abs_x = origin_x + (x * self.scale_horizontal) as i32;
+                    let abs_y = (y * self.scale_vertical) as i32;
+                    let abs_z = origin_z + (z * self.scale_horizontal) as i32;
+                    densities[self.uninterpolated_index(x, y, z)] += sampler(abs_x, abs_y, abs_z);
+                }
+            }
+        }
+        densities
+    }
+
     fn uninterpolated_index<N: ToPrimitive>(&self, x: N, y: N, z: N) -> usize {
         let length = (self.size_horizontal / self.scale_horizontal + 1) as usize;
         let height = (self.size_vertical / self.scale_vertical + 1) as usize;
@@ -201,6 +739,11 @@ impl<'a> NoiseLerper<'a> {
     }
 }
 
+#[inline(always)]
+fn lerp(from: f32, to: f32, weight: f32) -> f32 {
+    from + (to - from) * weight
+}
+
 pub fn index<N: ToPrimitive>(x: N, y: N, z: N) -> usize {
     let x = x.to_usize().unwrap();
     let y = y.to_usize().unwrap();
@@ -209,10 +752,89 @@ pub fn index<N: ToPrimitive>(x: N, y: N, z: N) -> usize {
     ((y << 8) | z << 4) | x
 }
 
+/// Returns the surface height of each column in a chunk of
+/// interpolated densities (as returned by `NoiseLerper`), indexed
+/// by `(z << 4) | x`.
+///
+/// The surface height is one above the highest solid (negative)
+/// density value, or zero if the column has none.
+pub fn surface_heights(density: &[f32]) -> Vec<f32> {
+    let mut heights = vec![0.0; 16 * 16];
+    for x in 0..16 {
+        for z in 0..16 {
+            if let Some(y) = (0..256).rev().find(|&y| density[index(x, y, z)] < 0.0) {
+                heights[(z << 4) | x] = (y + 1) as f32;
+            }
+        }
+    }
+    heights
+}
+
+/// Returns the terrain slope of each column in a chunk of
+/// interpolated densities, indexed by `(z << 4) | x`.
+///
+/// The slope is the magnitude of the surface height gradient
+/// in blocks per block, so flat terrain reads zero. Central
+/// differences are used, falling back to one-sided differences
+/// for columns at the chunk edges.
+pub fn slope_map(density: &[f32]) -> Vec<f32> {
+    let heights = surface_heights(density);
+    let height = |x: usize, z: usize| heights[(z << 4) | x];
+
+    // Difference along one axis at `i`, clamped to the chunk.
+    let derivative = |i: usize, sample: &dyn Fn(usize) -> f32| {
+        let lo = i.saturating_sub(1);
+        let hi = (i + 1).min(15);
+        (sample(hi) - sample(lo)) / (hi - lo) as f32
+    };
+
+    let mut slopes = vec![0.0; 16 * 16];
+    for x in 0..16 {
+        for z in 0..16 {
+            let dx = derivative(x, &|x| height(x, z));
+            let dz = derivative(z, &|z| height(x, z));
+            slopes[(z << 4) | x] = (dx * dx + dz * dz).sqrt();
+        }
+    }
+    slopes
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn upper_seed_bits_change_noise() {
+        let low = 0x1234_5678;
+        let high = (1 << 32) | low;
+        assert_ne!(NoiseSeed::new(low), NoiseSeed::new(high));
+        assert_eq!(NoiseSeed::new(high), NoiseSeed::new(high));
+
+        assert_ne!(
+            Wrapped3DPerlinNoise::new(low).generate(),
+            Wrapped3DPerlinNoise::new(high).generate()
+        );
+        assert_ne!(
+            Wrapped2DPerlinNoise::new(low).generate(),
+            Wrapped2DPerlinNoise::new(high).generate()
+        );
+        assert_ne!(
+            ColumnNoise::new(0).generate(low, 0.0, 0.0, 16),
+            ColumnNoise::new(0).generate(high, 0.0, 0.0, 16)
+        );
+    }
+
+    #[test]
+    fn shifted_noise_lines_up_across_chunks() {
+        // Grids starting at different offsets share their overlapping samples.
+        let seed = u64::MAX - 7;
+        let noise = ColumnNoise::new(0);
+        let wide = noise.generate(seed, 16.0, 32.0, 4);
+        let narrow = noise.generate(seed, 18.0, 32.0, 2);
+        approx::assert_relative_eq!(wide[2], narrow[0]);
+        approx::assert_relative_eq!(wide[3], narrow[1]);
+    }
+
     #[test]
     fn basic_test() {
         let densities = [0.0; 5 * 33 * 5];
@@ -226,4 +848,242 @@ mod tests {
             approx::assert_relative_eq!(x, 0.0);
         }
     }
+
+    /// Builds an uninterpolated density grid for the default
+    /// `NoiseLerper` settings by evaluating `f` at each subchunk
+    /// corner (in block coordinates).
+    fn density_fixture(f: impl Fn(f32, f32, f32) -> f32) -> Vec<f32> {
+        let lerper = NoiseLerper::new(&[]);
+        let length = 16 / 4 + 1;
+        let height = 256 / 8 + 1;
+
+        let mut densities = vec![0.0; length * height * length];
+        for x in 0..length {
+            for y in 0..height {
+                for z in 0..length {
+                    densities[lerper.uninterpolated_index(x, y, z)] =
+                        f((x * 4) as f32, (y * 8) as f32, (z * 4) as f32);
+                }
+            }
+        }
+        densities
+    }
+
+    #[test]
+    fn interpolation_matches_trilinear() {
+        // Trilinear interpolation reproduces any function of
+        // the form a + bx + cy + dz + exy + fxz + gyz + hxyz exactly.
+        let f = |x: f32, y: f32, z: f32| 1.0 + x - 0.5 * z + 0.25 * x * z + x * y * z / 64.0;
+        let densities = density_fixture(f);
+
+        let chunk = NoiseLerper::new(&densities).generate();
+
+        for &(x, y, z) in &[
+            (0, 0, 0),
+            (1, 1, 1),
+            (2, 5, 3),
+            (3, 7, 2),
+            (6, 100, 9),
+            (13, 200, 14),
+            (15, 255, 15),
+        ] {
+            approx::assert_relative_eq!(
+                chunk[index(x, y, z)],
+                f(x as f32, y as f32, z as f32),
+                max_relative = 1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn interpolation_between_constant_corners() {
+        // Bottom corners are 0 and top corners are 8, so the
+        // value increases by 1 per block along Y inside a subchunk.
+        let densities = density_fixture(|_, y, _| if (y as u32 / 8) % 2 == 0 { 0.0 } else { 8.0 });
+
+        let chunk = NoiseLerper::new(&densities).generate();
+
+        for y in 0..8 {
+            approx::assert_relative_eq!(chunk[index(2, y, 1)], y as f32);
+            approx::assert_relative_eq!(chunk[index(2, y + 8, 1)], 8.0 - y as f32);
+        }
+    }
+
+    #[test]
+    fn noise_2d_is_continuous_across_chunks() {
+        let noise = Wrapped2DPerlinNoise::new(17)
+            .with_frequency(0.05)
+            .with_amplitude(10.0);
+        let west = noise.clone().with_offset(-1, 3).generate();
+        let east = noise.with_offset(0, 3).generate();
+        assert_eq!(west.len(), 16 * 16);
+
+        for z in 0..16 {
+            // Extrapolate the last cell of the western chunk
+            // to its edge, which is shared with the eastern chunk.
+            let (start, end) = (west[(z << 4) | 12], west[(z << 4) | 15]);
+            let edge = start + (end - start) * 4.0 / 3.0;
+            approx::assert_abs_diff_eq!(edge, east[z << 4], epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn single_octave_matches_base_noise() {
+        let base = Wrapped3DPerlinNoise::new(4).with_offset(1, 2);
+        let octaves = OctaveNoise::new(base.clone()).with_octaves(1);
+        assert_eq!(octaves.generate(), base.generate());
+    }
+
+    #[test]
+    fn octaves_are_summed() {
+        let base = Wrapped3DPerlinNoise::new(4)
+            .with_frequency(0.01)
+            .with_amplitude(2.0);
+        let octaves = OctaveNoise::new(base.clone())
+            .with_octaves(3)
+            .with_persistence(0.5)
+            .with_lacunarity(3.0);
+        approx::assert_relative_eq!(octaves.max_amplitude(), 2.0 + 1.0 + 0.5);
+
+        let second = base
+            .clone()
+            .with_frequency(0.03)
+            .with_amplitude(1.0)
+            .generate_with_seed(5);
+        let third = base
+            .clone()
+            .with_frequency(0.09)
+            .with_amplitude(0.5)
+            .generate_with_seed(6);
+        let expected = base.generate();
+        for (i, value) in octaves.generate().into_iter().enumerate() {
+            approx::assert_abs_diff_eq!(value, expected[i] + second[i] + third[i], epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn generate_with_seed_leaves_seed_untouched() {
+        let noise = Wrapped3DPerlinNoise::new(10).with_offset(3, -2);
+
+        let stored = noise.generate();
+        let reseeded = noise.generate_with_seed(11);
+
+        assert_eq!(noise.seed(), 10);
+        assert_eq!(stored.len(), reseeded.len());
+        assert_ne!(stored, reseeded);
+        assert_eq!(stored, noise.generate());
+    }
+
+    #[test]
+    fn with_scale_keeps_size() {
+        // One density per corner of 8x16x8 cells.
+        let densities = [1.0; 3 * 17 * 3];
+        let lerper = NoiseLerper::new(&densities).with_scale(8, 16);
+        assert_eq!(lerper.scale_horizontal, 8);
+        assert_eq!(lerper.scale_vertical, 16);
+        assert_eq!(lerper.size_vertical, 256);
+
+        let chunk = lerper.generate();
+        assert_eq!(chunk.len(), 16 * 256 * 16);
+        assert!(chunk.iter().all(|&density| (density - 1.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn biome_density_applies_to_region() {
+        let densities = [0.0; 5 * 33 * 5];
+        // Make a region of the chunk at (1, 0) solid
+        // (negative density), in absolute block coordinates.
+        let sampler: DensitySampler =
+            Arc::new(|x, y, _| if x < 24 && y < 64 { -100.0 } else { 0.0 });
+
+        let chunk = NoiseLerper::new(&densities)
+            .with_offset(1, 0)
+            .with_3d_biome_density(sampler)
+            .generate();
+
+        let solid = |x: usize, y: usize| (0..16).all(|z| chunk[index(x, y, z)] < 0.0);
+        for y in 0..56 {
+            assert!((0..4).all(|x| solid(x, y)));
+        }
+        for y in 72..256 {
+            assert!((0..16).all(|x| !solid(x, y)));
+        }
+        assert!((12..16).all(|x| !solid(x, 200)));
+    }
+
+    #[test]
+    fn slope_of_ramp_and_flat_terrain() {
+        let terrain = |surface: &dyn Fn(usize, usize) -> usize| {
+            let mut density = vec![1.0; 16 * 256 * 16];
+            for x in 0..16 {
+                for z in 0..16 {
+                    for y in 0..surface(x, z) {
+                        density[index(x, y, z)] = -1.0;
+                    }
+                }
+            }
+            density
+        };
+
+        // Rises two blocks per block along X.
+        let ramp = slope_map(&terrain(&|x, _| 64 + 2 * x));
+        for &slope in &ramp {
+            approx::assert_relative_eq!(slope, 2.0);
+        }
+
+        let flat = slope_map(&terrain(&|_, _| 64));
+        for &slope in &flat {
+            approx::assert_abs_diff_eq!(slope, 0.0);
+        }
+    }
+
+    /// Returns the largest difference between neighboring values
+    /// of a `period`x`period` grid, and the largest difference
+    /// between values on opposite edges.
+    fn neighbor_and_seam_differences(grid: &[f32], period: usize) -> (f32, f32) {
+        let at = |x: usize, z: usize| grid[z * period + x];
+        let mut neighbor = 0.0f32;
+        let mut seam = 0.0f32;
+        for i in 0..period {
+            for j in 1..period {
+                neighbor = neighbor
+                    .max((at(j, i) - at(j - 1, i)).abs())
+                    .max((at(i, j) - at(i, j - 1)).abs());
+            }
+            seam = seam
+                .max((at(0, i) - at(period - 1, i)).abs())
+                .max((at(i, 0) - at(i, period - 1)).abs());
+        }
+        (neighbor, seam)
+    }
+
+    #[test]
+    fn tileable_noise_wraps_at_edges() {
+        let period = 32;
+        let noise = ColumnNoise::new(0)
+            .with_frequency(0.05)
+            .with_octaves(1)
+            .with_amplitude(100.0);
+        let tile = noise.generate_tileable(7, period as u32);
+        assert_eq!(tile.len(), period * period);
+
+        // Across the seam, values should differ about
+        // as much as neighbors within the tile.
+        let (neighbor, seam) = neighbor_and_seam_differences(&tile, period);
+        assert!(neighbor > 1.0, "noise is too flat: {}", neighbor);
+        assert!(seam <= 2.0 * neighbor, "seam {} > 2 * {}", seam, neighbor);
+
+        // Plain noise doesn't wrap, so the same check must fail.
+        let plain = noise.generate(7, 0.0, 0.0, period);
+        let (neighbor, seam) = neighbor_and_seam_differences(&plain, period);
+        assert!(seam > 2.0 * neighbor, "seam {} <= 2 * {}", seam, neighbor);
+    }
+
+    #[tokio::test]
+    async fn generate_async_matches_generate() {
+        let noise = Wrapped3DPerlinNoise::new(5).with_offset(-1, 7);
+
+        let expected = noise.generate();
+        assert_eq!(noise.generate_async().await, expected);
+    }
 }
@@ -0,0 +1,152 @@
+//! Surface painting: turns bare stone terrain into
+//! biome-appropriate soil above a bedrock floor.
+//!
+//! `BasicCompositionGenerator` paints each column
+//! after filling it with stone and water.
+
+use crate::composition::{top_soil_block, underneath_top_soil_block};
+use crate::ChunkBiomes;
+use base::{Biome, BlockId, Chunk};
+use rand::Rng;
+
+/// Number of stone blocks below the surface replaced with soil.
+const SOIL_DEPTH: usize = 4;
+/// Highest Y coordinate which may be bedrock.
+const BEDROCK_HEIGHT: usize = 4;
+
+/// Replaces the top layers of stone in each column of `chunk`
+/// with the biome's soil and generates a noisy bedrock floor.
+///
+/// See [`paint_column`] for details.
+pub fn paint_surface(chunk: &mut Chunk, biomes: &ChunkBiomes, rng: &mut impl Rng) {
+    for x in 0..16 {
+        for z in 0..16 {
+            paint_column(chunk, x, z, biomes.biome_at(x, z), rng);
+        }
+    }
+}
+
+/// Replaces the top layers of stone in a column with
+/// the biome's soil and generates a noisy bedrock floor.
+///
+/// Every stone block directly below air, water or ice starts a
+/// new surface, so overhangs and cave floors are painted too.
+/// Surfaces under water are covered with gravel, except in sandy
+/// biomes. Bedrock always covers `y = 0` and becomes sparser
+/// up to `y = 4`.
+pub(crate) fn paint_column(
+    chunk: &mut Chunk,
+    x: usize,
+    z: usize,
+    biome: Biome,
+    rng: &mut impl Rng,
+) {
+    // Soil blocks remaining below the current surface.
+    let mut remaining = 0;
+    let mut underwater = false;
+    for y in (0..256).rev() {
+        let block = chunk.block_at(x, y, z).unwrap();
+        if block.is_air() || block.is_fluid() || block == BlockId::ice() {
+            remaining = SOIL_DEPTH;
+            underwater = !block.is_air();
+            continue;
+        }
+        if block != BlockId::stone() || remaining == 0 {
+            remaining = 0;
+            continue;
+        }
+
+        let top = remaining == SOIL_DEPTH;
+        let soil = if underwater && !is_sandy(biome) {
+            BlockId::gravel()
+        } else if top {
+            top_soil_block(biome)
+        } else {
+            underneath_top_soil_block(biome)
+        };
+        chunk.set_block_at(x, y, z, soil);
+        remaining -= 1;
+    }
+
+    for y in 0..=BEDROCK_HEIGHT {
+        if y <= rng.gen_range(0, BEDROCK_HEIGHT + 1) {
+            chunk.set_block_at(x, y, z, BlockId::bedrock());
+        }
+    }
+}
+
+fn is_sandy(biome: Biome) -> bool {
+    top_soil_block(biome) == BlockId::sand() || top_soil_block(biome) == BlockId::red_sand()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::ChunkPosition;
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    fn terrain(height: usize, water: usize) -> Chunk {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..=height {
+                    chunk.set_block_at(x, y, z, BlockId::stone());
+                }
+                for y in height + 1..=water {
+                    chunk.set_block_at(x, y, z, BlockId::water());
+                }
+            }
+        }
+        chunk
+    }
+
+    fn paint(chunk: &mut Chunk, biome: Biome) {
+        let mut rng = XorShiftRng::seed_from_u64(31);
+        paint_surface(chunk, &ChunkBiomes::from_array([biome; 256]), &mut rng);
+    }
+
+    #[test]
+    fn paints_biome_soil() {
+        let mut chunk = terrain(70, 0);
+        paint(&mut chunk, Biome::Desert);
+
+        assert_eq!(chunk.block_at(3, 70, 5), Some(BlockId::sand()));
+        for y in 67..70 {
+            assert_eq!(chunk.block_at(3, y, 5), Some(BlockId::sandstone()));
+        }
+        assert_eq!(chunk.block_at(3, 66, 5), Some(BlockId::stone()));
+    }
+
+    #[test]
+    fn paints_gravel_under_water() {
+        let mut chunk = terrain(50, 63);
+        paint(&mut chunk, Biome::Plains);
+
+        for y in 47..=50 {
+            assert_eq!(chunk.block_at(8, y, 8), Some(BlockId::gravel()));
+        }
+        assert_eq!(chunk.block_at(8, 46, 8), Some(BlockId::stone()));
+        assert_eq!(chunk.block_at(8, 51, 8), Some(BlockId::water()));
+    }
+
+    #[test]
+    fn bedrock_floor() {
+        let mut chunk = terrain(70, 0);
+        paint(&mut chunk, Biome::Plains);
+
+        let mut bedrock = [0; BEDROCK_HEIGHT + 2];
+        for x in 0..16 {
+            for z in 0..16 {
+                for (y, count) in bedrock.iter_mut().enumerate() {
+                    if chunk.block_at(x, y, z) == Some(BlockId::bedrock()) {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+        assert_eq!(bedrock[0], 256);
+        assert!(bedrock[1] > bedrock[BEDROCK_HEIGHT]);
+        assert_eq!(bedrock[BEDROCK_HEIGHT + 1], 0);
+    }
+}
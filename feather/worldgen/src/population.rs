@@ -0,0 +1,427 @@
+//! Population of chunks with features which cross chunk
+//! borders, such as trees.
+//!
+//! A chunk is populated once it and its eight neighbors have been
+//! generated, and its populators may place blocks anywhere within
+//! that 3x3 region. A chunk is finished once it and its eight
+//! neighbors have been populated: any population which could reach
+//! it has then run, so it can be handed out and never touched again.
+
+use crate::{GenerationStage, WorldGenerator};
+use base::{Biome, BlockId, Chunk, ChunkPosition};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+/// A stage, run after all chunks around a chunk have been
+/// generated, which decorates it with features that may
+/// extend into the neighboring chunks.
+pub trait Populator: Send + Sync {
+    /// Populates the center chunk of `region`. Blocks may
+    /// also be placed in the neighboring chunks.
+    fn populate(&self, region: &mut ChunkRegion, seed: u64);
+
+    /// Returns the stage whose seed is passed to this populator.
+    fn stage(&self) -> GenerationStage {
+        GenerationStage::Decoration
+    }
+}
+
+/// A 3x3 grid of chunks, centered on the chunk being populated.
+///
+/// Block coordinates are relative to the lowest corner of the
+/// center chunk, so `x` and `z` range from -16 to 31.
+#[derive(Debug)]
+pub struct ChunkRegion {
+    /// The chunks in the region. The chunk at a given offset from
+    /// the center can be obtained using (x + 1) + (z + 1) * 3.
+    chunks: Vec<Chunk>,
+}
+
+impl ChunkRegion {
+    /// Creates a region from nine chunks, ordered like `NearbyBiomes`.
+    ///
+    /// # Panics
+    /// Panics if the chunks are not a 3x3 grid in that order.
+    pub fn new(chunks: Vec<Chunk>) -> Self {
+        assert_eq!(chunks.len(), 9);
+        let center = chunks[4].position();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let expected =
+                ChunkPosition::new(center.x + i as i32 % 3 - 1, center.z + i as i32 / 3 - 1);
+            assert_eq!(chunk.position(), expected);
+        }
+        Self { chunks }
+    }
+
+    /// Returns the position of the center chunk.
+    pub fn position(&self) -> ChunkPosition {
+        self.chunks[4].position()
+    }
+
+    /// Returns the chunk at the given offset from the center,
+    /// with both offsets in `-1..=1`.
+    pub fn chunk(&self, offset_x: i32, offset_z: i32) -> &Chunk {
+        &self.chunks[((offset_x + 1) + (offset_z + 1) * 3) as usize]
+    }
+
+    /// Gets the block at the given position, or `None`
+    /// if it is outside the region.
+    pub fn block_at(&self, x: i32, y: i32, z: i32) -> Option<BlockId> {
+        let (index, x, y, z) = Self::index(x, y, z)?;
+        self.chunks[index].block_at(x, y, z)
+    }
+
+    /// Sets the block at the given position. Returns `false`
+    /// if it is outside the region.
+    pub fn set_block_at(&mut self, x: i32, y: i32, z: i32, block: BlockId) -> bool {
+        match Self::index(x, y, z) {
+            Some((index, x, y, z)) => self.chunks[index].set_block_at(x, y, z, block).is_some(),
+            None => false,
+        }
+    }
+
+    /// Gets the biome of the given column, or `None`
+    /// if it is outside the region.
+    pub fn biome_at(&self, x: i32, z: i32) -> Option<Biome> {
+        let (index, x, _, z) = Self::index(x, 0, z)?;
+        Some(self.chunks[index].biomes().get(x / 4, 0, z / 4))
+    }
+
+    /// Returns the Y coordinate of the highest solid
+    /// block in the given column, if any.
+    pub fn top_solid_block(&self, x: i32, z: i32) -> Option<i32> {
+        (0..256)
+            .rev()
+            .find(|&y| self.block_at(x, y, z).map_or(false, BlockId::is_solid))
+    }
+
+    /// Consumes the region, returning its chunks.
+    pub fn into_chunks(self) -> Vec<Chunk> {
+        self.chunks
+    }
+
+    fn index(x: i32, y: i32, z: i32) -> Option<(usize, usize, usize, usize)> {
+        if !(-16..32).contains(&x) || !(0..256).contains(&y) || !(-16..32).contains(&z) {
+            return None;
+        }
+        let chunk = (x.div_euclid(16) + 1) + (z.div_euclid(16) + 1) * 3;
+        Some((
+            chunk as usize,
+            x.rem_euclid(16) as usize,
+            y as usize,
+            z.rem_euclid(16) as usize,
+        ))
+    }
+}
+
+/// Number of finished chunks kept by a `PopulationScheduler`,
+/// so that requesting them again doesn't require rebuilding them.
+const FINISHED_CHUNKS: usize = 256;
+
+/// Generates chunks using a `WorldGenerator`, deferring the
+/// population of each chunk until its neighbors have been generated.
+///
+/// Generated chunks are kept until they are finished, so chunks
+/// bordering the generated area stay in memory, ready for when their
+/// neighbors are requested. Finished chunks are then kept in a small
+/// cache whether or not they were requested, and a chunk's population
+/// is forgotten once every chunk it could reach is finished.
+pub struct PopulationScheduler {
+    generator: Arc<dyn WorldGenerator>,
+    /// Generated chunks which have not been finished yet.
+    chunks: HashMap<ChunkPosition, Chunk>,
+    /// The most recently finished chunks, oldest first.
+    finished: VecDeque<Chunk>,
+    /// Chunks which have been populated, and whose
+    /// regions have not all been finished yet.
+    populated: HashSet<ChunkPosition>,
+}
+
+impl PopulationScheduler {
+    pub fn new(generator: Arc<dyn WorldGenerator>) -> Self {
+        Self {
+            generator,
+            chunks: HashMap::new(),
+            finished: VecDeque::new(),
+            populated: HashSet::new(),
+        }
+    }
+
+    /// Returns whether the given chunk has been generated
+    /// and is waiting to be populated or finished.
+    pub fn is_generated(&self, pos: ChunkPosition) -> bool {
+        self.chunks.contains_key(&pos)
+    }
+
+    /// Returns whether the given chunk has been populated,
+    /// and some chunk in its region is not finished yet.
+    pub fn is_populated(&self, pos: ChunkPosition) -> bool {
+        self.populated.contains(&pos)
+    }
+
+    /// Returns whether the given chunk has been finished
+    /// and is still cached.
+    pub fn is_finished(&self, pos: ChunkPosition) -> bool {
+        self.finished_chunk(pos).is_some()
+    }
+
+    /// Returns the chunks which still need to be generated
+    /// before the chunk at the given position can be finished.
+    ///
+    /// They may be generated elsewhere, e.g. on another thread,
+    /// and passed to `insert_generated`.
+    pub fn missing_chunks(&self, pos: ChunkPosition) -> Vec<ChunkPosition> {
+        let mut missing = Vec::new();
+        if self.is_finished(pos) || self.was_evicted(pos) {
+            return missing;
+        }
+        for neighbor in region_around(pos).filter(|&neighbor| !self.is_populated(neighbor)) {
+            for required in region_around(neighbor) {
+                if !self.is_generated(required) && !missing.contains(&required) {
+                    missing.push(required);
+                }
+            }
+        }
+        missing
+    }
+
+    /// Adds a chunk generated by `WorldGenerator::generate_chunk`.
+    /// Ignored if the chunk was already generated.
+    pub fn insert_generated(&mut self, chunk: Chunk) {
+        let pos = chunk.position();
+        if !self.is_generated(pos) && !self.is_finished(pos) && !self.was_evicted(pos) {
+            self.chunks.insert(pos, chunk);
+        }
+    }
+
+    /// Generates the chunk at the given position, along with any
+    /// neighbors needed to populate it, and returns it once finished.
+    pub fn generate_chunk(&mut self, pos: ChunkPosition) -> Chunk {
+        if let Some(chunk) = self.finished_chunk(pos) {
+            return chunk.clone();
+        }
+        if self.was_evicted(pos) {
+            // The chunk was finished but is no longer cached, and
+            // populating it again would reach into finished chunks.
+            // Rebuild it from scratch.
+            return Self::new(Arc::clone(&self.generator)).generate_chunk(pos);
+        }
+
+        for neighbor in region_around(pos) {
+            self.populate(neighbor);
+        }
+
+        self.finished_chunk(pos)
+            .expect("populated chunk was not finished")
+            .clone()
+    }
+
+    fn finished_chunk(&self, pos: ChunkPosition) -> Option<&Chunk> {
+        self.finished.iter().find(|chunk| chunk.position() == pos)
+    }
+
+    /// Returns whether the given chunk was finished and then
+    /// evicted from the cache while some population which
+    /// reached it is still remembered.
+    fn was_evicted(&self, pos: ChunkPosition) -> bool {
+        !self.is_generated(pos)
+            && !self.is_finished(pos)
+            && region_around(pos).any(|neighbor| self.is_populated(neighbor))
+    }
+
+    fn populate(&mut self, pos: ChunkPosition) {
+        if self.is_populated(pos) {
+            return;
+        }
+
+        let mut chunks = Vec::with_capacity(9);
+        for neighbor in region_around(pos) {
+            let chunk = match self.chunks.remove(&neighbor) {
+                Some(chunk) => chunk,
+                None => self.generator.generate_chunk(neighbor),
+            };
+            chunks.push(chunk);
+        }
+
+        let mut region = ChunkRegion::new(chunks);
+        self.generator.populate(&mut region);
+        for chunk in region.into_chunks() {
+            self.chunks.insert(chunk.position(), chunk);
+        }
+        self.populated.insert(pos);
+
+        for neighbor in region_around(pos) {
+            if region_around(neighbor).all(|pos| self.is_populated(pos)) {
+                self.finish(neighbor);
+            }
+        }
+    }
+
+    /// Moves a chunk whose whole region has been populated to the
+    /// cache of finished chunks, and forgets the populations
+    /// whose regions are now all finished.
+    fn finish(&mut self, pos: ChunkPosition) {
+        let mut chunk = match self.chunks.remove(&pos) {
+            Some(chunk) => chunk,
+            None => return,
+        };
+        chunk.recalculate_heightmaps();
+        // Carving and decoration leave unused entries in the palettes.
+        chunk.optimize();
+        self.finished.push_back(chunk);
+        if self.finished.len() > FINISHED_CHUNKS {
+            self.finished.pop_front();
+        }
+
+        for neighbor in region_around(pos) {
+            if region_around(neighbor).all(|pos| !self.is_generated(pos)) {
+                self.populated.remove(&neighbor);
+            }
+        }
+    }
+}
+
+/// Returns the positions of the 3x3 region of chunks
+/// around `pos`, in the order expected by `ChunkRegion`.
+fn region_around(pos: ChunkPosition) -> impl Iterator<Item = ChunkPosition> {
+    (-1..=1).flat_map(move |z| (-1..=1).map(move |x| ChunkPosition::new(pos.x + x, pos.z + z)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EmptyWorldGenerator;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Generates empty chunks, and populates each chunk by
+    /// placing stone in the corner of its western neighbor.
+    struct MarkerGenerator;
+
+    impl WorldGenerator for MarkerGenerator {
+        fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+            EmptyWorldGenerator {}.generate_chunk(position)
+        }
+
+        fn populate(&self, region: &mut ChunkRegion) {
+            assert!(region.set_block_at(-1, 0, 0, BlockId::stone()));
+        }
+    }
+
+    #[test]
+    fn population_waits_for_neighbors() {
+        let mut scheduler = PopulationScheduler::new(Arc::new(MarkerGenerator));
+        let chunk = scheduler.generate_chunk(ChunkPosition::new(0, 0));
+
+        // Placed by the population of the eastern neighbor.
+        assert_eq!(chunk.block_at(15, 0, 0), Some(BlockId::stone()));
+
+        assert!(scheduler.is_populated(ChunkPosition::new(1, 1)));
+        assert!(!scheduler.is_populated(ChunkPosition::new(2, 0)));
+        assert!(scheduler.is_generated(ChunkPosition::new(2, 0)));
+        assert!(!scheduler.is_generated(ChunkPosition::new(3, 0)));
+
+        // The neighbor's own marker is only placed once
+        // the chunk to its west has been generated.
+        let east = scheduler.generate_chunk(ChunkPosition::new(1, 0));
+        assert_eq!(east.block_at(15, 0, 0), Some(BlockId::stone()));
+    }
+
+    #[test]
+    fn chunks_generated_elsewhere_are_used() {
+        let generator = Arc::new(MarkerGenerator);
+        let mut scheduler = PopulationScheduler::new(generator.clone());
+        let pos = ChunkPosition::new(-4, 2);
+
+        let missing = scheduler.missing_chunks(pos);
+        assert_eq!(missing.len(), 25);
+        for &required in &missing {
+            scheduler.insert_generated(generator.generate_chunk(required));
+        }
+        assert!(scheduler.missing_chunks(pos).is_empty());
+
+        let chunk = scheduler.generate_chunk(pos);
+        assert_eq!(chunk.block_at(15, 0, 0), Some(BlockId::stone()));
+        assert_eq!(scheduler.missing_chunks(ChunkPosition::new(-3, 2)).len(), 5);
+    }
+
+    /// Counts the chunks generated by a `MarkerGenerator`.
+    #[derive(Default)]
+    struct CountingGenerator {
+        generated: AtomicUsize,
+    }
+
+    impl WorldGenerator for CountingGenerator {
+        fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+            self.generated.fetch_add(1, Ordering::Relaxed);
+            MarkerGenerator.generate_chunk(position)
+        }
+
+        fn populate(&self, region: &mut ChunkRegion) {
+            MarkerGenerator.populate(region)
+        }
+    }
+
+    #[test]
+    fn finished_chunks_are_cached() {
+        let generator = Arc::new(CountingGenerator::default());
+        let mut scheduler = PopulationScheduler::new(generator.clone());
+        scheduler.generate_chunk(ChunkPosition::new(0, 0));
+        assert_eq!(generator.generated.load(Ordering::Relaxed), 25);
+
+        let chunk = scheduler.generate_chunk(ChunkPosition::new(0, 0));
+        assert_eq!(chunk.block_at(15, 0, 0), Some(BlockId::stone()));
+        assert_eq!(generator.generated.load(Ordering::Relaxed), 25);
+        assert!(scheduler
+            .missing_chunks(ChunkPosition::new(0, 0))
+            .is_empty());
+    }
+
+    #[test]
+    fn finished_chunks_are_evicted() {
+        let mut scheduler = PopulationScheduler::new(Arc::new(MarkerGenerator));
+        for x in 0..3 {
+            for z in 0..3 {
+                scheduler.generate_chunk(ChunkPosition::new(x, z));
+            }
+        }
+
+        // Neighbors which were finished without being requested are cached too.
+        assert!(scheduler.is_finished(ChunkPosition::new(1, 1)));
+        assert!(!scheduler.is_generated(ChunkPosition::new(1, 1)));
+        // Every chunk the center's population reached is finished.
+        assert!(!scheduler.is_populated(ChunkPosition::new(1, 1)));
+        assert!(scheduler.is_populated(ChunkPosition::new(0, 0)));
+    }
+
+    #[test]
+    fn evicted_chunks_are_rebuilt() {
+        let mut scheduler = PopulationScheduler::new(Arc::new(MarkerGenerator));
+        scheduler.generate_chunk(ChunkPosition::new(0, 0));
+        for x in 0..FINISHED_CHUNKS as i32 {
+            scheduler.generate_chunk(ChunkPosition::new(x, 10));
+        }
+        assert!(!scheduler.is_finished(ChunkPosition::new(0, 0)));
+
+        let chunk = scheduler.generate_chunk(ChunkPosition::new(0, 0));
+        assert_eq!(chunk.block_at(15, 0, 0), Some(BlockId::stone()));
+        assert!(!scheduler.is_generated(ChunkPosition::new(0, 0)));
+    }
+
+    #[test]
+    fn region_coordinates() {
+        let chunks = region_around(ChunkPosition::new(5, -2))
+            .map(Chunk::new)
+            .collect();
+        let mut region = ChunkRegion::new(chunks);
+
+        assert!(region.set_block_at(-16, 10, 31, BlockId::stone()));
+        assert!(!region.set_block_at(32, 10, 0, BlockId::stone()));
+        assert_eq!(
+            region.chunk(-1, 1).block_at(0, 10, 15),
+            Some(BlockId::stone())
+        );
+        assert_eq!(region.block_at(-16, 10, 31), Some(BlockId::stone()));
+        assert_eq!(region.top_solid_block(-16, 31), Some(10));
+        assert_eq!(region.block_at(0, 256, 0), None);
+    }
+}
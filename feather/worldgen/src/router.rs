@@ -0,0 +1,404 @@
+//! Composable density functions, evaluated per block
+//! by a `NoiseRouter`.
+//!
+//! Density functions are combined using the methods
+//! of [`DensityFunction`], e.g. `base.add(caves).clamp(-1.0, 1.0)`.
+//! As with other density values, negative values are solid.
+
+use crate::noise::{index, NoiseLerper};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// Horizontal distance between samples of an [`Interpolated`] function.
+const CELL_WIDTH: i32 = 4;
+/// Vertical distance between samples of an [`Interpolated`] function.
+const CELL_HEIGHT: i32 = 8;
+/// Number of chunks whose values an [`Interpolated`] function
+/// keeps, so that chunks generated concurrently don't evict
+/// each other.
+const CACHED_CHUNKS: usize = 16;
+
+/// A density value as a function of absolute block coordinates.
+pub trait DensityFunction: Send + Sync {
+    fn compute(&self, x: i32, y: i32, z: i32) -> f64;
+
+    /// Returns the sum of this function and `other`.
+    fn add<F: DensityFunction>(self, other: F) -> Add<Self, F>
+    where
+        Self: Sized,
+    {
+        Add(self, other)
+    }
+
+    /// Returns the product of this function and `other`.
+    fn mul<F: DensityFunction>(self, other: F) -> Mul<Self, F>
+    where
+        Self: Sized,
+    {
+        Mul(self, other)
+    }
+
+    /// Returns the minimum of this function and `other`.
+    fn min<F: DensityFunction>(self, other: F) -> Min<Self, F>
+    where
+        Self: Sized,
+    {
+        Min(self, other)
+    }
+
+    /// Returns the maximum of this function and `other`.
+    fn max<F: DensityFunction>(self, other: F) -> Max<Self, F>
+    where
+        Self: Sized,
+    {
+        Max(self, other)
+    }
+
+    /// Returns this function clamped to `[min, max]`.
+    fn clamp(self, min: f64, max: f64) -> Clamp<Self>
+    where
+        Self: Sized,
+    {
+        Clamp {
+            inner: self,
+            min,
+            max,
+        }
+    }
+
+    /// Returns this function sampled only at the corners of each
+    /// 4x8x4 cell, and linearly interpolated in between using
+    /// `NoiseLerper`. The values of the most recently evaluated
+    /// chunks are cached, so an expensive function shared by several
+    /// combinators is sampled once per cell rather than once per block.
+    fn interpolated(self) -> Interpolated<Self>
+    where
+        Self: Sized,
+    {
+        Interpolated {
+            inner: self,
+            cache: Mutex::new(VecDeque::with_capacity(CACHED_CHUNKS)),
+        }
+    }
+}
+
+impl<F> DensityFunction for F
+where
+    F: Fn(i32, i32, i32) -> f64 + Send + Sync,
+{
+    fn compute(&self, x: i32, y: i32, z: i32) -> f64 {
+        self(x, y, z)
+    }
+}
+
+/// A density function with the same value everywhere.
+#[derive(Copy, Clone, Debug)]
+pub struct Constant(pub f64);
+
+impl DensityFunction for Constant {
+    fn compute(&self, _x: i32, _y: i32, _z: i32) -> f64 {
+        self.0
+    }
+}
+
+/// See [`DensityFunction::add`].
+#[derive(Clone, Debug)]
+pub struct Add<A, B>(A, B);
+
+impl<A: DensityFunction, B: DensityFunction> DensityFunction for Add<A, B> {
+    fn compute(&self, x: i32, y: i32, z: i32) -> f64 {
+        self.0.compute(x, y, z) + self.1.compute(x, y, z)
+    }
+}
+
+/// See [`DensityFunction::mul`].
+#[derive(Clone, Debug)]
+pub struct Mul<A, B>(A, B);
+
+impl<A: DensityFunction, B: DensityFunction> DensityFunction for Mul<A, B> {
+    fn compute(&self, x: i32, y: i32, z: i32) -> f64 {
+        self.0.compute(x, y, z) * self.1.compute(x, y, z)
+    }
+}
+
+/// See [`DensityFunction::min`].
+#[derive(Clone, Debug)]
+pub struct Min<A, B>(A, B);
+
+impl<A: DensityFunction, B: DensityFunction> DensityFunction for Min<A, B> {
+    fn compute(&self, x: i32, y: i32, z: i32) -> f64 {
+        self.0.compute(x, y, z).min(self.1.compute(x, y, z))
+    }
+}
+
+/// See [`DensityFunction::max`].
+#[derive(Clone, Debug)]
+pub struct Max<A, B>(A, B);
+
+impl<A: DensityFunction, B: DensityFunction> DensityFunction for Max<A, B> {
+    fn compute(&self, x: i32, y: i32, z: i32) -> f64 {
+        self.0.compute(x, y, z).max(self.1.compute(x, y, z))
+    }
+}
+
+/// See [`DensityFunction::clamp`].
+#[derive(Clone, Debug)]
+pub struct Clamp<A> {
+    inner: A,
+    min: f64,
+    max: f64,
+}
+
+impl<A: DensityFunction> DensityFunction for Clamp<A> {
+    fn compute(&self, x: i32, y: i32, z: i32) -> f64 {
+        self.inner.compute(x, y, z).clamp(self.min, self.max)
+    }
+}
+
+/// See [`DensityFunction::interpolated`].
+#[derive(Debug)]
+pub struct Interpolated<A> {
+    inner: A,
+    /// Most recently used first.
+    cache: Mutex<VecDeque<CachedChunk>>,
+}
+
+/// Interpolated values of a chunk, indexable by `noise::index`.
+#[derive(Debug)]
+struct CachedChunk {
+    chunk_x: i32,
+    chunk_z: i32,
+    values: Arc<Vec<f32>>,
+}
+
+impl<A: DensityFunction> Interpolated<A> {
+    /// Returns the interpolated values of a chunk,
+    /// computing them if they aren't cached.
+    fn chunk_values(&self, chunk_x: i32, chunk_z: i32) -> Arc<Vec<f32>> {
+        // Values are computed without holding the lock, so that
+        // other chunks can be evaluated in the meantime. A poisoned
+        // lock is still usable since the cache is never left
+        // half-updated.
+        let cached = {
+            let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+            Self::take_cached(&mut cache, chunk_x, chunk_z)
+        };
+        let values = match cached {
+            Some(values) => values,
+            None => Arc::new(self.interpolate_chunk(chunk_x, chunk_z)),
+        };
+
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        // Another thread may have cached the same chunk meanwhile.
+        Self::take_cached(&mut cache, chunk_x, chunk_z);
+        cache.push_front(CachedChunk {
+            chunk_x,
+            chunk_z,
+            values: Arc::clone(&values),
+        });
+        cache.truncate(CACHED_CHUNKS);
+        values
+    }
+
+    fn take_cached(
+        cache: &mut VecDeque<CachedChunk>,
+        chunk_x: i32,
+        chunk_z: i32,
+    ) -> Option<Arc<Vec<f32>>> {
+        let position = cache
+            .iter()
+            .position(|cached| cached.chunk_x == chunk_x && cached.chunk_z == chunk_z)?;
+        cache.remove(position).map(|cached| cached.values)
+    }
+
+    fn interpolate_chunk(&self, chunk_x: i32, chunk_z: i32) -> Vec<f32> {
+        let length = 16 / CELL_WIDTH + 1;
+        let height = 256 / CELL_HEIGHT + 1;
+
+        // Same layout as `NoiseLerper` expects.
+        let mut samples = vec![0.0; (length * height * length) as usize];
+        for x in 0..length {
+            for y in 0..height {
+                for z in 0..length {
+                    let value = self.inner.compute(
+                        chunk_x * 16 + x * CELL_WIDTH,
+                        y * CELL_HEIGHT,
+                        chunk_z * 16 + z * CELL_WIDTH,
+                    );
+                    samples[(y * length + x + height * length * z) as usize] = value as f32;
+                }
+            }
+        }
+
+        NoiseLerper::new(&samples)
+            .with_offset(chunk_x, chunk_z)
+            .with_scale(CELL_WIDTH as u32, CELL_HEIGHT as u32)
+            .generate()
+    }
+}
+
+impl<A: DensityFunction> DensityFunction for Interpolated<A> {
+    fn compute(&self, x: i32, y: i32, z: i32) -> f64 {
+        let chunk_x = x.div_euclid(16);
+        let chunk_z = z.div_euclid(16);
+
+        let values = self.chunk_values(chunk_x, chunk_z);
+        values[index(x.rem_euclid(16), y.clamp(0, 255), z.rem_euclid(16))] as f64
+    }
+}
+
+/// Density values of a single chunk, such as those generated
+/// by `Wrapped3DPerlinNoise`, as a density function.
+///
+/// Coordinates outside the chunk are clamped to its edges.
+#[derive(Clone, Debug)]
+pub struct SampledChunk {
+    /// Values indexable by `noise::index`.
+    values: Vec<f32>,
+    chunk_x: i32,
+    chunk_z: i32,
+}
+
+impl SampledChunk {
+    /// Wraps `values`, which must be indexable by `noise::index`,
+    /// for the chunk at `(chunk_x, chunk_z)`.
+    pub fn new(values: Vec<f32>, chunk_x: i32, chunk_z: i32) -> Self {
+        assert_eq!(values.len(), 16 * 256 * 16);
+        Self {
+            values,
+            chunk_x,
+            chunk_z,
+        }
+    }
+}
+
+impl DensityFunction for SampledChunk {
+    fn compute(&self, x: i32, y: i32, z: i32) -> f64 {
+        let x = (x - self.chunk_x * 16).clamp(0, 15);
+        let y = y.clamp(0, 255);
+        let z = (z - self.chunk_z * 16).clamp(0, 15);
+        self.values[index(x, y, z)] as f64
+    }
+}
+
+/// Evaluates a pipeline of density functions
+/// for each block in a chunk.
+pub struct NoiseRouter {
+    final_density: Box<dyn DensityFunction>,
+}
+
+impl NoiseRouter {
+    /// Creates a router evaluating `final_density`,
+    /// usually composed of several other functions.
+    pub fn new(final_density: impl DensityFunction + 'static) -> Self {
+        Self {
+            final_density: Box::new(final_density),
+        }
+    }
+
+    /// Evaluates the final density at a single block.
+    pub fn compute(&self, x: i32, y: i32, z: i32) -> f64 {
+        self.final_density.compute(x, y, z)
+    }
+
+    /// Evaluates the final density for each block in the
+    /// chunk at `(chunk_x, chunk_z)`. The returned values are
+    /// indexable by `noise::index`.
+    pub fn fill_chunk(&self, chunk_x: i32, chunk_z: i32) -> Vec<f32> {
+        let mut values = vec![0.0; 16 * 256 * 16];
+        for y in 0..256 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    values[index(x, y, z)] =
+                        self.compute(chunk_x * 16 + x, y, chunk_z * 16 + z) as f32;
+                }
+            }
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise::Wrapped3DPerlinNoise;
+
+    #[test]
+    fn composed_router_matches_manual_computation() {
+        let base = |_x: i32, y: i32, _z: i32| y as f64 - 64.0;
+        let caves = |x: i32, _y: i32, z: i32| (x + z) as f64 * 0.5;
+        let router = NoiseRouter::new(
+            base.add(caves.mul(Constant(-1.0)))
+                .max(Constant(-50.0))
+                .clamp(-40.0, 100.0),
+        );
+
+        for &(x, y, z) in &[(0, 0, 0), (3, 64, -7), (20, 200, 5), (-30, 10, -40)] {
+            let expected = (y as f64 - 64.0 - (x + z) as f64 * 0.5)
+                .max(-50.0)
+                .clamp(-40.0, 100.0);
+            approx::assert_relative_eq!(router.compute(x, y, z), expected);
+        }
+    }
+
+    #[test]
+    fn sampled_noise_in_router() {
+        let noise = Wrapped3DPerlinNoise::new(3).with_offset(2, -1);
+        let expected = noise.generate();
+
+        let router = NoiseRouter::new(noise.generate_density_function().add(Constant(1.0)));
+        let chunk = router.fill_chunk(2, -1);
+
+        for (value, expected) in chunk.iter().zip(&expected) {
+            approx::assert_relative_eq!(*value, expected + 1.0, max_relative = 1e-5);
+        }
+    }
+
+    #[test]
+    fn interpolated_router_samples_once_per_cell() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let samples = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&samples);
+        let smooth = move |x: i32, y: i32, z: i32| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            (x as f64 * 0.05).sin() + (z as f64 * 0.05).cos() + y as f64 * 0.01
+        };
+        let direct = NoiseRouter::new(smooth.clone().add(Constant(1.0)));
+        let cached = NoiseRouter::new(smooth.interpolated().add(Constant(1.0)));
+
+        let expected = direct.fill_chunk(1, -2);
+        samples.store(0, Ordering::Relaxed);
+        let chunk = cached.fill_chunk(1, -2);
+
+        assert_eq!(samples.load(Ordering::Relaxed), 5 * 33 * 5);
+        assert!(samples.load(Ordering::Relaxed) < 16 * 256 * 16);
+        for (value, expected) in chunk.iter().zip(&expected) {
+            approx::assert_abs_diff_eq!(*value, *expected, epsilon = 0.01);
+        }
+    }
+
+    #[test]
+    fn interpolated_chunks_evaluated_together_stay_cached() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let samples = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&samples);
+        let function = (move |x: i32, y: i32, z: i32| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            (x + y + z) as f64
+        })
+        .interpolated();
+
+        // Alternating between chunks, as concurrent workers would.
+        for y in 0..256 {
+            for &(chunk_x, chunk_z) in &[(0, 0), (5, -3), (0, 0), (-8, 2)] {
+                function.compute(chunk_x * 16 + 3, y, chunk_z * 16 + 9);
+            }
+        }
+        assert_eq!(samples.load(Ordering::Relaxed), 3 * 5 * 33 * 5);
+    }
+}
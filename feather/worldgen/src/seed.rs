@@ -0,0 +1,123 @@
+//! Derivation of independent seeds for each stage
+//! of world generation from the world seed.
+//!
+//! Deriving seeds from the stage rather than drawing them in
+//! sequence keeps a stage's output unchanged when other
+//! stages are added, removed, or reordered.
+
+use crate::util::shuffle_seed_for_chunk;
+use base::ChunkPosition;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+/// A stage of world generation with its own seed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GenerationStage {
+    Biomes,
+    Terrain,
+    Surface,
+    Caves,
+    Ores,
+    Decoration,
+    Lakes,
+    Trees,
+    Structures,
+}
+
+impl GenerationStage {
+    /// Returns the value mixed into the world seed for this stage.
+    fn salt(self) -> u64 {
+        match self {
+            GenerationStage::Biomes => 0xB10E5,
+            GenerationStage::Terrain => 0x7E22A1,
+            GenerationStage::Surface => 0x5C2FACE,
+            GenerationStage::Caves => 0xCA7E5,
+            GenerationStage::Ores => 0x02E5,
+            GenerationStage::Decoration => 0xDEC0,
+            GenerationStage::Lakes => 0x1A4E5,
+            GenerationStage::Trees => 0x7EE,
+            GenerationStage::Structures => 0x5772C7,
+        }
+    }
+}
+
+/// Splits the world seed into a seed for each `GenerationStage`,
+/// and each of those into a seed for every chunk.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SeedSplitter {
+    world_seed: u64,
+}
+
+impl SeedSplitter {
+    pub fn new(world_seed: u64) -> Self {
+        Self { world_seed }
+    }
+
+    /// Returns the world seed.
+    pub fn world_seed(&self) -> u64 {
+        self.world_seed
+    }
+
+    /// Returns the seed for the given stage. Used by stages
+    /// which must agree across chunk borders, such as noise.
+    pub fn stage_seed(&self, stage: GenerationStage) -> u64 {
+        XorShiftRng::seed_from_u64(self.world_seed ^ stage.salt()).gen()
+    }
+
+    /// Returns the seed for the given stage in a single chunk.
+    pub fn chunk_seed(&self, stage: GenerationStage, chunk: ChunkPosition) -> u64 {
+        shuffle_seed_for_chunk(self.stage_seed(stage), chunk)
+    }
+
+    /// Returns an RNG for the given stage in a single chunk.
+    pub fn chunk_rng(&self, stage: GenerationStage, chunk: ChunkPosition) -> XorShiftRng {
+        XorShiftRng::seed_from_u64(self.chunk_seed(stage, chunk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAGES: [GenerationStage; 9] = [
+        GenerationStage::Biomes,
+        GenerationStage::Terrain,
+        GenerationStage::Surface,
+        GenerationStage::Caves,
+        GenerationStage::Ores,
+        GenerationStage::Decoration,
+        GenerationStage::Lakes,
+        GenerationStage::Trees,
+        GenerationStage::Structures,
+    ];
+
+    #[test]
+    fn seeds_are_deterministic_and_distinct() {
+        let splitter = SeedSplitter::new(1234);
+        let chunk = ChunkPosition::new(-3, 7);
+
+        let mut seeds: Vec<u64> = STAGES
+            .iter()
+            .map(|&stage| splitter.chunk_seed(stage, chunk))
+            .collect();
+        assert_eq!(
+            seeds,
+            STAGES
+                .iter()
+                .map(|&stage| SeedSplitter::new(1234).chunk_seed(stage, chunk))
+                .collect::<Vec<_>>()
+        );
+
+        seeds.sort_unstable();
+        seeds.dedup();
+        assert_eq!(seeds.len(), STAGES.len());
+
+        let terrain =
+            |x, z| splitter.chunk_seed(GenerationStage::Terrain, ChunkPosition::new(x, z));
+        assert_ne!(terrain(0, 1), terrain(1, 0));
+        assert_ne!(
+            terrain(0, 0),
+            SeedSplitter::new(1235).chunk_seed(GenerationStage::Terrain, ChunkPosition::new(0, 0))
+        );
+    }
+}
@@ -0,0 +1,48 @@
+//! Settings customizing the overworld `ComposableGenerator`.
+
+use crate::SEA_LEVEL;
+
+/// Settings applied on top of a `WorldPreset`.
+///
+/// These are the knobs exposed to server admins, so
+/// terrain can be tweaked without recompiling. Settings
+/// left at their defaults keep the preset's behavior.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeneratorSettings {
+    /// Frequency of the 3D density noises, overriding
+    /// the preset's. See `DensityMapGeneratorImpl::with_frequency`.
+    pub frequency: Option<f32>,
+    /// Factor applied to the height of the terrain, overriding
+    /// the preset's. See `DensityMapGeneratorImpl::with_height_scale`.
+    pub height_scale: Option<f32>,
+    /// Highest Y coordinate filled with water.
+    pub sea_level: usize,
+    /// Multiplier applied to the number of veins of every ore.
+    pub ore_rate: f32,
+    /// Whether caves are carved.
+    pub caves: bool,
+    /// Whether ores are placed.
+    pub ores: bool,
+    /// Whether water and lava lakes are placed.
+    pub lakes: bool,
+    /// Whether trees are placed.
+    pub trees: bool,
+    /// Whether dungeons, temples and other structures are placed.
+    pub structures: bool,
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        Self {
+            frequency: None,
+            height_scale: None,
+            sea_level: SEA_LEVEL,
+            ore_rate: 1.0,
+            caves: true,
+            ores: true,
+            lakes: true,
+            trees: true,
+            structures: true,
+        }
+    }
+}
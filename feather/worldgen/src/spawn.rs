@@ -0,0 +1,140 @@
+//! Selection of the world spawn point.
+
+use crate::{PopulationScheduler, WorldGenerator};
+use base::{Biome, BlockPosition, Chunk, ChunkPosition, SimplifiedBlockKind};
+use std::sync::Arc;
+
+/// Finds a safe spawn point near the origin, searching the chunks
+/// within `max_radius` chunks of it in rings of increasing size.
+///
+/// A spawn point is the block above the surface of a column whose
+/// surface is solid ground, outside of oceans and rivers. Chunks are
+/// generated and populated, so lakes and trees are taken into account.
+/// Within the first chunk containing such a column, the column closest
+/// to the origin is chosen.
+///
+/// Returns `None` if no column within the radius is suitable.
+pub fn find_spawn(generator: Arc<dyn WorldGenerator>, max_radius: i32) -> Option<BlockPosition> {
+    let mut scheduler = PopulationScheduler::new(generator);
+    for radius in 0..=max_radius {
+        for pos in ring(radius) {
+            let chunk = scheduler.generate_chunk(pos);
+            if let Some(spawn) = spawn_in_chunk(&chunk) {
+                return Some(spawn);
+            }
+        }
+    }
+    None
+}
+
+/// Returns the positions of the chunks at the given
+/// Chebyshev distance from the origin chunk.
+fn ring(radius: i32) -> impl Iterator<Item = ChunkPosition> {
+    (-radius..=radius)
+        .flat_map(move |z| (-radius..=radius).map(move |x| ChunkPosition::new(x, z)))
+        .filter(move |pos| pos.x.abs().max(pos.z.abs()) == radius)
+}
+
+/// Returns the suitable spawn point in the chunk
+/// closest to the origin, if any.
+fn spawn_in_chunk(chunk: &Chunk) -> Option<BlockPosition> {
+    let pos = chunk.position();
+    (0..16)
+        .flat_map(|x| (0..16).map(move |z| (x, z)))
+        .filter_map(|(x, z)| {
+            let y = surface_height(chunk, x, z)?;
+            Some(BlockPosition::new(
+                pos.x * 16 + x as i32,
+                y as i32,
+                pos.z * 16 + z as i32,
+            ))
+        })
+        .min_by_key(|spawn| spawn.x * spawn.x + spawn.z * spawn.z)
+}
+
+/// Returns the Y coordinate above the surface of the given
+/// column, if a player can safely spawn there.
+fn surface_height(chunk: &Chunk, x: usize, z: usize) -> Option<usize> {
+    if is_watery(chunk.biomes().get(x / 4, 0, z / 4)) {
+        return None;
+    }
+
+    // The heightmap includes fluids, so the
+    // surface is never below water or lava.
+    let height = chunk.heightmaps().motion_blocking.height(x, z)?;
+    if height == 0 || height + 1 >= 256 {
+        return None;
+    }
+    let ground = chunk.block_at(x, height - 1, z)?;
+    if !ground.is_solid() || ground.simplified_kind() == SimplifiedBlockKind::Leaves {
+        return None;
+    }
+    Some(height)
+}
+
+fn is_watery(biome: Biome) -> bool {
+    matches!(
+        biome,
+        Biome::River
+            | Biome::FrozenRiver
+            | Biome::Ocean
+            | Biome::FrozenOcean
+            | Biome::WarmOcean
+            | Biome::LukewarmOcean
+            | Biome::ColdOcean
+            | Biome::DeepOcean
+            | Biome::DeepWarmOcean
+            | Biome::DeepLukewarmOcean
+            | Biome::DeepColdOcean
+            | Biome::DeepFrozenOcean
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::BlockId;
+
+    /// Generates chunks of stone up to Y=60, with an ocean
+    /// biome in chunks with a negative X coordinate and a
+    /// pool of lava in the origin chunk.
+    struct TestGenerator;
+
+    impl WorldGenerator for TestGenerator {
+        fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+            let biome = if position.x < 0 {
+                Biome::Ocean
+            } else {
+                Biome::Plains
+            };
+            let mut chunk = Chunk::new_with_default_biome(position, biome);
+            for x in 0..16 {
+                for z in 0..16 {
+                    for y in 0..=60 {
+                        chunk.set_block_at(x, y, z, BlockId::stone());
+                    }
+                    if position == ChunkPosition::new(0, 0) {
+                        chunk.set_block_at(x, 61, z, BlockId::lava());
+                    }
+                }
+            }
+            chunk
+        }
+    }
+
+    #[test]
+    fn spawn_avoids_oceans_and_lava() {
+        let spawn = find_spawn(Arc::new(TestGenerator), 2).unwrap();
+        assert_eq!(spawn.y, 61);
+        assert!(spawn.x >= 0);
+        assert_eq!(spawn.chunk().x.abs().max(spawn.chunk().z.abs()), 1);
+        // The closest column to the origin in its chunk.
+        assert!(spawn.x == 0 || spawn.x == 16);
+    }
+
+    #[test]
+    fn no_spawn_without_ground() {
+        let generator = Arc::new(crate::EmptyWorldGenerator {});
+        assert_eq!(find_spawn(generator, 1), None);
+    }
+}
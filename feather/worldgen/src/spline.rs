@@ -0,0 +1,96 @@
+//! Piecewise-linear curves used to map noise values
+//! to terrain parameters.
+
+/// A piecewise-linear curve defined by `(input, output)` control points.
+///
+/// Inputs between two control points are linearly interpolated;
+/// inputs outside the range of control points are clamped
+/// to the first or last output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spline {
+    /// Control points, sorted by input.
+    points: Vec<(f32, f32)>,
+}
+
+impl Spline {
+    /// Creates a spline from the given control points.
+    /// The points need not be sorted.
+    ///
+    /// # Panics
+    /// Panics if `points` is empty or contains a NaN input.
+    pub fn new(points: impl IntoIterator<Item = (f32, f32)>) -> Self {
+        let mut points: Vec<(f32, f32)> = points.into_iter().collect();
+        assert!(
+            !points.is_empty(),
+            "spline needs at least one control point"
+        );
+        points.sort_by(|(a, _), (b, _)| a.partial_cmp(b).expect("NaN spline input"));
+        Self { points }
+    }
+
+    /// Returns the control points, sorted by input.
+    pub fn points(&self) -> &[(f32, f32)] {
+        &self.points
+    }
+
+    /// Maps `input` through the curve.
+    pub fn sample(&self, input: f32) -> f32 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        if input <= first.0 {
+            return first.1;
+        }
+        if input >= last.0 {
+            return last.1;
+        }
+
+        // Index of the first control point with an input greater than `input`.
+        // Never 0 or `len` because of the checks above.
+        let upper = self.points.partition_point(|(x, _)| *x <= input);
+        let (x0, y0) = self.points[upper - 1];
+        let (x1, y1) = self.points[upper];
+
+        let t = (input - x0) / (x1 - x0);
+        y0 + (y1 - y0) * t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn continentalness() -> Spline {
+        Spline::new(vec![(0.3, 8.0), (-1.0, -40.0), (-0.2, 0.0), (1.0, 60.0)])
+    }
+
+    #[test]
+    fn interpolates_between_points() {
+        let spline = continentalness();
+
+        approx::assert_relative_eq!(spline.sample(-1.0), -40.0);
+        approx::assert_relative_eq!(spline.sample(-0.6), -20.0);
+        approx::assert_relative_eq!(spline.sample(0.05), 4.0);
+        approx::assert_relative_eq!(spline.sample(0.3), 8.0);
+        approx::assert_relative_eq!(spline.sample(0.65), 34.0);
+    }
+
+    #[test]
+    fn clamps_outside_points() {
+        let spline = continentalness();
+
+        approx::assert_relative_eq!(spline.sample(-3.0), -40.0);
+        approx::assert_relative_eq!(spline.sample(5.0), 60.0);
+    }
+
+    #[test]
+    fn preserves_monotonicity() {
+        let spline = continentalness();
+
+        let mut previous = spline.sample(-1.5);
+        for i in 0..=300 {
+            let value = spline.sample(-1.5 + i as f32 / 100.0);
+            assert!(value >= previous);
+            previous = value;
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! The stages run by a `ComposableGenerator` once the
+//! biomes and density map of a chunk are known.
+
+use crate::structures::{self, StructureType};
+use crate::{
+    BiomeGenerator, CompositionGenerator, FinishingGenerator, GenerationStage, NearbyBiomes,
+    SeedSplitter, TopBlocks,
+};
+use base::{Biome, BlockId, Chunk, ChunkPosition};
+use bitvec::{order::LocalBits, slice::BitSlice};
+use once_cell::unsync::OnceCell;
+
+/// A step of the `ComposableGenerator` pipeline which
+/// modifies a chunk, such as carving caves or placing ores.
+pub trait GenStage: Send + Sync {
+    /// Returns the name identifying this stage within a
+    /// pipeline, used to insert other stages around it
+    /// or to replace it.
+    fn name(&self) -> &str;
+
+    /// Returns the stage whose seed is used by this stage,
+    /// and under which its time is recorded.
+    fn stage(&self) -> GenerationStage;
+
+    /// Applies this stage to `chunk`.
+    fn apply(&self, chunk: &mut Chunk, ctx: &GenContext);
+}
+
+/// Data shared by the stages generating a chunk.
+pub struct GenContext<'a> {
+    position: ChunkPosition,
+    biomes: &'a NearbyBiomes,
+    density: &'a BitSlice<LocalBits, u8>,
+    seeds: SeedSplitter,
+    biome_generator: &'a dyn BiomeGenerator,
+    top_blocks: OnceCell<TopBlocks>,
+}
+
+impl<'a> GenContext<'a> {
+    pub fn new(
+        position: ChunkPosition,
+        biomes: &'a NearbyBiomes,
+        density: &'a BitSlice<LocalBits, u8>,
+        seeds: SeedSplitter,
+        biome_generator: &'a dyn BiomeGenerator,
+    ) -> Self {
+        Self {
+            position,
+            biomes,
+            density,
+            seeds,
+            biome_generator,
+            top_blocks: OnceCell::new(),
+        }
+    }
+
+    /// Returns the position of the chunk being generated.
+    pub fn position(&self) -> ChunkPosition {
+        self.position
+    }
+
+    /// Returns the biomes of the chunk and its neighbors.
+    pub fn biomes(&self) -> &NearbyBiomes {
+        self.biomes
+    }
+
+    /// Returns the density map of the chunk,
+    /// indexable by `block_index`.
+    pub fn density(&self) -> &BitSlice<LocalBits, u8> {
+        self.density
+    }
+
+    /// Returns the seed of the given stage.
+    pub fn seed(&self, stage: GenerationStage) -> u64 {
+        self.seeds.stage_seed(stage)
+    }
+
+    /// Returns the seeds of all stages.
+    pub fn seeds(&self) -> SeedSplitter {
+        self.seeds
+    }
+
+    /// Returns the biome at the center of any chunk,
+    /// for stages which reach beyond the current one.
+    pub fn biome_of_chunk(&self, position: ChunkPosition) -> Biome {
+        self.biome_generator
+            .generate_for_chunk(position, self.seed(GenerationStage::Biomes))
+            .biome_at(8, 8)
+    }
+
+    /// Returns the highest non-air block of each column.
+    ///
+    /// These are computed from `chunk` the first time this is
+    /// called, and shared by all later stages, so that finishers
+    /// see the surface as it was after composition.
+    pub fn top_blocks(&self, chunk: &Chunk) -> &TopBlocks {
+        self.top_blocks.get_or_init(|| {
+            let mut top_blocks = TopBlocks::new();
+            for x in 0..16 {
+                for z in 0..16 {
+                    for y in (0..256).rev() {
+                        if chunk.block_at(x, y, z).unwrap() != BlockId::air() {
+                            top_blocks.set_top_block_at(x, z, y);
+                            break;
+                        }
+                    }
+                }
+            }
+            top_blocks
+        })
+    }
+}
+
+/// Fills the chunk with blocks using a `CompositionGenerator`.
+pub struct CompositionStage(Box<dyn CompositionGenerator>);
+
+impl CompositionStage {
+    pub fn new(composition: impl CompositionGenerator + 'static) -> Self {
+        Self(Box::new(composition))
+    }
+}
+
+impl GenStage for CompositionStage {
+    fn name(&self) -> &str {
+        "composition"
+    }
+
+    fn stage(&self) -> GenerationStage {
+        GenerationStage::Surface
+    }
+
+    fn apply(&self, chunk: &mut Chunk, ctx: &GenContext) {
+        self.0.generate_for_chunk(
+            chunk,
+            ctx.position(),
+            ctx.biomes(),
+            ctx.density(),
+            ctx.seed(self.stage()),
+        );
+        chunk.recalculate_heightmaps();
+    }
+}
+
+/// Runs a `FinishingGenerator`, named after the finisher.
+pub struct FinisherStage(Box<dyn FinishingGenerator>);
+
+impl FinisherStage {
+    pub fn new(finisher: Box<dyn FinishingGenerator>) -> Self {
+        Self(finisher)
+    }
+}
+
+impl GenStage for FinisherStage {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn stage(&self) -> GenerationStage {
+        self.0.stage()
+    }
+
+    fn apply(&self, chunk: &mut Chunk, ctx: &GenContext) {
+        let top_blocks = ctx.top_blocks(chunk);
+        self.0.generate_for_chunk(
+            chunk,
+            &ctx.biomes().biomes[4],
+            top_blocks,
+            ctx.seed(self.stage()),
+        );
+    }
+}
+
+/// Places the parts of structures which lie in the chunk.
+pub struct StructureStage(Vec<Box<dyn StructureType>>);
+
+impl StructureStage {
+    pub fn new(structures: impl IntoIterator<Item = Box<dyn StructureType>>) -> Self {
+        Self(structures.into_iter().collect())
+    }
+}
+
+impl GenStage for StructureStage {
+    fn name(&self) -> &str {
+        "structures"
+    }
+
+    fn stage(&self) -> GenerationStage {
+        GenerationStage::Structures
+    }
+
+    fn apply(&self, chunk: &mut Chunk, ctx: &GenContext) {
+        structures::place_structures(chunk, &self.0, ctx.seed(self.stage()), |pos| {
+            ctx.biome_of_chunk(pos)
+        });
+    }
+}
@@ -0,0 +1,222 @@
+use super::{BoundingBox, ClippedChunk, Structure, StructurePiece, StructureType};
+use crate::SEA_LEVEL;
+use base::{Biome, BlockId, ChunkPosition};
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+
+/// Width and depth of the temple.
+const SIZE: i32 = 21;
+/// Height of the temple's walls above its floor.
+const WALL_HEIGHT: i32 = 9;
+/// Depth of the treasure chamber's floor below the temple floor.
+const CHAMBER_DEPTH: i32 = 12;
+/// Maximum depth the foundation extends down to reach the ground.
+const FOUNDATION_DEPTH: i32 = 8;
+
+/// A sandstone temple in the desert, with a
+/// trapped treasure chamber beneath it.
+#[derive(Debug, Default)]
+pub struct DesertTemple;
+
+impl StructureType for DesertTemple {
+    fn spacing(&self) -> u32 {
+        32
+    }
+
+    fn separation(&self) -> u32 {
+        8
+    }
+
+    fn salt(&self) -> u64 {
+        14_357_617
+    }
+
+    fn max_radius(&self) -> i32 {
+        // The temple's corner lies within its chunk.
+        (15 + SIZE - 1) / 16
+    }
+
+    fn can_generate_in(&self, biome: Biome) -> bool {
+        matches!(
+            biome,
+            Biome::Desert | Biome::DesertHills | Biome::DesertLakes
+        )
+    }
+
+    fn generate(&self, origin: ChunkPosition, rng: &mut XorShiftRng) -> Structure {
+        let temple = Temple {
+            origin: (
+                origin.x * 16 + rng.gen_range(0, 16),
+                SEA_LEVEL as i32,
+                origin.z * 16 + rng.gen_range(0, 16),
+            ),
+        };
+        Structure::new(vec![Box::new(temple)])
+    }
+}
+
+/// The temple building, including its foundation and chamber.
+///
+/// Terrain heights aren't known while planning, so the floor is
+/// placed at sea level and the foundation is filled down to the
+/// ground, much like vanilla temples are partially buried.
+struct Temple {
+    /// The lowest corner of the temple's floor.
+    origin: (i32, i32, i32),
+}
+
+impl StructurePiece for Temple {
+    fn bounding_box(&self) -> BoundingBox {
+        let (x, y, z) = self.origin;
+        let bottom = y - CHAMBER_DEPTH.max(FOUNDATION_DEPTH) - 1;
+        BoundingBox::new(
+            (x, bottom, z),
+            (x + SIZE - 1, y + WALL_HEIGHT, z + SIZE - 1),
+        )
+    }
+
+    fn place(&self, chunk: &mut ClippedChunk) {
+        let (ox, oy, oz) = self.origin;
+        let mid = SIZE / 2;
+
+        for x in ox..ox + SIZE {
+            for z in oz..oz + SIZE {
+                // Fill the foundation down to solid ground.
+                for y in (oy - FOUNDATION_DEPTH..oy).rev() {
+                    match chunk.block_at(x, y, z) {
+                        Some(block) if block.is_air() || block.is_fluid() => {
+                            chunk.set_block_at(x, y, z, BlockId::sandstone())
+                        }
+                        _ => break,
+                    }
+                }
+
+                let edge = x == ox || x == ox + SIZE - 1 || z == oz || z == oz + SIZE - 1;
+                for y in oy..=oy + WALL_HEIGHT {
+                    let block = if y == oy || y == oy + WALL_HEIGHT {
+                        BlockId::sandstone()
+                    } else if edge {
+                        if y == oy + WALL_HEIGHT - 1 {
+                            BlockId::cut_sandstone()
+                        } else {
+                            BlockId::sandstone()
+                        }
+                    } else {
+                        BlockId::air()
+                    };
+                    chunk.set_block_at(x, y, z, block);
+                }
+            }
+        }
+
+        // Entrance in the north wall.
+        for x in ox + mid - 1..=ox + mid + 1 {
+            for y in oy + 1..oy + 4 {
+                chunk.set_block_at(x, y, oz, BlockId::air());
+            }
+        }
+
+        // Terracotta pattern in the floor, above the chamber.
+        for d in -2..=2 {
+            chunk.set_block_at(ox + mid + d, oy, oz + mid, BlockId::orange_terracotta());
+            chunk.set_block_at(ox + mid, oy, oz + mid + d, BlockId::orange_terracotta());
+        }
+        chunk.set_block_at(ox + mid, oy, oz + mid, BlockId::blue_terracotta());
+
+        self.place_chamber(chunk);
+    }
+}
+
+impl Temple {
+    /// Places the treasure chamber and the shaft leading down to it.
+    fn place_chamber(&self, chunk: &mut ClippedChunk) {
+        let (ox, oy, oz) = self.origin;
+        let (cx, cz) = (ox + SIZE / 2, oz + SIZE / 2);
+        let floor = oy - CHAMBER_DEPTH;
+
+        for x in cx - 2..=cx + 2 {
+            for z in cz - 2..=cz + 2 {
+                let wall = (x - cx).abs() == 2 || (z - cz).abs() == 2;
+                chunk.set_block_at(x, floor - 1, z, BlockId::sandstone());
+                for y in floor..floor + 4 {
+                    let block = if wall || y == floor + 3 {
+                        BlockId::cut_sandstone()
+                    } else {
+                        BlockId::air()
+                    };
+                    chunk.set_block_at(x, y, z, block);
+                }
+            }
+        }
+
+        // Shaft from under the blue terracotta to the chamber.
+        for y in floor + 1..oy {
+            chunk.set_block_at(cx, y, cz, BlockId::air());
+        }
+
+        chunk.set_block_at(cx, floor, cz, BlockId::stone_pressure_plate());
+        chunk.set_block_at(cx, floor - 2, cz, BlockId::tnt());
+        for &(dx, dz) in [(-1, 0), (1, 0), (0, -1), (0, 1)].iter() {
+            chunk.set_block_at(cx + dx, floor, cz + dz, BlockId::chest());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::Chunk;
+
+    #[test]
+    fn temple_is_complete_across_chunks() {
+        let temple = Temple {
+            origin: (10, SEA_LEVEL as i32, 8),
+        };
+        let bbox = temple.bounding_box();
+
+        let mut floor = 0;
+        for chunk_x in 0..=1 {
+            for chunk_z in 0..=1 {
+                let position = ChunkPosition::new(chunk_x, chunk_z);
+                assert!(bbox.intersects_chunk(position));
+
+                let mut chunk = Chunk::new(position);
+                Structure::new(vec![Box::new(Temple {
+                    origin: temple.origin,
+                })])
+                .place_in_chunk(&mut chunk);
+
+                for x in 0..16 {
+                    for z in 0..16 {
+                        if chunk.block_at(x, SEA_LEVEL, z) != Some(BlockId::air()) {
+                            floor += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Every block of the floor is placed exactly once.
+        assert_eq!(floor, SIZE * SIZE);
+        assert!(!bbox.intersects_chunk(ChunkPosition::new(2, 0)));
+    }
+
+    #[test]
+    fn foundation_reaches_ground() {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set_block_at(x, SEA_LEVEL - 5, z, BlockId::sand());
+            }
+        }
+        Structure::new(vec![Box::new(Temple {
+            origin: (0, SEA_LEVEL as i32, 0),
+        })])
+        .place_in_chunk(&mut chunk);
+
+        for y in SEA_LEVEL - 4..SEA_LEVEL {
+            assert_eq!(chunk.block_at(1, y, 1), Some(BlockId::sandstone()));
+        }
+        assert_eq!(chunk.block_at(1, SEA_LEVEL - 5, 1), Some(BlockId::sand()));
+    }
+}
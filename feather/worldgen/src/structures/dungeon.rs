@@ -0,0 +1,280 @@
+use super::{
+    assemble, direction_offset, BoundingBox, ClippedChunk, Connector, Structure, StructurePiece,
+    StructureType,
+};
+use base::{Biome, BlockId, ChunkPosition, Direction};
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+
+/// Width and depth of a dungeon room, including walls.
+const ROOM_SIZE: i32 = 7;
+/// Height of a dungeon room, including floor and ceiling.
+const ROOM_HEIGHT: i32 = 5;
+/// Maximum number of corridors between the room and a corridor's end.
+const MAX_CORRIDOR_DEPTH: u32 = 2;
+/// Maximum length of a corridor.
+const MAX_CORRIDOR_LENGTH: i32 = 8;
+
+/// An underground cobblestone room with a mob spawner and
+/// chests, with tunnels leading out of it.
+#[derive(Debug, Default)]
+pub struct Dungeon;
+
+impl StructureType for Dungeon {
+    fn spacing(&self) -> u32 {
+        4
+    }
+
+    fn separation(&self) -> u32 {
+        1
+    }
+
+    fn salt(&self) -> u64 {
+        20_083_232
+    }
+
+    fn max_radius(&self) -> i32 {
+        // The room lies within its chunk, and
+        // corridors can extend past the room's walls.
+        let reach = MAX_CORRIDOR_LENGTH * MAX_CORRIDOR_DEPTH as i32;
+        (reach + 15) / 16
+    }
+
+    fn can_generate_in(&self, biome: Biome) -> bool {
+        !matches!(
+            biome,
+            Biome::TheVoid
+                | Biome::TheEnd
+                | Biome::SmallEndIslands
+                | Biome::EndMidlands
+                | Biome::EndHighlands
+                | Biome::EndBarrens
+        )
+    }
+
+    fn generate(&self, origin: ChunkPosition, rng: &mut XorShiftRng) -> Structure {
+        let room = Room {
+            origin: (
+                origin.x * 16 + rng.gen_range(0, 16 - ROOM_SIZE),
+                rng.gen_range(10, 50),
+                origin.z * 16 + rng.gen_range(0, 16 - ROOM_SIZE),
+            ),
+        };
+        assemble(Box::new(room), MAX_CORRIDOR_DEPTH, rng, |connector, rng| {
+            if rng.gen_range(0, 3) == 0 {
+                return None;
+            }
+            let length = rng.gen_range(3, MAX_CORRIDOR_LENGTH + 1);
+            Some(Box::new(Corridor { connector, length }))
+        })
+    }
+}
+
+/// The dungeon's main room.
+struct Room {
+    /// Lowest corner of the room's walls.
+    origin: (i32, i32, i32),
+}
+
+impl Room {
+    /// Returns the positions of the doorways in the
+    /// center of each wall, and the directions out of them.
+    fn doorways(&self) -> [((i32, i32), Direction); 4] {
+        let (x, _, z) = self.origin;
+        let mid = ROOM_SIZE / 2;
+        [
+            ((x + mid, z), Direction::North),
+            ((x + mid, z + ROOM_SIZE - 1), Direction::South),
+            ((x, z + mid), Direction::West),
+            ((x + ROOM_SIZE - 1, z + mid), Direction::East),
+        ]
+    }
+}
+
+impl StructurePiece for Room {
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::from_size(
+            self.origin,
+            (ROOM_SIZE as u32, ROOM_HEIGHT as u32, ROOM_SIZE as u32),
+        )
+    }
+
+    fn connectors(&self) -> Vec<Connector> {
+        self.doorways()
+            .iter()
+            .map(|&((x, z), direction)| {
+                let (dx, dz) = direction_offset(direction);
+                Connector {
+                    position: (x + dx, self.origin.1 + 1, z + dz),
+                    direction,
+                }
+            })
+            .collect()
+    }
+
+    fn place(&self, chunk: &mut ClippedChunk) {
+        let (ox, oy, oz) = self.origin;
+        for x in ox..ox + ROOM_SIZE {
+            for y in oy..oy + ROOM_HEIGHT {
+                for z in oz..oz + ROOM_SIZE {
+                    let wall = x == ox
+                        || x == ox + ROOM_SIZE - 1
+                        || y == oy
+                        || y == oy + ROOM_HEIGHT - 1
+                        || z == oz
+                        || z == oz + ROOM_SIZE - 1;
+                    let block = if !wall {
+                        BlockId::cave_air()
+                    } else if is_mossy(x, y, z) {
+                        BlockId::mossy_cobblestone()
+                    } else {
+                        BlockId::cobblestone()
+                    };
+                    chunk.set_block_at(x, y, z, block);
+                }
+            }
+        }
+
+        for &((x, z), _) in self.doorways().iter() {
+            chunk.set_block_at(x, oy + 1, z, BlockId::cave_air());
+            chunk.set_block_at(x, oy + 2, z, BlockId::cave_air());
+        }
+
+        let mid = ROOM_SIZE / 2;
+        chunk.set_block_at(ox + mid, oy + 1, oz + mid, BlockId::spawner());
+        chunk.set_block_at(ox + 1, oy + 1, oz + 1, BlockId::chest());
+        chunk.set_block_at(
+            ox + ROOM_SIZE - 2,
+            oy + 1,
+            oz + ROOM_SIZE - 2,
+            BlockId::chest(),
+        );
+    }
+}
+
+/// A straight tunnel, one block wide and two tall,
+/// leading out from a connector.
+struct Corridor {
+    connector: Connector,
+    length: i32,
+}
+
+impl Corridor {
+    /// Returns the position of the `i`th block along the corridor floor.
+    fn block(&self, i: i32) -> (i32, i32, i32) {
+        let (x, y, z) = self.connector.position;
+        let (dx, dz) = direction_offset(self.connector.direction);
+        (x + dx * i, y, z + dz * i)
+    }
+}
+
+impl StructurePiece for Corridor {
+    fn bounding_box(&self) -> BoundingBox {
+        let start = self.block(0);
+        let end = self.block(self.length - 1);
+        BoundingBox::new(start, (end.0, end.1 + 1, end.2))
+    }
+
+    fn connectors(&self) -> Vec<Connector> {
+        let end = self.block(self.length);
+        let forward = Connector {
+            position: end,
+            direction: self.connector.direction,
+        };
+
+        // Turns start beside the last block, rather than past it.
+        let last = self.block(self.length - 1);
+        let sides = match self.connector.direction {
+            Direction::North | Direction::South => [Direction::East, Direction::West],
+            Direction::East | Direction::West => [Direction::North, Direction::South],
+        };
+        let mut connectors = vec![forward];
+        connectors.extend(sides.iter().map(|&direction| {
+            let (sx, sz) = direction_offset(direction);
+            Connector {
+                position: (last.0 + sx, last.1, last.2 + sz),
+                direction,
+            }
+        }));
+        connectors
+    }
+
+    fn place(&self, chunk: &mut ClippedChunk) {
+        for i in 0..self.length {
+            let (x, y, z) = self.block(i);
+            chunk.set_block_at(x, y, z, BlockId::cave_air());
+            chunk.set_block_at(x, y + 1, z, BlockId::cave_air());
+        }
+    }
+}
+
+/// Deterministically picks about a quarter of wall blocks to be mossy.
+fn is_mossy(x: i32, y: i32, z: i32) -> bool {
+    let hash =
+        (x.wrapping_mul(73_856_093)) ^ (y.wrapping_mul(19_349_663)) ^ (z.wrapping_mul(83_492_791));
+    hash & 3 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::{place_structures, structure_chunk};
+    use crate::util::feature_random;
+    use base::Chunk;
+
+    const SEED: u64 = 12;
+
+    fn dungeon_origin() -> ChunkPosition {
+        (0..8)
+            .flat_map(|x| (0..8).map(move |z| (x, z)))
+            .find_map(|(x, z)| structure_chunk(SEED, x, z, 4, 1, Dungeon.salt()))
+            .map(|(x, z)| ChunkPosition::new(x, z))
+            .expect("no dungeon found")
+    }
+
+    fn plan(origin: ChunkPosition) -> Structure {
+        let mut rng = feature_random(SEED, origin.x, origin.z, Dungeon.salt());
+        Dungeon.generate(origin, &mut rng)
+    }
+
+    #[test]
+    fn dungeon_pieces_do_not_overlap() {
+        let dungeon = plan(dungeon_origin());
+        let pieces = dungeon.pieces();
+        assert!(!pieces.is_empty());
+        for (i, a) in pieces.iter().enumerate() {
+            for b in &pieces[i + 1..] {
+                assert!(!a.bounding_box().intersects(&b.bounding_box()));
+            }
+        }
+    }
+
+    #[test]
+    fn dungeon_fits_within_radius() {
+        let origin = dungeon_origin();
+        let bbox = plan(origin).bounding_box().unwrap();
+        let radius = Dungeon.max_radius();
+        for x in origin.x - radius - 1..=origin.x + radius + 1 {
+            for z in origin.z - radius - 1..=origin.z + radius + 1 {
+                let outside = (x - origin.x).abs() > radius || (z - origin.z).abs() > radius;
+                if outside {
+                    assert!(!bbox.intersects_chunk(ChunkPosition::new(x, z)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spawner_is_placed_in_origin_chunk() {
+        let origin = dungeon_origin();
+        let types: Vec<Box<dyn StructureType>> = vec![Box::new(Dungeon)];
+        let mut chunk = Chunk::new(origin);
+        place_structures(&mut chunk, &types, SEED, |_| Biome::Plains);
+
+        let spawners = (0..16)
+            .flat_map(|x| (0..256).flat_map(move |y| (0..16).map(move |z| (x, y, z))))
+            .filter(|&(x, y, z)| chunk.block_at(x, y, z) == Some(BlockId::spawner()))
+            .count();
+        assert_eq!(spawners, 1);
+    }
+}
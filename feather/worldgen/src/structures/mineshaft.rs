@@ -0,0 +1,409 @@
+use super::{
+    assemble, direction_offset, BoundingBox, ClippedChunk, Connector, Structure, StructurePiece,
+    StructureType,
+};
+use base::{Biome, BlockId, ChunkPosition, Direction, RailShape};
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+
+/// Width and depth of the starting room.
+const ROOM_SIZE: i32 = 9;
+/// Height of the starting room, excluding its floor.
+const ROOM_HEIGHT: i32 = 4;
+/// Width and height of tunnels.
+const TUNNEL_SIZE: i32 = 3;
+/// Length of a corridor segment, each ending in a support.
+const SEGMENT_LENGTH: i32 = 5;
+/// Maximum number of segments in a corridor.
+const MAX_SEGMENTS: i32 = 3;
+/// Maximum number of pieces between the room and a tunnel's end.
+const MAX_DEPTH: u32 = 6;
+
+/// An abandoned mineshaft: a network of wooden corridors and
+/// crossings spreading out from a room carved underground.
+///
+/// Some corridors have rails, and some have a chest beside
+/// the track. Vanilla places chest minecarts instead, but
+/// generated chunks don't carry entities, so a chest block
+/// stands in for them. Cobwebs hang from corridor ceilings.
+#[derive(Debug, Default)]
+pub struct Mineshaft;
+
+impl StructureType for Mineshaft {
+    fn spacing(&self) -> u32 {
+        8
+    }
+
+    fn separation(&self) -> u32 {
+        2
+    }
+
+    fn salt(&self) -> u64 {
+        16_193_287
+    }
+
+    fn max_radius(&self) -> i32 {
+        // The room lies within its chunk, and each piece
+        // can extend the tunnels by a full corridor.
+        let reach = SEGMENT_LENGTH * MAX_SEGMENTS * MAX_DEPTH as i32;
+        (reach + 15) / 16
+    }
+
+    fn can_generate_in(&self, biome: Biome) -> bool {
+        !matches!(
+            biome,
+            Biome::TheVoid
+                | Biome::TheEnd
+                | Biome::SmallEndIslands
+                | Biome::EndMidlands
+                | Biome::EndHighlands
+                | Biome::EndBarrens
+        )
+    }
+
+    fn generate(&self, origin: ChunkPosition, rng: &mut XorShiftRng) -> Structure {
+        let room = Room {
+            origin: (
+                origin.x * 16 + rng.gen_range(0, 16 - ROOM_SIZE),
+                rng.gen_range(20, 50),
+                origin.z * 16 + rng.gen_range(0, 16 - ROOM_SIZE),
+            ),
+        };
+        assemble(Box::new(room), MAX_DEPTH, rng, |connector, rng| {
+            let tunnel = Tunnel { connector };
+            match rng.gen_range(0, 10) {
+                0 => None,
+                1 | 2 => Some(Box::new(Crossing { tunnel })),
+                _ => {
+                    let segments = rng.gen_range(1, MAX_SEGMENTS + 1);
+                    let length = segments * SEGMENT_LENGTH;
+                    let chest = if rng.gen_range(0, 4) == 0 {
+                        Some(rng.gen_range(0, length))
+                    } else {
+                        None
+                    };
+                    Some(Box::new(Corridor {
+                        tunnel,
+                        length,
+                        rails: rng.gen_range(0, 3) == 0,
+                        chest,
+                        cobweb_salt: rng.gen(),
+                    }))
+                }
+            }
+        })
+    }
+}
+
+/// The room at the center of a mineshaft, with
+/// a dirt floor and tunnels leaving each wall.
+struct Room {
+    /// Lowest corner of the room, at floor level.
+    origin: (i32, i32, i32),
+}
+
+impl StructurePiece for Room {
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::from_size(
+            self.origin,
+            (ROOM_SIZE as u32, ROOM_HEIGHT as u32 + 1, ROOM_SIZE as u32),
+        )
+    }
+
+    fn connectors(&self) -> Vec<Connector> {
+        let (x, y, z) = self.origin;
+        let mid = ROOM_SIZE / 2;
+        vec![
+            Connector {
+                position: (x + mid, y + 1, z - 1),
+                direction: Direction::North,
+            },
+            Connector {
+                position: (x + mid, y + 1, z + ROOM_SIZE),
+                direction: Direction::South,
+            },
+            Connector {
+                position: (x - 1, y + 1, z + mid),
+                direction: Direction::West,
+            },
+            Connector {
+                position: (x + ROOM_SIZE, y + 1, z + mid),
+                direction: Direction::East,
+            },
+        ]
+    }
+
+    fn place(&self, chunk: &mut ClippedChunk) {
+        let (ox, oy, oz) = self.origin;
+        for x in ox..ox + ROOM_SIZE {
+            for z in oz..oz + ROOM_SIZE {
+                chunk.set_block_at(x, oy, z, BlockId::dirt());
+                for y in oy + 1..=oy + ROOM_HEIGHT {
+                    chunk.set_block_at(x, y, z, BlockId::cave_air());
+                }
+            }
+        }
+    }
+}
+
+/// A straight run of tunnel, `TUNNEL_SIZE` blocks wide and
+/// tall, centered on and leading out from a connector.
+struct Tunnel {
+    connector: Connector,
+}
+
+impl Tunnel {
+    /// Returns the position of the `i`th floor block along the
+    /// center of the tunnel, moved `side` blocks to its right.
+    fn block(&self, i: i32, side: i32) -> (i32, i32, i32) {
+        let (x, y, z) = self.connector.position;
+        let (dx, dz) = direction_offset(self.connector.direction);
+        // Rotating the forward offset gives the offset to the right.
+        let (rx, rz) = (-dz, dx);
+        (x + dx * i + rx * side, y, z + dz * i + rz * side)
+    }
+
+    /// Returns the box containing the first `length`
+    /// blocks of the tunnel, including their floor.
+    fn bounding_box(&self, length: i32) -> BoundingBox {
+        let half = TUNNEL_SIZE / 2;
+        let start = self.block(0, -half);
+        let end = self.block(length - 1, half);
+        BoundingBox::new(
+            (start.0, start.1 - 1, start.2),
+            (end.0, end.1 + TUNNEL_SIZE - 1, end.2),
+        )
+    }
+
+    /// Returns a connector leading out of the side of the
+    /// tunnel at its `i`th block, to the right if `side` is
+    /// positive or to the left otherwise.
+    fn side_connector(&self, i: i32, side: i32) -> Connector {
+        let side = side.signum() * (TUNNEL_SIZE / 2 + 1);
+        let direction = match (self.connector.direction, side > 0) {
+            (Direction::North, true) | (Direction::South, false) => Direction::East,
+            (Direction::North, false) | (Direction::South, true) => Direction::West,
+            (Direction::East, true) | (Direction::West, false) => Direction::South,
+            (Direction::East, false) | (Direction::West, true) => Direction::North,
+        };
+        Connector {
+            position: self.block(i, side),
+            direction,
+        }
+    }
+
+    /// Carves out the first `length` blocks of the tunnel, bridging
+    /// gaps in its floor so that it can cross caves.
+    fn carve(&self, chunk: &mut ClippedChunk, length: i32) {
+        let half = TUNNEL_SIZE / 2;
+        for i in 0..length {
+            for side in -half..=half {
+                let (x, y, z) = self.block(i, side);
+                for dy in 0..TUNNEL_SIZE {
+                    chunk.set_block_at(x, y + dy, z, BlockId::cave_air());
+                }
+                if chunk
+                    .block_at(x, y - 1, z)
+                    .map_or(false, |block| !block.is_solid())
+                {
+                    chunk.set_block_at(x, y - 1, z, BlockId::oak_planks());
+                }
+            }
+        }
+    }
+}
+
+/// A corridor with a wooden support at the end of
+/// each segment, and optionally rails and a chest.
+struct Corridor {
+    tunnel: Tunnel,
+    length: i32,
+    rails: bool,
+    /// Index of the block along the corridor beside which a chest is placed.
+    chest: Option<i32>,
+    /// Varies the placement of cobwebs between corridors.
+    cobweb_salt: u32,
+}
+
+impl StructurePiece for Corridor {
+    fn bounding_box(&self) -> BoundingBox {
+        self.tunnel.bounding_box(self.length)
+    }
+
+    fn connectors(&self) -> Vec<Connector> {
+        vec![Connector {
+            position: self.tunnel.block(self.length, 0),
+            direction: self.tunnel.connector.direction,
+        }]
+    }
+
+    fn place(&self, chunk: &mut ClippedChunk) {
+        let tunnel = &self.tunnel;
+        tunnel.carve(chunk, self.length);
+
+        let half = TUNNEL_SIZE / 2;
+        let top = TUNNEL_SIZE - 1;
+        for i in 0..self.length {
+            if i % SEGMENT_LENGTH == SEGMENT_LENGTH - 1 {
+                // Fence posts on both walls, holding up a plank beam.
+                for &side in &[-half, half] {
+                    let (x, y, z) = tunnel.block(i, side);
+                    for dy in 0..top {
+                        chunk.set_block_at(x, y + dy, z, BlockId::oak_fence());
+                    }
+                }
+                for side in -half..=half {
+                    let (x, y, z) = tunnel.block(i, side);
+                    chunk.set_block_at(x, y + top, z, BlockId::oak_planks());
+                }
+            } else {
+                for side in -half..=half {
+                    let (x, y, z) = tunnel.block(i, side);
+                    if is_cobweb(x, y + top, z, self.cobweb_salt) {
+                        chunk.set_block_at(x, y + top, z, BlockId::cobweb());
+                    }
+                }
+            }
+
+            if self.rails {
+                let (x, y, z) = tunnel.block(i, 0);
+                let shape = match tunnel.connector.direction {
+                    Direction::North | Direction::South => RailShape::NorthSouth,
+                    Direction::East | Direction::West => RailShape::EastWest,
+                };
+                chunk.set_block_at(x, y, z, BlockId::rail().with_rail_shape(shape));
+            }
+        }
+
+        if let Some(i) = self.chest {
+            // Beside the track, off the support posts.
+            let i = if i % SEGMENT_LENGTH == SEGMENT_LENGTH - 1 {
+                i - 1
+            } else {
+                i
+            };
+            let (x, y, z) = tunnel.block(i, half);
+            chunk.set_block_at(x, y, z, BlockId::chest());
+        }
+    }
+}
+
+/// A short section of tunnel with openings
+/// straight ahead and to both sides.
+struct Crossing {
+    tunnel: Tunnel,
+}
+
+impl StructurePiece for Crossing {
+    fn bounding_box(&self) -> BoundingBox {
+        self.tunnel.bounding_box(TUNNEL_SIZE)
+    }
+
+    fn connectors(&self) -> Vec<Connector> {
+        let mid = TUNNEL_SIZE / 2;
+        vec![
+            Connector {
+                position: self.tunnel.block(TUNNEL_SIZE, 0),
+                direction: self.tunnel.connector.direction,
+            },
+            self.tunnel.side_connector(mid, -1),
+            self.tunnel.side_connector(mid, 1),
+        ]
+    }
+
+    fn place(&self, chunk: &mut ClippedChunk) {
+        self.tunnel.carve(chunk, TUNNEL_SIZE);
+    }
+}
+
+/// Deterministically picks about one in eight ceiling blocks to hold a cobweb.
+fn is_cobweb(x: i32, y: i32, z: i32, salt: u32) -> bool {
+    let hash = (x.wrapping_mul(73_856_093))
+        ^ (y.wrapping_mul(19_349_663))
+        ^ (z.wrapping_mul(83_492_791))
+        ^ salt as i32;
+    hash & 7 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::{place_structures, structure_chunk};
+    use crate::util::feature_random;
+    use base::Chunk;
+
+    const SEED: u64 = 31;
+
+    fn mineshaft_origin() -> ChunkPosition {
+        (0..16)
+            .flat_map(|x| (0..16).map(move |z| (x, z)))
+            .find_map(|(x, z)| structure_chunk(SEED, x, z, 8, 2, Mineshaft.salt()))
+            .map(|(x, z)| ChunkPosition::new(x, z))
+            .expect("no mineshaft found")
+    }
+
+    fn plan(origin: ChunkPosition) -> Structure {
+        let mut rng = feature_random(SEED, origin.x, origin.z, Mineshaft.salt());
+        Mineshaft.generate(origin, &mut rng)
+    }
+
+    #[test]
+    fn mineshaft_pieces_do_not_overlap() {
+        let mineshaft = plan(mineshaft_origin());
+        let pieces = mineshaft.pieces();
+        assert!(pieces.len() > 1);
+        for (i, a) in pieces.iter().enumerate() {
+            for b in &pieces[i + 1..] {
+                assert!(!a.bounding_box().intersects(&b.bounding_box()));
+            }
+        }
+    }
+
+    #[test]
+    fn mineshaft_fits_within_radius() {
+        let origin = mineshaft_origin();
+        let bbox = plan(origin).bounding_box().unwrap();
+        let radius = Mineshaft.max_radius();
+        for x in origin.x - radius - 1..=origin.x + radius + 1 {
+            for z in origin.z - radius - 1..=origin.z + radius + 1 {
+                let outside = (x - origin.x).abs() > radius || (z - origin.z).abs() > radius;
+                if outside {
+                    assert!(!bbox.intersects_chunk(ChunkPosition::new(x, z)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mineshaft_is_deterministic() {
+        let origin = mineshaft_origin();
+        let boxes = |structure: Structure| -> Vec<BoundingBox> {
+            structure
+                .pieces()
+                .iter()
+                .map(|piece| piece.bounding_box())
+                .collect()
+        };
+        assert_eq!(boxes(plan(origin)), boxes(plan(origin)));
+    }
+
+    #[test]
+    fn corridors_have_supports() {
+        let origin = mineshaft_origin();
+        let bbox = plan(origin).bounding_box().unwrap();
+        let types: Vec<Box<dyn StructureType>> = vec![Box::new(Mineshaft)];
+
+        let mut fences = 0;
+        for x in bbox.min.0.div_euclid(16)..=bbox.max.0.div_euclid(16) {
+            for z in bbox.min.2.div_euclid(16)..=bbox.max.2.div_euclid(16) {
+                let mut chunk = Chunk::new(ChunkPosition::new(x, z));
+                place_structures(&mut chunk, &types, SEED, |_| Biome::Plains);
+                fences += (0..16)
+                    .flat_map(|x| (0..256).flat_map(move |y| (0..16).map(move |z| (x, y, z))))
+                    .filter(|&(x, y, z)| chunk.block_at(x, y, z) == Some(BlockId::oak_fence()))
+                    .count();
+            }
+        }
+        assert!(fences > 0);
+    }
+}
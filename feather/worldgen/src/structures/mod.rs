@@ -0,0 +1,410 @@
+//! Placement and generation of structures like villages and temples.
+//!
+//! Structures are placed on a grid of square regions, `spacing`
+//! chunks wide. Each region contains exactly one candidate chunk,
+//! chosen randomly from the region's seed but never within
+//! `separation` chunks of the region's far edges, so that
+//! structures in neighboring regions are spread apart.
+//!
+//! A structure is assembled from pieces, each covering a bounding
+//! box in world coordinates, and may span several chunks. Rather than
+//! storing partially placed structures, every chunk plans the structures
+//! whose candidate chunks are close enough to reach it and places
+//! the blocks which fall inside it. Since planning depends only on the
+//! seed, structures come out whole in whatever order chunks are generated.
+
+mod desert_temple;
+mod dungeon;
+mod mineshaft;
+mod stronghold;
+pub mod template;
+mod village;
+
+pub use desert_temple::DesertTemple;
+pub use dungeon::Dungeon;
+pub use mineshaft::Mineshaft;
+pub use stronghold::{plan_stronghold, stronghold_positions, StrongholdStage, Strongholds};
+pub use template::Template;
+pub use village::Village;
+
+use crate::util::feature_random;
+use base::{Biome, BlockId, Chunk, ChunkPosition, Direction};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use std::collections::VecDeque;
+
+/// An axis-aligned box of blocks in world coordinates,
+/// inclusive on both ends.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min: (i32, i32, i32),
+    pub max: (i32, i32, i32),
+}
+
+impl BoundingBox {
+    /// Creates a bounding box spanning the two corners, in any order.
+    pub fn new(a: (i32, i32, i32), b: (i32, i32, i32)) -> Self {
+        Self {
+            min: (a.0.min(b.0), a.1.min(b.1), a.2.min(b.2)),
+            max: (a.0.max(b.0), a.1.max(b.1), a.2.max(b.2)),
+        }
+    }
+
+    /// Creates a bounding box with its lowest corner at `origin`
+    /// and the given width (X), height (Y), and depth (Z).
+    ///
+    /// # Panics
+    /// Panics if any dimension is zero.
+    pub fn from_size(origin: (i32, i32, i32), size: (u32, u32, u32)) -> Self {
+        assert!(size.0 > 0 && size.1 > 0 && size.2 > 0);
+        Self {
+            min: origin,
+            max: (
+                origin.0 + size.0 as i32 - 1,
+                origin.1 + size.1 as i32 - 1,
+                origin.2 + size.2 as i32 - 1,
+            ),
+        }
+    }
+
+    /// Returns whether the two boxes share at least one block.
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.0 <= other.max.0
+            && other.min.0 <= self.max.0
+            && self.min.1 <= other.max.1
+            && other.min.1 <= self.max.1
+            && self.min.2 <= other.max.2
+            && other.min.2 <= self.max.2
+    }
+
+    /// Returns whether the box overlaps the columns of the given chunk.
+    pub fn intersects_chunk(&self, chunk: ChunkPosition) -> bool {
+        let (x, z) = (chunk.x * 16, chunk.z * 16);
+        self.min.0 <= x + 15 && x <= self.max.0 && self.min.2 <= z + 15 && z <= self.max.2
+    }
+
+    /// Returns the smallest box containing both boxes.
+    pub fn union(&self, other: &BoundingBox) -> Self {
+        Self::new(
+            (
+                self.min.0.min(other.min.0),
+                self.min.1.min(other.min.1),
+                self.min.2.min(other.min.2),
+            ),
+            (
+                self.max.0.max(other.max.0),
+                self.max.1.max(other.max.1),
+                self.max.2.max(other.max.2),
+            ),
+        )
+    }
+}
+
+/// A point at which another piece can be attached to a piece.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Connector {
+    /// The first block outside the piece, where
+    /// the attached piece should start.
+    pub position: (i32, i32, i32),
+    /// The direction leading away from the piece.
+    pub direction: Direction,
+}
+
+/// Returns the X and Z offset of one block in the given direction.
+pub fn direction_offset(direction: Direction) -> (i32, i32) {
+    match direction {
+        Direction::North => (0, -1),
+        Direction::South => (0, 1),
+        Direction::East => (1, 0),
+        Direction::West => (-1, 0),
+    }
+}
+
+/// A part of a structure, such as a room or corridor.
+pub trait StructurePiece: Send + Sync {
+    /// Returns the blocks which this piece may modify.
+    fn bounding_box(&self) -> BoundingBox;
+
+    /// Returns the points at which other pieces
+    /// can be attached to this one.
+    fn connectors(&self) -> Vec<Connector> {
+        Vec::new()
+    }
+
+    /// Places the part of this piece which lies inside `chunk`.
+    fn place(&self, chunk: &mut ClippedChunk);
+}
+
+/// A chunk addressed using world coordinates, which
+/// ignores blocks outside of it.
+pub struct ClippedChunk<'a> {
+    chunk: &'a mut Chunk,
+}
+
+impl<'a> ClippedChunk<'a> {
+    pub fn new(chunk: &'a mut Chunk) -> Self {
+        Self { chunk }
+    }
+
+    /// Gets the block at the given world coordinates,
+    /// or `None` if it is outside the chunk.
+    pub fn block_at(&self, x: i32, y: i32, z: i32) -> Option<BlockId> {
+        let (x, y, z) = self.local(x, y, z)?;
+        self.chunk.block_at(x, y, z)
+    }
+
+    /// Sets the block at the given world coordinates,
+    /// doing nothing if it is outside the chunk.
+    pub fn set_block_at(&mut self, x: i32, y: i32, z: i32, block: BlockId) {
+        if let Some((x, y, z)) = self.local(x, y, z) {
+            self.chunk.set_block_at(x, y, z, block);
+        }
+    }
+
+    fn local(&self, x: i32, y: i32, z: i32) -> Option<(usize, usize, usize)> {
+        let pos = self.chunk.position();
+        let (x, z) = (x - pos.x * 16, z - pos.z * 16);
+        if (0..16).contains(&x) && (0..256).contains(&y) && (0..16).contains(&z) {
+            Some((x as usize, y as usize, z as usize))
+        } else {
+            None
+        }
+    }
+}
+
+/// A structure made up of non-overlapping pieces.
+#[derive(Default)]
+pub struct Structure {
+    pieces: Vec<Box<dyn StructurePiece>>,
+}
+
+impl Structure {
+    pub fn new(pieces: Vec<Box<dyn StructurePiece>>) -> Self {
+        Self { pieces }
+    }
+
+    pub fn pieces(&self) -> &[Box<dyn StructurePiece>] {
+        &self.pieces
+    }
+
+    /// Returns the box containing every piece, or
+    /// `None` if the structure has no pieces.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.pieces
+            .iter()
+            .map(|piece| piece.bounding_box())
+            .fold(None, |acc: Option<BoundingBox>, bbox| {
+                Some(acc.map_or(bbox, |acc| acc.union(&bbox)))
+            })
+    }
+
+    /// Places the parts of every piece which lie inside `chunk`.
+    pub fn place_in_chunk(&self, chunk: &mut Chunk) {
+        let position = chunk.position();
+        let mut chunk = ClippedChunk::new(chunk);
+        for piece in &self.pieces {
+            if piece.bounding_box().intersects_chunk(position) {
+                piece.place(&mut chunk);
+            }
+        }
+    }
+}
+
+/// Assembles a structure by repeatedly attaching pieces to
+/// the connectors of existing ones, starting from `start`.
+///
+/// `attach` is called for each open connector and may return a
+/// piece to attach there. Pieces which would overlap an existing
+/// piece are discarded. Pieces further than `max_depth` attachments
+/// from the start have their connectors left unused.
+pub fn assemble(
+    start: Box<dyn StructurePiece>,
+    max_depth: u32,
+    rng: &mut XorShiftRng,
+    mut attach: impl FnMut(Connector, &mut XorShiftRng) -> Option<Box<dyn StructurePiece>>,
+) -> Structure {
+    let mut pieces = vec![start];
+    let mut queue = VecDeque::new();
+    queue.push_back((0, 0));
+
+    while let Some((index, depth)) = queue.pop_front() {
+        if depth >= max_depth {
+            continue;
+        }
+
+        for connector in pieces[index].connectors() {
+            let piece = match attach(connector, rng) {
+                Some(piece) => piece,
+                None => continue,
+            };
+            let bbox = piece.bounding_box();
+            if pieces
+                .iter()
+                .any(|existing| existing.bounding_box().intersects(&bbox))
+            {
+                continue;
+            }
+
+            pieces.push(piece);
+            queue.push_back((pieces.len() - 1, depth + 1));
+        }
+    }
+
+    Structure::new(pieces)
+}
+
+/// A kind of structure generated by the `ComposableGenerator`.
+pub trait StructureType: Send + Sync {
+    /// Width of placement regions, in chunks.
+    fn spacing(&self) -> u32;
+
+    /// Minimum distance between structures
+    /// in neighboring regions, in chunks.
+    fn separation(&self) -> u32;
+
+    /// Distinguishes this structure type's placement from others.
+    fn salt(&self) -> u64;
+
+    /// Maximum distance, in chunks, a structure can
+    /// extend from the chunk it was planned in.
+    fn max_radius(&self) -> i32;
+
+    /// Returns whether the structure can generate in a chunk
+    /// with the given biome at its center.
+    fn can_generate_in(&self, biome: Biome) -> bool;
+
+    /// Plans a structure in the given chunk.
+    ///
+    /// Must be deterministic with respect to `rng`,
+    /// since it is called once for each chunk the
+    /// structure overlaps.
+    fn generate(&self, origin: ChunkPosition, rng: &mut XorShiftRng) -> Structure;
+}
+
+/// Places the parts of all structures of the given types which lie in `chunk`.
+///
+/// `biome_at` should return the biome at the center of the given chunk.
+pub fn place_structures(
+    chunk: &mut Chunk,
+    types: &[Box<dyn StructureType>],
+    world_seed: u64,
+    biome_at: impl Fn(ChunkPosition) -> Biome,
+) {
+    let position = chunk.position();
+    for structure_type in types {
+        let radius = structure_type.max_radius();
+        for dz in -radius..=radius {
+            for dx in -radius..=radius {
+                let (x, z) = (position.x + dx, position.z + dz);
+                let origin = match structure_chunk(
+                    world_seed,
+                    x,
+                    z,
+                    structure_type.spacing(),
+                    structure_type.separation(),
+                    structure_type.salt(),
+                ) {
+                    Some((x, z)) => ChunkPosition::new(x, z),
+                    None => continue,
+                };
+                if !structure_type.can_generate_in(biome_at(origin)) {
+                    continue;
+                }
+
+                let mut rng = feature_random(world_seed, origin.x, origin.z, structure_type.salt());
+                structure_type
+                    .generate(origin, &mut rng)
+                    .place_in_chunk(chunk);
+            }
+        }
+    }
+}
+
+/// Returns the position of the structure's chunk if the region
+/// containing `(chunk_x, chunk_z)` places its structure in that chunk.
+///
+/// `salt` distinguishes different structure types,
+/// so that they don't all land in the same chunks.
+///
+/// # Panics
+/// Panics if `separation` is not less than `spacing`.
+pub fn structure_chunk(
+    world_seed: u64,
+    chunk_x: i32,
+    chunk_z: i32,
+    spacing: u32,
+    separation: u32,
+    salt: u64,
+) -> Option<(i32, i32)> {
+    assert!(
+        separation < spacing,
+        "structure separation must be less than spacing"
+    );
+
+    let spacing = spacing as i32;
+    let region_x = chunk_x.div_euclid(spacing);
+    let region_z = chunk_z.div_euclid(spacing);
+
+    let region_seed = (region_x as u64)
+        .wrapping_mul(341_873_128_712)
+        .wrapping_add((region_z as u64).wrapping_mul(132_897_987_541))
+        .wrapping_add(world_seed)
+        .wrapping_add(salt);
+    let mut rng = XorShiftRng::seed_from_u64(region_seed);
+
+    let range = spacing - separation as i32;
+    let x = region_x * spacing + rng.gen_range(0, range);
+    let z = region_z * spacing + rng.gen_range(0, range);
+
+    if (x, z) == (chunk_x, chunk_z) {
+        Some((x, z))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn structures_in(seed: u64, salt: u64) -> Vec<(i32, i32)> {
+        let mut structures = Vec::new();
+        for chunk_x in -64..64 {
+            for chunk_z in -64..64 {
+                if let Some(pos) = structure_chunk(seed, chunk_x, chunk_z, 32, 8, salt) {
+                    structures.push(pos);
+                }
+            }
+        }
+        structures
+    }
+
+    #[test]
+    fn one_structure_per_region() {
+        let structures = structures_in(1234, 10_387_312);
+        assert_eq!(structures.len(), 4 * 4);
+    }
+
+    #[test]
+    fn structures_are_separated() {
+        let structures = structures_in(1234, 10_387_312);
+        for (i, a) in structures.iter().enumerate() {
+            for b in &structures[i + 1..] {
+                let distance = (a.0 - b.0).abs().max((a.1 - b.1).abs());
+                assert!(distance >= 8, "{:?} and {:?} are too close", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn placement_is_reproducible() {
+        assert_eq!(
+            structures_in(1234, 10_387_312),
+            structures_in(1234, 10_387_312)
+        );
+        assert_ne!(
+            structures_in(1234, 10_387_312),
+            structures_in(1234, 14_357_617)
+        );
+    }
+}
@@ -0,0 +1,514 @@
+use super::{
+    assemble, direction_offset, BoundingBox, ClippedChunk, Connector, Structure, StructurePiece,
+};
+use crate::util::feature_random;
+use crate::{GenContext, GenStage, GenerationStage};
+use base::{BlockId, Chunk, ChunkPosition, Direction, FacingCardinal};
+use once_cell::sync::OnceCell;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use std::f64::consts::PI;
+
+/// Number of strongholds in a world.
+const COUNT: usize = 128;
+/// Distance between rings of strongholds, in chunks.
+const RING_DISTANCE: f64 = 32.0;
+/// Number of strongholds in the innermost ring.
+const FIRST_RING_SIZE: usize = 3;
+/// Distinguishes stronghold placement from other structures.
+const SALT: u64 = 11_489_201;
+
+/// Width (X) of the portal room, including walls.
+const PORTAL_ROOM_WIDTH: i32 = 11;
+/// Height of the portal room, including floor and ceiling.
+const PORTAL_ROOM_HEIGHT: i32 = 8;
+/// Depth (Z) of the portal room, including walls.
+const PORTAL_ROOM_DEPTH: i32 = 16;
+/// Distance from the center of a hall to its walls.
+const HALF_WIDTH: i32 = 2;
+/// Height of the inside of a hall.
+const HALL_HEIGHT: i32 = 3;
+/// Length and width of a crossing, including walls.
+const CROSSING_SIZE: i32 = 9;
+/// Maximum length of a corridor.
+const MAX_CORRIDOR_LENGTH: i32 = 9;
+/// Maximum number of pieces between the portal room and a hall's end.
+const MAX_DEPTH: u32 = 6;
+/// Maximum distance, in chunks, a stronghold
+/// can extend from the chunk it was planned in.
+const MAX_RADIUS: i32 =
+    (16 + PORTAL_ROOM_DEPTH + CROSSING_SIZE.max(MAX_CORRIDOR_LENGTH) * MAX_DEPTH as i32) / 16 + 1;
+
+/// Returns the chunks in which the strongholds of the world start.
+///
+/// Like vanilla, strongholds are spread over rings around the
+/// origin: three in the innermost ring, about 128 chunks away,
+/// and more in each ring further out.
+pub fn stronghold_positions(world_seed: u64) -> Vec<ChunkPosition> {
+    let mut rng = XorShiftRng::seed_from_u64(world_seed ^ SALT);
+    let mut positions = Vec::with_capacity(COUNT);
+
+    let mut angle = rng.gen::<f64>() * PI * 2.0;
+    let mut ring = 0;
+    let mut ring_size = FIRST_RING_SIZE;
+    let mut placed_in_ring = 0;
+    for i in 0..COUNT {
+        let distance = RING_DISTANCE * (4.0 + ring as f64 * 6.0)
+            + (rng.gen::<f64>() - 0.5) * RING_DISTANCE * 2.5;
+        positions.push(ChunkPosition::new(
+            (angle.cos() * distance).round() as i32,
+            (angle.sin() * distance).round() as i32,
+        ));
+
+        angle += PI * 2.0 / ring_size as f64;
+        placed_in_ring += 1;
+        if placed_in_ring == ring_size {
+            ring += 1;
+            placed_in_ring = 0;
+            ring_size += 2 * ring_size / (ring + 1);
+            ring_size = ring_size.min(COUNT - i - 1);
+            angle += rng.gen::<f64>() * PI * 2.0;
+        }
+    }
+    positions
+}
+
+/// The strongholds of a world, computed once per seed.
+#[derive(Debug)]
+pub struct Strongholds {
+    world_seed: u64,
+    positions: Vec<ChunkPosition>,
+}
+
+impl Strongholds {
+    /// Computes the positions of the strongholds of the world.
+    pub fn new(world_seed: u64) -> Self {
+        Self {
+            world_seed,
+            positions: stronghold_positions(world_seed),
+        }
+    }
+
+    /// Returns the chunks in which the strongholds start.
+    pub fn positions(&self) -> &[ChunkPosition] {
+        &self.positions
+    }
+
+    /// Returns the start chunk of the stronghold closest
+    /// to the given chunk, as an Eye of Ender would find it.
+    pub fn nearest(&self, chunk: ChunkPosition) -> ChunkPosition {
+        self.positions
+            .iter()
+            .copied()
+            .min_by_key(|pos| {
+                let (dx, dz) = ((pos.x - chunk.x) as i64, (pos.z - chunk.z) as i64);
+                dx * dx + dz * dz
+            })
+            .expect("no strongholds")
+    }
+
+    /// Places the parts of strongholds which lie in `chunk`.
+    pub fn place_in_chunk(&self, chunk: &mut Chunk) {
+        let position = chunk.position();
+        for &origin in &self.positions {
+            if (origin.x - position.x).abs() > MAX_RADIUS
+                || (origin.z - position.z).abs() > MAX_RADIUS
+            {
+                continue;
+            }
+            let mut rng = feature_random(self.world_seed, origin.x, origin.z, SALT);
+            plan_stronghold(origin, &mut rng).place_in_chunk(chunk);
+        }
+    }
+}
+
+/// Plans the stronghold starting in the given chunk: a room
+/// holding the end portal, with stone brick corridors and
+/// crossings branching out from it.
+///
+/// Unlike vanilla, the halls don't change level and the
+/// room is always entered from the north.
+pub fn plan_stronghold(origin: ChunkPosition, rng: &mut XorShiftRng) -> Structure {
+    let portal_room = PortalRoom {
+        origin: (
+            origin.x * 16 + rng.gen_range(0, 16 - PORTAL_ROOM_WIDTH),
+            rng.gen_range(10, 25),
+            origin.z * 16 + rng.gen_range(0, 16 - PORTAL_ROOM_WIDTH),
+        ),
+        eyes: {
+            let mut eyes = [false; 12];
+            eyes.iter_mut()
+                .for_each(|eye| *eye = rng.gen_range(0, 10) == 0);
+            eyes
+        },
+    };
+    assemble(
+        Box::new(portal_room),
+        MAX_DEPTH,
+        rng,
+        |connector, rng| match rng.gen_range(0, 8) {
+            0 => None,
+            1 | 2 => Some(Box::new(Hall {
+                connector,
+                length: CROSSING_SIZE,
+                doors: [true, rng.gen(), rng.gen()],
+            })),
+            _ => Some(Box::new(Hall {
+                connector,
+                length: rng.gen_range(4, MAX_CORRIDOR_LENGTH + 1),
+                doors: [true, false, false],
+            })),
+        },
+    )
+}
+
+/// Generation stage placing the parts of strongholds
+/// which lie in the chunk.
+///
+/// The stronghold positions are computed on the first
+/// chunk and reused for the rest.
+#[derive(Debug, Default)]
+pub struct StrongholdStage {
+    strongholds: OnceCell<Strongholds>,
+}
+
+impl GenStage for StrongholdStage {
+    fn name(&self) -> &str {
+        "strongholds"
+    }
+
+    fn stage(&self) -> GenerationStage {
+        GenerationStage::Structures
+    }
+
+    fn apply(&self, chunk: &mut Chunk, ctx: &GenContext) {
+        let seed = ctx.seed(self.stage());
+        let strongholds = self.strongholds.get_or_init(|| Strongholds::new(seed));
+        debug_assert_eq!(strongholds.world_seed, seed);
+        strongholds.place_in_chunk(chunk);
+    }
+}
+
+/// The room containing the end portal frame, raised
+/// over a pool of lava, and a silverfish spawner.
+struct PortalRoom {
+    /// Lowest corner of the room's walls.
+    origin: (i32, i32, i32),
+    /// Whether each portal frame holds an eye of ender.
+    eyes: [bool; 12],
+}
+
+impl PortalRoom {
+    /// Returns the positions of the portal frame blocks
+    /// and the directions they face, toward the portal.
+    fn frames(&self) -> Vec<((i32, i32, i32), FacingCardinal)> {
+        let (ox, oy, oz) = self.origin;
+        let (x, y, z) = (ox + PORTAL_ROOM_WIDTH / 2, oy + 3, oz + 11);
+        let mut frames = Vec::with_capacity(12);
+        for i in -1..=1 {
+            frames.push(((x + i, y, z - 2), FacingCardinal::South));
+            frames.push(((x + i, y, z + 2), FacingCardinal::North));
+            frames.push(((x - 2, y, z + i), FacingCardinal::East));
+            frames.push(((x + 2, y, z + i), FacingCardinal::West));
+        }
+        frames
+    }
+}
+
+impl StructurePiece for PortalRoom {
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::from_size(
+            self.origin,
+            (
+                PORTAL_ROOM_WIDTH as u32,
+                PORTAL_ROOM_HEIGHT as u32,
+                PORTAL_ROOM_DEPTH as u32,
+            ),
+        )
+    }
+
+    fn connectors(&self) -> Vec<Connector> {
+        let (x, y, z) = self.origin;
+        vec![Connector {
+            position: (x + PORTAL_ROOM_WIDTH / 2, y + 1, z - 1),
+            direction: Direction::North,
+        }]
+    }
+
+    fn place(&self, chunk: &mut ClippedChunk) {
+        let (ox, oy, oz) = self.origin;
+        let (max_x, max_y, max_z) = (
+            ox + PORTAL_ROOM_WIDTH - 1,
+            oy + PORTAL_ROOM_HEIGHT - 1,
+            oz + PORTAL_ROOM_DEPTH - 1,
+        );
+        for x in ox..=max_x {
+            for y in oy..=max_y {
+                for z in oz..=max_z {
+                    let wall =
+                        x == ox || x == max_x || y == oy || y == max_y || z == oz || z == max_z;
+                    let block = if wall {
+                        stone_bricks(x, y, z)
+                    } else {
+                        BlockId::cave_air()
+                    };
+                    chunk.set_block_at(x, y, z, block);
+                }
+            }
+        }
+
+        // The doorway.
+        let mid_x = ox + PORTAL_ROOM_WIDTH / 2;
+        for x in mid_x - 1..=mid_x + 1 {
+            for y in oy + 1..=oy + HALL_HEIGHT {
+                chunk.set_block_at(x, y, oz, BlockId::cave_air());
+            }
+        }
+
+        // The platform under the portal, with lava in its middle.
+        let (portal_x, portal_z) = (mid_x, oz + 11);
+        for x in portal_x - 3..=portal_x + 3 {
+            for z in portal_z - 3..=portal_z + 3 {
+                for y in oy + 1..=oy + 2 {
+                    let lava =
+                        y == oy + 2 && (x - portal_x).abs() <= 1 && (z - portal_z).abs() <= 1;
+                    let block = if lava {
+                        BlockId::lava()
+                    } else {
+                        stone_bricks(x, y, z)
+                    };
+                    chunk.set_block_at(x, y, z, block);
+                }
+            }
+        }
+
+        for (((x, y, z), facing), &eye) in self.frames().into_iter().zip(self.eyes.iter()) {
+            let frame = BlockId::end_portal_frame()
+                .with_facing_cardinal(facing)
+                .with_eye(eye);
+            chunk.set_block_at(x, y, z, frame);
+        }
+
+        chunk.set_block_at(mid_x, oy + 1, oz + 4, BlockId::spawner());
+    }
+}
+
+/// A stone brick hall leading out from a connector,
+/// three blocks wide and tall inside.
+///
+/// Corridors are open at both ends, while crossings are
+/// walled, with a door at each end and optional doors in
+/// the middle of their sides.
+struct Hall {
+    connector: Connector,
+    /// Length of the hall, including end walls for crossings.
+    length: i32,
+    /// Whether there is a door forward, to the
+    /// left, and to the right, in that order.
+    doors: [bool; 3],
+}
+
+impl Hall {
+    fn is_crossing(&self) -> bool {
+        self.length == CROSSING_SIZE
+    }
+
+    fn half_width(&self) -> i32 {
+        if self.is_crossing() {
+            CROSSING_SIZE / 2
+        } else {
+            HALF_WIDTH
+        }
+    }
+
+    /// Returns the position of the `i`th block along the center of
+    /// the hall, at floor level, moved `side` blocks to its right.
+    fn block(&self, i: i32, side: i32) -> (i32, i32, i32) {
+        let (x, y, z) = self.connector.position;
+        let (dx, dz) = direction_offset(self.connector.direction);
+        // Rotating the forward offset gives the offset to the right.
+        let (rx, rz) = (-dz, dx);
+        (x + dx * i + rx * side, y, z + dz * i + rz * side)
+    }
+
+    /// Returns whether the block is in one of the hall's doorways.
+    fn is_doorway(&self, i: i32, side: i32, dy: i32) -> bool {
+        if !(0..HALL_HEIGHT).contains(&dy) {
+            return false;
+        }
+        let half = self.half_width();
+        let (end, middle) = (side.abs() <= 1, (i - self.length / 2).abs() <= 1);
+        (i == 0 && end)
+            || (i == self.length - 1 && end && self.doors[0])
+            || (side == -half && middle && self.doors[1])
+            || (side == half && middle && self.doors[2])
+    }
+}
+
+impl StructurePiece for Hall {
+    fn bounding_box(&self) -> BoundingBox {
+        let half = self.half_width();
+        let start = self.block(0, -half);
+        let end = self.block(self.length - 1, half);
+        BoundingBox::new(
+            (start.0, start.1 - 1, start.2),
+            (end.0, end.1 + HALL_HEIGHT, end.2),
+        )
+    }
+
+    fn connectors(&self) -> Vec<Connector> {
+        let half = self.half_width();
+        let (left, right) = match self.connector.direction {
+            Direction::North => (Direction::West, Direction::East),
+            Direction::South => (Direction::East, Direction::West),
+            Direction::East => (Direction::North, Direction::South),
+            Direction::West => (Direction::South, Direction::North),
+        };
+        let end = self.block(self.length, 0);
+        let mut connectors = Vec::new();
+        if self.doors[0] {
+            connectors.push(Connector {
+                position: end,
+                direction: self.connector.direction,
+            });
+        }
+        if self.doors[1] {
+            connectors.push(Connector {
+                position: self.block(self.length / 2, -half - 1),
+                direction: left,
+            });
+        }
+        if self.doors[2] {
+            connectors.push(Connector {
+                position: self.block(self.length / 2, half + 1),
+                direction: right,
+            });
+        }
+        connectors
+    }
+
+    fn place(&self, chunk: &mut ClippedChunk) {
+        let half = self.half_width();
+        let crossing = self.is_crossing();
+        for i in 0..self.length {
+            for side in -half..=half {
+                let (x, y, z) = self.block(i, side);
+                for dy in -1..=HALL_HEIGHT {
+                    let end_wall = crossing && (i == 0 || i == self.length - 1);
+                    let wall = side.abs() == half || dy == -1 || dy == HALL_HEIGHT || end_wall;
+                    let block = if wall && !self.is_doorway(i, side, dy) {
+                        stone_bricks(x, y + dy, z)
+                    } else {
+                        BlockId::cave_air()
+                    };
+                    chunk.set_block_at(x, y + dy, z, block);
+                }
+            }
+        }
+    }
+}
+
+/// Picks a deterministic mix of plain, mossy,
+/// and cracked stone bricks for a wall block.
+fn stone_bricks(x: i32, y: i32, z: i32) -> BlockId {
+    let hash =
+        (x.wrapping_mul(73_856_093)) ^ (y.wrapping_mul(19_349_663)) ^ (z.wrapping_mul(83_492_791));
+    match hash & 7 {
+        0 => BlockId::mossy_stone_bricks(),
+        1 => BlockId::cracked_stone_bricks(),
+        _ => BlockId::stone_bricks(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::BlockKind;
+
+    const SEED: u64 = 8;
+
+    fn plan(origin: ChunkPosition) -> Structure {
+        let mut rng = feature_random(SEED, origin.x, origin.z, SALT);
+        plan_stronghold(origin, &mut rng)
+    }
+
+    #[test]
+    fn strongholds_are_placed_in_rings() {
+        let positions = stronghold_positions(SEED);
+        assert_eq!(positions.len(), COUNT);
+        assert_eq!(positions, stronghold_positions(SEED));
+        assert_ne!(positions, stronghold_positions(SEED + 1));
+
+        let distance = |pos: &ChunkPosition| ((pos.x * pos.x + pos.z * pos.z) as f64).sqrt();
+        for pos in &positions[..FIRST_RING_SIZE] {
+            assert!((80.0..=176.0).contains(&distance(pos)), "{:?}", pos);
+        }
+        // The second ring is further out.
+        assert!(distance(&positions[FIRST_RING_SIZE]) > 176.0);
+    }
+
+    #[test]
+    fn nearest_stronghold_is_closest() {
+        let strongholds = Strongholds::new(SEED);
+        let positions = strongholds.positions();
+        for &pos in &positions[..10] {
+            assert_eq!(strongholds.nearest(pos), pos);
+        }
+        let nearest = strongholds.nearest(ChunkPosition::new(0, 0));
+        assert!(positions[..FIRST_RING_SIZE].contains(&nearest));
+    }
+
+    #[test]
+    fn stronghold_pieces_do_not_overlap() {
+        let stronghold = plan(ChunkPosition::new(3, -7));
+        let pieces = stronghold.pieces();
+        assert!(pieces.len() > 1);
+        for (i, a) in pieces.iter().enumerate() {
+            for b in &pieces[i + 1..] {
+                assert!(!a.bounding_box().intersects(&b.bounding_box()));
+            }
+        }
+    }
+
+    #[test]
+    fn portal_room_has_full_frame() {
+        let origin = stronghold_positions(SEED)[0];
+        let bbox = plan(origin).bounding_box().unwrap();
+        let strongholds = Strongholds::new(SEED);
+
+        let mut frames = 0;
+        let mut lava = 0;
+        let mut spawners = 0;
+        for x in origin.x - MAX_RADIUS - 1..=origin.x + MAX_RADIUS + 1 {
+            for z in origin.z - MAX_RADIUS - 1..=origin.z + MAX_RADIUS + 1 {
+                let position = ChunkPosition::new(x, z);
+                let outside =
+                    (x - origin.x).abs() > MAX_RADIUS || (z - origin.z).abs() > MAX_RADIUS;
+                if outside {
+                    assert!(!bbox.intersects_chunk(position));
+                    continue;
+                }
+                if !bbox.intersects_chunk(position) {
+                    continue;
+                }
+
+                let mut chunk = Chunk::new(position);
+                strongholds.place_in_chunk(&mut chunk);
+                for block in (0..16).flat_map(|x| {
+                    let chunk = &chunk;
+                    (0..256).flat_map(move |y| (0..16).map(move |z| chunk.block_at(x, y, z)))
+                }) {
+                    match block.unwrap().kind() {
+                        BlockKind::EndPortalFrame => frames += 1,
+                        BlockKind::Lava => lava += 1,
+                        BlockKind::Spawner => spawners += 1,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        assert_eq!(frames, 12);
+        assert_eq!(lava, 9);
+        assert_eq!(spawners, 1);
+    }
+}
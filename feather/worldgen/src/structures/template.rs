@@ -0,0 +1,156 @@
+//! Prefabricated pieces described by layers of characters.
+
+use super::ClippedChunk;
+use base::{BlockId, Direction};
+
+/// A prefab of blocks which can be placed facing any direction.
+///
+/// Templates are built from layers, bottom to top, each a list of
+/// rows running from north to south. Every character in a row maps
+/// to a block through the palette, or to `None` to leave the existing
+/// block in place. Unrotated, the template's front is its south side.
+#[derive(Debug, Clone)]
+pub struct Template {
+    width: i32,
+    depth: i32,
+    blocks: Vec<Option<BlockId>>,
+}
+
+impl Template {
+    /// Creates a template from the given layers.
+    ///
+    /// # Panics
+    /// Panics if the layers or rows differ in size,
+    /// or a character is missing from the palette.
+    pub fn new(layers: &[&[&str]], palette: &[(char, Option<BlockId>)]) -> Self {
+        let depth = layers[0].len();
+        let width = layers[0][0].len();
+        let mut blocks = Vec::with_capacity(layers.len() * depth * width);
+        for layer in layers {
+            assert_eq!(layer.len(), depth, "template layers differ in depth");
+            for row in layer.iter() {
+                assert_eq!(row.len(), width, "template rows differ in width");
+                for c in row.chars() {
+                    let block = palette
+                        .iter()
+                        .find(|(key, _)| *key == c)
+                        .unwrap_or_else(|| panic!("no block for '{}' in template palette", c))
+                        .1;
+                    blocks.push(block);
+                }
+            }
+        }
+        Self {
+            width: width as i32,
+            depth: depth as i32,
+            blocks,
+        }
+    }
+
+    /// Returns the size of the template along X, unrotated.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Returns the size of the template along Z, unrotated.
+    pub fn depth(&self) -> i32 {
+        self.depth
+    }
+
+    /// Returns the number of layers.
+    pub fn height(&self) -> i32 {
+        self.blocks.len() as i32 / (self.width * self.depth)
+    }
+
+    /// Returns the X and Z size of the template
+    /// when its front faces `facing`.
+    pub fn footprint(&self, facing: Direction) -> (i32, i32) {
+        match facing {
+            Direction::North | Direction::South => (self.width, self.depth),
+            Direction::East | Direction::West => (self.depth, self.width),
+        }
+    }
+
+    /// Returns the offset from the template's lowest corner of
+    /// the column at `(x, z)` within the template, when its
+    /// front faces `facing`.
+    pub fn offset(&self, x: i32, z: i32, facing: Direction) -> (i32, i32) {
+        match facing {
+            Direction::South => (x, z),
+            Direction::North => (self.width - 1 - x, self.depth - 1 - z),
+            Direction::East => (z, self.width - 1 - x),
+            Direction::West => (self.depth - 1 - z, x),
+        }
+    }
+
+    /// Returns the block at the given position within the
+    /// template, or `None` if it leaves the existing block.
+    pub fn block_at(&self, x: i32, y: i32, z: i32) -> Option<BlockId> {
+        self.blocks[((y * self.depth + z) * self.width + x) as usize]
+    }
+
+    /// Places the template with its lowest corner at `origin`
+    /// and its front facing `facing`.
+    pub fn place(&self, chunk: &mut ClippedChunk, origin: (i32, i32, i32), facing: Direction) {
+        let (ox, oy, oz) = origin;
+        for y in 0..self.height() {
+            for z in 0..self.depth {
+                for x in 0..self.width {
+                    if let Some(block) = self.block_at(x, y, z) {
+                        let (dx, dz) = self.offset(x, z, facing);
+                        chunk.set_block_at(ox + dx, oy + y, oz + dz, block);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::{Chunk, ChunkPosition};
+
+    fn template() -> Template {
+        Template::new(
+            &[&["###", "#d#"], &["...", ".d."]],
+            &[
+                ('#', Some(BlockId::stone())),
+                ('d', Some(BlockId::dirt())),
+                ('.', None),
+            ],
+        )
+    }
+
+    #[test]
+    fn templates_rotate() {
+        let template = template();
+        assert_eq!(template.height(), 2);
+        assert_eq!(template.footprint(Direction::South), (3, 2));
+        assert_eq!(template.footprint(Direction::East), (2, 3));
+
+        // The dirt column is in the middle of the front row.
+        for &(facing, column) in &[
+            (Direction::South, (1, 1)),
+            (Direction::North, (1, 0)),
+            (Direction::East, (1, 1)),
+            (Direction::West, (0, 1)),
+        ] {
+            let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+            template.place(&mut ClippedChunk::new(&mut chunk), (4, 10, 4), facing);
+            let (x, z) = (4 + column.0 as usize, 4 + column.1 as usize);
+            assert_eq!(
+                chunk.block_at(x, 10, z),
+                Some(BlockId::dirt()),
+                "{:?}",
+                facing
+            );
+            assert_eq!(
+                chunk.block_at(x, 11, z),
+                Some(BlockId::dirt()),
+                "{:?}",
+                facing
+            );
+        }
+    }
+}
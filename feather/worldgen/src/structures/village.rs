@@ -0,0 +1,556 @@
+use super::template::Template;
+use super::{
+    assemble, direction_offset, BoundingBox, ClippedChunk, Connector, Structure, StructurePiece,
+    StructureType,
+};
+use base::{Biome, BlockId, ChunkPosition, Direction};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+
+/// Width of a street.
+const STREET_WIDTH: i32 = 3;
+/// Minimum length of a street.
+const MIN_STREET_LENGTH: i32 = 10;
+/// Maximum length of a street.
+const MAX_STREET_LENGTH: i32 = 24;
+/// Distance along a street between the lots beside it.
+const LOT_SPACING: i32 = 8;
+/// Maximum number of pieces between the well and the edge of the village.
+const MAX_DEPTH: u32 = 4;
+/// Maximum depth a building's foundation extends down to reach the ground.
+const FOUNDATION_DEPTH: i32 = 6;
+/// Size of the largest building along either axis.
+const MAX_BUILDING_SIZE: i32 = 9;
+
+/// A village of houses and farms around a well,
+/// connected by gravel streets.
+///
+/// Terrain heights aren't known while planning, so pieces
+/// adapt to the terrain as they are placed. Streets follow the
+/// ground column by column, bridging water with planks. Buildings
+/// are planned to lie within a single chunk, so their floor can
+/// be leveled to the average height of the ground under them,
+/// with a foundation filled in below and the terrain cut away above.
+#[derive(Debug, Default)]
+pub struct Village;
+
+impl StructureType for Village {
+    fn spacing(&self) -> u32 {
+        32
+    }
+
+    fn separation(&self) -> u32 {
+        8
+    }
+
+    fn salt(&self) -> u64 {
+        10_387_312
+    }
+
+    fn max_radius(&self) -> i32 {
+        // The well lies within its chunk, and each piece can
+        // extend the village by a full street or building.
+        let reach = MAX_STREET_LENGTH.max(MAX_BUILDING_SIZE) * MAX_DEPTH as i32;
+        (reach + 15) / 16
+    }
+
+    fn can_generate_in(&self, biome: Biome) -> bool {
+        matches!(
+            biome,
+            Biome::Plains
+                | Biome::SunflowerPlains
+                | Biome::Desert
+                | Biome::Savanna
+                | Biome::Taiga
+                | Biome::SnowyTundra
+        )
+    }
+
+    fn generate(&self, origin: ChunkPosition, rng: &mut XorShiftRng) -> Structure {
+        let (width, depth) = WELL.footprint(Direction::South);
+        let well = Building {
+            kind: BuildingKind::Well,
+            origin: (
+                origin.x * 16 + rng.gen_range(0, 16 - width),
+                origin.z * 16 + rng.gen_range(0, 16 - depth),
+            ),
+            facing: Direction::South,
+        };
+        assemble(Box::new(well), MAX_DEPTH, rng, |connector, rng| {
+            match rng.gen_range(0, 10) {
+                0..=4 => {
+                    let kind = match rng.gen_range(0, 8) {
+                        0..=2 => BuildingKind::SmallHouse,
+                        3 | 4 => BuildingKind::LargeHouse,
+                        5 | 6 => BuildingKind::Farm,
+                        _ => BuildingKind::LampPost,
+                    };
+                    Building::facing_street(kind, connector)
+                        .map(|building| Box::new(building) as Box<dyn StructurePiece>)
+                }
+                5..=7 => Some(Box::new(Street {
+                    start: connector,
+                    length: rng.gen_range(MIN_STREET_LENGTH, MAX_STREET_LENGTH + 1),
+                })),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// Returns a bounding box over the full height of the
+/// world, so that village pieces only overlap if their
+/// columns do.
+fn column_box(min: (i32, i32), max: (i32, i32)) -> BoundingBox {
+    BoundingBox::new((min.0, 0, min.1), (max.0, 255, max.1))
+}
+
+/// Returns the height of the highest solid or fluid
+/// block in the given column, if it is in the chunk.
+fn surface_at(chunk: &ClippedChunk, x: i32, z: i32) -> Option<i32> {
+    (0..256).rev().find(|&y| {
+        chunk
+            .block_at(x, y, z)
+            .map_or(false, |block| block.is_solid() || block.is_fluid())
+    })
+}
+
+fn opposite(direction: Direction) -> Direction {
+    match direction {
+        Direction::North => Direction::South,
+        Direction::South => Direction::North,
+        Direction::East => Direction::West,
+        Direction::West => Direction::East,
+    }
+}
+
+/// A gravel street leading out from a connector, with
+/// lots on either side for buildings or more streets.
+struct Street {
+    start: Connector,
+    length: i32,
+}
+
+impl Street {
+    /// Returns the `i`th column along the center of the
+    /// street, moved `side` columns to its right.
+    fn column(&self, i: i32, side: i32) -> (i32, i32) {
+        let (x, _, z) = self.start.position;
+        let (dx, dz) = direction_offset(self.start.direction);
+        // Rotating the forward offset gives the offset to the right.
+        let (rx, rz) = (-dz, dx);
+        (x + dx * i + rx * side, z + dz * i + rz * side)
+    }
+
+    /// Returns a connector leading to the lot beside the `i`th
+    /// column, to the right if `side` is positive or to the left
+    /// otherwise.
+    fn lot(&self, i: i32, side: i32) -> Connector {
+        let (x, z) = self.column(i, side.signum() * (STREET_WIDTH / 2 + 1));
+        let direction = match (self.start.direction, side > 0) {
+            (Direction::North, true) | (Direction::South, false) => Direction::East,
+            (Direction::North, false) | (Direction::South, true) => Direction::West,
+            (Direction::East, true) | (Direction::West, false) => Direction::South,
+            (Direction::East, false) | (Direction::West, true) => Direction::North,
+        };
+        Connector {
+            position: (x, 0, z),
+            direction,
+        }
+    }
+}
+
+impl StructurePiece for Street {
+    fn bounding_box(&self) -> BoundingBox {
+        let half = STREET_WIDTH / 2;
+        let start = self.column(0, -half);
+        let end = self.column(self.length - 1, half);
+        column_box(start, end)
+    }
+
+    fn connectors(&self) -> Vec<Connector> {
+        let (x, z) = self.column(self.length, 0);
+        let mut connectors = vec![Connector {
+            position: (x, 0, z),
+            direction: self.start.direction,
+        }];
+        for i in (LOT_SPACING / 2..self.length).step_by(LOT_SPACING as usize) {
+            connectors.push(self.lot(i, -1));
+            connectors.push(self.lot(i, 1));
+        }
+        connectors
+    }
+
+    fn place(&self, chunk: &mut ClippedChunk) {
+        let half = STREET_WIDTH / 2;
+        for i in 0..self.length {
+            for side in -half..=half {
+                let (x, z) = self.column(i, side);
+                let y = match surface_at(chunk, x, z) {
+                    Some(y) => y,
+                    None => continue,
+                };
+                let surface = chunk.block_at(x, y, z).unwrap();
+                let block = if surface.is_fluid() {
+                    BlockId::oak_planks()
+                } else {
+                    BlockId::gravel()
+                };
+                chunk.set_block_at(x, y, z, block);
+
+                // Clear plants growing on the street.
+                if chunk.block_at(x, y + 1, z).map_or(false, |b| !b.is_air()) {
+                    chunk.set_block_at(x, y + 1, z, BlockId::air());
+                }
+            }
+        }
+    }
+}
+
+/// The kinds of buildings in a village.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum BuildingKind {
+    Well,
+    SmallHouse,
+    LargeHouse,
+    Farm,
+    LampPost,
+}
+
+impl BuildingKind {
+    fn template(self) -> &'static Template {
+        match self {
+            BuildingKind::Well => &WELL,
+            BuildingKind::SmallHouse => &SMALL_HOUSE,
+            BuildingKind::LargeHouse => &LARGE_HOUSE,
+            BuildingKind::Farm => &FARM,
+            BuildingKind::LampPost => &LAMP_POST,
+        }
+    }
+}
+
+/// A building placed from a template, leveled to the terrain.
+struct Building {
+    kind: BuildingKind,
+    /// Lowest X and Z coordinates of the building.
+    origin: (i32, i32),
+    facing: Direction,
+}
+
+impl Building {
+    /// Returns a building whose entrance, in the middle of its
+    /// front, is at the connector and faces back along it.
+    ///
+    /// Returns `None` if the building would span several chunks.
+    fn facing_street(kind: BuildingKind, connector: Connector) -> Option<Self> {
+        let template = kind.template();
+        let facing = opposite(connector.direction);
+        let (front_x, front_z) = (template.width() / 2, template.depth() - 1);
+        let (dx, dz) = template.offset(front_x, front_z, facing);
+        let building = Self {
+            kind,
+            origin: (connector.position.0 - dx, connector.position.2 - dz),
+            facing,
+        };
+
+        let bbox = building.bounding_box();
+        let same_chunk = bbox.min.0.div_euclid(16) == bbox.max.0.div_euclid(16)
+            && bbox.min.2.div_euclid(16) == bbox.max.2.div_euclid(16);
+        if same_chunk {
+            Some(building)
+        } else {
+            None
+        }
+    }
+}
+
+impl StructurePiece for Building {
+    fn bounding_box(&self) -> BoundingBox {
+        let (width, depth) = self.kind.template().footprint(self.facing);
+        let (x, z) = self.origin;
+        column_box((x, z), (x + width - 1, z + depth - 1))
+    }
+
+    fn connectors(&self) -> Vec<Connector> {
+        if self.kind != BuildingKind::Well {
+            return Vec::new();
+        }
+
+        // Streets leave each side of the well.
+        let (width, depth) = self.kind.template().footprint(self.facing);
+        let (x, z) = self.origin;
+        let (mid_x, mid_z) = (x + width / 2, z + depth / 2);
+        vec![
+            Connector {
+                position: (mid_x, 0, z - 1),
+                direction: Direction::North,
+            },
+            Connector {
+                position: (mid_x, 0, z + depth),
+                direction: Direction::South,
+            },
+            Connector {
+                position: (x - 1, 0, mid_z),
+                direction: Direction::West,
+            },
+            Connector {
+                position: (x + width, 0, mid_z),
+                direction: Direction::East,
+            },
+        ]
+    }
+
+    fn place(&self, chunk: &mut ClippedChunk) {
+        let template = self.kind.template();
+        let (width, depth) = template.footprint(self.facing);
+        let (ox, oz) = self.origin;
+
+        let columns: Vec<(i32, i32)> = (ox..ox + width)
+            .flat_map(|x| (oz..oz + depth).map(move |z| (x, z)))
+            .collect();
+        let heights: Vec<i32> = columns
+            .iter()
+            .filter_map(|&(x, z)| surface_at(chunk, x, z))
+            .collect();
+        if heights.len() != columns.len() {
+            // Not in this chunk, or missing ground.
+            return;
+        }
+        let floor = (heights.iter().sum::<i32>() as f32 / heights.len() as f32).round() as i32;
+
+        for (&(x, z), &surface) in columns.iter().zip(&heights) {
+            // Fill the foundation down to solid ground.
+            for y in (floor - FOUNDATION_DEPTH..floor).rev() {
+                match chunk.block_at(x, y, z) {
+                    Some(block) if !block.is_solid() => {
+                        chunk.set_block_at(x, y, z, BlockId::cobblestone())
+                    }
+                    _ => break,
+                }
+            }
+
+            // Cut away the terrain above the floor.
+            for y in floor + 1..=surface.max(floor + template.height()) {
+                chunk.set_block_at(x, y, z, BlockId::air());
+            }
+        }
+
+        template.place(chunk, (ox, floor, oz), self.facing);
+    }
+}
+
+/// Characters used in the village templates.
+fn palette() -> [(char, Option<BlockId>); 12] {
+    [
+        (' ', None),
+        ('.', Some(BlockId::air())),
+        ('c', Some(BlockId::cobblestone())),
+        ('P', Some(BlockId::oak_planks())),
+        ('L', Some(BlockId::oak_log())),
+        ('G', Some(BlockId::glass())),
+        ('T', Some(BlockId::crafting_table())),
+        ('f', Some(BlockId::oak_fence())),
+        ('t', Some(BlockId::torch())),
+        ('~', Some(BlockId::water())),
+        ('F', Some(BlockId::farmland())),
+        ('#', Some(BlockId::wheat())),
+    ]
+}
+
+static WELL: Lazy<Template> = Lazy::new(|| {
+    Template::new(
+        &[
+            &["cccc", "cccc", "cccc", "cccc"],
+            &["cccc", "c~~c", "c~~c", "cccc"],
+            &["f..f", "....", "....", "f..f"],
+            &["f..f", "....", "....", "f..f"],
+            &["cccc", "cccc", "cccc", "cccc"],
+        ],
+        &palette(),
+    )
+});
+
+static SMALL_HOUSE: Lazy<Template> = Lazy::new(|| {
+    Template::new(
+        &[
+            &["ccccc", "ccccc", "ccccc", "ccccc", "ccccc"],
+            &["LPPPL", "P..TP", "P...P", "P...P", "LP.PL"],
+            &["LPGPL", "P...P", "G...G", "P...P", "LP.PL"],
+            &["LPPPL", "P...P", "P...P", "P...P", "LPPPL"],
+            &["PPPPP", "PPPPP", "PPPPP", "PPPPP", "PPPPP"],
+        ],
+        &palette(),
+    )
+});
+
+static LARGE_HOUSE: Lazy<Template> = Lazy::new(|| {
+    Template::new(
+        &[
+            &[
+                "ccccccc", "ccccccc", "ccccccc", "ccccccc", "ccccccc", "ccccccc", "ccccccc",
+            ],
+            &[
+                "LPPPPPL", "PT....P", "P.....P", "P.....P", "P.....P", "P.....P", "LPP.PPL",
+            ],
+            &[
+                "LPGPGPL", "P.....P", "G.....G", "P.....P", "G.....G", "P.....P", "LPP.PPL",
+            ],
+            &[
+                "LPPPPPL", "P.....P", "P.....P", "P.....P", "P.....P", "P.....P", "LPPPPPL",
+            ],
+            &[
+                "PPPPPPP", "PPPPPPP", "PPPPPPP", "PPPPPPP", "PPPPPPP", "PPPPPPP", "PPPPPPP",
+            ],
+            &[
+                "       ", " PPPPP ", " PPPPP ", " PPPPP ", " PPPPP ", " PPPPP ", "       ",
+            ],
+        ],
+        &palette(),
+    )
+});
+
+static FARM: Lazy<Template> = Lazy::new(|| {
+    Template::new(
+        &[
+            &[
+                "LLLLLLL", "LFF~FFL", "LFF~FFL", "LFF~FFL", "LFF~FFL", "LFF~FFL", "LFF~FFL",
+                "LFF~FFL", "LLLLLLL",
+            ],
+            &[
+                "       ", " ## ## ", " ## ## ", " ## ## ", " ## ## ", " ## ## ", " ## ## ",
+                " ## ## ", "       ",
+            ],
+        ],
+        &palette(),
+    )
+});
+
+static LAMP_POST: Lazy<Template> =
+    Lazy::new(|| Template::new(&[&["c"], &["f"], &["f"], &["f"], &["t"]], &palette()));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::structure_chunk;
+    use crate::util::feature_random;
+    use base::Chunk;
+
+    const SEED: u64 = 1234;
+
+    fn village_origin() -> ChunkPosition {
+        (0..64)
+            .flat_map(|x| (0..64).map(move |z| (x, z)))
+            .find_map(|(x, z)| structure_chunk(SEED, x, z, 32, 8, Village.salt()))
+            .map(|(x, z)| ChunkPosition::new(x, z))
+            .expect("no village found")
+    }
+
+    fn plan(origin: ChunkPosition) -> Structure {
+        let mut rng = feature_random(SEED, origin.x, origin.z, Village.salt());
+        Village.generate(origin, &mut rng)
+    }
+
+    /// A chunk of stone sloping up toward the east.
+    fn sloped_chunk(position: ChunkPosition) -> Chunk {
+        let mut chunk = Chunk::new(position);
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..60 + x {
+                    chunk.set_block_at(x, y, z, BlockId::stone());
+                }
+            }
+        }
+        chunk
+    }
+
+    #[test]
+    fn village_pieces_do_not_overlap() {
+        let village = plan(village_origin());
+        let pieces = village.pieces();
+        assert!(pieces.len() > 1);
+        for (i, a) in pieces.iter().enumerate() {
+            for b in &pieces[i + 1..] {
+                assert!(!a.bounding_box().intersects(&b.bounding_box()));
+            }
+        }
+    }
+
+    #[test]
+    fn village_fits_within_radius() {
+        let origin = village_origin();
+        let bbox = plan(origin).bounding_box().unwrap();
+        let radius = Village.max_radius();
+        for x in origin.x - radius - 1..=origin.x + radius + 1 {
+            for z in origin.z - radius - 1..=origin.z + radius + 1 {
+                let outside = (x - origin.x).abs() > radius || (z - origin.z).abs() > radius;
+                if outside {
+                    assert!(!bbox.intersects_chunk(ChunkPosition::new(x, z)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn buildings_are_leveled() {
+        let house = Building {
+            kind: BuildingKind::SmallHouse,
+            origin: (4, 4),
+            facing: Direction::South,
+        };
+        let mut chunk = sloped_chunk(ChunkPosition::new(0, 0));
+        Structure::new(vec![Box::new(house)]).place_in_chunk(&mut chunk);
+
+        // Ground heights under the house are 63 to 67,
+        // so the floor is at their average.
+        for x in 4..9 {
+            for z in 4..9 {
+                assert_eq!(chunk.block_at(x, 65, z), Some(BlockId::cobblestone()));
+            }
+        }
+        // Foundation on the low side, cleared terrain on the high side.
+        assert_eq!(chunk.block_at(4, 64, 4), Some(BlockId::cobblestone()));
+        assert_eq!(chunk.block_at(4, 63, 4), Some(BlockId::stone()));
+        assert_eq!(chunk.block_at(7, 66, 6), Some(BlockId::air()));
+        assert_eq!(chunk.block_at(8, 67, 6), Some(BlockId::air()));
+        // The doorway is at the middle of the front.
+        assert_eq!(chunk.block_at(6, 66, 8), Some(BlockId::air()));
+        assert_eq!(chunk.block_at(5, 66, 8), Some(BlockId::oak_planks()));
+    }
+
+    #[test]
+    fn streets_follow_terrain() {
+        let street = Street {
+            start: Connector {
+                position: (0, 0, 8),
+                direction: Direction::East,
+            },
+            length: 16,
+        };
+        let mut chunk = sloped_chunk(ChunkPosition::new(0, 0));
+        Structure::new(vec![Box::new(street)]).place_in_chunk(&mut chunk);
+
+        for x in 0..16 {
+            let surface = 59 + x;
+            assert_eq!(chunk.block_at(x, surface, 8), Some(BlockId::gravel()));
+        }
+    }
+
+    #[test]
+    fn houses_face_the_street() {
+        let connector = Connector {
+            position: (8, 0, 6),
+            direction: Direction::North,
+        };
+        let house = Building::facing_street(BuildingKind::SmallHouse, connector).unwrap();
+        assert_eq!(house.facing, Direction::South);
+        assert_eq!(house.origin, (6, 2));
+
+        // Would cross into the chunk to the west.
+        let connector = Connector {
+            position: (1, 0, 8),
+            direction: Direction::North,
+        };
+        assert!(Building::facing_street(BuildingKind::SmallHouse, connector).is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use base::{anvil::level::SuperflatGeneratorOptions, Biome, BlockId, Chunk, ChunkPosition};
+use base::{anvil::level::SuperflatGeneratorOptions, Biome, Chunk, ChunkPosition};
 
 use crate::WorldGenerator;
 
@@ -12,16 +12,16 @@ impl WorldGenerator for SuperflatWorldGenerator {
         let mut chunk = Chunk::new_with_default_biome(position, biome);
 
         let mut y_counter = 0;
-        for layer in self.options.clone().layers {
+        for layer in &self.options.layers {
             if layer.height == 0 {
                 continue;
             }
-            let layer_block = BlockId::from_identifier(layer.block.as_str());
-            if let Some(layer_block) = layer_block {
-                for y in y_counter..(y_counter + layer.height) {
+            let height = layer.height as usize;
+            if let Some(layer_block) = layer.block_id() {
+                for y in y_counter..(y_counter + height).min(256) {
                     for x in 0..16 {
                         for z in 0..16 {
-                            chunk.set_block_at(x as usize, y as usize, z as usize, layer_block);
+                            chunk.set_block_at(x, y, z, layer_block);
                         }
                     }
                 }
@@ -30,7 +30,7 @@ impl WorldGenerator for SuperflatWorldGenerator {
                 log::debug!("Failed to generate layer: unknown block {}", layer.block);
             }
 
-            y_counter += layer.height;
+            y_counter += height;
         }
 
         chunk.recalculate_heightmaps();
@@ -42,6 +42,7 @@ impl WorldGenerator for SuperflatWorldGenerator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use base::BlockId;
 
     #[test]
     #[ignore] // TODO (1.16): account for new 3D biomes
@@ -0,0 +1,85 @@
+//! Measurement of the time spent in each stage of generation.
+
+use crate::GenerationStage;
+use std::time::{Duration, Instant};
+
+/// Time spent in each `GenerationStage`, accumulated
+/// over any number of generated chunks.
+///
+/// Finishers and populators are attributed to the stage
+/// they take their seed from. Stages are listed in the
+/// order they first ran.
+#[derive(Clone, Debug, Default)]
+pub struct StageTimings {
+    stages: Vec<(GenerationStage, Duration)>,
+}
+
+impl StageTimings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `f`, adding the time it took to `stage`.
+    pub fn time<T>(&mut self, stage: GenerationStage, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(stage, start.elapsed());
+        result
+    }
+
+    /// Adds `duration` to the time spent in `stage`.
+    pub fn record(&mut self, stage: GenerationStage, duration: Duration) {
+        match self.stages.iter_mut().find(|(s, _)| *s == stage) {
+            Some((_, total)) => *total += duration,
+            None => self.stages.push((stage, duration)),
+        }
+    }
+
+    /// Returns the time spent in `stage`.
+    pub fn get(&self, stage: GenerationStage) -> Duration {
+        self.stages
+            .iter()
+            .find(|(s, _)| *s == stage)
+            .map_or(Duration::default(), |(_, duration)| *duration)
+    }
+
+    /// Iterates over the stages which ran and the time spent in each.
+    pub fn iter(&self) -> impl Iterator<Item = (GenerationStage, Duration)> + '_ {
+        self.stages.iter().copied()
+    }
+
+    /// Returns the time spent in all stages.
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|(_, duration)| *duration).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timings_accumulate_in_order() {
+        let mut timings = StageTimings::new();
+        timings.record(GenerationStage::Terrain, Duration::from_millis(3));
+        timings.record(GenerationStage::Biomes, Duration::from_millis(1));
+        timings.record(GenerationStage::Terrain, Duration::from_millis(2));
+        assert_eq!(timings.time(GenerationStage::Ores, || 5), 5);
+
+        let stages: Vec<_> = timings.iter().map(|(stage, _)| stage).collect();
+        assert_eq!(
+            stages,
+            vec![
+                GenerationStage::Terrain,
+                GenerationStage::Biomes,
+                GenerationStage::Ores
+            ]
+        );
+        assert_eq!(
+            timings.get(GenerationStage::Terrain),
+            Duration::from_millis(5)
+        );
+        assert_eq!(timings.get(GenerationStage::Caves), Duration::default());
+        assert!(timings.total() >= Duration::from_millis(6));
+    }
+}
@@ -1,12 +1,14 @@
 //! Utilities for world generation.
 
 use base::ChunkPosition;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
 
 /// Deterministically a seed for the given chunk. This allows
 /// different seeds to be used for different chunk.
 pub fn shuffle_seed_for_chunk(seed: u64, chunk: ChunkPosition) -> u64 {
-    seed.wrapping_mul((chunk.x as u64).wrapping_add(1))
-        .wrapping_add((chunk.z as u64).wrapping_add(1))
+    let position = ((chunk.x as u32 as u64) << 32) | chunk.z as u32 as u64;
+    XorShiftRng::seed_from_u64(seed ^ position).gen()
 }
 
 /// Deterministically shuffles a seed for the given chunk and chunk column.
@@ -16,3 +18,89 @@ pub fn shuffle_seed_for_column(seed: u64, chunk: ChunkPosition, col_x: usize, co
         .wrapping_mul(((col_x as u64) << 4) + 4)
         .wrapping_mul(col_z as u64 + 4)
 }
+
+/// Returns an RNG for placing the feature identified by
+/// `feature_salt` in the given chunk.
+///
+/// The RNG depends only on its arguments, so features can be
+/// placed in any order with the same results. The seed is derived
+/// like Minecraft's population seed: the world seed determines two
+/// odd multipliers for the chunk's block coordinates, and the salt
+/// is added to the result.
+pub fn feature_random(
+    world_seed: u64,
+    chunk_x: i32,
+    chunk_z: i32,
+    feature_salt: u64,
+) -> XorShiftRng {
+    let mut java = JavaRandom::new(world_seed);
+    let a = java.next_long() | 1;
+    let b = java.next_long() | 1;
+    let population_seed = ((chunk_x as i64 * 16)
+        .wrapping_mul(a)
+        .wrapping_add((chunk_z as i64 * 16).wrapping_mul(b)) as u64)
+        ^ world_seed;
+    XorShiftRng::seed_from_u64(population_seed.wrapping_add(feature_salt))
+}
+
+/// The linear congruential generator of `java.util.Random`.
+struct JavaRandom {
+    seed: u64,
+}
+
+impl JavaRandom {
+    const MULTIPLIER: u64 = 0x5_DEEC_E66D;
+    const MASK: u64 = (1 << 48) - 1;
+
+    fn new(seed: u64) -> Self {
+        Self {
+            seed: (seed ^ Self::MULTIPLIER) & Self::MASK,
+        }
+    }
+
+    fn next(&mut self, bits: u32) -> i32 {
+        self.seed = self.seed.wrapping_mul(Self::MULTIPLIER).wrapping_add(0xB) & Self::MASK;
+        (self.seed >> (48 - bits)) as i32
+    }
+
+    fn next_long(&mut self) -> i64 {
+        ((self.next(32) as i64) << 32).wrapping_add(self.next(32) as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    const ORES: u64 = 0;
+    const TREES: u64 = 1;
+
+    /// Places a few blocks at random positions
+    /// in the chunk, as a feature would.
+    fn place(placed: &mut BTreeSet<(u64, u32, u32, u32)>, chunk_x: i32, chunk_z: i32, salt: u64) {
+        let mut rng = feature_random(1234, chunk_x, chunk_z, salt);
+        for _ in 0..8 {
+            placed.insert((
+                salt,
+                rng.gen_range(0, 16),
+                rng.gen_range(0, 256),
+                rng.gen_range(0, 16),
+            ));
+        }
+    }
+
+    #[test]
+    fn feature_order_does_not_matter() {
+        let mut ores_first = BTreeSet::new();
+        place(&mut ores_first, 3, -7, ORES);
+        place(&mut ores_first, 3, -7, TREES);
+
+        let mut trees_first = BTreeSet::new();
+        place(&mut trees_first, 3, -7, TREES);
+        place(&mut trees_first, 3, -7, ORES);
+
+        assert_eq!(ores_first, trees_first);
+        assert_eq!(JavaRandom::new(0).next_long(), -4962768465676381896);
+    }
+}
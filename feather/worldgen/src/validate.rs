@@ -0,0 +1,255 @@
+//! Invariant checks on the output of each generation stage.
+//!
+//! Checks are enabled by default in debug builds and can be
+//! toggled with `set_validation`. A failed check panics with
+//! the violated invariant, pointing at the stage which broke it
+//! rather than at whatever later trips over the bad chunk.
+
+use crate::{block_index, SKY_LIMIT};
+use base::{BlockId, Chunk};
+use bitvec::order::LocalBits;
+use bitvec::slice::BitSlice;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(cfg!(debug_assertions));
+
+/// Enables or disables invariant checks for all generators.
+pub fn set_validation(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether invariant checks are enabled.
+pub fn validation_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// An invariant broken by a generation stage.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    /// Noise contained a NaN or infinite value.
+    NonFiniteNoise { index: usize, value: f32 },
+    /// A density map didn't have one bit per block.
+    DensityLength { expected: usize, actual: usize },
+    /// A block was stored above the sky limit or below Y=0.
+    BlockOutsideWorld { x: usize, y: i32, z: usize },
+    /// The bottom of a column wasn't bedrock.
+    MissingBedrock { x: usize, z: usize },
+    /// A heightmap disagreed with the blocks in its column.
+    Heightmap {
+        x: usize,
+        z: usize,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::NonFiniteNoise { index, value } => {
+                write!(f, "noise value {} at index {} is not finite", value, index)
+            }
+            Violation::DensityLength { expected, actual } => {
+                write!(f, "density map has {} bits instead of {}", actual, expected)
+            }
+            Violation::BlockOutsideWorld { x, y, z } => {
+                write!(f, "block at {},{},{} is outside the world", x, y, z)
+            }
+            Violation::MissingBedrock { x, z } => {
+                write!(f, "column {},{} has no bedrock at Y=0", x, z)
+            }
+            Violation::Heightmap {
+                x,
+                z,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "motion blocking height of column {},{} is {} instead of {}",
+                x, z, actual, expected
+            ),
+        }
+    }
+}
+
+/// Checks that noise contains only finite values.
+pub fn check_noise(noise: &[f32]) -> Result<(), Violation> {
+    match noise.iter().position(|value| !value.is_finite()) {
+        Some(index) => Err(Violation::NonFiniteNoise {
+            index,
+            value: noise[index],
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Checks that a density map can be indexed by `block_index`.
+pub fn check_density(density: &BitSlice<LocalBits, u8>) -> Result<(), Violation> {
+    let expected = block_index(15, SKY_LIMIT, 15) + 1;
+    if density.len() != expected {
+        return Err(Violation::DensityLength {
+            expected,
+            actual: density.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Checks that a generated chunk has no blocks outside the
+/// world, that its heightmaps match its blocks, and, if
+/// `bedrock` is set, that every column has bedrock at Y=0.
+pub fn check_chunk(chunk: &Chunk, bedrock: bool) -> Result<(), Violation> {
+    // The sections below and above the world
+    // only exist to store light.
+    let sections = chunk.sections();
+    for (section, base_y) in &[(0, -16), (sections.len() - 1, SKY_LIMIT as i32 + 1)] {
+        if let Some(section) = &sections[*section] {
+            for x in 0..16 {
+                for y in 0..16 {
+                    for z in 0..16 {
+                        if section.block_at(x, y, z).map_or(false, |b| !b.is_air()) {
+                            return Err(Violation::BlockOutsideWorld {
+                                x,
+                                y: base_y + y as i32,
+                                z,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    for x in 0..16 {
+        for z in 0..16 {
+            if bedrock && chunk.block_at(x, 0, z) != Some(BlockId::bedrock()) {
+                return Err(Violation::MissingBedrock { x, z });
+            }
+
+            let expected = (0..=SKY_LIMIT)
+                .rev()
+                .find(|&y| {
+                    chunk
+                        .block_at(x, y, z)
+                        .map_or(false, |b| b.is_solid() || b.is_fluid())
+                })
+                .map_or(0, |y| y + 1);
+            let actual = chunk.heightmaps().motion_blocking.height(x, z).unwrap_or(0);
+            if actual != expected {
+                return Err(Violation::Heightmap {
+                    x,
+                    z,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Panics if `result` is a violation and checks are enabled.
+pub(crate) fn enforce(stage: &str, result: impl FnOnce() -> Result<(), Violation>) {
+    if validation_enabled() {
+        if let Err(violation) = result() {
+            panic!("worldgen invariant violated after {}: {}", stage, violation);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise::{Wrapped2DPerlinNoise, Wrapped3DPerlinNoise};
+    use crate::{ComposableGenerator, WorldGenerator};
+    use base::ChunkPosition;
+    use bitvec::vec::BitVec;
+    use proptest::prelude::*;
+
+    #[test]
+    fn non_finite_noise_is_rejected() {
+        assert_eq!(check_noise(&[0.0, -1.5, 3.0]), Ok(()));
+        assert_eq!(
+            check_noise(&[0.0, f32::INFINITY]),
+            Err(Violation::NonFiniteNoise {
+                index: 1,
+                value: f32::INFINITY
+            })
+        );
+        assert!(check_noise(&[f32::NAN]).is_err());
+    }
+
+    #[test]
+    fn density_length_is_checked() {
+        let density = BitVec::<LocalBits, u8>::from_vec(vec![0u8; 16 * 256 * 16 / 8]);
+        assert_eq!(check_density(&density), Ok(()));
+        let short = BitVec::<LocalBits, u8>::from_vec(vec![0u8; 16 * 16]);
+        assert_eq!(
+            check_density(&short),
+            Err(Violation::DensityLength {
+                expected: 65536,
+                actual: 2048
+            })
+        );
+    }
+
+    #[test]
+    fn chunks_are_checked() {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        assert_eq!(check_chunk(&chunk, false), Ok(()));
+        assert_eq!(
+            check_chunk(&chunk, true),
+            Err(Violation::MissingBedrock { x: 0, z: 0 })
+        );
+
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set_block_at(x, 0, z, BlockId::bedrock());
+            }
+        }
+        assert_eq!(check_chunk(&chunk, true), Ok(()));
+
+        // Out of the world, in the light section above it.
+        chunk.set_block_at(3, 260, 4, BlockId::stone());
+        assert_eq!(
+            check_chunk(&chunk, true),
+            Err(Violation::BlockOutsideWorld { x: 3, y: 260, z: 4 })
+        );
+    }
+
+    proptest! {
+        // Each case generates a whole chunk.
+        #![proptest_config(ProptestConfig::with_cases(8))]
+
+        #[test]
+        fn generated_chunks_hold_invariants(
+            seed in any::<u64>(),
+            x in -10_000i32..10_000,
+            z in -10_000i32..10_000,
+        ) {
+            // Density and noise are checked during generation
+            // too, since tests run with debug assertions.
+            let generator = ComposableGenerator::default_with_seed(seed);
+            let chunk = generator.generate_chunk(ChunkPosition::new(x, z));
+            prop_assert_eq!(check_chunk(&chunk, true), Ok(()));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn noise_is_finite(
+            seed in any::<u64>(),
+            x in -100_000i32..100_000,
+            z in -100_000i32..100_000,
+        ) {
+            let noise = Wrapped3DPerlinNoise::new(seed).with_offset(x, z).generate();
+            prop_assert_eq!(noise.len(), 16 * 256 * 16);
+            prop_assert_eq!(check_noise(&noise), Ok(()));
+
+            let noise = Wrapped2DPerlinNoise::new(seed).with_offset(x, z).generate();
+            prop_assert_eq!(check_noise(&noise), Ok(()));
+        }
+    }
+}
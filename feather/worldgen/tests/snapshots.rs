@@ -0,0 +1,163 @@
+//! Regression snapshots of generated terrain.
+//!
+//! Each case generates one chunk and hashes its blocks. The hashes
+//! are compared against `tests/snapshots.txt`, so that refactors
+//! which unintentionally change terrain fail this test.
+//!
+//! After an intentional change, regenerate the snapshots with
+//!
+//! ```sh
+//! UPDATE_WORLDGEN_SNAPSHOTS=1 cargo test -p feather-worldgen --test snapshots
+//! ```
+//!
+//! and commit the updated file along with the change. The same
+//! command records cases added to `CASES`; until then, they fail.
+
+use std::{collections::BTreeMap, env, fmt::Write as _, fs, path::PathBuf};
+
+use base::{Chunk, ChunkPosition};
+use feather_worldgen::{ComposableGenerator, WorldGenerator, WorldPreset};
+
+const UPDATE_VAR: &str = "UPDATE_WORLDGEN_SNAPSHOTS";
+
+/// The chunks checked, as (preset, seed, chunk X, chunk Z).
+const CASES: &[(&str, u64, i32, i32)] = &[
+    ("default", 0, 0, 0),
+    ("default", 0, -1, 3),
+    ("default", 42, 7, -12),
+    ("default", 42, 250, 1000),
+    ("default", 0xDEAD_BEEF, -300, -300),
+    ("default", u64::MAX, 12_345, -6_789),
+    ("amplified", 0, 0, 0),
+    ("amplified", 1_000_000_007, 40, -40),
+    ("large_biomes", 0, 0, 0),
+    ("large_biomes", 99, -64, 128),
+];
+
+fn preset(name: &str) -> WorldPreset {
+    match name {
+        "default" => WorldPreset::Default,
+        "amplified" => WorldPreset::Amplified,
+        "large_biomes" => WorldPreset::LargeBiomes,
+        _ => panic!("unknown preset {}", name),
+    }
+}
+
+fn snapshot_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots.txt")
+}
+
+/// Hashes the blocks of a chunk with 64-bit FNV-1a,
+/// which doesn't depend on the platform or Rust version.
+fn chunk_hash(chunk: &Chunk) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for y in 0..256 {
+        for x in 0..16 {
+            for z in 0..16 {
+                let id = chunk.block_at(x, y, z).unwrap().vanilla_id();
+                for &byte in &id.to_le_bytes() {
+                    hash ^= byte as u64;
+                    hash = hash.wrapping_mul(0x0100_0000_01b3);
+                }
+            }
+        }
+    }
+    hash
+}
+
+/// Parses the snapshot file into a map from
+/// "preset seed x z" keys to hashes.
+fn parse_snapshots(contents: &str) -> BTreeMap<String, u64> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut parts = line.rsplitn(2, ' ');
+            let hash = parts.next().unwrap();
+            let key = parts
+                .next()
+                .unwrap_or_else(|| panic!("malformed snapshot line {:?}", line));
+            let hash = u64::from_str_radix(hash, 16)
+                .unwrap_or_else(|_| panic!("malformed snapshot hash in {:?}", line));
+            (key.to_owned(), hash)
+        })
+        .collect()
+}
+
+fn format_snapshots(snapshots: &BTreeMap<String, u64>) -> String {
+    let mut contents = String::from(
+        "# Hashes of generated chunks, checked by tests/snapshots.rs.\n\
+         # Format: preset seed chunk_x chunk_z hash\n",
+    );
+    for (key, hash) in snapshots {
+        writeln!(contents, "{} {:016x}", key, hash).unwrap();
+    }
+    contents
+}
+
+#[test]
+fn generated_terrain_matches_snapshots() {
+    let path = snapshot_path();
+    let update = env::var_os(UPDATE_VAR).is_some();
+    let stored = fs::read_to_string(&path)
+        .map(|contents| parse_snapshots(&contents))
+        .unwrap_or_default();
+
+    let mut snapshots = BTreeMap::new();
+    let mut mismatches = Vec::new();
+    let mut missing = Vec::new();
+    for &(preset_name, seed, x, z) in CASES {
+        let generator = ComposableGenerator::with_preset(preset(preset_name), seed);
+        let hash = chunk_hash(&generator.generate_chunk(ChunkPosition::new(x, z)));
+        let key = format!("{} {} {} {}", preset_name, seed, x, z);
+        match stored.get(&key) {
+            Some(&expected) if expected != hash => {
+                mismatches.push(format!(
+                    "{}: expected {:016x}, generated {:016x}",
+                    key, expected, hash
+                ));
+            }
+            None => missing.push(key.clone()),
+            _ => {}
+        }
+        snapshots.insert(key, hash);
+    }
+
+    if update {
+        // Also drops cases removed from `CASES`.
+        fs::write(&path, format_snapshots(&snapshots)).expect("failed to write snapshots");
+        return;
+    }
+
+    assert!(
+        missing.is_empty(),
+        "no snapshots recorded for:\n{}\n\
+         Rerun with {}=1 and commit tests/snapshots.txt.",
+        missing.join("\n"),
+        UPDATE_VAR
+    );
+    assert!(
+        mismatches.is_empty(),
+        "generated terrain differs from the snapshots:\n{}\n\
+         If the change is intentional, rerun with {}=1 and commit tests/snapshots.txt.",
+        mismatches.join("\n"),
+        UPDATE_VAR
+    );
+}
+
+#[test]
+fn snapshots_round_trip() {
+    let mut snapshots = BTreeMap::new();
+    snapshots.insert("default 0 0 0".to_owned(), 0x0123_4567_89ab_cdef);
+    snapshots.insert("amplified 42 -1 3".to_owned(), u64::MAX);
+    assert_eq!(parse_snapshots(&format_snapshots(&snapshots)), snapshots);
+}
+
+#[test]
+fn chunk_hash_depends_on_blocks() {
+    let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+    let empty = chunk_hash(&chunk);
+    chunk.set_block_at(3, 64, 9, base::BlockId::stone());
+    assert_ne!(chunk_hash(&chunk), empty);
+}